@@ -1,8 +1,10 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use sha2::{Digest, Sha256};
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn rust_string_literal(value: &str) -> String {
     let trimmed = value.trim();
@@ -28,6 +30,66 @@ fn parse_bool(s: &str) -> bool {
     matches!(cleaned.as_str(), "true" | "1" | "on" | "yes")
 }
 
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Compresses the files of web/ and generates the table of the assets served by the HTTP server,
+/// the ETag is a digest of the uncompressed content
+fn bundle_web_assets(out_dir: &Path) {
+    let web_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("web");
+    println!("cargo:rerun-if-changed={}", web_dir.display());
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&web_dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()).collect())
+        .unwrap_or_default();
+    paths.sort();
+
+    let assets_dir = out_dir.join("web");
+    fs::create_dir_all(&assets_dir).unwrap();
+
+    let dest_path = out_dir.join("assets.rs");
+    let mut f = File::create(&dest_path).unwrap();
+
+    writeln!(f, "// Auto-generated by build.rs from the files in web/").unwrap();
+    writeln!(f, "").unwrap();
+    writeln!(f, "const ASSETS: &[Asset] = &[").unwrap();
+
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let content = fs::read(&path).unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&content).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let compressed_path = assets_dir.join(format!("{name}.gz"));
+        fs::write(&compressed_path, &compressed).unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(&content);
+        let digest = format!("{:x}", hasher.finalize());
+        let etag = format!("\"{}\"", &digest[..16]);
+
+        writeln!(f, "    Asset {{ path: {:?}, content_type: {:?}, etag: {:?}, data: include_bytes!({:?}) }},",
+            format!("/{name}"), content_type(&path), etag, compressed_path.display().to_string()).unwrap();
+
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+
+    writeln!(f, "];").unwrap();
+    f.flush().unwrap();
+}
+
 fn main() {
     // Read configuration from environment variables set by CMake
     let default_wifi_ssid = env_string_literal("HHG_DEFAULT_WIFI_SSID", "");
@@ -45,6 +107,19 @@ fn main() {
     let default_ntp_msg_len = env::var("HHG_DEFAULT_NTP_MSG_LEN").unwrap_or_else(|_| "48".to_string()).parse::<u16>().unwrap_or(48);
     let default_ntp_port = env::var("HHG_DEFAULT_NTP_PORT").unwrap_or_else(|_| "123".to_string()).parse::<u16>().unwrap_or(123);
     let default_ntp_server = env_string_literal("HHG_DEFAULT_NTP_SERVER", "0.europe.pool.ntp.org");
+    let default_mqtt_host = env_string_literal("HHG_DEFAULT_MQTT_HOST", "");
+    let default_mqtt_port = env::var("HHG_DEFAULT_MQTT_PORT").unwrap_or_else(|_| "1883".to_string()).parse::<u16>().unwrap_or(1883);
+    let default_mqtt_user = env_string_literal("HHG_DEFAULT_MQTT_USER", "");
+    let default_mqtt_password = env_string_literal("HHG_DEFAULT_MQTT_PASSWORD", "");
+    let default_mqtt_topic = env_string_literal("HHG_DEFAULT_MQTT_TOPIC", "hhg");
+    let default_mqtt_keepalive = env::var("HHG_DEFAULT_MQTT_KEEPALIVE").unwrap_or_else(|_| "60".to_string()).parse::<u16>().unwrap_or(60);
+    let default_mqtt_enabled = parse_bool(&env::var("HHG_DEFAULT_MQTT_ENABLED").unwrap_or_else(|_| "false".to_string()));
+    let default_telnet_port = env::var("HHG_DEFAULT_TELNET_PORT").unwrap_or_else(|_| "2323".to_string()).parse::<u16>().unwrap_or(2323);
+    let default_telnet_idle = env::var("HHG_DEFAULT_TELNET_IDLE").unwrap_or_else(|_| "300".to_string()).parse::<u16>().unwrap_or(300);
+    let default_telnet_enabled = parse_bool(&env::var("HHG_DEFAULT_TELNET_ENABLED").unwrap_or_else(|_| "false".to_string()));
+    let default_http_port = env::var("HHG_DEFAULT_HTTP_PORT").unwrap_or_else(|_| "80".to_string()).parse::<u16>().unwrap_or(80);
+    let default_http_enabled = parse_bool(&env::var("HHG_DEFAULT_HTTP_ENABLED").unwrap_or_else(|_| "false".to_string()));
+    let default_uart_plain_login = parse_bool(&env::var("HHG_DEFAULT_UART_PLAIN_LOGIN").unwrap_or_else(|_| "true".to_string()));
     let hhg_aes_key_salt = env_string_literal("HHG_AES_KEY_SALT", "AES_KEY");
     let hhg_aes_iv_salt = env_string_literal("HHG_AES_IV_SALT", "AES_IV");
     let default_system_user_email = env_string_literal("HHG_DEFAULT_SYSTEM_USER_EMAIL", "");
@@ -63,6 +138,9 @@ fn main() {
     let mut hasher = Sha256::new();
     hasher.update(raw_password.as_bytes());
     let default_system_user_password = format!("{:?}", format!("{:x}", hasher.finalize()));
+    // Slot 0 is the system user and slot 1 the local user of the display
+    let max_users = env::var("HHG_MAX_USERS").unwrap_or_else(|_| "8".to_string()).parse::<usize>().unwrap_or(8).max(2);
+    let password_iterations = env::var("HHG_PASSWORD_ITERATIONS").unwrap_or_else(|_| "10000".to_string()).parse::<u32>().unwrap_or(10000).max(1);
 
     // Generate defaults.rs file
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
@@ -86,10 +164,25 @@ fn main() {
     writeln!(f, "pub const DEFAULT_NTP_MSG_LEN: u16 = {};", default_ntp_msg_len).unwrap();
     writeln!(f, "pub const DEFAULT_NTP_PORT: u16 = {};", default_ntp_port).unwrap();
     writeln!(f, "pub const DEFAULT_NTP_SERVER: &str = {};", default_ntp_server).unwrap();
+    writeln!(f, "pub const DEFAULT_MQTT_HOST: &str = {};", default_mqtt_host).unwrap();
+    writeln!(f, "pub const DEFAULT_MQTT_PORT: u16 = {};", default_mqtt_port).unwrap();
+    writeln!(f, "pub const DEFAULT_MQTT_USER: &str = {};", default_mqtt_user).unwrap();
+    writeln!(f, "pub const DEFAULT_MQTT_PASSWORD: &str = {};", default_mqtt_password).unwrap();
+    writeln!(f, "pub const DEFAULT_MQTT_TOPIC: &str = {};", default_mqtt_topic).unwrap();
+    writeln!(f, "pub const DEFAULT_MQTT_KEEPALIVE: u16 = {};", default_mqtt_keepalive).unwrap();
+    writeln!(f, "pub const DEFAULT_MQTT_ENABLED: bool = {};", default_mqtt_enabled).unwrap();
+    writeln!(f, "pub const DEFAULT_TELNET_PORT: u16 = {};", default_telnet_port).unwrap();
+    writeln!(f, "pub const DEFAULT_TELNET_IDLE: u16 = {};", default_telnet_idle).unwrap();
+    writeln!(f, "pub const DEFAULT_TELNET_ENABLED: bool = {};", default_telnet_enabled).unwrap();
+    writeln!(f, "pub const DEFAULT_HTTP_PORT: u16 = {};", default_http_port).unwrap();
+    writeln!(f, "pub const DEFAULT_HTTP_ENABLED: bool = {};", default_http_enabled).unwrap();
+    writeln!(f, "pub const DEFAULT_UART_PLAIN_LOGIN: bool = {};", default_uart_plain_login).unwrap();
     writeln!(f, "pub const AES_KEY_SALT: &str = {};", hhg_aes_key_salt).unwrap();
     writeln!(f, "pub const AES_IV_SALT: &str = {};", hhg_aes_iv_salt).unwrap();
     writeln!(f, "pub const DEFAULT_SYSTEM_USER_EMAIL: &str = {};", default_system_user_email).unwrap();
     writeln!(f, "pub const DEFAULT_SYSTEM_USER_PASSWORD: &str = {};", default_system_user_password).unwrap();
+    writeln!(f, "pub const MAX_USERS: usize = {};", max_users).unwrap();
+    writeln!(f, "pub const PASSWORD_ITERATIONS: u32 = {};", password_iterations).unwrap();
 
     // Flush and close file explicitly
     f.flush().unwrap();
@@ -97,6 +190,8 @@ fn main() {
     
     println!("cargo:warning=File written and flushed to: {}", dest_path.display());
 
+    bundle_web_assets(&out_dir);
+
     
     println!("cargo:rerun-if-env-changed=HHG_DEFAULT_WIFI_SSID");
     println!("cargo:rerun-if-env-changed=HHG_DEFAULT_WIFI_PASSWORD");
@@ -113,8 +208,23 @@ fn main() {
     println!("cargo:rerun-if-env-changed=HHG_DEFAULT_NTP_MSG_LEN");
     println!("cargo:rerun-if-env-changed=HHG_DEFAULT_NTP_PORT");
     println!("cargo:rerun-if-env-changed=HHG_DEFAULT_NTP_SERVER");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_MQTT_HOST");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_MQTT_PORT");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_MQTT_USER");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_MQTT_PASSWORD");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_MQTT_TOPIC");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_MQTT_KEEPALIVE");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_MQTT_ENABLED");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_TELNET_PORT");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_TELNET_IDLE");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_TELNET_ENABLED");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_HTTP_PORT");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_HTTP_ENABLED");
+    println!("cargo:rerun-if-env-changed=HHG_DEFAULT_UART_PLAIN_LOGIN");
     println!("cargo:rerun-if-env-changed=HHG_AES_KEY_SALT");
     println!("cargo:rerun-if-env-changed=HHG_AES_IV_SALT");
     println!("cargo:rerun-if-env-changed=HHG_DEFAULT_SYSTEM_USER_EMAIL");
     println!("cargo:rerun-if-env-changed=HHG_DEFAULT_SYSTEM_USER_PASSWORD");
+    println!("cargo:rerun-if-env-changed=HHG_MAX_USERS");
+    println!("cargo:rerun-if-env-changed=HHG_PASSWORD_ITERATIONS");
 }
@@ -18,14 +18,20 @@
  *
  ***************************************************************************/
 
+mod audit;
 mod config;
 mod display;
+mod events;
+mod http;
 mod main;
+mod mqtt;
 mod parser;
+mod permission;
 mod screen_route;
 mod session;
 mod signals;
 mod sprinkler;
+mod telnet;
 mod system_handler;
 mod system_led;
 mod utils;
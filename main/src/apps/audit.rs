@@ -0,0 +1,284 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+///! Audit log of the security events and of the commands of the logged users, a ring of
+///! records saved in the log directory. The parser writes a record for each set and exec
+///! line of a session, the admins read them with AT+AUD.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use alloc::sync::Arc;
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult, at_quoted};
+use osal_rs::{access_static_option, log_info, log_warning};
+use osal_rs::os::{Mutex, MutexFn, RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::FS_LOG_DIR;
+use crate::traits::rtc::RTC;
+use crate::traits::state::Initializable;
+
+static mut SHARED: AuditLog = AuditLog {
+    records: [AuditRecord::new(); AuditLog::SIZE],
+    next: 0,
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+/// Records added since the last save, see `AuditLog::flush`
+static UNSAVED: AtomicU8 = AtomicU8::new(0);
+
+/// Time source of the records, set once the hardware is ready
+static mut CLOCK: Option<Arc<Mutex<dyn RTC + 'static>>> = None;
+
+const APP_TAG: &str = "AuditLog";
+
+/// Result of the audited event
+#[repr(u8)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(in crate::apps) enum AuditOutcome {
+    #[default]
+    Ok,
+
+    Failed,
+
+    /// too many failed logins, the login is locked
+    Locked,
+}
+
+impl From<u8> for AuditOutcome {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => AuditOutcome::Failed,
+            2 => AuditOutcome::Locked,
+            _ => AuditOutcome::Ok,
+        }
+    }
+}
+
+impl Serialize for AuditOutcome {
+    #[inline]
+    fn serialize<S: Serializer>(&self, name: &str, serializer: &mut S) -> core::result::Result<(), S::Error> {
+        Ok(serializer.serialize_u8(name, *self as u8)?)
+    }
+}
+
+impl Deserialize for AuditOutcome {
+    #[inline]
+    fn deserialize<D: Deserializer>(deserializer: &mut D, name: &str) -> core::result::Result<Self, D::Error> {
+        Ok(AuditOutcome::from(deserializer.deserialize_u8(name)?))
+    }
+}
+
+/// Audited event, never holds the arguments of the command
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct AuditRecord {
+
+    /// timestamp of the event, 0 if the clock was not available
+    pub(in crate::apps) timestamp: i64,
+
+    /// session slot of the source: 0 UART, 1 MQTT, 2 TCP, 3 HTTP, 4 local display
+    pub(in crate::apps) channel: u8,
+
+    /// email of the user, empty if unknown
+    pub(in crate::apps) email: Bytes<32>,
+
+    /// AT command, empty for an empty record
+    pub(in crate::apps) command: Bytes<12>,
+
+    /// verb of the set form, empty for the other forms and for the commands without verbs
+    pub(in crate::apps) verb: Bytes<4>,
+
+    pub(in crate::apps) outcome: AuditOutcome,
+}
+
+impl AuditRecord {
+    pub(in crate::apps) const fn new() -> Self {
+        Self {
+            timestamp: 0,
+            channel: 0,
+            email: Bytes::new(),
+            command: Bytes::new(),
+            verb: Bytes::new(),
+            outcome: AuditOutcome::Ok,
+        }
+    }
+
+    pub(in crate::apps) fn event(channel: usize, email: &str, command: &str, verb: &str, outcome: AuditOutcome) -> Self {
+        Self {
+            timestamp: 0,
+            channel: channel as u8,
+            email: Bytes::from_str(email),
+            command: Bytes::from_str(command),
+            verb: Bytes::from_str(verb),
+            outcome,
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.command.len() == 0
+    }
+}
+
+/// Ring of the last audited events, oldest are overwritten
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct AuditLog {
+    records: [AuditRecord; AuditLog::SIZE],
+
+    /// position of the next record to write
+    next: u8,
+}
+
+impl Default for AuditLog {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            records: [AuditRecord::new(); AuditLog::SIZE],
+            next: 0,
+        }
+    }
+}
+
+impl Initializable for AuditLog {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init audit log");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = deserialize_file::<AuditLog>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_LOG_DIR, AuditLog::FILE_NAME)?;
+
+        if self.next as usize >= AuditLog::SIZE {
+            self.next = 0;
+        }
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for AuditLog {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        Ok(at_cmd_response!(at_response; self.len(), AuditLog::SIZE))
+    }
+
+    #[inline]
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<record>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let idx: usize = args.get(0).ok_or((at_response, AtError::InvalidArgs))?
+            .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let record = self.get(idx).ok_or((at_response, AtError::InvalidArgs))?;
+        Ok(at_cmd_response!(at_response;
+            idx,
+            record.timestamp,
+            record.channel,
+            at_quoted!(record.email.as_str()),
+            record.command.as_str(),
+            record.verb.as_str(),
+            record.outcome as u8
+        ))
+    }
+}
+
+impl AuditLog {
+    pub(in crate::apps) const SIZE: usize = 32;
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+AUD";
+    pub(in crate::apps) const AT_RESP: &'static str = "+AUD: ";
+    const FILE_NAME: &'static str = "audit.json";
+
+    /// Unsaved records that force a save, bounds what a power loss drops during a long session
+    const FLUSH_RECORDS: u8 = 8;
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    pub(in crate::apps) fn set_rtc(rtc: Arc<Mutex<dyn RTC + 'static>>) {
+        unsafe {
+            CLOCK = Some(rtc);
+        }
+    }
+
+    /// Number of records stored
+    pub(in crate::apps) fn len(&self) -> usize {
+        self.records.iter().filter(|record| !record.is_empty()).count()
+    }
+
+    /// Record by age, 0 is the newest
+    pub(in crate::apps) fn get(&self, idx: usize) -> Option<&AuditRecord> {
+        if idx >= self.len() {
+            return None;
+        }
+        let position = (self.next as usize + AuditLog::SIZE - 1 - idx) % AuditLog::SIZE;
+        self.records.get(position)
+    }
+
+    /// Stores the event with the current time. A lockout is saved at once,
+    /// the commands are saved with the logout or after `FLUSH_RECORDS` of them
+    pub(in crate::apps) fn add(&mut self, mut record: AuditRecord) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        record.timestamp = unsafe { (*&raw const CLOCK).as_ref() }
+            .and_then(|rtc| rtc.lock().ok()?.get_timestamp().ok())
+            .unwrap_or(0);
+
+        self.records[self.next as usize] = record;
+        self.next = ((self.next as usize + 1) % AuditLog::SIZE) as u8;
+
+        let unsaved = UNSAVED.fetch_add(1, Ordering::Relaxed) + 1;
+        if record.outcome == AuditOutcome::Locked || unsaved >= AuditLog::FLUSH_RECORDS {
+            self.flush();
+        }
+    }
+
+    /// Saves the records added since the last save, called when a session ends
+    /// instead of after each command to spare the flash
+    pub(in crate::apps) fn flush(&self) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if UNSAVED.swap(0, Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        if let Err(e) = serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_LOG_DIR, AuditLog::FILE_NAME, self) {
+            log_warning!(APP_TAG, "Failed to save audit log: {e}");
+        }
+    }
+}
@@ -28,9 +28,12 @@ use osal_rs::{access_static_option, log_info};
 use osal_rs_serde::{Deserialize, Serialize};
 use at_parser_rs::at_quoted as quoted;
 
+use crate::apps::http::Http;
+use crate::apps::mqtt::Mqtt;
 use crate::apps::parser::{Parser, at_cmd_response};
 use crate::apps::session::Session;
-use crate::apps::signals::status::{StatusFlag, StatusSignal};
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
+use crate::apps::telnet::Telnet;
 use crate::apps::utils::{deserialize_file, serialize_file};
 use crate::drivers::date_time::DateTime;
 use crate::drivers::network::Network;
@@ -44,7 +47,7 @@ use at_parser_rs::context::AtContext;
 
 // Include default configuration values generated by build.rs from CMake
 #[allow(dead_code)]
-mod defaults {
+pub(super) mod defaults {
     include!(concat!(env!("OUT_DIR"), "/defaults.rs"));
 }
 
@@ -80,6 +83,25 @@ static mut SHARED: Config = Config {
         port: 123,
         msg_len: 48,
     },
+    mqtt: MqttConfig {
+        host: Bytes::new(),
+        port: 1883,
+        user: Bytes::new(),
+        password: Bytes::new(),
+        topic: Bytes::new(),
+        keepalive: 60,
+        enabled: false,
+        discovery: true,
+    },
+    telnet: TelnetConfig {
+        port: 2323,
+        idle: 300,
+        enabled: false,
+    },
+    http: HttpConfig {
+        port: 80,
+        enabled: false,
+    },
 };
 
 #[derive(Serialize, Deserialize, Clone, Copy)]
@@ -140,9 +162,6 @@ impl AtContext<{ Parser::CMD_SIZE }> for DaylightSavingTime {
     }
 
     fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
         let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
 
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
@@ -181,6 +200,7 @@ impl AtContext<{ Parser::CMD_SIZE }> for DaylightSavingTime {
         }
 
         Config::shared().apply_daylight_saving_time();
+        SchedulerSignal::set(SchedulerFlag::ClockChanged.into());
 
         Ok(at_cmd_response!(at_response; ""))
     }
@@ -263,9 +283,6 @@ impl AtContext<{ Parser::CMD_SIZE }> for WifiConfig {
     }
 
     fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
         let ssid = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
         if ssid.len() > 32 {
             return Err((at_response, AtError::Unhandled("ssid max len 32")));
@@ -356,9 +373,6 @@ impl AtContext<{ Parser::CMD_SIZE }> for NtpConfig {
     }
 
     fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
         let server = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
         if server.len() > 64 {
             return Err((at_response, AtError::Unhandled("server max len 64")));
@@ -380,8 +394,316 @@ impl AtContext<{ Parser::CMD_SIZE }> for NtpConfig {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub(in crate::apps) struct MqttConfig {
+    host: Bytes<64>,
+    port: u16,
+    user: Bytes<32>,
+    password: Bytes<32>,
+    topic: Bytes<16>,
+    keepalive: u16,
+    enabled: bool,
+
+    /// publish the Home Assistant discovery configs and states
+    discovery: bool,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: Bytes::from_str(DEFAULT_MQTT_HOST),
+            port: DEFAULT_MQTT_PORT,
+            user: Bytes::from_str(DEFAULT_MQTT_USER),
+            password: Bytes::from_str(DEFAULT_MQTT_PASSWORD),
+            topic: Bytes::from_str(DEFAULT_MQTT_TOPIC),
+            keepalive: DEFAULT_MQTT_KEEPALIVE,
+            enabled: DEFAULT_MQTT_ENABLED,
+            discovery: true,
+        }
+    }
+}
+
+impl MqttConfig {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+MQTT";
+    pub(in crate::apps) const AT_RESP: &'static str = "+MQTT: ";
+
+    pub(in crate::apps) fn get_host(&self) -> Bytes<64> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.host
+    }
+
+    pub(in crate::apps) fn get_port(&self) -> u16 {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.port
+    }
+
+    pub(in crate::apps) fn get_user(&self) -> Bytes<32> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.user
+    }
+
+    pub(in crate::apps) fn get_password(&self) -> Bytes<32> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.password
+    }
+
+    pub(in crate::apps) fn get_topic(&self) -> Bytes<16> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.topic
+    }
+
+    pub(in crate::apps) fn get_keepalive(&self) -> u16 {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.keepalive
+    }
+
+    pub(in crate::apps) fn is_enabled(&self) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.enabled
+    }
+
+    pub(in crate::apps) fn is_discovery_enabled(&self) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.discovery
+    }
+}
+
+impl AtContext<{ Parser::CMD_SIZE }> for MqttConfig {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        Ok(at_cmd_response!(at_response; quoted!(self.host.as_str()), self.port, self.keepalive, self.enabled as u8, Mqtt::is_connected() as u8))
+    }
+
+    #[inline]
+    /// hs = host, pt = port, us = user and password, tp = topic prefix, ka = keepalive, en = enabled,
+    /// ha = Home Assistant discovery, rd = read user, topic prefix and discovery
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        Ok(at_cmd_response!(at_response; "<hs|pt|us|tp|ka|en|ha>,<value>[,<password>] | rd"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match cmd.as_ref() {
+            "hs" => { // host
+                let value = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+                if value.len() > 64 {
+                    return Err((at_response, AtError::Unhandled("host max len 64")));
+                }
+                self.host = Bytes::from_str(value.as_ref());
+            }
+            "pt" => // port
+                self.port = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?,
+            "us" => { // user and password, no user for anonymous access
+                let user = match args.get(1) {
+                    Some(value) if value.len() > 32 => return Err((at_response, AtError::Unhandled("user max len 32"))),
+                    Some(value) => Bytes::from_str(value.as_ref()),
+                    None => Bytes::new(),
+                };
+                let password = match args.get(2) {
+                    Some(value) if value.len() > 32 => return Err((at_response, AtError::Unhandled("password max len 32"))),
+                    Some(value) => Bytes::from_str(value.as_ref()),
+                    None => Bytes::new(),
+                };
+                self.user = user;
+                self.password = password;
+            }
+            "tp" => { // topic prefix
+                let value = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+                let value: &str = value.as_ref();
+                if value.is_empty() || value.len() > 16 || value.contains(['/', '+', '#']) {
+                    return Err((at_response, AtError::Unhandled("Invalid topic prefix")));
+                }
+                self.topic = Bytes::from_str(value);
+            }
+            "ka" => // keepalive
+                self.keepalive = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?,
+            "en" => { // enabled
+                let value: u8 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                self.enabled = value != 0;
+            }
+            "ha" => { // Home Assistant discovery
+                let value: u8 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                self.discovery = value != 0;
+            }
+            "rd" => { // read user, topic prefix and discovery
+                return Ok(at_cmd_response!(at_response; quoted!(self.user.as_str()), quoted!(self.topic.as_str()), self.discovery as u8));
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Config::shared().apply_mqtt();
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Copy)]
+pub(in crate::apps) struct TelnetConfig {
+    port: u16,
+
+    /// seconds without input before the client is dropped, 0 never
+    idle: u16,
+    enabled: bool,
+}
+
+impl Default for TelnetConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_TELNET_PORT,
+            idle: DEFAULT_TELNET_IDLE,
+            enabled: DEFAULT_TELNET_ENABLED,
+        }
+    }
+}
+
+impl TelnetConfig {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+TEL";
+    pub(in crate::apps) const AT_RESP: &'static str = "+TEL: ";
+
+    pub(in crate::apps) fn get_port(&self) -> u16 {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.port
+    }
+
+    pub(in crate::apps) fn get_idle(&self) -> u16 {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.idle
+    }
+
+    pub(in crate::apps) fn is_enabled(&self) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.enabled
+    }
+}
+
+impl AtContext<{ Parser::CMD_SIZE }> for TelnetConfig {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        Ok(at_cmd_response!(at_response; self.port, self.idle, self.enabled as u8, Telnet::is_connected() as u8))
+    }
+
+    #[inline]
+    /// pt = port, id = idle timeout in seconds, en = enabled
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        Ok(at_cmd_response!(at_response; "<pt|id|en>,<value>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match cmd.as_ref() {
+            "pt" => { // port
+                let value: u16 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value == 0 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                if value == Config::shared().http.port {
+                    return Err((at_response, AtError::Unhandled("Port in use")));
+                }
+                self.port = value;
+            }
+            "id" => // idle timeout
+                self.idle = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?,
+            "en" => { // enabled
+                let value: u8 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                self.enabled = value != 0;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Config::shared().apply_telnet();
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub(in crate::apps) struct HttpConfig {
+    port: u16,
+    enabled: bool,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_HTTP_PORT,
+            enabled: DEFAULT_HTTP_ENABLED,
+        }
+    }
+}
+
+impl HttpConfig {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+HTTP";
+    pub(in crate::apps) const AT_RESP: &'static str = "+HTTP: ";
+
+    pub(in crate::apps) fn get_port(&self) -> u16 {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.port
+    }
+
+    pub(in crate::apps) fn is_enabled(&self) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.enabled
+    }
+}
+
+impl AtContext<{ Parser::CMD_SIZE }> for HttpConfig {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        Ok(at_cmd_response!(at_response; self.port, self.enabled as u8))
+    }
+
+    #[inline]
+    /// pt = port, en = enabled
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        Ok(at_cmd_response!(at_response; "<pt|en>,<value>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match cmd.as_ref() {
+            "pt" => { // port
+                let value: u16 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value == 0 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                if value == Config::shared().telnet.port {
+                    return Err((at_response, AtError::Unhandled("Port in use")));
+                }
+                self.port = value;
+            }
+            "en" => { // enabled
+                let value: u8 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                self.enabled = value != 0;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Config::shared().apply_http();
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+
+#[derive(Serialize, Clone, Copy)]
 pub(in crate::apps) struct Config {
     version: u8,
     serial: Bytes<16>,
@@ -390,6 +712,29 @@ pub(in crate::apps) struct Config {
     session: Session,
     wifi: WifiConfig,
     ntp: NtpConfig,
+    mqtt: MqttConfig,
+    telnet: TelnetConfig,
+    http: HttpConfig,
+}
+
+impl Deserialize for Config {
+    fn deserialize<D: osal_rs_serde::Deserializer>(deserializer: &mut D, name: &str) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_struct_start(name)?;
+        let version = u8::deserialize(deserializer, "version")?;
+        let serial = Bytes::deserialize(deserializer, "serial")?;
+        let timezone = i16::deserialize(deserializer, "timezone")?;
+        let daylight_saving_time = DaylightSavingTime::deserialize(deserializer, "daylight_saving_time")?;
+        let session = Session::deserialize(deserializer, "session")?;
+        let wifi = WifiConfig::deserialize(deserializer, "wifi")?;
+        let ntp = NtpConfig::deserialize(deserializer, "ntp")?;
+        // The configs saved before the MQTT, telnet and HTTP servers get their defaults
+        let mqtt = MqttConfig::deserialize(deserializer, "mqtt").unwrap_or_default();
+        let telnet = TelnetConfig::deserialize(deserializer, "telnet").unwrap_or_default();
+        let http = HttpConfig::deserialize(deserializer, "http").unwrap_or_default();
+        deserializer.deserialize_struct_end()?;
+
+        Ok(Self { version, serial, timezone, daylight_saving_time, session, wifi, ntp, mqtt, telnet, http })
+    }
 }
 
 impl Default for Config {
@@ -402,6 +747,9 @@ impl Default for Config {
             session: Default::default(),
             wifi: Default::default(),
             ntp: Default::default(),
+            mqtt: Default::default(),
+            telnet: Default::default(),
+            http: Default::default(),
         };
 
         // Set system user (position 0) from CMake defaults
@@ -445,9 +793,6 @@ impl AtContext<{ Parser::CMD_SIZE }> for Config {
     }
 
     fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
         let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
 
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
@@ -465,6 +810,7 @@ impl AtContext<{ Parser::CMD_SIZE }> for Config {
                     .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
                 self.timezone = value;
                 self.apply_locale();
+                SchedulerSignal::set(SchedulerFlag::ClockChanged.into());
             }
             "sv" => { // save
                 Config::save().map_err(|_| (at_response, AtError::Unhandled("Save error")))?;
@@ -517,6 +863,21 @@ impl Config {
         );
     }
 
+    /// The MQTT client picks up the new settings on its next connection
+    pub(in crate::apps) fn apply_mqtt(&self) {
+        Mqtt::reconnect();
+    }
+
+    /// The server is restarted with the new settings, the connected client is dropped
+    pub(in crate::apps) fn apply_telnet(&self) {
+        Telnet::restart();
+    }
+
+    /// The server is restarted with the new settings, the pending request is dropped
+    pub(in crate::apps) fn apply_http(&self) {
+        Http::restart();
+    }
+
     pub(in crate::apps) fn apply_session(&self) {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
         self.session.set_user_local();
@@ -567,6 +928,21 @@ impl Config {
         self.ntp
     }
 
+    pub(in crate::apps) fn get_mqtt_config(&self) -> MqttConfig {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.mqtt
+    }
+
+    pub(in crate::apps) fn get_telnet_config(&self) -> TelnetConfig {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.telnet
+    }
+
+    pub(in crate::apps) fn get_http_config(&self) -> HttpConfig {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.http
+    }
+
     // The *_mut accessors hand out references that outlive any lock scope:
     // the data they point to is protected by the locks taken inside the
     // methods of the returned types, not by locking here.
@@ -583,9 +959,58 @@ impl Config {
         &mut self.wifi
     }
 
+    pub(in crate::apps) fn get_mqtt_config_mut(&mut self) -> &mut MqttConfig {
+        &mut self.mqtt
+    }
+
+    pub(in crate::apps) fn get_telnet_config_mut(&mut self) -> &mut TelnetConfig {
+        &mut self.telnet
+    }
+
+    pub(in crate::apps) fn get_http_config_mut(&mut self) -> &mut HttpConfig {
+        &mut self.http
+    }
+
     pub(in crate::apps) fn get_session(&mut self) -> &mut Session {
         &mut self.session
     }
 
 
 }
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    use cjson_binding::{from_json, to_json};
+
+    /// config.json as saved by the firmware before the MQTT, telnet and HTTP servers
+    const OLD_CONFIG: &str = r#"{"version":1,"serial":"HHG-0042","timezone":60,"daylight_saving_time":{"start_month":3,"start_day":255,"start_hour":2,"end_month":10,"end_day":255,"end_hour":3,"enabled":true},"session":{"users":[{"email":"admin@hhg","password":"5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8"},{"email":"","password":""}]},"wifi":{"ssid":"garden","password":"secret","auth":3,"enabled":true},"ntp":{"server":"pool.ntp.org","port":123,"msg_len":48}}"#;
+
+    #[test]
+    fn old_configs_keep_their_settings() {
+        let Ok(loaded) = from_json::<Config>(OLD_CONFIG) else { panic!("old config not loaded") };
+
+        assert_eq!((loaded.version, loaded.serial.as_str(), loaded.timezone), (1, "HHG-0042", 60));
+        assert!(loaded.daylight_saving_time.enabled);
+        assert_eq!((loaded.wifi.ssid.as_str(), loaded.wifi.password.as_str(), loaded.wifi.enabled), ("garden", "secret", true));
+        assert_eq!((loaded.mqtt.port, loaded.mqtt.enabled), (DEFAULT_MQTT_PORT, DEFAULT_MQTT_ENABLED));
+        assert_eq!((loaded.telnet.port, loaded.telnet.enabled), (DEFAULT_TELNET_PORT, DEFAULT_TELNET_ENABLED));
+        assert_eq!((loaded.http.port, loaded.http.enabled), (DEFAULT_HTTP_PORT, DEFAULT_HTTP_ENABLED));
+    }
+
+    #[test]
+    fn configs_round_trip() {
+        let mut config = Config::default();
+        config.serial = Bytes::from_str("HHG-0042");
+        config.mqtt.port = 8883;
+        config.telnet.enabled = !DEFAULT_TELNET_ENABLED;
+        config.http.port = 8080;
+        let Ok(json) = to_json(&config) else { panic!("config not serialized") };
+
+        let Ok(loaded) = from_json::<Config>(&json) else { panic!("config not loaded") };
+
+        assert_eq!(loaded.serial.as_str(), "HHG-0042");
+        assert_eq!((loaded.mqtt.port, loaded.telnet.enabled, loaded.http.port), (8883, !DEFAULT_TELNET_ENABLED, 8080));
+    }
+}
@@ -26,6 +26,7 @@ pub(super) mod date;
 mod header;
 pub(super) mod input;
 pub(super) mod number;
+pub(super) mod qr;
 pub(super) mod select;
 pub(super) mod text;
 pub(super) mod time;
@@ -48,6 +48,8 @@ pub(super) struct Header {
     date_time: DateTime,
     rssi_status: RSSIStatus,
     show_admin_icon: bool,
+    /// text shown in place of date and time, pump lockout or winterization
+    banner: Option<&'static str>,
 }
 
 impl Header {
@@ -60,6 +62,7 @@ impl Header {
             date_time: DateTime::default(),
             rssi_status: RSSIStatus::Unknown,
             show_admin_icon: false,
+            banner: None,
         }
     }
 
@@ -106,6 +109,20 @@ impl Header {
             
         
 
+        let banner = if ErrorSignal::get() & <ErrorFlag as Into<u32>>::into(ErrorFlag::PumpLockout) != 0 {
+            Some("PUMP LOCKED")
+        } else if StatusFlag::Winterize.check_signal(*status_signal) {
+            Some("WINTERIZING")
+        } else if StatusFlag::Winterized.check_signal(*status_signal) {
+            Some("WINTERIZED")
+        } else {
+            None
+        };
+        if self.banner != banner {
+            self.banner = banner;
+            redraw_needed = true;
+        }
+
         if !redraw_needed {
             return Ok(()); // No need to redraw if nothing has changed
         }
@@ -140,7 +157,12 @@ impl Header {
         }
 
 
-        if date_time.is_valid() {
+        if let Some(banner) = self.banner {
+            lcd.draw_str(banner, display_width - (banner.len() as u8 * 5) - 5, 1, &FONT_5X8).unwrap_or_else(|e| {
+                log_error!("Header", "Failed to draw banner on LCD: {}", e);
+                ErrorSignal::set(ErrorFlag::Display.into());
+            });
+        } else if date_time.is_valid() {
             let now = format!("{:04}-{:02}-{:02}  {:02}:{:02}", date_time.year, date_time.month, date_time.mday, date_time.hour, date_time.minute);
             lcd.draw_str(&now, display_width - (now.len() as u8 * 5) - 5, 1, &FONT_5X8).unwrap_or_else(|e| {
                 log_error!("Header", "Failed to draw time on LCD: {}", e);
@@ -0,0 +1,277 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use alloc::sync::Arc;
+use osal_rs::os::Mutex;
+use osal_rs::os::types::EventBits;
+use osal_rs::utils::{AsSyncStr, Error, Result};
+
+use crate::apps::signals::display::DisplayFlag;
+use crate::traits::lcd_display::{LCDDisplayFn, LCDWriteMode};
+use crate::traits::rtc::RTC;
+use crate::traits::screen::{Screen, ScreenCallback, ScreenParam};
+
+// QR code version 3 with error correction L, one block and the mask 0:
+// 29x29 modules of 2x2 px on a lit square as high as the display
+const SIZE: usize = 29;
+const DATA_CODEWORDS: usize = 55;
+const EC_CODEWORDS: usize = 15;
+const ALIGNMENT: usize = 22;
+const MODULE_PX: u8 = 2;
+const BORDER_PX: u8 = 3;
+
+/// Bytes that fit after the mode, the length and the terminator
+pub(in crate::apps) const MAX_SIZE: usize = DATA_CODEWORDS - 2;
+
+/// One row for each y, bit x set for a dark module
+type Modules = [u32; SIZE];
+
+pub(in crate::apps) struct Qr {
+    modules: Option<Modules>,
+}
+
+impl Screen<()> for Qr
+{
+    fn draw(&mut self,
+        lcd: &mut dyn LCDDisplayFn,
+        signal: &mut EventBits,
+        _: &Arc<Mutex<dyn RTC + 'static>>,
+        text: &dyn AsSyncStr,
+        _: ScreenParam,
+        callback: ScreenCallback
+    ) -> Result<()> {
+
+        if self.modules.is_none() {
+            self.modules = Some(encode(text.as_str().as_bytes()).ok_or(Error::Unhandled("Text too long for the QR code"))?);
+        }
+        let modules = self.modules.as_ref().ok_or(Error::NullPtr)?;
+
+        // The code covers the header too, a scanner needs every module
+        let (width, height) = lcd.get_size();
+        let side = SIZE as u8 * MODULE_PX + 2 * BORDER_PX;
+        let x_start = (width - side) / 2;
+        let y_start = (height - side) / 2;
+
+        lcd.clear();
+        lcd.draw_rect(x_start, y_start, side, side, LCDWriteMode::ADD)?;
+        for (y, row) in modules.iter().enumerate() {
+            for x in (0..SIZE).filter(|&x| row & 1 << x != 0) {
+                lcd.draw_rect(
+                    x_start + BORDER_PX + x as u8 * MODULE_PX,
+                    y_start + BORDER_PX + y as u8 * MODULE_PX,
+                    MODULE_PX, MODULE_PX,
+                    LCDWriteMode::REMOVE
+                )?;
+            }
+        }
+
+        if *signal & (DisplayFlag::ButtonReleased as u32 | DisplayFlag::EncoderButtonReleased as u32) != 0 {
+            if let Some(cb) = callback {
+                cb(Option::None, *signal & DisplayFlag::EncoderButtonReleased as u32 != 0);
+            }
+            *signal |= DisplayFlag::Draw as u32;
+        }
+
+        Ok(())
+    }
+
+    fn get_value(&self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+
+impl Qr {
+
+    pub(in crate::apps) const fn new() -> Self {
+        Self { modules: None }
+    }
+
+}
+
+/// Modules of the bytes in byte mode, None if they do not fit
+fn encode(data: &[u8]) -> Option<Modules> {
+    if data.len() > MAX_SIZE {
+        return None;
+    }
+
+    let mut codewords = [0u8; DATA_CODEWORDS + EC_CODEWORDS];
+    let mut bit = 0;
+    let mut push = |value: u32, len: usize| {
+        for i in (0..len).rev() {
+            codewords[bit >> 3] |= (((value >> i) & 1) as u8) << (7 - (bit & 7));
+            bit += 1;
+        }
+    };
+    push(0b0100, 4);
+    push(data.len() as u32, 8);
+    for &byte in data {
+        push(byte as u32, 8);
+    }
+    // Terminator, MAX_SIZE leaves room for all its 4 bits, then the pad codewords
+    push(0, 4);
+    let used = bit.div_ceil(8);
+    for (i, codeword) in codewords[used..DATA_CODEWORDS].iter_mut().enumerate() {
+        *codeword = if i % 2 == 0 { 0xEC } else { 0x11 };
+    }
+
+    let (data_codewords, ec_codewords) = codewords.split_at_mut(DATA_CODEWORDS);
+    ec_codewords.copy_from_slice(&reed_solomon(data_codewords));
+
+    let mut modules = [0u32; SIZE];
+    let mut function = [0u32; SIZE];
+    draw_function_patterns(&mut modules, &mut function);
+    draw_codewords(&mut modules, &function, &codewords);
+
+    // Mask 0 on the data modules
+    for (y, (row, function)) in modules.iter_mut().zip(function.iter()).enumerate() {
+        let mask = if y % 2 == 0 { 0x5555_5555 } else { 0xAAAA_AAAA };
+        *row ^= mask & !function & ((1 << SIZE) - 1);
+    }
+    Some(modules)
+}
+
+#[inline]
+fn set_function(modules: &mut Modules, function: &mut Modules, x: usize, y: usize, dark: bool) {
+    if dark {
+        modules[y] |= 1 << x;
+    } else {
+        modules[y] &= !(1 << x);
+    }
+    function[y] |= 1 << x;
+}
+
+/// Timing, finder and alignment patterns with the format bits of L and mask 0
+fn draw_function_patterns(modules: &mut Modules, function: &mut Modules) {
+    for i in 0..SIZE {
+        set_function(modules, function, 6, i, i % 2 == 0);
+        set_function(modules, function, i, 6, i % 2 == 0);
+    }
+
+    for (center_x, center_y) in [(3, 3), (SIZE - 4, 3), (3, SIZE - 4)] {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (x, y) = (center_x as i32 + dx, center_y as i32 + dy);
+                if (0..SIZE as i32).contains(&x) && (0..SIZE as i32).contains(&y) {
+                    let distance = dx.abs().max(dy.abs());
+                    set_function(modules, function, x as usize, y as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    for dy in -2i32..=2 {
+        for dx in -2i32..=2 {
+            let (x, y) = ((ALIGNMENT as i32 + dx) as usize, (ALIGNMENT as i32 + dy) as usize);
+            set_function(modules, function, x, y, dx.abs().max(dy.abs()) != 1);
+        }
+    }
+
+    // Error correction L is 01, mask 000, then the BCH remainder
+    let data: u32 = 0b01 << 3;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    let bits = (data << 10 | remainder) ^ 0x5412;
+    let format = |i: usize| (bits >> i) & 1 != 0;
+
+    for i in 0..=5 {
+        set_function(modules, function, 8, i, format(i));
+    }
+    set_function(modules, function, 8, 7, format(6));
+    set_function(modules, function, 8, 8, format(7));
+    set_function(modules, function, 7, 8, format(8));
+    for i in 9..15 {
+        set_function(modules, function, 14 - i, 8, format(i));
+    }
+    for i in 0..8 {
+        set_function(modules, function, SIZE - 1 - i, 8, format(i));
+    }
+    for i in 8..15 {
+        set_function(modules, function, 8, SIZE - 15 + i, format(i));
+    }
+    set_function(modules, function, 8, SIZE - 8, true);
+}
+
+/// Codewords in the zigzag of column pairs from the bottom right corner
+fn draw_codewords(modules: &mut Modules, function: &Modules, codewords: &[u8]) {
+    let mut bit = 0;
+    let mut right = SIZE - 1;
+    loop {
+        if right == 6 {
+            right = 5;
+        }
+        let upward = (right + 1) & 2 == 0;
+        for vertical in 0..SIZE {
+            let y = if upward { SIZE - 1 - vertical } else { vertical };
+            for x in [right, right - 1] {
+                if function[y] & 1 << x == 0 && bit < codewords.len() * 8 {
+                    if (codewords[bit >> 3] >> (7 - (bit & 7))) & 1 != 0 {
+                        modules[y] |= 1 << x;
+                    }
+                    bit += 1;
+                }
+            }
+        }
+        if right < 2 {
+            break;
+        }
+        right -= 2;
+    }
+}
+
+/// Product in GF(256) with the polynomial of the QR codes
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u16 >> i) & 1) * x as u16;
+    }
+    z as u8
+}
+
+/// Error correction codewords of the data
+fn reed_solomon(data: &[u8]) -> [u8; EC_CODEWORDS] {
+    let mut divisor = [0u8; EC_CODEWORDS];
+    divisor[EC_CODEWORDS - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..EC_CODEWORDS {
+        for j in 0..EC_CODEWORDS {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < EC_CODEWORDS {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+
+    let mut remainder = [0u8; EC_CODEWORDS];
+    for &byte in data {
+        let factor = byte ^ remainder[0];
+        remainder.copy_within(1.., 0);
+        remainder[EC_CODEWORDS - 1] = 0;
+        for (value, &coefficient) in remainder.iter_mut().zip(divisor.iter()) {
+            *value ^= gf_multiply(coefficient, factor);
+        }
+    }
+    remainder
+}
@@ -0,0 +1,286 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! Unsolicited result codes: the apps post `Event`s, the events thread writes them as
+///! `+EVT: <event>` lines on the channels subscribed with `AT+URC`, between the
+///! response lines of the parser.
+
+use core::fmt::{Display, Formatter};
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::time::Duration;
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{log_info, log_warning};
+use osal_rs::os::{Queue, QueueFn, Thread, ThreadFn, ToTick};
+use osal_rs::os::types::{StackType, UBaseType};
+use osal_rs::utils::{Bytes, Error, Result};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::signals::error::{ErrorFlag, ErrorSignal};
+use crate::drivers::platform::ThreadPriority;
+use crate::traits::rx_tx::Source;
+use crate::traits::signal::Signal;
+use crate::traits::state::Initializable;
+use crate::traits::wifi::WifiStatus;
+
+const APP_TAG: &str = "AppEvents";
+const THREAD_NAME: &str = "app_events_trd";
+const STACK_SIZE: StackType = 1_024;
+const QUEUE_SIZE: UBaseType = 16;
+
+/// Period of the check of the error flags
+const ERROR_POLL_MS: u64 = 1_000;
+
+const URC_SIZE: usize = 48;
+const URC_PREFIX: &str = "+EVT: ";
+
+static mut QUEUE: Option<Queue> = None;
+
+static mut SHARED: EventSubscription = EventSubscription;
+
+/// Subscribed event classes of each channel, see `EventClass`
+static MASKS: [AtomicU8; EventSubscription::CHANNELS] = [const { AtomicU8::new(0) }; EventSubscription::CHANNELS];
+
+/// Classes of events, a channel receives the classes set in its subscription mask
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(in crate::apps) enum EventClass {
+    Zone = 0x01,
+    Schedule = 0x02,
+    Wifi = 0x04,
+    Error = 0x08,
+}
+
+impl EventClass {
+    const ALL: u8 = 0x0F;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(in crate::apps) enum Event {
+
+    /// first opening of a valve, target in minutes or in liters
+    ZoneStart { zone: u8, target: u16, liters: bool },
+
+    /// valve closed at the end of its run
+    ZoneStop { zone: u8 },
+
+    ScheduleStart { schedule: u8 },
+
+    ScheduleEnd { schedule: u8 },
+
+    Wifi(WifiStatus),
+
+    /// error flag raised or cleared
+    Error { flag: ErrorFlag, set: bool },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        use Event::*;
+        match self {
+            ZoneStart { zone, target, liters: true } => write!(f, "ZONE,{zone},START,{target}L"),
+            ZoneStart { zone, target, liters: false } => write!(f, "ZONE,{zone},START,{target}"),
+            ZoneStop { zone } => write!(f, "ZONE,{zone},STOP"),
+            ScheduleStart { schedule } => write!(f, "SCH,{schedule},START"),
+            ScheduleEnd { schedule } => write!(f, "SCH,{schedule},END"),
+            Wifi(status) => write!(f, "WIFI,{status}"),
+            Error { flag, set: true } => write!(f, "ERR,{flag}"),
+            Error { flag, set: false } => write!(f, "ERR,{flag},CLEAR"),
+        }
+    }
+}
+
+impl Event {
+    /// Bytes of an event in the queue: tag and up to four bytes of payload
+    const SIZE: usize = 5;
+
+    const ZONE_START: u8 = 0x01;
+    const ZONE_STOP: u8 = 0x02;
+    const SCHEDULE_START: u8 = 0x03;
+    const SCHEDULE_END: u8 = 0x04;
+    const WIFI: u8 = 0x05;
+    const ERROR: u8 = 0x06;
+
+    fn class(&self) -> EventClass {
+        use Event::*;
+        match self {
+            ZoneStart { .. } | ZoneStop { .. } => EventClass::Zone,
+            ScheduleStart { .. } | ScheduleEnd { .. } => EventClass::Schedule,
+            Wifi(_) => EventClass::Wifi,
+            Error { .. } => EventClass::Error,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; Event::SIZE] {
+        use Event::*;
+        match self {
+            ZoneStart { zone, target, liters } => {
+                let [high, low] = target.to_be_bytes();
+                [Event::ZONE_START, zone, high, low, liters as u8]
+            }
+            ZoneStop { zone } => [Event::ZONE_STOP, zone, 0, 0, 0],
+            ScheduleStart { schedule } => [Event::SCHEDULE_START, schedule, 0, 0, 0],
+            ScheduleEnd { schedule } => [Event::SCHEDULE_END, schedule, 0, 0, 0],
+            Wifi(status) => [Event::WIFI, status as u8, 0, 0, 0],
+            Error { flag, set } => [Event::ERROR, <ErrorFlag as Into<u32>>::into(flag) as u8, set as u8, 0, 0],
+        }
+    }
+
+    fn from_bytes(bytes: &[u8; Event::SIZE]) -> Option<Self> {
+        use Event::*;
+        match bytes[0] {
+            Event::ZONE_START => Some(ZoneStart { zone: bytes[1], target: u16::from_be_bytes([bytes[2], bytes[3]]), liters: bytes[4] != 0 }),
+            Event::ZONE_STOP => Some(ZoneStop { zone: bytes[1] }),
+            Event::SCHEDULE_START => Some(ScheduleStart { schedule: bytes[1] }),
+            Event::SCHEDULE_END => Some(ScheduleEnd { schedule: bytes[1] }),
+            Event::WIFI => Some(Wifi(WifiStatus::from(bytes[1]))),
+            Event::ERROR => Some(Error { flag: ErrorFlag::from(bytes[1] as u32), set: bytes[2] != 0 }),
+            _ => None,
+        }
+    }
+}
+
+pub(in crate::apps) struct Events(Thread);
+
+impl Initializable for Events {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init app events");
+
+        unsafe {
+            QUEUE = Some(Queue::new(QUEUE_SIZE, Event::SIZE as _).map_err(|_| Error::OutOfMemory)?);
+        }
+
+        self.0 = self.0.spawn_simple(move || {
+
+            let timeout = Duration::from_millis(ERROR_POLL_MS).to_ticks();
+            let mut errors = 0;
+
+            loop {
+                let mut bytes = [0u8; Event::SIZE];
+                if unsafe { (*&raw const QUEUE).as_ref() }.is_some_and(|queue| queue.fetch(&mut bytes, timeout).is_ok()) {
+                    if let Some(event) = Event::from_bytes(&bytes) {
+                        Self::emit(event);
+                    }
+                }
+
+                // the error flags are raised from many places, their changes are detected here
+                let current = ErrorSignal::get();
+                let changed = current ^ errors;
+                for bit in 0..u32::BITS {
+                    let mask = 1 << bit;
+                    let flag = ErrorFlag::from(mask);
+                    if changed & mask != 0 && flag != ErrorFlag::None {
+                        Self::emit(Event::Error { flag, set: current & mask != 0 });
+                    }
+                }
+                errors = current;
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Events {
+    pub(in crate::apps) fn new() -> Self {
+        Self(Thread::new_with_to_priority(THREAD_NAME, STACK_SIZE, ThreadPriority::Normal))
+    }
+
+    /// Queue the event without blocking, it is dropped if the queue is full
+    pub(in crate::apps) fn post(event: Event) {
+        let Some(queue) = (unsafe { (*&raw const QUEUE).as_ref() }) else {
+            return;
+        };
+
+        if queue.post_with_to_tick(&event.to_bytes(), Duration::ZERO).is_err() {
+            log_warning!(APP_TAG, "Event dropped, queue full: {event}");
+        }
+    }
+
+    fn emit(event: Event) {
+        let class = event.class() as u8;
+
+        let mut urc = Bytes::<URC_SIZE>::new();
+        urc.format(format_args!("{URC_PREFIX}{event}"));
+
+        for source in [Source::Uart, Source::Mqtt, Source::Telnet] {
+            if EventSubscription::channel(source).is_some_and(|channel| MASKS[channel].load(Ordering::Relaxed) & class != 0) {
+                Parser::transmit_urc(source, urc.as_str().as_bytes());
+            }
+        }
+    }
+}
+
+/// `AT+URC` subscription of the channel the command is received from
+pub(in crate::apps) struct EventSubscription;
+
+impl AtContext<{Parser::CMD_SIZE}> for EventSubscription {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let channel = Parser::get_source().and_then(EventSubscription::channel).ok_or((at_response, AtError::InvalidArgs))?;
+
+        Ok(at_cmd_response!(at_response; MASKS[channel].load(Ordering::Relaxed)))
+    }
+
+    #[inline]
+    /// mask = 1 zone, 2 schedule, 4 wifi, 8 error
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<mask 0-15>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let channel = Parser::get_source().and_then(EventSubscription::channel).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let mask: u8 = args.get(0).ok_or((at_response, AtError::InvalidArgs))?
+            .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+        if mask & !EventClass::ALL != 0 {
+            return Err((at_response, AtError::InvalidArgs));
+        }
+
+        MASKS[channel].store(mask, Ordering::Relaxed);
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl EventSubscription {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+URC";
+    pub(in crate::apps) const AT_RESP: &'static str = "+URC: ";
+
+    const CHANNELS: usize = 3;
+
+    #[inline]
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    /// Mask slot of the source, None for the HTTP requests that have no stream to write to
+    #[inline]
+    fn channel(source: Source) -> Option<usize> {
+        match source {
+            Source::Uart => Some(0),
+            Source::Mqtt => Some(1),
+            Source::Telnet => Some(2),
+            Source::Http => None,
+        }
+    }
+}
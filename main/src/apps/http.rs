@@ -0,0 +1,271 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! HTTP/1.1 server of the REST API and of the web dashboard, see `api` for the
+///! endpoints and `assets` for the files. One request for each connection, the
+///! response is sent with `Connection: close`. The writes run
+///! as AT command lines on the `Http` source of the parser, whose response line is
+///! captured by the transmit of this channel.
+
+use core::fmt::Arguments;
+use core::str::from_utf8;
+use core::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use core::time::Duration;
+
+use alloc::sync::Arc;
+use osal_rs::{log_error, log_info, log_warning};
+use osal_rs::os::{Mutex, MutexFn, System, SystemFn, Thread, ThreadFn, ToTick};
+use osal_rs::os::types::StackType;
+use osal_rs::utils::{Bytes, Error, Result};
+
+use crate::apps::config::Config;
+use crate::drivers::date_time::DateTime;
+use crate::drivers::network::Network;
+use crate::drivers::platform::ThreadPriority;
+use crate::traits::rtc::RTC;
+use crate::traits::rx_tx::{OnReceive, SetOnReceive, SetTransmit, Source};
+use crate::traits::state::Initializable;
+
+mod api;
+mod assets;
+mod request;
+mod response;
+
+use request::{REQUEST_SIZE, Request};
+use response::{Response, StatusCode};
+
+const APP_TAG: &str = "AppHttp";
+const THREAD_NAME: &str = "app_http_trd";
+const STACK_SIZE: StackType = 2_048;
+const TICK_INTERVAL_MS: u64 = 20;
+const RETRY_SECONDS: u64 = 5;
+const REQUEST_TIMEOUT_SECONDS: u64 = 5;
+const WRITE_RETRIES: u8 = 50;
+const WRITE_RETRY_MS: u64 = 10;
+const WRITE_CHUNK_SIZE: usize = 512;
+
+/// Max size of a command line, the line terminator included
+const LINE_SIZE: usize = 160;
+
+/// Max size of a captured response line
+const REPLY_SIZE: usize = 128;
+const REPLY_TIMEOUT_MS: u64 = 2_000;
+const NEW_LINE: &[u8] = b"\r\n";
+
+static RESTART: AtomicBool = AtomicBool::new(false);
+static PORT: AtomicU16 = AtomicU16::new(0);
+
+static mut ON_RECEIVE: Option<&'static dyn OnReceive> = None;
+static mut CLOCK: Option<Arc<Mutex<dyn RTC + 'static>>> = None;
+
+/// Request being received, too big for the thread stack
+static mut REQUEST: [u8; REQUEST_SIZE] = [0; REQUEST_SIZE];
+
+/// Response line of the last command, written by the parser thread until `REPLY_DONE`
+static mut REPLY: Bytes<REPLY_SIZE> = Bytes::new();
+static REPLY_DONE: AtomicBool = AtomicBool::new(false);
+
+pub(in crate::apps) struct Http(Thread);
+
+impl Initializable for Http {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init app http");
+
+        self.0 = self.0.spawn_simple(move || {
+            loop {
+                RESTART.store(false, Ordering::SeqCst);
+
+                let config = Config::shared().get_http_config();
+                if !config.is_enabled() || !Network::is_link_up() || !Network::dhcp_supplied_address() {
+                    System::delay_with_to_tick(Duration::from_secs(1));
+                    continue;
+                }
+
+                if let Err(e) = Network::tcp_listen(config.get_port()) {
+                    log_error!(APP_TAG, "Listen on port {} failed: {e}", config.get_port());
+                    System::delay_with_to_tick(Duration::from_secs(RETRY_SECONDS));
+                    continue;
+                }
+                log_info!(APP_TAG, "Listening on port {}", config.get_port());
+                PORT.store(config.get_port(), Ordering::SeqCst);
+
+                Self::serve();
+
+                Network::tcp_unlisten(PORT.swap(0, Ordering::SeqCst));
+                log_info!(APP_TAG, "Stopped");
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+impl SetOnReceive<'static> for Http {
+    fn set_on_receive(&mut self, on_receive: &'static dyn OnReceive) {
+        unsafe {
+            ON_RECEIVE = Some(on_receive);
+        }
+    }
+}
+
+impl SetTransmit for Http {
+    /// Captures the response line, the parser writes the line terminator on its own
+    fn transmit(&self, data: &[u8]) -> usize {
+        if data == NEW_LINE {
+            REPLY_DONE.store(true, Ordering::SeqCst);
+            return data.len();
+        }
+
+        let reply = unsafe { &mut *&raw mut REPLY };
+        if reply.len() + data.len() <= REPLY_SIZE {
+            reply.append_str(from_utf8(data).unwrap_or_default());
+        }
+
+        data.len()
+    }
+}
+
+impl Http {
+    pub(in crate::apps) fn new() -> Self {
+        Self(Thread::new_with_to_priority(THREAD_NAME, STACK_SIZE, ThreadPriority::Normal))
+    }
+
+    pub(in crate::apps) fn set_rtc(&mut self, rtc: Arc<Mutex<dyn RTC + 'static>>) {
+        unsafe {
+            CLOCK = Some(rtc);
+        }
+    }
+
+    /// Drops the pending request and listens again with the updated configuration
+    #[inline]
+    pub(in crate::apps) fn restart() {
+        RESTART.store(true, Ordering::SeqCst);
+    }
+
+    /// Serves the requests until a restart is requested or the link goes down
+    fn serve() {
+        let port = PORT.load(Ordering::SeqCst);
+
+        while !RESTART.load(Ordering::SeqCst) && Network::is_link_up() {
+            if Network::tcp_accept(port) {
+                if let Some(response) = Self::receive(port) {
+                    Self::respond(port, &response);
+                }
+                Network::tcp_server_close(port);
+            }
+
+            System::delay_with_to_tick(Duration::from_millis(TICK_INTERVAL_MS));
+        }
+    }
+
+    /// Reads the request and runs it, None when the client went away
+    fn receive(port: u16) -> Option<Response> {
+        let buffer = unsafe { &mut *&raw mut REQUEST };
+        let mut len = 0;
+
+        let timeout = Duration::from_secs(REQUEST_TIMEOUT_SECONDS).to_ticks();
+        let start = System::get_tick_count();
+
+        loop {
+            len += Network::tcp_server_read(port, &mut buffer[len..]).ok()?;
+
+            match Request::parse(&buffer[..len]) {
+                Ok(Some(request)) => return Some(Self::route(&request)),
+                Ok(None) if System::get_tick_count().wrapping_sub(start) >= timeout => {
+                    log_warning!(APP_TAG, "Request timeout");
+                    return Some(Response::error(StatusCode::RequestTimeout, StatusCode::RequestTimeout.reason()));
+                }
+                Ok(None) => System::delay_with_to_tick(Duration::from_millis(TICK_INTERVAL_MS)),
+                Err(code) => return Some(Response::error(code, code.reason())),
+            }
+        }
+    }
+
+    /// The API lives under `/api`, every other path is a file of the dashboard
+    fn route(request: &Request) -> Response {
+        if request.path == "/api" || request.path.starts_with("/api/") {
+            api::handle(request)
+        } else {
+            assets::handle(request)
+        }
+    }
+
+    fn respond(port: u16, response: &Response) {
+        let head = response.head();
+        if Self::write(port, head.as_str().as_bytes()) {
+            for chunk in response.content().chunks(WRITE_CHUNK_SIZE) {
+                if !Self::write(port, chunk) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Waits a little for room in the send buffer, false when the client is gone or stalled
+    fn write(port: u16, data: &[u8]) -> bool {
+        for _ in 0..WRITE_RETRIES {
+            match Network::tcp_server_write(port, data) {
+                Ok(_) => return true,
+                Err(_) if Network::tcp_server_is_connected(port) => System::delay_with_to_tick(Duration::from_millis(WRITE_RETRY_MS)),
+                Err(_) => return false,
+            }
+        }
+
+        log_warning!(APP_TAG, "Client too slow, response dropped");
+        false
+    }
+
+    /// Time of the run engine, None until the RTC is wired or when it can not be read
+    fn now() -> Option<DateTime> {
+        let rtc = unsafe { (*&raw const CLOCK).as_ref() }?;
+        let timestamp = rtc.lock().ok()?.get_timestamp().ok()?;
+        DateTime::from_timestamp(timestamp).ok()
+    }
+
+    /// Runs the command line on the parser and returns its response line
+    fn execute(line: Arguments) -> Result<Bytes<REPLY_SIZE>> {
+        let on_receive = unsafe { *&raw const ON_RECEIVE }.ok_or(Error::NullPtr)?;
+
+        let mut cmd = Bytes::<LINE_SIZE>::new();
+        cmd.format(line);
+        cmd.format(format_args!("\r\n"));
+
+        unsafe {
+            REPLY = Bytes::new();
+        }
+        REPLY_DONE.store(false, Ordering::SeqCst);
+
+        on_receive.on_receive(Source::Http, cmd.as_str().as_bytes())?;
+
+        let timeout = Duration::from_millis(REPLY_TIMEOUT_MS).to_ticks();
+        let start = System::get_tick_count();
+        while !REPLY_DONE.load(Ordering::SeqCst) {
+            if System::get_tick_count().wrapping_sub(start) >= timeout {
+                log_warning!(APP_TAG, "No response from the parser");
+                return Err(Error::Unhandled("Parser timeout"));
+            }
+            System::delay_with_to_tick(Duration::from_millis(WRITE_RETRY_MS));
+        }
+
+        Ok(unsafe { *&raw const REPLY })
+    }
+}
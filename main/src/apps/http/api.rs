@@ -0,0 +1,545 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! REST endpoints under `/api`. The reads serialize the same objects behind the AT
+///! commands, the writes are run as AT command lines through the parser so the
+///! validation, the session and the persistence behave exactly like on the other channels.
+
+use core::fmt::Arguments;
+use core::mem::size_of;
+use core::time::Duration;
+
+use cjson_binding::from_json;
+use osal_rs::os::{System as OsSystem, SystemFn, ToTick};
+use osal_rs::os::types::{EventBits, TickType};
+use osal_rs::utils::{Bytes, bytes_to_hex};
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::config::{Config, DaylightSavingTime, NtpConfig, WifiConfig};
+use crate::apps::parser::Parser;
+use crate::apps::session::Session;
+use crate::apps::signals::error::ErrorSignal;
+use crate::apps::signals::status::StatusSignal;
+use crate::apps::sprinkler::history::{HistoryController, RunRecord};
+use crate::apps::sprinkler::manual::ManualController;
+use crate::apps::sprinkler::schedule::{Schedule, ScheduleController};
+use crate::apps::sprinkler::zone::{Zone, ZoneController, ZoneRelay};
+use crate::apps::utils::same_bytes;
+use crate::drivers::error::HardwareErrorSignal;
+use crate::drivers::network::Network;
+use crate::drivers::platform::Hardware;
+use crate::drivers::wifi::Auth;
+use crate::traits::hardware::HardwareFn;
+use crate::traits::network::IPV6_ADDR_LEN;
+use crate::traits::rx_tx::Source;
+use crate::traits::signal::Signal;
+
+use super::Http;
+use super::request::{Method, Request};
+use super::response::{Response, StatusCode};
+
+const TOKEN_BYTES: usize = 16;
+const TOKEN_SIZE: usize = TOKEN_BYTES * 2;
+
+const KO_RESPONSE: &str = "KO";
+
+/// Exec response of AT+ZN and AT+SCH without staged changes, an unchanged PUT is not an error
+const NO_MODIFY_RESPONSE: &str = "No modify applied";
+
+/// Seconds a challenge waits for its answer before another client may ask for one
+const CHALLENGE_SECONDS: u64 = 30;
+
+/// Bearer token of the session opened over HTTP, a new one at each login
+static mut TOKEN: Option<Bytes<TOKEN_SIZE>> = None;
+
+/// Tick of the challenge waiting for its answer. The HTTP clients share one session slot,
+/// a new challenge would replace the nonce of the login in progress
+static mut CHALLENGED: Option<TickType> = None;
+
+type ApiResult<T = Response> = core::result::Result<T, Response>;
+
+/// Email of the login about to start
+#[derive(Default, Deserialize)]
+struct ChallengeRequest {
+    email: Bytes<32>,
+}
+
+/// Fields of `AT+SESS=c`, what the client derives the answer with
+#[derive(Serialize)]
+struct Challenge {
+    nonce: Bytes<32>,
+    salt: Bytes<32>,
+    iterations: u32,
+}
+
+/// Answer to the challenge, the password never leaves the client.
+/// The code of the second factor is needed by the users who enabled it
+#[derive(Default, Deserialize)]
+struct Login {
+    email: Bytes<32>,
+    response: Bytes<64>,
+    code: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Token {
+    token: Bytes<TOKEN_SIZE>,
+}
+
+/// Same flags of `AT+SYS?` and the address of the board
+#[derive(Serialize)]
+struct System {
+    hardware_error: EventBits,
+    error: EventBits,
+    status: EventBits,
+    ip: Bytes<IPV6_ADDR_LEN>,
+}
+
+/// Fields of `AT+CNF`
+#[derive(Default, Serialize, Deserialize)]
+struct Settings {
+    serial: Bytes<16>,
+    timezone: i16,
+}
+
+/// Fields of `AT+WIFI?`, the password is never returned
+#[derive(Serialize)]
+struct Wifi {
+    ssid: Bytes<32>,
+    auth: Auth,
+    enabled: bool,
+}
+
+/// Fields of `AT+WIFI=`
+#[derive(Default, Deserialize)]
+struct WifiUpdate {
+    ssid: Bytes<32>,
+    password: Bytes<32>,
+    auth: u8,
+    enabled: bool,
+}
+
+/// Next start of each schedule, 0 when it has none
+#[derive(Serialize)]
+struct NextStarts {
+    starts: [i64; ScheduleController::SIZE],
+}
+
+/// Run records newest first, the unused ones have timestamp 0
+#[derive(Serialize)]
+struct History {
+    records: [RunRecord; HistoryController::SIZE],
+}
+
+/// Fields of `AT+RUN=`
+#[derive(Default, Deserialize)]
+struct Run {
+    zone: u8,
+    minutes: Option<u8>,
+}
+
+pub(super) fn handle(request: &Request) -> Response {
+    let mut segments = request.path.trim_matches('/').split('/');
+    if segments.next() != Some("api") {
+        return not_found();
+    }
+    let resource = segments.next().unwrap_or_default();
+    let id = segments.next();
+    if segments.next().is_some() {
+        return not_found();
+    }
+
+    let result = match (resource, id, request.method) {
+        ("challenge", None, Method::Post) => challenge(request.body),
+        ("session", None, Method::Post) => login(request.body),
+        ("session", None, Method::Delete) => authorized(request).and_then(|_| logout()),
+
+        ("system", None, Method::Get) => Ok(system()),
+
+        ("config", None, Method::Get) => Ok(Response::json(&Settings {
+            serial: Config::shared().get_serial(),
+            timezone: Config::shared().get_timezone(),
+        })),
+        ("config", None, Method::Put) => authorized(request).and_then(|_| put_config(request.body)),
+
+        ("wifi", None, Method::Get) => Ok(get_wifi()),
+        ("wifi", None, Method::Put) => authorized(request).and_then(|_| put_wifi(request.body)),
+
+        ("ntp", None, Method::Get) => Ok(Response::json(&Config::shared().get_ntp_config())),
+        ("ntp", None, Method::Put) => authorized(request).and_then(|_| put_ntp(request.body)),
+
+        ("dst", None, Method::Get) => Ok(Response::json(&*Config::shared().get_daylight_saving_time())),
+        ("dst", None, Method::Put) => authorized(request).and_then(|_| put_dst(request.body)),
+
+        ("zones", None, Method::Get) => authorized(request).map(|_| Response::json(&*ZoneController::shared())),
+        ("zones", Some(id), Method::Get) => authorized(request).and_then(|_| get_zone(id)),
+        ("zones", Some(id), Method::Put) => authorized(request).and_then(|_| put_zone(id, request.body)),
+
+        ("schedules", None, Method::Get) => authorized(request).map(|_| Response::json(&ScheduleController::shared().snapshot())),
+        ("schedules", Some(id), Method::Get) => authorized(request).and_then(|_| get_schedule(id)),
+        ("schedules", Some(id), Method::Put) => authorized(request).and_then(|_| put_schedule(id, request.body)),
+
+        ("next", None, Method::Get) => authorized(request).and_then(|_| next()),
+
+        ("history", None, Method::Get) => authorized(request).map(|_| history()),
+
+        ("run", None, Method::Post) => authorized(request).and_then(|_| run(request.body)),
+        ("run", None, Method::Delete) => authorized(request).and_then(|_| stop()),
+
+        ("challenge" | "session" | "system" | "config" | "wifi" | "ntp" | "dst" | "next" | "history" | "run", None, _) | ("zones" | "schedules", _, _) =>
+            Err(Response::error(StatusCode::MethodNotAllowed, "Method not allowed")),
+        _ => Err(not_found()),
+    };
+
+    result.unwrap_or_else(|response| response)
+}
+
+/// The bearer token must be the one of the session opened over HTTP
+fn authorized(request: &Request) -> ApiResult<()> {
+    let logged = Session::is_logged(&Source::Http);
+
+    match (unsafe { *&raw const TOKEN }, request.token) {
+        (Some(token), Some(bearer)) if logged && same_bytes(token.as_str().as_bytes(), bearer.as_bytes()) => {
+            Session::reset_timer(&Source::Http);
+            Ok(())
+        }
+        _ => Err(Response::error(StatusCode::Unauthorized, Parser::NOT_LOGGED_RESPONSE)),
+    }
+}
+
+fn challenge(body: &str) -> ApiResult {
+    let ChallengeRequest { email } = parse(body)?;
+
+    let now = OsSystem::get_tick_count();
+    let pending = unsafe { *&raw const CHALLENGED }
+        .is_some_and(|tick| now.wrapping_sub(tick) < Duration::from_secs(CHALLENGE_SECONDS).to_ticks());
+    if pending {
+        return Err(Response::error(StatusCode::Conflict, "Login in progress"));
+    }
+
+    let reply = Http::execute(format_args!("{}=c,\"{}\"", Session::AT_CMD, text(email.as_str())?)).map_err(|_| timeout())?;
+    let fields = reply.as_str().strip_prefix(Session::AT_RESP).map(|fields| {
+        let mut fields = fields.split(',');
+        (fields.next(), fields.next(), fields.next().and_then(|iterations| iterations.parse().ok()))
+    });
+    match fields {
+        Some((Some(nonce), Some(salt), Some(iterations))) => {
+            unsafe {
+                CHALLENGED = Some(now);
+            }
+            Ok(Response::json(&Challenge {
+                nonce: Bytes::from_str(nonce),
+                salt: Bytes::from_str(salt),
+                iterations,
+            }))
+        }
+        _ => check(Session::AT_RESP, reply.as_str()).map(|_| Response::error(StatusCode::InternalServerError, reply.as_str())),
+    }
+}
+
+fn login(body: &str) -> ApiResult {
+    let Login { email, response, code } = parse(body)?;
+
+    match code {
+        Some(code) => execute(Session::AT_RESP, format_args!("{}=r,\"{}\",{:06}", Session::AT_CMD, text(response.as_str())?, code))?,
+        None => execute(Session::AT_RESP, format_args!("{}=r,\"{}\"", Session::AT_CMD, text(response.as_str())?))?,
+    };
+
+    // On success the response is the email, a failed login keeps the session already open
+    let reply = Http::execute(format_args!("{}", Session::AT_CMD)).map_err(|_| timeout())?;
+    if let Some(locked) = reply.as_str().strip_prefix(Session::AT_RESP).filter(|error| error.starts_with(Session::LOCKED_RESPONSE)) {
+        return Err(Response::error(StatusCode::TooManyRequests, locked));
+    }
+    if reply.as_str().strip_prefix(Session::AT_RESP) == Some(Session::CODE_RESPONSE) {
+        return Err(Response::error(StatusCode::Unauthorized, Session::CODE_RESPONSE));
+    }
+    if reply.as_str().strip_prefix(Session::AT_RESP) != Some(email.as_str()) || !Session::is_logged(&Source::Http) {
+        return Err(Response::error(StatusCode::Unauthorized, "Invalid credentials"));
+    }
+
+    // Only a valid answer ends the challenge before it times out, a wrong or malformed one
+    // of another client leaves it pending
+    unsafe {
+        CHALLENGED = None;
+    }
+
+    let mut random = [0u8; TOKEN_BYTES];
+    for chunk in random.chunks_mut(size_of::<u64>()) {
+        chunk.copy_from_slice(&Hardware::get_random().to_le_bytes());
+    }
+    let token = Bytes::from_str(bytes_to_hex(&random).as_str());
+
+    unsafe {
+        TOKEN = Some(token);
+    }
+
+    Ok(Response::json(&Token { token }))
+}
+
+fn logout() -> ApiResult {
+    execute(Session::AT_RESP, format_args!("{}=o", Session::AT_CMD))?;
+    execute(Session::AT_RESP, format_args!("{}", Session::AT_CMD))?;
+
+    unsafe {
+        TOKEN = None;
+    }
+
+    Ok(Response::no_content())
+}
+
+fn system() -> Response {
+    Response::json(&System {
+        hardware_error: HardwareErrorSignal::get(),
+        error: ErrorSignal::get(),
+        status: StatusSignal::get(),
+        ip: Network::dhcp_get_ip_address(),
+    })
+}
+
+fn put_config(body: &str) -> ApiResult {
+    let Settings { serial, timezone } = parse(body)?;
+
+    execute(Config::AT_RESP, format_args!("{}=sn,\"{}\"", Config::AT_CMD, text(serial.as_str())?))?;
+    execute(Config::AT_RESP, format_args!("{}=tz,{}", Config::AT_CMD, timezone))?;
+
+    save()
+}
+
+fn get_wifi() -> Response {
+    let wifi = Config::shared().get_wifi_config();
+    Response::json(&Wifi {
+        ssid: wifi.get_ssid(),
+        auth: wifi.get_auth(),
+        enabled: wifi.is_enabled(),
+    })
+}
+
+fn put_wifi(body: &str) -> ApiResult {
+    let WifiUpdate { ssid, password, auth, enabled } = parse(body)?;
+
+    execute(WifiConfig::AT_RESP, format_args!("{}=\"{}\",\"{}\",{},{}",
+        WifiConfig::AT_CMD, text(ssid.as_str())?, text(password.as_str())?, auth, enabled as u8))?;
+
+    save()
+}
+
+fn put_ntp(body: &str) -> ApiResult {
+    let ntp: NtpConfig = parse(body)?;
+
+    execute(NtpConfig::AT_RESP, format_args!("{}=\"{}\",{},{}",
+        NtpConfig::AT_CMD, text(ntp.get_server().as_str())?, ntp.get_port(), ntp.get_msg_len()))?;
+
+    save()
+}
+
+fn put_dst(body: &str) -> ApiResult {
+    let dst: DaylightSavingTime = parse(body)?;
+
+    for (field, value) in [
+        ("smo", dst.start_month),
+        ("sdy", dst.start_day),
+        ("shr", dst.start_hour),
+        ("emo", dst.end_month),
+        ("edy", dst.end_day),
+        ("ehr", dst.end_hour),
+        ("en", dst.enabled as u8),
+    ] {
+        execute(DaylightSavingTime::AT_RESP, format_args!("{}={},{}", DaylightSavingTime::AT_CMD, field, value))?;
+    }
+
+    save()
+}
+
+/// Persists the configuration like `AT+CNF=sv`
+fn save() -> ApiResult {
+    execute(Config::AT_RESP, format_args!("{}=sv", Config::AT_CMD))?;
+    Ok(Response::no_content())
+}
+
+fn get_zone(id: &str) -> ApiResult {
+    let zone_relay = ZoneRelay::from(zone_index(id)?);
+    ZoneController::shared().get(zone_relay).map(Response::json).ok_or_else(not_found)
+}
+
+/// Body as returned by the GET, the relay and the status are ignored
+fn put_zone(id: &str, body: &str) -> ApiResult {
+    let idx = zone_index(id)?;
+    let zone: Zone = parse(body)?;
+    let description = text(zone.description.as_str())?;
+
+    zone_field(idx, format_args!("wt,{}", zone.weight))?;
+    zone_field(idx, format_args!("ds,\"{}\"", description))?;
+    zone_field(idx, format_args!("fr,{}", zone.flow_rate))?;
+    zone_field(idx, format_args!("mx,{}", zone.max_minutes))?;
+    match zone.profile {
+        Some(profile) => zone_field(idx, format_args!("pf,{profile}"))?,
+        None => zone_field(idx, format_args!("pf"))?,
+    }
+
+    execute(ZoneController::AT_RESP, format_args!("{}={},sv", ZoneController::AT_CMD, idx))?;
+    Ok(Response::no_content())
+}
+
+/// Computed from the stored schedules as the run engine does, the starts already handled are not known
+fn next() -> ApiResult {
+    let now = Http::now().ok_or_else(|| Response::error(StatusCode::InternalServerError, "Clock not available"))?.to_timestamp();
+
+    let mut starts = [0; ScheduleController::SIZE];
+    for (start, schedule) in starts.iter_mut().zip(&mut ScheduleController::shared().snapshot()) {
+        *start = schedule.next_start(now).unwrap_or(0);
+    }
+
+    Ok(Response::json(&NextStarts { starts }))
+}
+
+fn history() -> Response {
+    let history = HistoryController::shared();
+
+    let mut records = [RunRecord::new(); HistoryController::SIZE];
+    for (idx, record) in records.iter_mut().enumerate() {
+        if let Some(stored) = history.get(idx) {
+            *record = *stored;
+        }
+    }
+
+    Response::json(&History { records })
+}
+
+/// Without minutes the zone runs for the time of its profile, 0 minutes closes it
+fn run(body: &str) -> ApiResult {
+    let Run { zone, minutes } = parse(body)?;
+    match minutes {
+        Some(minutes) => execute(ManualController::AT_RESP, format_args!("{}={},{}", ManualController::AT_CMD, zone, minutes))?,
+        None => execute(ManualController::AT_RESP, format_args!("{}={}", ManualController::AT_CMD, zone))?,
+    }
+    Ok(Response::no_content())
+}
+
+/// Closes every zone and stops the running schedule
+fn stop() -> ApiResult {
+    execute(ManualController::AT_RESP, format_args!("{}", ManualController::AT_CMD))?;
+    Ok(Response::no_content())
+}
+
+/// `AT+ZN` stages each field from the live zone, so every field is applied on its own
+fn zone_field(idx: u8, field: Arguments) -> ApiResult<()> {
+    execute(ZoneController::AT_RESP, format_args!("{}={},{}", ZoneController::AT_CMD, idx, field))?;
+    apply(ZoneController::AT_RESP, ZoneController::AT_CMD)
+}
+
+fn get_schedule(id: &str) -> ApiResult {
+    let idx = schedule_index(id)?;
+    ScheduleController::shared().snapshot().get(idx).map(Response::json).ok_or_else(not_found)
+}
+
+/// Body as returned by the GET, the schedule is cleared first so the missing zones are removed
+fn put_schedule(id: &str, body: &str) -> ApiResult {
+    let idx = schedule_index(id)?;
+    let schedule: Schedule = parse(body)?;
+    let description = text(schedule.description.as_str())?;
+
+    schedule_field(idx, format_args!("cl"))?;
+    schedule_field(idx, format_args!("mi,{}", schedule.minute))?;
+    schedule_field(idx, format_args!("hr,{}", schedule.hour))?;
+    schedule_field(idx, format_args!("dy,{}", schedule.days))?;
+    schedule_field(idx, format_args!("mo,{}", schedule.month))?;
+    schedule_field(idx, format_args!("ds,\"{}\"", description))?;
+    for zone in schedule.zones.iter().flatten() {
+        if zone.liters > 0 {
+            schedule_field(idx, format_args!("zv,{},{}", zone.target, zone.liters))?;
+        } else {
+            schedule_field(idx, format_args!("zn,{},{}", zone.target, zone.minutes))?;
+        }
+    }
+    schedule_field(idx, format_args!("st,{}", u8::from(schedule.status)))?;
+
+    apply(ScheduleController::AT_RESP, ScheduleController::AT_CMD)?;
+    execute(ScheduleController::AT_RESP, format_args!("{}={},sv", ScheduleController::AT_CMD, idx))?;
+    Ok(Response::no_content())
+}
+
+#[inline]
+fn schedule_field(idx: usize, field: Arguments) -> ApiResult<()> {
+    execute(ScheduleController::AT_RESP, format_args!("{}={},{}", ScheduleController::AT_CMD, idx, field))
+}
+
+fn zone_index(id: &str) -> ApiResult<u8> {
+    id.parse::<u8>().ok()
+        .filter(|&idx| (idx as usize) < ZoneController::SIZE)
+        .ok_or_else(not_found)
+}
+
+fn schedule_index(id: &str) -> ApiResult<usize> {
+    id.parse::<usize>().ok()
+        .filter(|&idx| idx < ScheduleController::SIZE)
+        .ok_or_else(not_found)
+}
+
+/// Runs the command line, Ok when the parser answers OK
+fn execute(at_resp: &str, line: Arguments) -> ApiResult<()> {
+    let reply = Http::execute(line).map_err(|_| timeout())?;
+    check(at_resp, reply.as_str())
+}
+
+/// Exec of the staged changes
+fn apply(at_resp: &str, at_cmd: &str) -> ApiResult<()> {
+    let reply = Http::execute(format_args!("{}", at_cmd)).map_err(|_| timeout())?;
+    if reply.as_str().strip_prefix(at_resp) == Some(NO_MODIFY_RESPONSE) {
+        return Ok(());
+    }
+    check(at_resp, reply.as_str())
+}
+
+fn check(at_resp: &str, reply: &str) -> ApiResult<()> {
+    match reply.strip_prefix(at_resp) {
+        Some("OK") => Ok(()),
+        Some(KO_RESPONSE) => Err(Response::error(StatusCode::BadRequest, "Invalid arguments")),
+        Some(Parser::NOT_LOGGED_RESPONSE) => Err(Response::error(StatusCode::Unauthorized, Parser::NOT_LOGGED_RESPONSE)),
+        Some(Parser::DENIED_RESPONSE) => Err(Response::error(StatusCode::Forbidden, Parser::DENIED_RESPONSE)),
+        Some(Parser::PASSWORD_CHANGE_RESPONSE) => Err(Response::error(StatusCode::Forbidden, Parser::PASSWORD_CHANGE_RESPONSE)),
+        Some(error) => Err(Response::error(StatusCode::BadRequest, error)),
+        None => Err(Response::error(StatusCode::InternalServerError, reply)),
+    }
+}
+
+fn parse<T: Deserialize>(body: &str) -> ApiResult<T> {
+    from_json::<T>(body).map_err(|_| Response::error(StatusCode::BadRequest, "Invalid JSON"))
+}
+
+/// The strings are sent quoted, the characters that would break the command line are refused
+fn text(value: &str) -> ApiResult<&str> {
+    if value.contains(['"', '\r', '\n']) {
+        Err(Response::error(StatusCode::BadRequest, "Invalid character"))
+    } else {
+        Ok(value)
+    }
+}
+
+#[inline]
+fn not_found() -> Response {
+    Response::error(StatusCode::NotFound, "Not found")
+}
+
+#[inline]
+fn timeout() -> Response {
+    Response::error(StatusCode::GatewayTimeout, "No response from the parser")
+}
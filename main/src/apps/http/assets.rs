@@ -0,0 +1,61 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! Files of the web dashboard, gzip compressed into the firmware by `build.rs` from
+///! `main/web`. They are sent as they are stored, with an ETag so the browser revalidates
+///! its copy instead of downloading it again.
+
+use super::request::{Method, Request};
+use super::response::{Response, StatusCode};
+
+const INDEX: &str = "/index.html";
+
+pub(super) struct Asset {
+    pub(super) path: &'static str,
+    pub(super) content_type: &'static str,
+
+    /// quoted digest of the uncompressed content
+    pub(super) etag: &'static str,
+
+    /// gzip compressed content
+    pub(super) data: &'static [u8],
+}
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+pub(super) fn handle(request: &Request) -> Response {
+    let path = if request.path == "/" { INDEX } else { request.path };
+
+    let Some(asset) = ASSETS.iter().find(|asset| asset.path == path) else {
+        return Response::error(StatusCode::NotFound, "Not found");
+    };
+
+    if request.method != Method::Get {
+        return Response::error(StatusCode::MethodNotAllowed, "Method not allowed");
+    }
+
+    if !request.gzip {
+        return Response::error(StatusCode::NotAcceptable, "gzip encoding required");
+    }
+
+    Response::asset(asset, request.if_none_match)
+}
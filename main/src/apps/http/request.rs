@@ -0,0 +1,123 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! HTTP/1.1 request parsing, only what the REST API needs: the request line,
+///! `Content-Length`, a bearer token in `Authorization` and the cache validation of the
+///! dashboard files. Chunked bodies are refused.
+
+use core::str::from_utf8;
+
+use super::response::StatusCode;
+
+/// Max size of the request line, the headers and the body together
+pub(super) const REQUEST_SIZE: usize = 1_024;
+
+const HEADER_END: &[u8] = b"\r\n\r\n";
+const BEARER: &str = "Bearer ";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Method {
+    Get,
+    Put,
+    Post,
+    Delete,
+}
+
+pub(super) struct Request<'a> {
+    pub(super) method: Method,
+
+    /// path without the query string
+    pub(super) path: &'a str,
+
+    /// bearer token of the `Authorization` header
+    pub(super) token: Option<&'a str>,
+
+    /// ETag of the copy cached by the client
+    pub(super) if_none_match: Option<&'a str>,
+
+    /// gzip listed in `Accept-Encoding`
+    pub(super) gzip: bool,
+
+    pub(super) body: &'a str,
+}
+
+impl<'a> Request<'a> {
+    /// None while the headers or the body are not complete
+    pub(super) fn parse(data: &'a [u8]) -> core::result::Result<Option<Self>, StatusCode> {
+        let Some(end) = data.windows(HEADER_END.len()).position(|window| window == HEADER_END) else {
+            return if data.len() >= REQUEST_SIZE {
+                Err(StatusCode::PayloadTooLarge)
+            } else {
+                Ok(None)
+            };
+        };
+
+        let head = from_utf8(&data[..end]).map_err(|_| StatusCode::BadRequest)?;
+        let mut lines = head.split("\r\n");
+
+        let mut request_line = lines.next().ok_or(StatusCode::BadRequest)?.split(' ');
+        let method = match request_line.next() {
+            Some("GET") => Method::Get,
+            Some("PUT") => Method::Put,
+            Some("POST") => Method::Post,
+            Some("DELETE") => Method::Delete,
+            _ => return Err(StatusCode::MethodNotAllowed),
+        };
+        let target = request_line.next().ok_or(StatusCode::BadRequest)?;
+        let path = target.split_once('?').map_or(target, |(path, _)| path);
+
+        let mut content_length = 0usize;
+        let mut token = None;
+        let mut if_none_match = None;
+        let mut gzip = false;
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(StatusCode::BadRequest);
+            };
+            let value = value.trim();
+
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().map_err(|_| StatusCode::BadRequest)?;
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                return Err(StatusCode::LengthRequired);
+            } else if name.eq_ignore_ascii_case("authorization") {
+                token = value.strip_prefix(BEARER).map(str::trim);
+            } else if name.eq_ignore_ascii_case("if-none-match") {
+                if_none_match = Some(value);
+            } else if name.eq_ignore_ascii_case("accept-encoding") {
+                gzip = value.split(',').any(|encoding| encoding.trim().starts_with("gzip"));
+            }
+        }
+
+        let body_start = end + HEADER_END.len();
+        if body_start + content_length > REQUEST_SIZE {
+            return Err(StatusCode::PayloadTooLarge);
+        }
+        if data.len() < body_start + content_length {
+            return Ok(None);
+        }
+
+        let body = from_utf8(&data[body_start..body_start + content_length]).map_err(|_| StatusCode::BadRequest)?;
+
+        Ok(Some(Self { method, path, token, if_none_match, gzip, body }))
+    }
+}
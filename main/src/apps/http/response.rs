@@ -0,0 +1,159 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! HTTP/1.1 responses: JSON bodies of the REST API serialized by `cjson_binding` and
+///! the gzip compressed files of the dashboard.
+
+use alloc::string::String;
+
+use cjson_binding::to_json;
+use osal_rs::utils::Bytes;
+use osal_rs_serde::Serialize;
+
+use super::assets::Asset;
+
+/// Max size of the status line and the headers
+pub(super) const HEAD_SIZE: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum StatusCode {
+    Ok = 200,
+    NoContent = 204,
+    NotModified = 304,
+    BadRequest = 400,
+    Unauthorized = 401,
+    Forbidden = 403,
+    NotFound = 404,
+    MethodNotAllowed = 405,
+    NotAcceptable = 406,
+    RequestTimeout = 408,
+    Conflict = 409,
+    LengthRequired = 411,
+    PayloadTooLarge = 413,
+    TooManyRequests = 429,
+    InternalServerError = 500,
+    GatewayTimeout = 504,
+}
+
+impl StatusCode {
+    pub(super) fn reason(&self) -> &'static str {
+        use StatusCode::*;
+        match self {
+            Ok => "OK",
+            NoContent => "No Content",
+            NotModified => "Not Modified",
+            BadRequest => "Bad Request",
+            Unauthorized => "Unauthorized",
+            Forbidden => "Forbidden",
+            NotFound => "Not Found",
+            MethodNotAllowed => "Method Not Allowed",
+            NotAcceptable => "Not Acceptable",
+            RequestTimeout => "Request Timeout",
+            Conflict => "Conflict",
+            LengthRequired => "Length Required",
+            PayloadTooLarge => "Payload Too Large",
+            TooManyRequests => "Too Many Requests",
+            InternalServerError => "Internal Server Error",
+            GatewayTimeout => "Gateway Timeout",
+        }
+    }
+}
+
+/// Body of the error responses, `{"error":"<message>"}`
+#[derive(Serialize)]
+struct ErrorBody {
+    error: Bytes<96>,
+}
+
+pub(super) enum Body {
+    Empty,
+    Json(String),
+
+    /// file of the dashboard, sent as stored
+    Asset(&'static Asset),
+}
+
+pub(super) struct Response {
+    pub(super) code: StatusCode,
+    pub(super) body: Body,
+}
+
+impl Response {
+    #[inline]
+    pub(super) fn no_content() -> Self {
+        Self { code: StatusCode::NoContent, body: Body::Empty }
+    }
+
+    pub(super) fn json<T: Serialize>(value: &T) -> Self {
+        match to_json(value) {
+            Ok(body) => Self { code: StatusCode::Ok, body: Body::Json(body) },
+            Err(_) => Self::error(StatusCode::InternalServerError, "Impossible serialize"),
+        }
+    }
+
+    pub(super) fn error(code: StatusCode, message: &str) -> Self {
+        let body = to_json(&ErrorBody { error: Bytes::from_str(message) }).unwrap_or_default();
+        Self { code, body: Body::Json(body) }
+    }
+
+    /// The file, or only its headers when the client copy has the same ETag
+    pub(super) fn asset(asset: &'static Asset, if_none_match: Option<&str>) -> Self {
+        let cached = if_none_match.is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == asset.etag));
+        let code = if cached { StatusCode::NotModified } else { StatusCode::Ok };
+        Self { code, body: Body::Asset(asset) }
+    }
+
+    /// Bytes sent after the headers, none for `304 Not Modified`
+    pub(super) fn content(&self) -> &[u8] {
+        match &self.body {
+            _ if self.code == StatusCode::NotModified => &[],
+            Body::Empty => &[],
+            Body::Json(body) => body.as_bytes(),
+            Body::Asset(asset) => asset.data,
+        }
+    }
+
+    /// Status line and headers, the connection is closed after each response
+    pub(super) fn head(&self) -> Bytes<HEAD_SIZE> {
+        let mut head = Bytes::new();
+        head.format(format_args!("HTTP/1.1 {} {}\r\n", self.code as u16, self.code.reason()));
+        match &self.body {
+            Body::Empty => {}
+            Body::Json(body) if body.is_empty() => {}
+            Body::Json(_) => {
+                head.format(format_args!("Content-Type: application/json\r\n"));
+            }
+            Body::Asset(asset) => {
+                // The browser keeps its copy but asks every time whether it is still current
+                head.format(format_args!("ETag: {}\r\nCache-Control: no-cache\r\n", asset.etag));
+                if self.code != StatusCode::NotModified {
+                    head.format(format_args!("Content-Type: {}\r\nContent-Encoding: gzip\r\n", asset.content_type));
+                }
+            }
+        }
+        if self.code != StatusCode::NotModified {
+            head.format(format_args!("Content-Length: {}\r\n", self.content().len()));
+        }
+        head.format(format_args!("Connection: close\r\n\r\n"));
+        head
+    }
+}
@@ -22,18 +22,27 @@ use core::time::Duration;
 
 use alloc::boxed::Box;
 use alloc::sync::Arc;
-use osal_rs::{log_debug, log_info};
+use osal_rs::{log_debug, log_error, log_info};
 use osal_rs::os::types::StackType;
-use osal_rs::os::{MutexFn as _, System, Thread, ThreadFn, ThreadParam};
+use osal_rs::os::{EventGroup, MutexFn as _, Thread, ThreadFn, ThreadParam, ToTick};
 use osal_rs::utils::{Error, Result};
 
+use crate::apps::audit::AuditLog;
 use crate::apps::config::Config;
 use crate::apps::display::Display;
+use crate::apps::events::Events;
+use crate::apps::mqtt::Mqtt;
 use crate::apps::parser::Parser;
+use crate::apps::session::Totp;
 use crate::apps::signals::error::ErrorSignal;
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
 use crate::apps::signals::status::{StatusFlag, StatusSignal};
 use crate::apps::sprinkler::Sprinkler;
+use crate::apps::sprinkler::schedule::Timetable;
+use crate::apps::sprinkler::winterization::WinterizationController;
 use crate::apps::system_led::SystemLed;
+use crate::apps::telnet::Telnet;
+use crate::apps::http::Http;
 use crate::apps::wifi::Wifi;
 use crate::drivers::date_time::DateTime;
 use crate::drivers::platform::{Hardware, LCDDisplay, ThreadPriority};
@@ -69,6 +78,10 @@ pub(crate) struct AppMain {
     hardware: &'static mut Hardware,
     display: Display<LCDDisplay>,
     wifi: Wifi,
+    mqtt: Mqtt,
+    telnet: Telnet,
+    http: Http,
+    events: Events,
     parser: Parser,
     system_led: SystemLed,
     sprinkler: Sprinkler,
@@ -85,15 +98,23 @@ impl Initializable for AppMain{
 
         StatusSignal::init()?;
         ErrorSignal::init()?;
+        SchedulerSignal::init()?;
 
         let config = Config::shared();
         
 
         config.init()?;
+        AuditLog::shared().init()?;
+        // The commands audited since the last save survive a reboot
+        Hardware::set_on_reset(|| AuditLog::shared().flush());
         self.sprinkler.init()?;
         self.system_led.init()?;
         self.parser.init()?;
+        self.events.init()?;
         self.wifi.init()?;
+        self.mqtt.init()?;
+        self.telnet.init()?;
+        self.http.init()?;
         self.display.set_enabled_wifi(config.get_wifi_config().is_enabled());
         self.display.init()?;
 
@@ -117,6 +138,10 @@ impl AppMain {
             hardware,
             display,
             wifi: Wifi::shared(),
+            mqtt: Mqtt::new(),
+            telnet: Telnet::new(),
+            http: Http::new(),
+            events: Events::new(),
             parser: Parser::shared(),
             system_led: SystemLed::new(),
             sprinkler: Sprinkler::new(),
@@ -146,6 +171,8 @@ impl AppMain {
 
         let config = Config::shared();
 
+        let winterization = WinterizationController::shared();
+
         let mut status_current = StatusFlag::None;
         let mut status_old = StatusFlag::None;
 
@@ -155,15 +182,28 @@ impl AppMain {
         unsafe {
             let display_ptr = &raw mut me.display;
             let wifi_ptr = &raw mut me.wifi;
+            let mqtt_ptr = &raw mut me.mqtt;
+            let telnet_ptr = &raw mut me.telnet;
+            let http_ptr = &raw mut me.http;
             let hardware_ptr = &raw mut me.hardware;
 
             loop {
+                let mut sleep = Duration::from_millis(TICK_INTERVAL_MS.into());
+
                 match status_current {
                     StatusFlag::None => {
                         set_current_status!(status_old, status_current, StatusFlag::Startup);
                     }
                     StatusFlag::Startup => {
                         log_debug!(APP_TAG, "Start MAIN FSM");
+
+                        // Set relays, flow meter and pump sensors used by the sprinkler run engine
+                        me.sprinkler.set_relays(*hardware_ptr);
+                        me.sprinkler.set_flow_meter(*hardware_ptr);
+                        me.sprinkler.set_pump_sensors(*hardware_ptr);
+
+                        // MCU temperature published to Home Assistant
+                        (&mut *mqtt_ptr).set_hardware(*hardware_ptr);
                         
                         set_current_status!(status_old, status_current, StatusFlag::EnableSystemHandler);
                     }
@@ -179,6 +219,25 @@ impl AppMain {
 
                         (*hardware_ptr).set_on_receive(&me.parser);
 
+                        // MQTT commands reach the same parser, the responses go back to the broker
+                        Parser::set_mqtt_transmit(&*mqtt_ptr);
+                        (&mut *mqtt_ptr).set_on_receive(&me.parser);
+
+                        // Same for the lines of the TCP client
+                        Parser::set_telnet_transmit(&*telnet_ptr);
+                        (&mut *telnet_ptr).set_on_receive(&me.parser);
+
+                        // The REST writes run as command lines, the channel captures the response
+                        Parser::set_http_transmit(&*http_ptr);
+                        (&mut *http_ptr).set_on_receive(&me.parser);
+                        (&mut *http_ptr).set_rtc((*hardware_ptr).get_rtc());
+
+                        // The audit records are stamped with the RTC time
+                        AuditLog::set_rtc((*hardware_ptr).get_rtc());
+
+                        // The codes of the second factor follow the RTC time
+                        Totp::set_rtc((*hardware_ptr).get_rtc());
+
                         set_current_status!(status_old, status_current, StatusFlag::EnableDisplay);
                     }
                     StatusFlag::EnableDisplay => {
@@ -208,15 +267,66 @@ impl AppMain {
 
                         let now: DateTime = DateTime::from_timestamp(rtc.lock()?.get_timestamp()?)?;
 
-                        me.sprinkler.check(now);
+                        if winterization.take_start_request() {
+                            if let Err(e) = me.sprinkler.begin_winterization(now) {
+                                log_error!(APP_TAG, "Error starting winterization: {e}");
+                            }
+                            set_current_status!(status_old, status_current, StatusFlag::Winterize);
+                        } else if me.sprinkler.is_winterized() {
+                            set_current_status!(status_old, status_current, StatusFlag::Winterized);
+                        } else {
+                            me.sprinkler.check(now);
+
+                            StatusSignal::set(StatusFlag::Ready.into());
+
+                            // idle until the next due start, a change of the schedules or of the clock or a request
+                            if !me.sprinkler.is_running() {
+                                sleep = Duration::from_secs(me.sprinkler.get_sleep_seconds(now.to_timestamp()) as u64);
+                            }
+                        }
+                    },
+                    StatusFlag::Winterize => {
 
-                        StatusSignal::set(StatusFlag::Ready.into());
+                        let now: DateTime = DateTime::from_timestamp(rtc.lock()?.get_timestamp()?)?;
+
+                        if winterization.take_enable_request() {
+                            if let Err(e) = me.sprinkler.end_winterization() {
+                                log_error!(APP_TAG, "Error ending winterization: {e}");
+                            }
+                            set_current_status!(status_old, status_current, StatusFlag::Ready);
+                        } else if me.sprinkler.winterize(now) {
+                            set_current_status!(status_old, status_current, StatusFlag::Winterized);
+                        }
+                    },
+                    StatusFlag::Winterized => {
+                        // only a request leaves this state
+                        sleep = Duration::from_secs(Timetable::MAX_SLEEP_SECONDS as u64);
+
+                        if winterization.take_start_request() {
+                            let now: DateTime = DateTime::from_timestamp(rtc.lock()?.get_timestamp()?)?;
+
+                            if let Err(e) = me.sprinkler.begin_winterization(now) {
+                                log_error!(APP_TAG, "Error starting winterization: {e}");
+                            }
+                            set_current_status!(status_old, status_current, StatusFlag::Winterize);
+                        } else if winterization.take_enable_request() {
+                            if let Err(e) = me.sprinkler.end_winterization() {
+                                log_error!(APP_TAG, "Error ending winterization: {e}");
+                            }
+                            set_current_status!(status_old, status_current, StatusFlag::Ready);
+                        }
                     },
                     StatusFlag::Error => todo!("handle error, maybe set status to Error and log it"),
                     StatusFlag::Reset | _  => todo!("handle reset, maybe set status to Reset and log it"),
                 
                 }
-                System::delay_with_to_tick(Duration::from_millis(TICK_INTERVAL_MS.into()));
+
+                let signal = SchedulerSignal::wait(EventGroup::MAX_MASK, sleep.to_ticks());
+                SchedulerSignal::clear(signal);
+
+                if signal & SchedulerFlag::RESCHEDULE != 0 {
+                    me.sprinkler.reschedule();
+                }
             }
         
         }
@@ -0,0 +1,403 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! MQTT client transport of the AT parser: the commands published on
+///! `<topic>/<serial>/cmd` are executed like UART lines and every response line
+///! is published on `<topic>/<serial>/rsp`. `<topic>/<serial>/status` holds the
+///! retained `online`/`offline` availability, `offline` is the last will.
+///! With the discovery enabled the controller is also announced to Home Assistant,
+///! see `home_assistant`.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use osal_rs::{log_error, log_info, log_warning};
+use osal_rs::os::{System, SystemFn, Thread, ThreadFn, ToTick};
+use osal_rs::os::types::{StackType, TickType};
+use osal_rs::utils::{Bytes, Error, Result};
+
+use crate::apps::config::{Config, MqttConfig};
+use crate::apps::signals::error::{ErrorFlag, ErrorSignal};
+use crate::drivers::network::Network;
+use crate::drivers::platform::{Hardware, ThreadPriority};
+use crate::traits::rx_tx::{OnReceive, SetOnReceive, SetTransmit, Source};
+use crate::traits::signal::Signal;
+use crate::traits::state::Initializable;
+
+mod packet;
+mod home_assistant;
+
+use home_assistant::HomeAssistant;
+use packet::{Packet, Will};
+
+const APP_TAG: &str = "AppMqtt";
+const THREAD_NAME: &str = "app_mqtt_trd";
+const STACK_SIZE: StackType = 2_048;
+const TICK_INTERVAL_MS: u64 = 100;
+const ACK_TIMEOUT_MS: u64 = 5_000;
+const RECONNECT_MIN_SECONDS: u64 = 1;
+const RECONNECT_MAX_SECONDS: u64 = 60;
+
+const RX_BUFFER_SIZE: usize = 512;
+const TX_BUFFER_SIZE: usize = 384;
+const LINE_SIZE: usize = 256;
+const TOPIC_SIZE: usize = 64;
+
+const TOPIC_COMMAND: &str = "cmd";
+const TOPIC_RESPONSE: &str = "rsp";
+const TOPIC_STATUS: &str = "status";
+const STATUS_ONLINE: &str = "online";
+const STATUS_OFFLINE: &str = "offline";
+const NEW_LINE: &[u8] = b"\r\n";
+const SUBSCRIBE_PACKET_ID: u16 = 1;
+
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+static RECONNECT: AtomicBool = AtomicBool::new(false);
+
+static mut ON_RECEIVE: Option<&'static dyn OnReceive> = None;
+
+/// Source of the MCU temperature published to Home Assistant
+static mut HARDWARE: Option<&'static Hardware> = None;
+static mut LAST_TX: TickType = 0;
+
+static mut RX: [u8; RX_BUFFER_SIZE] = [0; RX_BUFFER_SIZE];
+static mut RX_LEN: usize = 0;
+
+static mut RESPONSE_TOPIC: Bytes<TOPIC_SIZE> = Bytes::new();
+static mut LINE: [u8; LINE_SIZE] = [0; LINE_SIZE];
+static mut LINE_LEN: usize = 0;
+
+/// Topics of the current connection
+struct Topics {
+    command: Bytes<TOPIC_SIZE>,
+    status: Bytes<TOPIC_SIZE>,
+
+    /// None if the Home Assistant discovery is disabled
+    home_assistant: Option<HomeAssistant>,
+}
+
+pub(in crate::apps) struct Mqtt(Thread);
+
+impl Initializable for Mqtt {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init app mqtt");
+
+        self.0 = self.0.spawn_simple(move || {
+
+            let mut reconnect_seconds = RECONNECT_MIN_SECONDS;
+
+            loop {
+                RECONNECT.store(false, Ordering::SeqCst);
+
+                let config = Config::shared().get_mqtt_config();
+                if !config.is_enabled() || config.get_host().is_empty() || !Network::is_link_up() || !Network::dhcp_supplied_address() {
+                    System::delay_with_to_tick(Duration::from_secs(RECONNECT_MIN_SECONDS));
+                    continue;
+                }
+
+                match Self::connect(&config) {
+                    Ok(mut topics) => {
+                        ErrorSignal::clear(ErrorFlag::Mqtt.into());
+                        reconnect_seconds = RECONNECT_MIN_SECONDS;
+
+                        if let Err(e) = Self::session(&config, &mut topics) {
+                            log_warning!(APP_TAG, "Connection lost: {e}");
+                        }
+                    }
+                    Err(e) => {
+                        log_error!(APP_TAG, "Connection to {}:{} failed: {e}", config.get_host(), config.get_port());
+                        ErrorSignal::set(ErrorFlag::Mqtt.into());
+                    }
+                }
+
+                CONNECTED.store(false, Ordering::SeqCst);
+                Network::tcp_close();
+
+                System::delay_with_to_tick(Duration::from_secs(reconnect_seconds));
+                reconnect_seconds = (reconnect_seconds * 2).min(RECONNECT_MAX_SECONDS);
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+impl SetOnReceive<'static> for Mqtt {
+    fn set_on_receive(&mut self, on_receive: &'static dyn OnReceive) {
+        unsafe {
+            ON_RECEIVE = Some(on_receive);
+        }
+    }
+}
+
+impl SetTransmit for Mqtt {
+    /// Collects the parser output and publishes it one line at a time,
+    /// only the parser thread calls it
+    fn transmit(&self, data: &[u8]) -> usize {
+        unsafe {
+            let line = &mut *&raw mut LINE;
+
+            for &byte in data {
+                if byte == b'\n' {
+                    let end = if LINE_LEN > 0 && line[LINE_LEN - 1] == b'\r' { LINE_LEN - 1 } else { LINE_LEN };
+
+                    if CONNECTED.load(Ordering::SeqCst) {
+                        if let Err(e) = Self::publish((*&raw const RESPONSE_TOPIC).as_str(), &line[..end], false) {
+                            log_error!(APP_TAG, "Error publishing response: {e}");
+                        }
+                    }
+                    LINE_LEN = 0;
+                } else if LINE_LEN < LINE_SIZE {
+                    line[LINE_LEN] = byte;
+                    LINE_LEN += 1;
+                }
+            }
+        }
+
+        data.len()
+    }
+}
+
+impl Mqtt {
+    pub(in crate::apps) fn new() -> Self {
+        Self(Thread::new_with_to_priority(THREAD_NAME, STACK_SIZE, ThreadPriority::Normal))
+    }
+
+    #[inline]
+    pub(in crate::apps) fn set_hardware(&mut self, hardware: &'static Hardware) {
+        unsafe {
+            HARDWARE = Some(hardware);
+        }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_connected() -> bool {
+        CONNECTED.load(Ordering::SeqCst)
+    }
+
+    /// Closes the current connection, the next one uses the updated configuration
+    #[inline]
+    pub(in crate::apps) fn reconnect() {
+        RECONNECT.store(true, Ordering::SeqCst);
+    }
+
+    fn topic(prefix: &Bytes<16>, serial: &Bytes<16>, leaf: &str) -> Bytes<TOPIC_SIZE> {
+        let mut topic = Bytes::new();
+        topic.format(format_args!("{}/{}/{}", prefix, serial, leaf));
+        topic
+    }
+
+    fn write(data: &[u8]) -> Result<()> {
+        Network::tcp_write(data)?;
+        unsafe {
+            LAST_TX = System::get_tick_count();
+        }
+        Ok(())
+    }
+
+    fn publish(topic: &str, payload: &[u8], retain: bool) -> Result<()> {
+        let mut buffer = [0u8; TX_BUFFER_SIZE];
+        let len = packet::publish(&mut buffer, topic, payload, retain)?;
+        Self::write(&buffer[..len])
+    }
+
+    /// Reads the received bytes and hands every complete packet to `handle`
+    fn poll(mut handle: impl FnMut(Packet<'_>) -> Result<()>) -> Result<()> {
+        unsafe {
+            let rx = &mut *&raw mut RX;
+
+            RX_LEN += Network::tcp_read(&mut rx[RX_LEN..])?;
+
+            loop {
+                match packet::decode(&rx[..RX_LEN])? {
+                    Some((packet, size)) => {
+                        handle(packet)?;
+                        rx.copy_within(size..RX_LEN, 0);
+                        RX_LEN -= size;
+                    }
+                    None if RX_LEN == RX_BUFFER_SIZE => return Err(Error::Unhandled("MQTT packet too large")),
+                    None => return Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Waits for the acknowledge selected by `accept`
+    fn wait<T>(mut accept: impl FnMut(Packet<'_>) -> Option<T>) -> Result<T> {
+        let timeout = Duration::from_millis(ACK_TIMEOUT_MS).to_ticks();
+        let start = System::get_tick_count();
+
+        while System::get_tick_count().wrapping_sub(start) < timeout {
+            let mut result = None;
+            Self::poll(|packet| {
+                if result.is_none() {
+                    result = accept(packet);
+                }
+                Ok(())
+            })?;
+
+            if let Some(result) = result {
+                return Ok(result);
+            }
+
+            System::delay_with_to_tick(Duration::from_millis(TICK_INTERVAL_MS));
+        }
+
+        Err(Error::Unhandled("MQTT acknowledge timeout"))
+    }
+
+    fn connect(config: &MqttConfig) -> Result<Topics> {
+        let serial = Config::shared().get_serial();
+        let prefix = config.get_topic();
+
+        let mut topics = Topics {
+            command: Self::topic(&prefix, &serial, TOPIC_COMMAND),
+            status: Self::topic(&prefix, &serial, TOPIC_STATUS),
+            home_assistant: if config.is_discovery_enabled() { Some(HomeAssistant::new(&prefix, &serial)) } else { None },
+        };
+        unsafe {
+            RESPONSE_TOPIC = Self::topic(&prefix, &serial, TOPIC_RESPONSE);
+            RX_LEN = 0;
+            LINE_LEN = 0;
+        }
+
+        let mut client_id = Bytes::<TOPIC_SIZE>::new();
+        client_id.format(format_args!("{}-{}", prefix, serial));
+
+        let ip = Network::dns_resolve_addrress(&config.get_host())?;
+        Network::tcp_connect(ip, config.get_port())?;
+
+        let mut buffer = [0u8; TX_BUFFER_SIZE];
+
+        let will = Will { topic: topics.status.as_str(), message: STATUS_OFFLINE };
+        let len = packet::connect(&mut buffer, client_id.as_str(), config.get_user().as_str(), config.get_password().as_str(), config.get_keepalive(), &will)?;
+        Self::write(&buffer[..len])?;
+
+        let code = Self::wait(|packet| match packet {
+            Packet::ConnAck { code } => Some(code),
+            _ => None,
+        })?;
+        if code != packet::ACCEPTED {
+            return Err(Error::ReturnWithCode(code as i32));
+        }
+
+        let len = packet::subscribe(&mut buffer, SUBSCRIBE_PACKET_ID, topics.command.as_str(), 0)?;
+        Self::write(&buffer[..len])?;
+
+        let code = Self::wait(|packet| match packet {
+            Packet::SubAck { packet_id: SUBSCRIBE_PACKET_ID, code } => Some(code),
+            _ => None,
+        })?;
+        if code == packet::SUBSCRIBE_FAILURE {
+            return Err(Error::Unhandled("MQTT subscribe refused"));
+        }
+
+        if let Some(home_assistant) = &topics.home_assistant {
+            let len = packet::subscribe(&mut buffer, home_assistant::SUBSCRIBE_PACKET_ID, home_assistant.command_filter().as_str(), 0)?;
+            Self::write(&buffer[..len])?;
+
+            let code = Self::wait(|packet| match packet {
+                Packet::SubAck { packet_id: home_assistant::SUBSCRIBE_PACKET_ID, code } => Some(code),
+                _ => None,
+            })?;
+            if code == packet::SUBSCRIBE_FAILURE {
+                return Err(Error::Unhandled("MQTT subscribe refused"));
+            }
+        }
+
+        Self::publish(topics.status.as_str(), STATUS_ONLINE.as_bytes(), true)?;
+
+        if let Some(home_assistant) = &mut topics.home_assistant {
+            home_assistant.discover(topics.status.as_str())?;
+        }
+
+        CONNECTED.store(true, Ordering::SeqCst);
+        log_info!(APP_TAG, "Connected to {}:{}, commands on {}", config.get_host(), config.get_port(), topics.command);
+
+        Ok(topics)
+    }
+
+    /// Serves the connection until it is lost or a reconnection is requested
+    fn session(config: &MqttConfig, topics: &mut Topics) -> Result<()> {
+        let keepalive = Duration::from_secs(config.get_keepalive() as u64).to_ticks();
+        let mut ping_sent: Option<TickType> = None;
+        let mut buffer = [0u8; 8];
+
+        loop {
+            if RECONNECT.load(Ordering::SeqCst) {
+                log_info!(APP_TAG, "Disconnect, configuration changed");
+
+                Self::publish(topics.status.as_str(), STATUS_OFFLINE.as_bytes(), true)?;
+                let len = packet::disconnect(&mut buffer)?;
+                return Self::write(&buffer[..len]);
+            }
+
+            Self::poll(|packet| {
+                match packet {
+                    Packet::Publish { topic, payload, packet_id } => {
+                        if let Some(packet_id) = packet_id {
+                            let mut buffer = [0u8; 4];
+                            let len = packet::puback(&mut buffer, packet_id)?;
+                            Self::write(&buffer[..len])?;
+                        }
+
+                        let Some(on_receive) = (unsafe { *&raw const ON_RECEIVE }) else {
+                            return Ok(());
+                        };
+
+                        if topic == topics.command.as_str() {
+                            let ret = on_receive.on_receive(Source::Mqtt, payload)
+                                .and_then(|_| if payload.ends_with(b"\n") { Ok(()) } else { on_receive.on_receive(Source::Mqtt, NEW_LINE) });
+                            if let Err(e) = ret {
+                                log_warning!(APP_TAG, "Command dropped: {e}");
+                            }
+                        } else if let Some(home_assistant) = &topics.home_assistant {
+                            if let Err(e) = home_assistant.command(topic, payload, on_receive) {
+                                log_warning!(APP_TAG, "Home Assistant command dropped: {e}");
+                            }
+                        }
+                    }
+                    Packet::PingResp => ping_sent = None,
+                    _ => {}
+                }
+                Ok(())
+            })?;
+
+            if let Some(home_assistant) = &mut topics.home_assistant {
+                home_assistant.update(unsafe { *&raw const HARDWARE })?;
+            }
+
+            let now = System::get_tick_count();
+            match ping_sent {
+                Some(sent) if now.wrapping_sub(sent) >= keepalive / 2 => return Err(Error::Unhandled("MQTT ping timeout")),
+                None if keepalive > 0 && now.wrapping_sub(unsafe { LAST_TX }) >= keepalive / 2 => {
+                    let len = packet::pingreq(&mut buffer)?;
+                    Self::write(&buffer[..len])?;
+                    ping_sent = Some(now);
+                }
+                _ => {}
+            }
+
+            System::delay_with_to_tick(Duration::from_millis(TICK_INTERVAL_MS));
+        }
+    }
+}
@@ -0,0 +1,297 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! Home Assistant MQTT discovery: the zones are published as valves, the schedules as
+///! switches, the wifi signal, the MCU temperature and the error flags as sensors.
+///! The commands of Home Assistant are translated in AT lines and executed by the parser
+///! as the ones on the `cmd` topic, so they need a user logged over MQTT.
+
+use core::fmt::{Display, Formatter, Write};
+use core::time::Duration;
+
+use alloc::string::String;
+use alloc::vec;
+use osal_rs::log_info;
+use osal_rs::os::{System, SystemFn, ToTick};
+use osal_rs::os::types::TickType;
+use osal_rs::utils::{Bytes, Error, Result};
+
+use crate::apps::mqtt::{Mqtt, TOPIC_SIZE};
+use crate::apps::mqtt::packet;
+use crate::apps::signals::error::{ErrorFlag, ErrorSignal};
+use crate::apps::sprinkler::schedule::ScheduleController;
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+use crate::apps::wifi::Wifi;
+use crate::drivers::platform::Hardware;
+use crate::traits::hardware::HardwareFn;
+use crate::traits::rx_tx::{OnReceive, Source};
+use crate::traits::signal::Signal;
+
+pub(super) const SUBSCRIBE_PACKET_ID: u16 = 2;
+
+const DISCOVERY_PREFIX: &str = "homeassistant";
+const MANUFACTURER: &str = "Hi Happy Garden";
+const MODEL: &str = "Sprinkler controller";
+const TEMPERATURE_INTERVAL_SECONDS: u64 = 60;
+
+/// `homeassistant/<component>/<node>/<object><idx>/config` with the longest topic prefix and serial
+const CONFIG_TOPIC_SIZE: usize = 96;
+
+const ZONE: &str = "zone";
+const SCHEDULE: &str = "schedule";
+const RSSI: &str = "rssi";
+const TEMPERATURE: &str = "temperature";
+const ERRORS: &str = "errors";
+const SET: &str = "set";
+const STATE: &str = "state";
+
+const ZONE_OPEN: &str = "OPEN";
+const ZONE_CLOSE: &str = "CLOSE";
+const ZONE_OPENED: &str = "open";
+const ZONE_CLOSED: &str = "closed";
+const SCHEDULE_ON: &str = "ON";
+const SCHEDULE_OFF: &str = "OFF";
+
+/// JSON string content, quotes, backslashes and control chars escaped
+struct Escaped<'a>(&'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Discovery and states of the controller, the last published states are kept
+/// to publish only the changes
+pub(super) struct HomeAssistant {
+
+    /// `<topic>/<serial>`, root of the state and command topics
+    base: Bytes<TOPIC_SIZE>,
+
+    /// `<topic>_<serial>`, node id of the discovery topics and prefix of the unique ids
+    node: Bytes<TOPIC_SIZE>,
+
+    serial: Bytes<16>,
+
+    /// bitmask of the open valves
+    zones: Option<u8>,
+
+    /// bitmask of the active schedules
+    schedules: Option<u8>,
+
+    rssi: Option<i8>,
+
+    errors: Option<u32>,
+
+    /// tick of the last temperature published
+    temperature_at: Option<TickType>,
+}
+
+impl HomeAssistant {
+    pub(super) fn new(prefix: &Bytes<16>, serial: &Bytes<16>) -> Self {
+        let mut base = Bytes::new();
+        base.format(format_args!("{}/{}", prefix, serial));
+
+        let mut node = Bytes::new();
+        node.format(format_args!("{}_{}", prefix, serial));
+
+        Self {
+            base,
+            node,
+            serial: *serial,
+            zones: None,
+            schedules: None,
+            rssi: None,
+            errors: None,
+            temperature_at: None,
+        }
+    }
+
+    /// Filter of the command topics, `<topic>/<serial>/<zone|schedule>/<n>/set`
+    pub(super) fn command_filter(&self) -> Bytes<TOPIC_SIZE> {
+        let mut topic = Bytes::new();
+        topic.format(format_args!("{}/+/+/{}", self.base, SET));
+        topic
+    }
+
+    /// Publish the retained discovery configs, the states are published again at the next update
+    pub(super) fn discover(&mut self, availability: &str) -> Result<()> {
+        let zones = ZoneController::shared();
+        for idx in 0..ZoneController::SIZE {
+            let Some(zone) = zones.get(ZoneRelay::from(idx as u8)) else {
+                continue;
+            };
+
+            let mut json = self.header(ZONE, idx, zone.description.as_str(), availability);
+            let _ = write!(json, ",\"dev_cla\":\"water\",\"cmd_t\":\"~/{ZONE}/{idx}/{SET}\",\"stat_t\":\"~/{ZONE}/{idx}/{STATE}\"\
+                ,\"pl_open\":\"{ZONE_OPEN}\",\"pl_cls\":\"{ZONE_CLOSE}\",\"stat_open\":\"{ZONE_OPENED}\",\"stat_clsd\":\"{ZONE_CLOSED}\"");
+            self.config("valve", ZONE, idx, json)?;
+        }
+
+        for (idx, schedule) in ScheduleController::shared().into_iter().enumerate() {
+            let mut json = self.header(SCHEDULE, idx, schedule.description.as_str(), availability);
+            let _ = write!(json, ",\"cmd_t\":\"~/{SCHEDULE}/{idx}/{SET}\",\"stat_t\":\"~/{SCHEDULE}/{idx}/{STATE}\"\
+                ,\"pl_on\":\"{SCHEDULE_ON}\",\"pl_off\":\"{SCHEDULE_OFF}\"");
+            self.config("switch", SCHEDULE, idx, json)?;
+        }
+
+        let mut json = self.header(RSSI, 0, "Wifi signal", availability);
+        let _ = write!(json, ",\"dev_cla\":\"signal_strength\",\"unit_of_meas\":\"dBm\",\"stat_cla\":\"measurement\",\"ent_cat\":\"diagnostic\",\"stat_t\":\"~/{RSSI}\"");
+        self.config("sensor", RSSI, 0, json)?;
+
+        let mut json = self.header(TEMPERATURE, 0, "MCU temperature", availability);
+        let _ = write!(json, ",\"dev_cla\":\"temperature\",\"unit_of_meas\":\"°C\",\"stat_cla\":\"measurement\",\"ent_cat\":\"diagnostic\",\"stat_t\":\"~/{TEMPERATURE}\"");
+        self.config("sensor", TEMPERATURE, 0, json)?;
+
+        let mut json = self.header(ERRORS, 0, "Errors", availability);
+        let _ = write!(json, ",\"ic\":\"mdi:alert-circle\",\"ent_cat\":\"diagnostic\",\"stat_t\":\"~/{ERRORS}\"");
+        self.config("sensor", ERRORS, 0, json)?;
+
+        log_info!(super::APP_TAG, "Home Assistant discovery published for {}", self.node);
+
+        self.zones = None;
+        self.schedules = None;
+        self.rssi = None;
+        self.errors = None;
+        self.temperature_at = None;
+
+        Ok(())
+    }
+
+    /// Publish the states changed since the last update, the temperature every `TEMPERATURE_INTERVAL_SECONDS`
+    pub(super) fn update(&mut self, hardware: Option<&Hardware>) -> Result<()> {
+        let zones = ZoneController::shared().get_open();
+        if self.zones != Some(zones) {
+            for idx in (0..ZoneController::SIZE).filter(|idx| self.zones.is_none_or(|old| (old ^ zones) & 1 << idx != 0)) {
+                let state = if zones & 1 << idx != 0 { ZONE_OPENED } else { ZONE_CLOSED };
+                self.state(format_args!("{ZONE}/{idx}/{STATE}"), state)?;
+            }
+            self.zones = Some(zones);
+        }
+
+        let schedules = ScheduleController::shared().get_active();
+        if self.schedules != Some(schedules) {
+            for idx in (0..ScheduleController::SIZE).filter(|idx| self.schedules.is_none_or(|old| (old ^ schedules) & 1 << idx != 0)) {
+                let state = if schedules & 1 << idx != 0 { SCHEDULE_ON } else { SCHEDULE_OFF };
+                self.state(format_args!("{SCHEDULE}/{idx}/{STATE}"), state)?;
+            }
+            self.schedules = Some(schedules);
+        }
+
+        let rssi = Wifi::get_rssi();
+        if self.rssi != Some(rssi) {
+            let mut payload = Bytes::<8>::new();
+            payload.format(format_args!("{rssi}"));
+            self.state(format_args!("{RSSI}"), payload.as_str())?;
+            self.rssi = Some(rssi);
+        }
+
+        let errors = ErrorSignal::get();
+        if self.errors != Some(errors) {
+            let mut payload = String::new();
+            for bit in 0..u32::BITS {
+                let flag = ErrorFlag::from(errors & 1 << bit);
+                if flag != ErrorFlag::None {
+                    let _ = write!(payload, "{}{flag}", if payload.is_empty() { "" } else { "," });
+                }
+            }
+            if payload.is_empty() {
+                let _ = write!(payload, "{}", ErrorFlag::None);
+            }
+            self.state(format_args!("{ERRORS}"), payload.as_str())?;
+            self.errors = Some(errors);
+        }
+
+        let now = System::get_tick_count();
+        let interval = Duration::from_secs(TEMPERATURE_INTERVAL_SECONDS).to_ticks();
+        if let Some(hardware) = hardware.filter(|_| self.temperature_at.is_none_or(|at| now.wrapping_sub(at) >= interval)) {
+            let mut payload = Bytes::<16>::new();
+            payload.format(format_args!("{:.1}", hardware.get_temperature()));
+            self.state(format_args!("{TEMPERATURE}"), payload.as_str())?;
+            self.temperature_at = Some(now);
+        }
+
+        Ok(())
+    }
+
+    /// Translate a command of Home Assistant in AT lines for the parser
+    pub(super) fn command(&self, topic: &str, payload: &[u8], on_receive: &dyn OnReceive) -> Result<()> {
+        let mut parts = topic.strip_prefix(self.base.as_str()).unwrap_or_default().split('/');
+        let (Some(""), Some(kind), Some(idx), Some(SET), None) = (parts.next(), parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(Error::Unhandled("Invalid Home Assistant topic"));
+        };
+        let idx: u8 = idx.parse().map_err(|_| Error::Unhandled("Invalid Home Assistant index"))?;
+        let payload = core::str::from_utf8(payload).map_err(|_| Error::Unhandled("Invalid Home Assistant payload"))?.trim();
+
+        let mut line = Bytes::<32>::new();
+        match (kind, payload) {
+            (ZONE, ZONE_OPEN) => line.format(format_args!("AT+RUN={idx}\r\n")),
+            (ZONE, ZONE_CLOSE) => line.format(format_args!("AT+RUN={idx},0\r\n")),
+            (SCHEDULE, SCHEDULE_ON | SCHEDULE_OFF) => line.format(format_args!("AT+SCH={idx},en,{}\r\n", (payload == SCHEDULE_ON) as u8)),
+            _ => return Err(Error::Unhandled("Invalid Home Assistant command")),
+        }
+        on_receive.on_receive(Source::Mqtt, line.as_str().as_bytes())
+    }
+
+    /// Fields shared by all the entities: base topic, name, unique id, availability and device
+    fn header(&self, object: &str, idx: usize, name: &str, availability: &str) -> String {
+        let mut json = String::new();
+        let _ = write!(json, "{{\"~\":\"{}\",\"name\":\"{}\",\"uniq_id\":\"{}_{object}{idx}\",\"avty_t\":\"{}\"\
+            ,\"dev\":{{\"ids\":[\"{}\"],\"name\":\"{MANUFACTURER} {}\",\"mf\":\"{MANUFACTURER}\",\"mdl\":\"{MODEL}\",\"sw\":\"{}\"}}",
+            Escaped(self.base.as_str()), Escaped(name), Escaped(self.node.as_str()), Escaped(availability),
+            Escaped(self.node.as_str()), Escaped(self.serial.as_str()), env!("CARGO_PKG_VERSION"));
+        json
+    }
+
+    /// Close the JSON and publish it retained on `homeassistant/<component>/<node>/<object><idx>/config`
+    fn config(&self, component: &str, object: &str, idx: usize, mut json: String) -> Result<()> {
+        json.push('}');
+
+        let mut topic = Bytes::<CONFIG_TOPIC_SIZE>::new();
+        topic.format(format_args!("{DISCOVERY_PREFIX}/{component}/{}/{object}{idx}/config", self.node));
+
+        Self::publish(topic.as_str(), json.as_bytes(), true)
+    }
+
+    /// Publish a retained state on `<topic>/<serial>/<leaf>`
+    fn state(&self, leaf: core::fmt::Arguments<'_>, payload: &str) -> Result<()> {
+        let mut topic = Bytes::<TOPIC_SIZE>::new();
+        topic.format(format_args!("{}/{}", self.base, leaf));
+
+        Self::publish(topic.as_str(), payload.as_bytes(), true)
+    }
+
+    /// The discovery configs do not fit the transmit buffer of the client, the packet is sized on the payload
+    fn publish(topic: &str, payload: &[u8], retain: bool) -> Result<()> {
+        let mut buffer = vec![0u8; topic.len() + payload.len() + 8];
+        let len = packet::publish(&mut buffer, topic, payload, retain)?;
+        Mqtt::write(&buffer[..len])
+    }
+}
@@ -0,0 +1,544 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! MQTT 3.1.1 packets, only the subset used by the client: QoS 0 publish,
+///! QoS 1 inbound publish acknowledge, one topic per subscribe.
+
+use core::str::from_utf8;
+
+use osal_rs::utils::{Error, Result};
+
+pub(super) const CONNECT: u8 = 0x10;
+pub(super) const CONNACK: u8 = 0x20;
+pub(super) const PUBLISH: u8 = 0x30;
+pub(super) const PUBACK: u8 = 0x40;
+pub(super) const SUBSCRIBE: u8 = 0x82;
+pub(super) const SUBACK: u8 = 0x90;
+pub(super) const PINGREQ: u8 = 0xC0;
+pub(super) const PINGRESP: u8 = 0xD0;
+pub(super) const DISCONNECT: u8 = 0xE0;
+
+const PROTOCOL_NAME: &str = "MQTT";
+const PROTOCOL_LEVEL: u8 = 4;
+
+const FLAG_CLEAN_SESSION: u8 = 0x02;
+const FLAG_WILL: u8 = 0x04;
+const FLAG_WILL_QOS_1: u8 = 0x08;
+const FLAG_WILL_RETAIN: u8 = 0x20;
+const FLAG_PASSWORD: u8 = 0x40;
+const FLAG_USER: u8 = 0x80;
+
+const FLAG_RETAIN: u8 = 0x01;
+
+const MAX_REMAINING_LENGTH: usize = 268_435_455;
+
+/// CONNACK return code of an accepted connection
+pub(super) const ACCEPTED: u8 = 0x00;
+
+/// SUBACK return code of a refused subscription
+pub(super) const SUBSCRIBE_FAILURE: u8 = 0x80;
+
+pub(super) enum Packet<'a> {
+    ConnAck { code: u8 },
+    Publish { topic: &'a str, payload: &'a [u8], packet_id: Option<u16> },
+    SubAck { packet_id: u16, code: u8 },
+    PingResp,
+    Other(u8),
+}
+
+/// Last will published by the broker when the connection is lost
+pub(super) struct Will<'a> {
+    pub(super) topic: &'a str,
+    pub(super) message: &'a str,
+}
+
+struct Writer<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer, len: 0 }
+    }
+
+    fn bytes(&mut self, value: &[u8]) -> Result<()> {
+        if self.len + value.len() > self.buffer.len() {
+            return Err(Error::OutOfMemory);
+        }
+        self.buffer[self.len..self.len + value.len()].copy_from_slice(value);
+        self.len += value.len();
+        Ok(())
+    }
+
+    #[inline]
+    fn u8(&mut self, value: u8) -> Result<()> {
+        self.bytes(&[value])
+    }
+
+    #[inline]
+    fn u16(&mut self, value: u16) -> Result<()> {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn string(&mut self, value: &[u8]) -> Result<()> {
+        if value.len() > u16::MAX as usize {
+            return Err(Error::InvalidQueueSize);
+        }
+        self.u16(value.len() as u16)?;
+        self.bytes(value)
+    }
+
+    /// Fixed header, the remaining length is a variable byte integer
+    fn header(&mut self, kind: u8, remaining: usize) -> Result<()> {
+        if remaining > MAX_REMAINING_LENGTH {
+            return Err(Error::InvalidQueueSize);
+        }
+        self.u8(kind)?;
+
+        let mut remaining = remaining;
+        loop {
+            let mut byte = (remaining % 128) as u8;
+            remaining /= 128;
+            if remaining > 0 {
+                byte |= 0x80;
+            }
+            self.u8(byte)?;
+            if remaining == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[inline]
+const fn string_len(value: &str) -> usize {
+    2 + value.len()
+}
+
+#[inline]
+fn read_u16(buffer: &[u8], offset: usize) -> Result<u16> {
+    match buffer.get(offset..offset + 2) {
+        Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]])),
+        None => Err(Error::Unhandled("MQTT packet truncated")),
+    }
+}
+
+/// Clean session CONNECT, the password is sent only together with a user
+pub(super) fn connect(buffer: &mut [u8], client_id: &str, user: &str, password: &str, keepalive: u16, will: &Will) -> Result<usize> {
+    let mut flags = FLAG_CLEAN_SESSION | FLAG_WILL | FLAG_WILL_QOS_1 | FLAG_WILL_RETAIN;
+    let mut remaining = string_len(PROTOCOL_NAME) + 4 + string_len(client_id) + string_len(will.topic) + string_len(will.message);
+    if !user.is_empty() {
+        flags |= FLAG_USER;
+        remaining += string_len(user);
+        if !password.is_empty() {
+            flags |= FLAG_PASSWORD;
+            remaining += string_len(password);
+        }
+    }
+
+    let mut writer = Writer::new(buffer);
+    writer.header(CONNECT, remaining)?;
+    writer.string(PROTOCOL_NAME.as_bytes())?;
+    writer.u8(PROTOCOL_LEVEL)?;
+    writer.u8(flags)?;
+    writer.u16(keepalive)?;
+    writer.string(client_id.as_bytes())?;
+    writer.string(will.topic.as_bytes())?;
+    writer.string(will.message.as_bytes())?;
+    if flags & FLAG_USER != 0 {
+        writer.string(user.as_bytes())?;
+    }
+    if flags & FLAG_PASSWORD != 0 {
+        writer.string(password.as_bytes())?;
+    }
+    Ok(writer.len)
+}
+
+pub(super) fn subscribe(buffer: &mut [u8], packet_id: u16, topic: &str, qos: u8) -> Result<usize> {
+    let mut writer = Writer::new(buffer);
+    writer.header(SUBSCRIBE, 2 + string_len(topic) + 1)?;
+    writer.u16(packet_id)?;
+    writer.string(topic.as_bytes())?;
+    writer.u8(qos)?;
+    Ok(writer.len)
+}
+
+/// QoS 0 PUBLISH, without packet identifier
+pub(super) fn publish(buffer: &mut [u8], topic: &str, payload: &[u8], retain: bool) -> Result<usize> {
+    let mut writer = Writer::new(buffer);
+    writer.header(PUBLISH | if retain { FLAG_RETAIN } else { 0 }, string_len(topic) + payload.len())?;
+    writer.string(topic.as_bytes())?;
+    writer.bytes(payload)?;
+    Ok(writer.len)
+}
+
+pub(super) fn puback(buffer: &mut [u8], packet_id: u16) -> Result<usize> {
+    let mut writer = Writer::new(buffer);
+    writer.header(PUBACK, 2)?;
+    writer.u16(packet_id)?;
+    Ok(writer.len)
+}
+
+pub(super) fn pingreq(buffer: &mut [u8]) -> Result<usize> {
+    let mut writer = Writer::new(buffer);
+    writer.header(PINGREQ, 0)?;
+    Ok(writer.len)
+}
+
+pub(super) fn disconnect(buffer: &mut [u8]) -> Result<usize> {
+    let mut writer = Writer::new(buffer);
+    writer.header(DISCONNECT, 0)?;
+    Ok(writer.len)
+}
+
+/// Decodes the first packet of the buffer, returns the packet and its size,
+/// or None while the packet is still incomplete
+pub(super) fn decode(buffer: &[u8]) -> Result<Option<(Packet<'_>, usize)>> {
+    if buffer.len() < 2 {
+        return Ok(None);
+    }
+
+    let mut remaining = 0usize;
+    let mut multiplier = 1usize;
+    let mut pos = 1;
+    loop {
+        let Some(&byte) = buffer.get(pos) else {
+            return Ok(None);
+        };
+        pos += 1;
+        remaining += (byte & 0x7F) as usize * multiplier;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        if pos > 4 {
+            return Err(Error::Unhandled("MQTT malformed remaining length"));
+        }
+        multiplier *= 128;
+    }
+
+    let size = pos + remaining;
+    if buffer.len() < size {
+        return Ok(None);
+    }
+
+    let kind = buffer[0];
+    let body = &buffer[pos..size];
+
+    let packet = match kind & 0xF0 {
+        CONNACK => Packet::ConnAck { code: *body.get(1).ok_or(Error::Unhandled("MQTT packet truncated"))? },
+        PUBLISH => {
+            let qos = (kind >> 1) & 0x03;
+            let topic_len = read_u16(body, 0)? as usize;
+            let topic = body.get(2..2 + topic_len).ok_or(Error::Unhandled("MQTT packet truncated"))?;
+            let topic = from_utf8(topic).map_err(|_| Error::Unhandled("MQTT invalid topic"))?;
+            let mut offset = 2 + topic_len;
+            let packet_id = if qos > 0 {
+                let packet_id = read_u16(body, offset)?;
+                offset += 2;
+                Some(packet_id)
+            } else {
+                None
+            };
+            Packet::Publish { topic, payload: &body[offset..], packet_id }
+        }
+        SUBACK => Packet::SubAck {
+            packet_id: read_u16(body, 0)?,
+            code: *body.get(2).ok_or(Error::Unhandled("MQTT packet truncated"))?,
+        },
+        PINGRESP => Packet::PingResp,
+        _ => Packet::Other(kind),
+    };
+
+    Ok(Some((packet, size)))
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::io::{Read, Write};
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use std::vec::Vec;
+
+    use osal_rs::utils::Bytes;
+
+    use crate::drivers::network::{IP4Addr, Network};
+
+    const BUFFER_SIZE: usize = 512;
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    const WILL: Will = Will { topic: "hhg/test/status", message: "offline" };
+
+    /// 127.0.0.1, the first octet in the most significant byte
+    static LOOPBACK: IP4Addr = IP4Addr { addr: 0x7F00_0001 };
+
+    #[test]
+    fn connect_encodes_the_credentials_and_the_will() {
+        let mut buffer = [0u8; BUFFER_SIZE];
+        let len = connect(&mut buffer, "hhg", "user", "secret", 60, &WILL).unwrap();
+
+        assert_eq!(buffer[0], CONNECT);
+        assert_eq!(buffer[1] as usize, len - 2);
+        assert_eq!(&buffer[2..8], b"\x00\x04MQTT");
+        assert_eq!(buffer[8], PROTOCOL_LEVEL);
+        assert_eq!(buffer[9], FLAG_CLEAN_SESSION | FLAG_WILL | FLAG_WILL_QOS_1 | FLAG_WILL_RETAIN | FLAG_USER | FLAG_PASSWORD);
+        assert_eq!(&buffer[10..12], &60u16.to_be_bytes());
+        assert!(buffer[..len].ends_with(b"\x00\x04user\x00\x06secret"));
+
+        // No user, the password is not sent either
+        let len = connect(&mut buffer, "hhg", "", "secret", 60, &WILL).unwrap();
+        assert_eq!(buffer[9] & (FLAG_USER | FLAG_PASSWORD), 0);
+        assert!(buffer[..len].ends_with(b"offline"));
+    }
+
+    #[test]
+    fn decode_waits_for_the_whole_packet() {
+        let mut buffer = [0u8; BUFFER_SIZE];
+        let payload = [b'x'; 200];
+        let len = publish(&mut buffer, "hhg/cmd", &payload, false).unwrap();
+
+        // 200 + 9 bytes of remaining length take two bytes
+        assert_eq!(&buffer[..3], &[PUBLISH, 0xD1, 0x01]);
+        assert!(decode(&buffer[..1]).unwrap().is_none());
+        assert!(decode(&buffer[..len - 1]).unwrap().is_none());
+
+        let Some((Packet::Publish { topic, payload: decoded, packet_id }, size)) = decode(&buffer[..len]).unwrap() else {
+            panic!("PUBLISH not decoded");
+        };
+        assert_eq!((topic, decoded, packet_id, size), ("hhg/cmd", &payload[..], None, len));
+    }
+
+    #[test]
+    fn decode_reads_the_packet_id_of_qos_1() {
+        let buffer = [PUBLISH | 0x02, 9, 0, 3, b'a', b'/', b'b', 0x12, 0x34, b'o', b'n'];
+
+        let Some((Packet::Publish { topic, payload, packet_id }, size)) = decode(&buffer).unwrap() else {
+            panic!("PUBLISH not decoded");
+        };
+        assert_eq!((topic, payload, packet_id, size), ("a/b", &b"on"[..], Some(0x1234), buffer.len()));
+
+        let mut ack = [0u8; 4];
+        assert_eq!(puback(&mut ack, 0x1234).unwrap(), 4);
+        assert_eq!(ack, [PUBACK, 2, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn decode_rejects_a_malformed_length() {
+        assert!(decode(&[PUBLISH, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]).is_err());
+        assert!(matches!(decode(&[SUBACK, 3, 0, 7, SUBSCRIBE_FAILURE]).unwrap(), Some((Packet::SubAck { packet_id: 7, code: SUBSCRIBE_FAILURE }, 5))));
+    }
+
+    /// Reads from the client connection until a whole packet is received
+    fn receive(buffer: &mut [u8; BUFFER_SIZE], len: &mut usize) -> (u8, Option<u16>, usize) {
+        let start = Instant::now();
+        loop {
+            if let Some((packet, size)) = decode(&buffer[..*len]).unwrap() {
+                return match packet {
+                    Packet::ConnAck { code } => (CONNACK, Some(code as u16), size),
+                    Packet::SubAck { code, .. } => (SUBACK, Some(code as u16), size),
+                    Packet::Publish { packet_id, .. } => (PUBLISH, packet_id, size),
+                    Packet::PingResp => (PINGRESP, None, size),
+                    Packet::Other(kind) => (kind & 0xF0, None, size),
+                };
+            }
+            assert!(start.elapsed() < TIMEOUT, "no packet from the broker");
+            *len += Network::tcp_read(&mut buffer[*len..]).unwrap();
+        }
+    }
+
+    fn consume(buffer: &mut [u8; BUFFER_SIZE], len: &mut usize, size: usize) {
+        buffer.copy_within(size..*len, 0);
+        *len -= size;
+    }
+
+    /// Broker on the loopback: acknowledges the CONNECT, the SUBSCRIBE and the PINGREQ, delivers
+    /// a QoS 1 command after the SUBACK and returns the kind and packet id of what the client sent
+    fn broker_stub(listener: TcpListener, topic: &str) -> Vec<(u8, Option<u16>)> {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+        let mut received = Vec::new();
+        let mut buffer = [0u8; BUFFER_SIZE];
+        let mut len = 0;
+        loop {
+            let Some((packet, size)) = decode(&buffer[..len]).unwrap() else {
+                let read = stream.read(&mut buffer[len..]).unwrap();
+                assert!(read > 0, "client gone without DISCONNECT");
+                len += read;
+                continue;
+            };
+            let kind = buffer[0] & 0xF0;
+            let packet_id = match packet {
+                Packet::Publish { packet_id, .. } => packet_id,
+                _ if kind == SUBSCRIBE & 0xF0 || kind == PUBACK => Some(read_u16(&buffer[..size], 2).unwrap()),
+                _ => None,
+            };
+            received.push((kind, packet_id));
+            consume(&mut buffer, &mut len, size);
+
+            let mut tx = [0u8; BUFFER_SIZE];
+            match kind {
+                CONNECT => stream.write_all(&[CONNACK, 2, 0, ACCEPTED]).unwrap(),
+                PINGREQ => stream.write_all(&[PINGRESP, 0]).unwrap(),
+                DISCONNECT => return received,
+                _ if kind == SUBSCRIBE & 0xF0 => {
+                    let [high, low] = packet_id.unwrap().to_be_bytes();
+                    stream.write_all(&[SUBACK, 3, high, low, 1]).unwrap();
+
+                    let mut command = Writer::new(&mut tx);
+                    command.header(PUBLISH | 0x02, string_len(topic) + 2 + 7).unwrap();
+                    command.string(topic.as_bytes()).unwrap();
+                    command.u16(9).unwrap();
+                    command.bytes(b"AT+VER?").unwrap();
+                    let len = command.len;
+                    stream.write_all(&tx[..len]).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn exchange_with_a_broker_stub() {
+        let topic = "hhg/test/command";
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let broker = thread::spawn(move || broker_stub(listener, topic));
+
+        Network::tcp_connect(&LOOPBACK, port).unwrap();
+
+        let mut tx = [0u8; BUFFER_SIZE];
+        let mut rx = [0u8; BUFFER_SIZE];
+        let mut rx_len = 0;
+
+        let len = connect(&mut tx, "hhg-test", "", "", 60, &WILL).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let (kind, code, size) = receive(&mut rx, &mut rx_len);
+        assert_eq!((kind, code), (CONNACK, Some(ACCEPTED as u16)));
+        consume(&mut rx, &mut rx_len, size);
+
+        let len = subscribe(&mut tx, 1, topic, 1).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let (kind, code, size) = receive(&mut rx, &mut rx_len);
+        assert_eq!((kind, code), (SUBACK, Some(1)));
+        consume(&mut rx, &mut rx_len, size);
+
+        // The command delivered at QoS 1 is acknowledged, the response goes out at QoS 0
+        let (kind, packet_id, size) = receive(&mut rx, &mut rx_len);
+        assert_eq!((kind, packet_id), (PUBLISH, Some(9)));
+        consume(&mut rx, &mut rx_len, size);
+        let len = puback(&mut tx, 9).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let len = publish(&mut tx, "hhg/test/response", b"+VER: 1", false).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+
+        let len = pingreq(&mut tx).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let (kind, _, _) = receive(&mut rx, &mut rx_len);
+        assert_eq!(kind, PINGRESP);
+
+        let len = disconnect(&mut tx).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        Network::tcp_close();
+
+        assert_eq!(broker.join().unwrap(), [
+            (CONNECT, None),
+            (SUBSCRIBE & 0xF0, Some(1)),
+            (PUBACK, Some(9)),
+            (PUBLISH, None),
+            (PINGREQ, None),
+            (DISCONNECT, None),
+        ]);
+    }
+
+    /// Needs a broker without authentication, HHG_MQTT_BROKER and HHG_MQTT_PORT
+    /// select it: cargo test --no-default-features --features host -- --ignored
+    #[test]
+    #[ignore]
+    fn exchange_with_the_broker() {
+        let host = env::var("HHG_MQTT_BROKER").unwrap_or_else(|_| "localhost".into());
+        let port = env::var("HHG_MQTT_PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(1883);
+        let topic = "hhg/test/command";
+
+        let address = Network::dns_resolve_addrress(&Bytes::from_str(&host)).unwrap();
+        Network::tcp_connect(address, port).unwrap();
+
+        let mut tx = [0u8; BUFFER_SIZE];
+        let mut rx = [0u8; BUFFER_SIZE];
+        let mut rx_len = 0;
+
+        let len = connect(&mut tx, "hhg-test", "", "", 60, &WILL).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let (kind, code, size) = receive(&mut rx, &mut rx_len);
+        assert_eq!((kind, code), (CONNACK, Some(ACCEPTED as u16)));
+        consume(&mut rx, &mut rx_len, size);
+
+        let len = subscribe(&mut tx, 1, topic, 1).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let (kind, code, size) = receive(&mut rx, &mut rx_len);
+        assert_eq!((kind, code), (SUBACK, Some(1)));
+        consume(&mut rx, &mut rx_len, size);
+
+        // Our own QoS 0 publish comes back at QoS 0
+        let len = publish(&mut tx, topic, b"AT+VER?", false).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let (kind, packet_id, size) = receive(&mut rx, &mut rx_len);
+        assert_eq!((kind, packet_id), (PUBLISH, None));
+        consume(&mut rx, &mut rx_len, size);
+
+        // A QoS 1 publish, the broker delivers it with a packet id and waits for the PUBACK
+        let mut qos_1 = Writer::new(&mut tx);
+        qos_1.header(PUBLISH | 0x02, string_len(topic) + 2 + 2).unwrap();
+        qos_1.string(topic.as_bytes()).unwrap();
+        qos_1.u16(7).unwrap();
+        qos_1.bytes(b"on").unwrap();
+        let len = qos_1.len;
+        Network::tcp_write(&tx[..len]).unwrap();
+
+        let mut acked = false;
+        let mut delivered = None;
+        while !acked || delivered.is_none() {
+            let (kind, packet_id, size) = receive(&mut rx, &mut rx_len);
+            match kind {
+                PUBACK => acked = true,
+                PUBLISH => delivered = packet_id,
+                _ => panic!("unexpected packet {kind:#x}"),
+            }
+            consume(&mut rx, &mut rx_len, size);
+        }
+        let len = puback(&mut tx, delivered.unwrap()).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+
+        let len = pingreq(&mut tx).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        let (kind, _, _) = receive(&mut rx, &mut rx_len);
+        assert_eq!(kind, PINGRESP);
+
+        let len = disconnect(&mut tx).unwrap();
+        Network::tcp_write(&tx[..len]).unwrap();
+        Network::tcp_close();
+    }
+}
@@ -21,24 +21,37 @@
 use core::str::from_utf8;
 use core::time::Duration;
 
+use alloc::vec;
+use alloc::vec::Vec;
 use at_parser_rs::AtError;
 use at_parser_rs::context::AtContext;
 use at_parser_rs::parser::AtParser;
 use osal_rs::{access_static_option, log_error, log_info};
-use osal_rs::os::{Queue, QueueFn, Thread, ThreadFn};
+use osal_rs::os::{Queue, QueueFn, RawMutex, RawMutexGuard, Thread, ThreadFn};
 use osal_rs::os::types::{StackType, TickType, UBaseType};
-use osal_rs::utils::{Error, Result};
-
-use crate::apps::config::{Config, DaylightSavingTime, WifiConfig, NtpConfig};
-use crate::apps::session::{Session, User};
+use osal_rs::utils::{Bytes, Error, Result};
+
+use crate::apps::audit::{AuditLog, AuditOutcome, AuditRecord};
+use crate::apps::config::{Config, DaylightSavingTime, WifiConfig, NtpConfig, MqttConfig, TelnetConfig, HttpConfig};
+use crate::apps::events::EventSubscription;
+use crate::apps::permission::{self, Form};
+use crate::apps::session::{Account, Password, Session, Totp, User};
+use crate::apps::sprinkler::budget::BudgetController;
+use crate::apps::sprinkler::dosing::DosingController;
+use crate::apps::sprinkler::group::GroupController;
+use crate::apps::sprinkler::history::HistoryController;
+use crate::apps::sprinkler::manual::ManualController;
+use crate::apps::sprinkler::profile::ProfileController;
+use crate::apps::sprinkler::protection::ProtectionController;
+use crate::apps::sprinkler::winterization::WinterizationController;
+use crate::apps::sprinkler::program::ProgramHandler;
 use crate::apps::sprinkler::schedule::ScheduleController;
+use crate::apps::sprinkler::statistics::StatisticsController;
 use crate::apps::sprinkler::zone::ZoneController;
 use crate::apps::system_handler::SystemHandler;
 use crate::drivers::platform::ThreadPriority;
 use crate::traits::rx_tx::{OnReceive, SetTransmit, Source};
-use crate::traits::signal::Signal;
 use crate::traits::state::Initializable;
-use crate::apps::signals::status::{StatusSignal, StatusFlag};
 
 
 const APP_TAG: &str = "AppParser";
@@ -51,11 +64,24 @@ const KO_RESPONSE: &str = "KO";
 const BUFFER_SIZE: usize = 256;
 const QUEUE_SIZE: UBaseType = BUFFER_SIZE as UBaseType;
 
+/// Each queued byte carries the slot of its source, the lines of the sources are built apart
+const ITEM_SIZE: usize = 2;
+
+/// Sources in the order of their session slots
+const SOURCES: [Source; 4] = [Source::Uart, Source::Mqtt, Source::Telnet, Source::Http];
+
 static mut QUEUE: Option<Queue> = None;
-static mut SOURCE: Option<Source> = None;
+
+/// Source of the line in execution, the commands check its session
+static mut ISSUER: Option<Source> = None;
 
 static mut UART_CHANNEL: Option<&'static dyn SetTransmit> = None;
 static mut MQTT_CHANNEL: Option<&'static dyn SetTransmit> = None;
+static mut TELNET_CHANNEL: Option<&'static dyn SetTransmit> = None;
+static mut HTTP_CHANNEL: Option<&'static dyn SetTransmit> = None;
+
+/// Held while a whole line is written, the URCs never break a response line
+static mut TX_MUTEX: Option<RawMutex> = None;
 
 macro_rules! at_cmd_response {
     ($at_resp:expr; $($args:expr),+) => {
@@ -75,21 +101,16 @@ pub(super) struct Parser (Thread);
 
 impl OnReceive for Parser {
     fn on_receive(&self, source: Source, data: &[u8]) -> Result<()> {
-        
-
         let queue = access_static_option!(QUEUE);
+        let slot = Session::slot(&source) as u8;
 
         for &byte in data {
             match &source {
-                Source::Uart => queue.post_from_isr(&[byte])?,
-                Source::Mqtt => queue.post_with_to_tick(&[byte], Duration::from_millis(100))?,
+                Source::Uart => queue.post_from_isr(&[slot, byte])?,
+                Source::Mqtt | Source::Telnet | Source::Http => queue.post_with_to_tick(&[slot, byte], Duration::from_millis(100))?,
             }
         }
 
-        unsafe {
-            SOURCE = Some(source);
-        }
-
         Ok(())
     }
 }
@@ -98,7 +119,7 @@ impl Initializable for Parser {
     fn init(&mut self) -> Result<()> {
         log_info!(APP_TAG, "Init app parser");
 
-        if let Ok(queue) =  Queue::new(QUEUE_SIZE, 1) {
+        if let Ok(queue) =  Queue::new(QUEUE_SIZE, ITEM_SIZE as UBaseType) {
             unsafe {
                 QUEUE = Some(queue);
             }
@@ -107,6 +128,10 @@ impl Initializable for Parser {
             return Err(Error::OutOfMemory)
         }
 
+        unsafe {
+            TX_MUTEX = Some(RawMutex::new()?);
+        }
+
         self.0 = self.0.spawn_simple(move || {
 
             let mut parser: AtParser<dyn AtContext<{Parser::CMD_SIZE}>, {Parser::CMD_SIZE}> = AtParser::new();
@@ -116,133 +141,149 @@ impl Initializable for Parser {
                 (Config::AT_CMD, Config::AT_RESP, Config::shared()),
                 (Session::AT_CMD, Session::AT_RESP, Config::shared().get_session()),
                 (User::AT_CMD, User::AT_RESP, User::get_local()),
+                (Account::AT_CMD, Account::AT_RESP, Account::get()),
+                (Password::AT_CMD, Password::AT_RESP, Password::get()),
+                (Totp::AT_CMD, Totp::AT_RESP, Totp::get()),
                 (SystemHandler::AT_CMD, SystemHandler::AT_RESP, SystemHandler::get()),
                 (DaylightSavingTime::AT_CMD, DaylightSavingTime::AT_RESP, Config::shared().get_daylight_saving_time()),
                 (WifiConfig::AT_CMD, WifiConfig::AT_RESP, Config::shared().get_wifi_config()),
                 (NtpConfig::AT_CMD, NtpConfig::AT_RESP, Config::shared().get_ntp_config_mut()),
+                (MqttConfig::AT_CMD, MqttConfig::AT_RESP, Config::shared().get_mqtt_config_mut()),
+                (TelnetConfig::AT_CMD, TelnetConfig::AT_RESP, Config::shared().get_telnet_config_mut()),
+                (HttpConfig::AT_CMD, HttpConfig::AT_RESP, Config::shared().get_http_config_mut()),
+                (EventSubscription::AT_CMD, EventSubscription::AT_RESP, EventSubscription::shared()),
                 (ScheduleController::AT_CMD, ScheduleController::AT_RESP, ScheduleController::shared()),
                 (ZoneController::AT_CMD, ZoneController::AT_RESP, ZoneController::shared()),
+                (GroupController::AT_CMD, GroupController::AT_RESP, GroupController::shared()),
+                (ProfileController::AT_CMD, ProfileController::AT_RESP, ProfileController::shared()),
+                (DosingController::AT_CMD, DosingController::AT_RESP, DosingController::shared()),
+                (ProtectionController::AT_CMD, ProtectionController::AT_RESP, ProtectionController::shared()),
+                (BudgetController::AT_CMD, BudgetController::AT_RESP, BudgetController::shared()),
+                (WinterizationController::AT_CMD, WinterizationController::AT_RESP, WinterizationController::shared()),
+                (ManualController::AT_CMD, ManualController::AT_RESP, ManualController::shared()),
+                (StatisticsController::AT_CMD, StatisticsController::AT_RESP, StatisticsController::shared()),
+                (HistoryController::AT_CMD, HistoryController::AT_RESP, HistoryController::shared()),
+                (AuditLog::AT_CMD, AuditLog::AT_RESP, AuditLog::shared()),
+                (ProgramHandler::AT_CMD, ProgramHandler::AT_RESP, ProgramHandler::get()),
             ];
 
+            // Response prefix of each command, for the lines refused before running them
+            let responses: Vec<(&'static str, &'static str)> = commands.iter().map(|(cmd, resp, _)| (*cmd, *resp)).collect();
+
             parser.set_commands(commands);
 
-            let mut buffer = [0u8; BUFFER_SIZE];
-            let mut buffer_count = 0;
+            // A line for each source, the bytes of two sources never mix
+            let mut buffers = vec![[0u8; BUFFER_SIZE]; SOURCES.len()];
+            let mut buffer_counts = [0usize; SOURCES.len()];
 
             loop {
-
-
-                let mut byte = [0u8; 1];
-                if access_static_option!(QUEUE).fetch(&mut byte, TickType::MAX).is_ok() {
-                    buffer[buffer_count] = byte[0];
-                    buffer_count += 1;
-                    if buffer_count >= buffer.len() {
-                        log_error!(APP_TAG, "Buffer overflow, data too long");
-                        buffer_count = 0; 
-                    }
+                let mut item = [0u8; ITEM_SIZE];
+                if access_static_option!(QUEUE).fetch(&mut item, TickType::MAX).is_err() {
+                    continue;
+                }
+                let [slot, byte] = item;
+                let Some(&src) = SOURCES.get(slot as usize) else {
+                    continue;
+                };
+                let buffer = &mut buffers[slot as usize];
+                let buffer_count = &mut buffer_counts[slot as usize];
+
+                buffer[*buffer_count] = byte;
+                *buffer_count += 1;
+                if *buffer_count >= buffer.len() {
+                    log_error!(APP_TAG, "Buffer overflow, data too long");
+                    *buffer_count = 0;
                 }
 
-                if buffer_count > 0 && buffer[buffer_count - 1] == b'\n' {
-                    buffer[buffer_count - 1] = 0; 
-                    buffer_count -= 1;
-                    if buffer_count > 0 && buffer[buffer_count - 1] == b'\r' {
-                        buffer[buffer_count - 1] = 0; 
-                        buffer_count -= 1; 
+                if *buffer_count > 0 && buffer[*buffer_count - 1] == b'\n' {
+                    buffer[*buffer_count - 1] = 0;
+                    *buffer_count -= 1;
+                    if *buffer_count > 0 && buffer[*buffer_count - 1] == b'\r' {
+                        buffer[*buffer_count - 1] = 0;
+                        *buffer_count -= 1;
                     }
 
-                    
-                    let src = access_static_option!(SOURCE);
-                    let source_flag = <StatusFlag as Into<u32>>::into(StatusFlag::from(src));
-
                     let channel = match src {
                         Source::Uart => *access_static_option!(UART_CHANNEL),
                         Source::Mqtt => *access_static_option!(MQTT_CHANNEL),
+                        Source::Telnet => *access_static_option!(TELNET_CHANNEL),
+                        Source::Http => *access_static_option!(HTTP_CHANNEL),
                     };
 
+                    let cmd = from_utf8(&buffer[..*buffer_count]).unwrap_or("<invalid utf-8>").trim();
 
-                    let status = StatusSignal::get();
-                    let mut is_logged = status & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) != 0;
-                    
-                    if is_logged && (status & source_flag == 0) {
-                        channel.transmit(KO_RESPONSE.as_bytes());
-                        channel.transmit(NEW_LINE.as_bytes());
-                        clear_buffer!(buffer, buffer_count);
-                        continue;
+                    unsafe {
+                        ISSUER = Some(src);
                     }
 
-                    let cmd = from_utf8(&buffer[..buffer_count]).unwrap_or("<invalid utf-8>").trim();
-                    
-                    
+                    let (name, form, args) = permission::classify(cmd);
+                    let known = responses.iter().find(|(at_cmd, _)| *at_cmd == name);
+                    let denied = known
+                        .and_then(|(_, at_response)| permission::check(cmd, Session::get_role(&src), Session::must_change_password(&src)).map(|error| (*at_response, error)));
 
-                    match parser.execute(cmd) {
-                        Ok((at_response, response)) => {
-                            let status = StatusSignal::get();
-                            let new_is_logged = status & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) != 0;
-                            if !is_logged && new_is_logged {
-                                StatusSignal::set(source_flag);
-                                is_logged = true;
-                            }
+                    // The user of the line, before a login or a logout changes it
+                    let email = Session::get_email(&src);
+
+                    let audited = known.is_some() && matches!(form, Form::Exec | Form::Set);
+
+                    // The lines that reboot or erase the device do not come back from execute,
+                    // their record is saved before running them
+                    let resets = denied.is_none() && SystemHandler::resets(name, form, args[0]);
+                    if let Some(email) = email.as_ref().filter(|_| audited && resets) {
+                        Self::audit(&src, email, name, cmd, true);
+                        AuditLog::shared().flush();
+                    }
 
+                    let result = match denied {
+                        Some((at_response, error)) => Err((at_response, AtError::Unhandled(error))),
+                        None => parser.execute(cmd),
+                    };
+
+                    // A reboot or an erase that came back has failed, it gets a second record
+                    if let Some(email) = email.filter(|_| audited) {
+                        let ok = match &result {
+                            Ok(_) => true,
+                            Err((_, AtError::Unhandled(error))) => error.is_empty(),
+                            Err(_) => false,
+                        };
+                        Self::audit(&src, &email, name, cmd, ok);
+                    }
+
+                    match result {
+                        Ok((at_response, response)) => {
                             if response.is_empty() {
-                                channel.transmit(at_response.as_bytes());
-                                channel.transmit(OK_RESPONSE.as_bytes());
-                                channel.transmit(NEW_LINE.as_bytes());
+                                Self::transmit_line(channel, &[at_response.as_bytes(), OK_RESPONSE.as_bytes()]);
                             } else {
-                                channel.transmit(at_response.as_bytes());
-                                channel.transmit(response.as_raw_bytes());
-                                channel.transmit(NEW_LINE.as_bytes());
+                                Self::transmit_line(channel, &[at_response.as_bytes(), response.as_raw_bytes()]);
                             }
-
-                            if is_logged {
-                                Session::reset_timer(); // Reset the session timer on each command if logged in
-                            }    
                         }
                         Err((at_response, AtError::Unhandled(error)))  => {
                             if error.is_empty() {
-                                channel.transmit(at_response.as_bytes());
-                                channel.transmit(OK_RESPONSE.as_bytes());
-                                channel.transmit(NEW_LINE.as_bytes());
+                                Self::transmit_line(channel, &[at_response.as_bytes(), OK_RESPONSE.as_bytes()]);
                             } else {
-                                channel.transmit(at_response.as_bytes());
-                                channel.transmit(error.as_bytes());
-                                channel.transmit(NEW_LINE.as_bytes());
-                            }
-
-                            if is_logged {
-                                Session::reset_timer(); // Reset the session timer on each command if logged in
+                                Self::transmit_line(channel, &[at_response.as_bytes(), error.as_bytes()]);
                             }
                         }
                         Err((at_response, AtError::UnhandledOwned(error)))  => {
                             if error.is_empty() {
-                                channel.transmit(at_response.as_bytes());
-                                channel.transmit(KO_RESPONSE.as_bytes());
-                                channel.transmit(NEW_LINE.as_bytes());
+                                Self::transmit_line(channel, &[at_response.as_bytes(), KO_RESPONSE.as_bytes()]);
                             } else {
-                                channel.transmit(at_response.as_bytes());
-                                channel.transmit(error.as_bytes());
-                                channel.transmit(NEW_LINE.as_bytes());
-                            }
-
-
-                            if is_logged {
-                                Session::reset_timer(); // Reset the session timer on each command if logged in
+                                Self::transmit_line(channel, &[at_response.as_bytes(), error.as_bytes()]);
                             }
                         }
                         Err((at_response, _)) => {
-                            channel.transmit(at_response.as_bytes());
-                            channel.transmit(KO_RESPONSE.as_bytes());
-                            channel.transmit(NEW_LINE.as_bytes());
+                            Self::transmit_line(channel, &[at_response.as_bytes(), KO_RESPONSE.as_bytes()]);
                         }
                     }
 
-                    if !is_logged {
-                        StatusSignal::clear(source_flag); // Clear the status flag for the source of the command
+                    if Session::is_logged(&src) {
+                        Session::reset_timer(&src); // Reset the session timer of the source on each command if logged in
                     }
-                    
+
                     unsafe {
-                        SOURCE = None;
+                        ISSUER = None;
                     }
-                    clear_buffer!(buffer, buffer_count);
+                    clear_buffer!(buffer, *buffer_count);
                 }
             }
         })?;
@@ -254,6 +295,8 @@ impl Initializable for Parser {
 
 impl Parser {
     pub(super) const NOT_LOGGED_RESPONSE: &str = "KO: Not logged in";
+    pub(super) const DENIED_RESPONSE: &str = "KO: Permission denied";
+    pub(super) const PASSWORD_CHANGE_RESPONSE: &str = "KO: Password change required";
     pub(super) const CMD_SIZE : usize = 96;
 
 
@@ -264,11 +307,73 @@ impl Parser {
         }
     }
 
+    #[inline]
+    pub(super) fn set_mqtt_transmit(transmit: &'static dyn SetTransmit) {
+        unsafe {
+            MQTT_CHANNEL = Some(transmit);
+        }
+    }
+
+    #[inline]
+    pub(super) fn set_telnet_transmit(transmit: &'static dyn SetTransmit) {
+        unsafe {
+            TELNET_CHANNEL = Some(transmit);
+        }
+    }
+
+    #[inline]
+    pub(super) fn set_http_transmit(transmit: &'static dyn SetTransmit) {
+        unsafe {
+            HTTP_CHANNEL = Some(transmit);
+        }
+    }
+
 
-    #[allow(unused)]
+    /// Source of the command in execution, None outside the parser thread
     #[inline]
     pub(super) fn get_source() -> Option<Source> {
-        unsafe { SOURCE }
+        unsafe { ISSUER }
+    }
+
+    /// Write an unsolicited result code on the channel of the source, if set
+    pub(super) fn transmit_urc(source: Source, urc: &[u8]) {
+        let channel = match source {
+            Source::Uart => unsafe { *&raw const UART_CHANNEL },
+            Source::Mqtt => unsafe { *&raw const MQTT_CHANNEL },
+            Source::Telnet => unsafe { *&raw const TELNET_CHANNEL },
+            Source::Http => unsafe { *&raw const HTTP_CHANNEL },
+        };
+
+        if let Some(channel) = channel {
+            Self::transmit_line(channel, &[urc]);
+        }
+    }
+
+    /// Records the set or exec line run by the user, the command and its verb without the arguments.
+    /// The reads of the audit log are not recorded, they would shift the records being read
+    fn audit(source: &Source, email: &Bytes<32>, name: &str, line: &str, ok: bool) {
+        if name == AuditLog::AT_CMD {
+            return;
+        }
+
+        let outcome = if ok { AuditOutcome::Ok } else { AuditOutcome::Failed };
+        let verb = permission::verb(line).unwrap_or_default();
+        AuditLog::shared().add(AuditRecord::event(Session::slot(source), email.as_str(), name, verb, outcome));
+
+        // The line that ended the session is saved with the rest of it
+        if !Session::is_logged(source) {
+            AuditLog::shared().flush();
+        }
+    }
+
+    /// Write the parts and the line terminator without other lines in between
+    fn transmit_line(channel: &dyn SetTransmit, parts: &[&[u8]]) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(TX_MUTEX));
+
+        for part in parts {
+            channel.transmit(part);
+        }
+        channel.transmit(NEW_LINE.as_bytes());
     }
 
     #[inline]
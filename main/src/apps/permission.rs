@@ -0,0 +1,238 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+///! Rights of the roles on the AT commands, checked by the parser before running a line
+///! on the session of the source it comes from.
+
+use crate::apps::audit::AuditLog;
+use crate::apps::config::{Config, DaylightSavingTime, HttpConfig, MqttConfig, NtpConfig, TelnetConfig, WifiConfig};
+use crate::apps::events::EventSubscription;
+use crate::apps::parser::Parser;
+use crate::apps::session::{Account, Password, Role, Session, User};
+use crate::apps::sprinkler::budget::BudgetController;
+use crate::apps::sprinkler::dosing::DosingController;
+use crate::apps::sprinkler::group::GroupController;
+use crate::apps::sprinkler::history::HistoryController;
+use crate::apps::sprinkler::manual::ManualController;
+use crate::apps::sprinkler::profile::ProfileController;
+use crate::apps::sprinkler::program::ProgramHandler;
+use crate::apps::sprinkler::protection::ProtectionController;
+use crate::apps::sprinkler::schedule::ScheduleController;
+use crate::apps::sprinkler::statistics::StatisticsController;
+use crate::apps::sprinkler::winterization::WinterizationController;
+use crate::apps::sprinkler::zone::ZoneController;
+use crate::apps::system_handler::SystemHandler;
+
+/// Matches every command
+const ANY: &str = "*";
+
+/// Commands with a set form of values only, passwords among them, that have no verb to audit
+const NO_VERB: &[&str] = &[Password::AT_CMD, User::AT_CMD, WifiConfig::AT_CMD];
+
+/// Longest verb of a set form
+const VERB_SIZE: usize = 3;
+
+/// Target of the commands that put it before the verb and is not a number, the budget total
+const TARGET_WORDS: &[&str] = &["tt"];
+
+/// Form of an AT command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Form {
+    /// `AT+CMD`
+    Exec,
+    /// `AT+CMD?`
+    Query,
+    /// `AT+CMD=?`
+    Test,
+    /// `AT+CMD=<args>`
+    Set,
+}
+
+/// Role needed by a form of a command, the first entry that matches the line applies
+struct Permission {
+    cmd: &'static str,
+    form: Form,
+
+    /// index of the set form argument the command dispatches on and its value, None for any
+    verb: Option<(usize, &'static str)>,
+
+    /// None when no login is needed
+    role: Option<Role>,
+}
+
+impl Permission {
+    const fn new(cmd: &'static str, form: Form, verb: Option<(usize, &'static str)>, role: Option<Role>) -> Self {
+        Self { cmd, form, verb, role }
+    }
+
+    /// A verb after the target matches only behind a target, the commands dispatch on their verbs
+    /// of the first argument before reading a target
+    fn matches(&self, cmd: &str, form: Form, args: &[Option<&str>; 2]) -> bool {
+        (self.cmd == ANY || self.cmd == cmd) && self.form == form && self.verb.is_none_or(|(index, verb)| {
+            args.get(index) == Some(&Some(verb)) && args[..index].iter().all(|arg| arg.is_some_and(is_target))
+        })
+    }
+}
+
+/// The lines not listed here need an admin
+const PERMISSIONS: &[Permission] = &[
+    // admin: the switch of the plain login, ahead of the open login verbs
+    Permission::new(Session::AT_CMD, Form::Set, Some((0, "pl")), Some(Role::Admin)),
+
+    // open: syntax, login and the settings readable without a session
+    Permission::new(ANY, Form::Test, None, None),
+    Permission::new(Session::AT_CMD, Form::Exec, None, None),
+    Permission::new(Session::AT_CMD, Form::Query, None, None),
+    Permission::new(Session::AT_CMD, Form::Set, None, None),
+    Permission::new(SystemHandler::AT_CMD, Form::Query, None, None),
+    Permission::new(Config::AT_CMD, Form::Query, None, None),
+    Permission::new(DaylightSavingTime::AT_CMD, Form::Query, None, None),
+    Permission::new(WifiConfig::AT_CMD, Form::Query, None, None),
+    Permission::new(NtpConfig::AT_CMD, Form::Query, None, None),
+    Permission::new(MqttConfig::AT_CMD, Form::Query, None, None),
+    Permission::new(TelnetConfig::AT_CMD, Form::Query, None, None),
+    Permission::new(HttpConfig::AT_CMD, Form::Query, None, None),
+
+    // admin: the users, the audit log and the reboots
+    Permission::new(User::AT_CMD, Form::Query, None, Some(Role::Admin)),
+    Permission::new(Account::AT_CMD, Form::Query, None, Some(Role::Admin)),
+    Permission::new(AuditLog::AT_CMD, Form::Query, None, Some(Role::Admin)),
+    Permission::new(SystemHandler::AT_CMD, Form::Set, Some((0, "rs")), Some(Role::Admin)),
+    Permission::new(SystemHandler::AT_CMD, Form::Set, Some((0, "fr")), Some(Role::Admin)),
+
+    // viewer: every other read, its own password and the notifications of its own channel
+    Permission::new(ANY, Form::Query, None, Some(Role::Viewer)),
+    Permission::new(Password::AT_CMD, Form::Set, None, Some(Role::Viewer)),
+    Permission::new(SystemHandler::AT_CMD, Form::Set, None, Some(Role::Viewer)),
+    Permission::new(EventSubscription::AT_CMD, Form::Set, None, Some(Role::Viewer)),
+    Permission::new(ZoneController::AT_CMD, Form::Set, Some((1, "rd")), Some(Role::Viewer)),
+    Permission::new(ProfileController::AT_CMD, Form::Set, Some((1, "rd")), Some(Role::Viewer)),
+    Permission::new(BudgetController::AT_CMD, Form::Set, Some((1, "rd")), Some(Role::Viewer)),
+    Permission::new(StatisticsController::AT_CMD, Form::Set, Some((1, "rd")), Some(Role::Viewer)),
+    Permission::new(ProgramHandler::AT_CMD, Form::Set, Some((0, "ex")), Some(Role::Viewer)),
+    Permission::new(ProgramHandler::AT_CMD, Form::Set, Some((0, "rd")), Some(Role::Viewer)),
+    Permission::new(HistoryController::AT_CMD, Form::Set, Some((0, "rs")), Some(Role::Operator)),
+    Permission::new(HistoryController::AT_CMD, Form::Set, None, Some(Role::Viewer)),
+
+    // operator: runs and irrigation settings
+    Permission::new(ManualController::AT_CMD, Form::Exec, None, Some(Role::Operator)),
+    Permission::new(ManualController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(ScheduleController::AT_CMD, Form::Exec, None, Some(Role::Operator)),
+    Permission::new(ScheduleController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(ZoneController::AT_CMD, Form::Exec, None, Some(Role::Operator)),
+    Permission::new(ZoneController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(WinterizationController::AT_CMD, Form::Exec, None, Some(Role::Operator)),
+    Permission::new(WinterizationController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(ProgramHandler::AT_CMD, Form::Exec, None, Some(Role::Operator)),
+    Permission::new(ProgramHandler::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(GroupController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(ProfileController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(DosingController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(ProtectionController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(BudgetController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+    Permission::new(StatisticsController::AT_CMD, Form::Set, None, Some(Role::Operator)),
+];
+
+/// Zone, profile or group number or a target word
+fn is_target(arg: &str) -> bool {
+    arg.parse::<u8>().is_ok() || TARGET_WORDS.contains(&arg)
+}
+
+/// Command, form and first two arguments of a line
+pub(super) fn classify(line: &str) -> (&str, Form, [Option<&str>; 2]) {
+    match line.split_once('=') {
+        Some((cmd, "?")) => (cmd, Form::Test, [None; 2]),
+        Some((cmd, args)) => {
+            let mut args = args.split(',').map(str::trim);
+            (cmd, Form::Set, [args.next(), args.next()])
+        }
+        None => match line.strip_suffix('?') {
+            Some(cmd) => (cmd, Form::Query, [None; 2]),
+            None => (line, Form::Exec, [None; 2]),
+        },
+    }
+}
+
+/// None when the role may run the line, otherwise the error to send back,
+/// a user that must change its password runs only the lines open to everyone and AT+PWD
+pub(super) fn check(line: &str, role: Option<Role>, must_change: bool) -> Option<&'static str> {
+    let (cmd, form, args) = classify(line);
+
+    let required = PERMISSIONS.iter()
+        .find(|permission| permission.matches(cmd, form, &args))
+        .map_or(Some(Role::Admin), |permission| permission.role);
+
+    match (required, role) {
+        (None, _) => None,
+        (Some(_), None) => Some(Parser::NOT_LOGGED_RESPONSE),
+        (Some(_), Some(_)) if must_change && !(cmd == Password::AT_CMD && form == Form::Set) => Some(Parser::PASSWORD_CHANGE_RESPONSE),
+        (Some(required), Some(role)) if role >= required => None,
+        (Some(_), Some(_)) => Some(Parser::DENIED_RESPONSE),
+    }
+}
+
+/// Verb of a set form line, the first of its first two arguments made of a few lowercase letters.
+/// None for the other forms and for the commands without verbs, whose arguments may be secrets
+pub(super) fn verb(line: &str) -> Option<&str> {
+    let (cmd, form, args) = classify(line);
+    if form != Form::Set || NO_VERB.contains(&cmd) {
+        return None;
+    }
+    args.into_iter()
+        .flatten()
+        .find(|arg| (1..=VERB_SIZE).contains(&arg.len()) && arg.bytes().all(|byte| byte.is_ascii_lowercase()))
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewers_read_behind_a_target() {
+        for line in ["AT+ZN=0,rd", "AT+PRF=1,rd", "AT+BDG=2,rd", "AT+BDG=tt,rd", "AT+STAT=3,rd", "AT+PRG=ex", "AT+PRG=rd,0"] {
+            assert_eq!(check(line, Some(Role::Viewer), false), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn viewers_do_not_write_with_a_read_verb_in_the_other_argument() {
+        for line in [
+            "AT+ZN=fm,rd",
+            "AT+ZN=sv,rd",
+            "AT+PRF=sv,rd",
+            "AT+BDG=rs,rd",
+            "AT+BDG=sv,rd",
+            "AT+STAT=rs,rd",
+            "AT+PRG=bg,ex",
+            "AT+PRG=bg,rd",
+            "AT+PRG=im,rd",
+        ] {
+            assert_eq!(check(line, Some(Role::Viewer), false), Some(Parser::DENIED_RESPONSE), "{line}");
+        }
+    }
+
+    #[test]
+    fn only_the_dispatched_verb_needs_an_admin() {
+        assert_eq!(check("AT+SYS=rs", Some(Role::Operator), false), Some(Parser::DENIED_RESPONSE));
+        assert_eq!(check("AT+SYS=fr", Some(Role::Operator), false), Some(Parser::DENIED_RESPONSE));
+        assert_eq!(check("AT+HIST=0,rs", Some(Role::Viewer), false), None);
+        assert_eq!(check("AT+HIST=rs", Some(Role::Viewer), false), Some(Parser::DENIED_RESPONSE));
+    }
+}
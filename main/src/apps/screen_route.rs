@@ -22,6 +22,7 @@
 mod auth;
 mod date_time;
 mod daylight_saving_time;
+mod enroll;
 mod info;
 mod login;
 mod main;
@@ -41,6 +42,7 @@ use osal_rs::os::types::EventBits;
 use osal_rs::utils::Result;
 use set_config::ScreenSetConfig;
 use crate::apps::config::Config;
+use crate::apps::screen_route::enroll::ScreenEnroll;
 use crate::apps::screen_route::info::ScreenInfo;
 use crate::apps::screen_route::login::ScreenLogin;
 use crate::apps::screen_route::main::{ScreenMain, FSMState as MainFSMState};
@@ -49,6 +51,7 @@ use crate::apps::screen_route::daylight_saving_time::ScreenDaylightSavingTime;
 use crate::apps::screen_route::sprinkler::ScreenSprinkler;
 use crate::apps::screen_route::wifi::ScreenWifi;
 use crate::apps::screen_route::user::ScreenUser;
+use crate::apps::session::{Session, Totp};
 use crate::apps::signals::display::{DisplayFlag, DisplaySignal};
 use crate::apps::signals::status::StatusFlag;
 use crate::traits::rtc::RTC;
@@ -72,6 +75,7 @@ enum FSMState {
     MenuWifi,
     MenuUser,
     MenuSprinkler,
+    Enroll,
 }
 
 impl From<i8> for FSMState {
@@ -87,6 +91,7 @@ impl From<i8> for FSMState {
             7 => FSMState::MenuWifi,
             8 => FSMState::MenuUser,
             9 => FSMState::MenuSprinkler,
+            10 => FSMState::Enroll,
             _ => FSMState::Init, // Default case
         }
     }
@@ -105,6 +110,7 @@ impl From<FSMState> for i8 {
             FSMState::MenuWifi => 7,
             FSMState::MenuUser => 8,
             FSMState::MenuSprinkler => 9,
+            FSMState::Enroll => 10,
         }
     }
 }
@@ -125,6 +131,14 @@ impl ScreenRouteFn for ScreenRoute {
         status_signal: &mut EventBits, 
         rtc: &Arc<Mutex<dyn RTC + 'static>>,
     ) -> Result<()> {
+
+        // A TOTP enrollment shows its QR code over any screen but the first configuration
+        if !matches!(self.fsm_state, FSMState::SetConfig | FSMState::Enroll) && Totp::take_display() {
+            self.current_screen = None;
+            self.fsm_state = FSMState::Enroll;
+            *display_signal &= !Self::BUTTON_MASK;
+            Self::request_redraw(display_signal);
+        }
         
         match self.fsm_state {
             FSMState::Init                      => self.handle_init(status_signal),
@@ -136,7 +150,8 @@ impl ScreenRouteFn for ScreenRoute {
             FSMState::MenuDaylightSavingTime    => self.handle_submenu(lcd, display_signal, status_signal, rtc, MainFSMState::DaylightSavingTime, || Box::new(ScreenDaylightSavingTime::new())),
             FSMState::MenuWifi                  => self.handle_submenu(lcd, display_signal, status_signal, rtc, MainFSMState::Wifi, || Box::new(ScreenWifi::new())),
             FSMState::MenuUser                  => self.handle_submenu(lcd, display_signal, status_signal, rtc, MainFSMState::User, || Box::new(ScreenUser::new())),
-            FSMState::MenuSprinkler             => self.handle_submenu(lcd, display_signal, status_signal, rtc, MainFSMState::Sprinkler, move || Box::new(ScreenSprinkler::new()))
+            FSMState::MenuSprinkler             => self.handle_submenu(lcd, display_signal, status_signal, rtc, MainFSMState::Sprinkler, move || Box::new(ScreenSprinkler::new())),
+            FSMState::Enroll                    => self.handle_enroll(lcd, display_signal, status_signal, rtc),
             
         }
 
@@ -235,6 +250,25 @@ impl ScreenRoute {
         
     }
 
+    fn handle_enroll(
+        &mut self,
+        lcd: &mut dyn LCDDisplayFn,
+        display_signal: &mut EventBits,
+        status_signal: &mut EventBits,
+        rtc: &Arc<Mutex<dyn RTC + 'static>>
+    ) {
+        if self.current_screen.is_none() {
+            self.current_screen = Some(Box::new(ScreenEnroll::new()));
+        }
+        if let Some(screen) = &mut self.current_screen {
+            if screen.draw(lcd, display_signal, status_signal, rtc).is_ok() {
+                self.current_screen = None;
+                self.fsm_state = FSMState::Init;
+                Self::request_redraw(display_signal);
+            }
+        }
+    }
+
     fn handle_menu(
         &mut self,
         lcd: &mut dyn LCDDisplayFn,
@@ -278,7 +312,7 @@ impl ScreenRoute {
         back: MainFSMState,
         build_screen: impl FnOnce() -> Box<dyn ScreenRouteFn>,
     ) {
-        if self.has_local_user && !StatusFlag::UserLogged.check_signal(*status_signal) && MainFSMState::Info != back {
+        if self.has_local_user && !Session::is_local_logged() && MainFSMState::Info != back {
             self.main_fsm_state = back;
             self.fsm_state = FSMState::Login;
             Self::request_redraw(display_signal);
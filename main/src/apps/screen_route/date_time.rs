@@ -32,10 +32,12 @@ use crate::apps::display::date::Date;
 use crate::apps::display::time::Time;
 use crate::apps::signals::display::DisplayFlag;
 use crate::apps::signals::error::ErrorFlag;
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
 use crate::drivers::date_time::DateTime;
 use crate::traits::lcd_display::LCDDisplayFn;
 use crate::traits::rtc::RTC;
 use crate::traits::screen::{Screen, ScreenParam, ScreenRoute};
+use crate::traits::signal::Signal;
 
 static mut FSM_STATE: FSMState = FSMState::Date;
 static UPDATE_DRAW: AtomicBool = AtomicBool::new(false);
@@ -146,6 +148,7 @@ impl ScreenDateTime {
         let DateTime { hour, minute, second, .. } = self.time.get_value()?;
         let date_time = DateTime::new(year, month, wday, mday, hour, minute, second)?;
         rtc.lock()?.set_timestamp(date_time.to_timestamp())?;
+        SchedulerSignal::set(SchedulerFlag::ClockChanged.into());
 
         unsafe { FSM_STATE = FSMState::End; }
         UPDATE_DRAW.store(true, Ordering::SeqCst);
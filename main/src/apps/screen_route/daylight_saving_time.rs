@@ -30,10 +30,12 @@ use crate::apps::DISPLAY_INPUT_MAX_SIZE;
 use crate::apps::config::Config;
 use crate::apps::display::check::Check;
 use crate::apps::signals::display::DisplayFlag;
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
 use crate::drivers::date_time::DateTime;
 use crate::traits::lcd_display::LCDDisplayFn;
 use crate::traits::rtc::RTC;
 use crate::traits::screen::{Screen, ScreenParam, ScreenRoute};
+use crate::traits::signal::Signal;
 
 static mut FSM_STATE: FSMState = FSMState::Enable;
 static UPDATE_DRAW: AtomicBool = AtomicBool::new(false);
@@ -116,6 +118,7 @@ impl ScreenDaylightSavingTime {
         DateTime::set_daylight_saving_time(enabled);
         Config::shared().get_daylight_saving_time().set_enabled(enabled);
         Config::shared().apply_daylight_saving_time();
+        SchedulerSignal::set(SchedulerFlag::ClockChanged.into());
         Config::save()?;
 
         unsafe { FSM_STATE = FSMState::End; }
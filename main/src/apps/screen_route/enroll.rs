@@ -0,0 +1,94 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::any::Any;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use alloc::sync::Arc;
+use osal_rs::os::Mutex;
+use osal_rs::os::types::EventBits;
+use osal_rs::utils::{Error, Result};
+
+use crate::apps::display::qr::Qr;
+use crate::apps::session::Totp;
+use crate::traits::lcd_display::LCDDisplayFn;
+use crate::traits::rtc::RTC;
+use crate::traits::screen::{Screen, ScreenParam, ScreenRoute};
+
+static BACK: AtomicBool = AtomicBool::new(false);
+
+/// QR code of the TOTP secret started with `AT+OTP=en`, until a button or the confirmation
+pub(super) struct ScreenEnroll {
+    qr: Qr,
+}
+
+impl ScreenRoute for ScreenEnroll {
+    #[allow(unused)]
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    #[allow(unused)]
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn draw(&mut self, 
+        lcd: &mut dyn LCDDisplayFn,
+        display_signal: &mut EventBits, 
+        _status_signal: &mut EventBits, 
+        rtc: &Arc<Mutex<dyn RTC + 'static>>,
+        
+    ) -> Result<()> {
+
+        // Confirmed, cancelled or replaced by a logout
+        let Some(uri) = Totp::enrollment_uri() else {
+            return Ok(());
+        };
+
+        self.qr.draw(
+            lcd, 
+            display_signal, 
+            rtc, 
+            &uri, 
+            ScreenParam::<u16>::default(), 
+            Some(|_, _| {
+                BACK.store(true, Ordering::Relaxed);
+            })
+        )?;
+
+        if BACK.load(Ordering::SeqCst) {
+            BACK.store(false, Ordering::SeqCst);
+            Ok(())
+        } else {
+            Err(Error::ReturnWithCode(1))
+        }
+    }
+}
+
+impl ScreenEnroll {
+    pub(super) fn new() -> Self {
+        Self {
+            qr: Qr::new(),
+        }
+    }
+}
@@ -19,7 +19,7 @@
  ***************************************************************************/
 
 use core::any::Any;
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use alloc::sync::Arc;
 use osal_rs::os::Mutex;
@@ -29,6 +29,9 @@ use osal_rs::utils::{Bytes, Error, Result};
 use crate::apps::config::Config;
 use crate::apps::display::text::Text;
 use crate::apps::DISPLAY_INPUT_MAX_SIZE;
+use crate::apps::signals::display::DisplayFlag;
+use crate::apps::sprinkler::statistics::StatisticsController;
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
 use crate::apps::wifi::Wifi;
 use crate::traits::lcd_display::LCDDisplayFn;
 use crate::traits::rtc::RTC;
@@ -36,6 +39,11 @@ use crate::traits::screen::{Screen, ScreenParam, ScreenRoute};
 
 static BACK: AtomicBool = AtomicBool::new(false);
 
+/// Page shown, 0 is the network info then one page of statistics for each zone
+static PAGE: AtomicUsize = AtomicUsize::new(0);
+
+const PAGES: usize = ZoneController::SIZE + 1;
+
 pub(super) struct ScreenInfo {
     text: Text,
 }
@@ -61,12 +69,29 @@ impl ScreenRoute for ScreenInfo {
         
     ) -> Result<()> {
 
-        let mut text = Bytes::<DISPLAY_INPUT_MAX_SIZE>::new();
+        let page = PAGE.load(Ordering::SeqCst);
+        if *display_signal & DisplayFlag::EncoderRotatedClockwise as u32 != 0 {
+            PAGE.store((page + 1) % PAGES, Ordering::SeqCst);
+        } else if *display_signal & DisplayFlag::EncoderRotatedCounterClockwise as u32 != 0 {
+            PAGE.store((page + PAGES - 1) % PAGES, Ordering::SeqCst);
+        }
 
-        if Config::shared().get_wifi_config().is_enabled() {
-            text.format(format_args!("Ip Address|{}", Wifi::get_ip_address()));
-        } else {
-            text.append_str("Wifi: Disabled");
+        let mut text = Bytes::<{DISPLAY_INPUT_MAX_SIZE * 2}>::new();
+
+        match PAGE.load(Ordering::SeqCst) {
+            0 if Config::shared().get_wifi_config().is_enabled() => {
+                text.format(format_args!("Ip Address|{}", Wifi::get_ip_address()));
+            }
+            0 => {
+                text.append_str("Wifi: Disabled");
+            }
+            page => {
+                let zone_relay = ZoneRelay::from((page - 1) as u8);
+                let description = ZoneController::shared().get(zone_relay).map_or("", |zone| zone.description.as_str());
+                if let Some(statistics) = StatisticsController::shared().get(zone_relay) {
+                    text.format(format_args!("{}|{} runs {} min", description, statistics.runs, statistics.run_minutes));
+                }
+            }
         }
 
         self.text.draw(
@@ -82,6 +107,7 @@ impl ScreenRoute for ScreenInfo {
 
         if BACK.load(Ordering::SeqCst) {
             BACK.store(false, Ordering::SeqCst);
+            PAGE.store(0, Ordering::SeqCst);
             Ok(())
         } else {
             Err(Error::ReturnWithCode(1))
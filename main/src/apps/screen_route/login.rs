@@ -22,8 +22,6 @@ use core::any::Any;
 use core::sync::atomic::{AtomicBool, Ordering};
 
 use alloc::sync::Arc;
-use at_parser_rs::Args;
-use at_parser_rs::context::AtContext;
 use osal_rs::os::Mutex;
 use osal_rs::os::types::EventBits;
 use osal_rs::utils::{Bytes, Error, Result};
@@ -45,6 +43,8 @@ static LOGGED: AtomicBool = AtomicBool::new(false);
 enum FSMState {
     Email,
     EmailPasswd,
+    Code,
+    Verify,
     Status,
     End,
 }
@@ -53,6 +53,7 @@ pub(super) struct ScreenLogin {
     config: &'static mut Config,
     email: Input,
     email_passwd: Input,
+    code: Input,
     status: Text,
 }
 
@@ -86,6 +87,8 @@ impl ScreenRoute for ScreenLogin {
         match fsm_state {
             FSMState::Email => self.handle_email(lcd, display_signal, rtc)?,
             FSMState::EmailPasswd => self.handle_email_passwd(lcd, display_signal, rtc)?,
+            FSMState::Code => self.handle_code(lcd, display_signal, rtc)?,
+            FSMState::Verify => self.handle_verify()?,
             FSMState::Status => self.handle_status(lcd, display_signal, rtc)?,
             FSMState::End => return Ok(())
         }
@@ -105,6 +108,7 @@ impl ScreenLogin {
             config: Config::shared(),
             email: Input::new(),
             email_passwd: Input::new(),
+            code: Input::new(),
             status: Text::new(),
         }
     }
@@ -155,7 +159,7 @@ impl ScreenLogin {
             ScreenParam::<u16>::default(),
             Some(|_, confirmed| {
                 if confirmed {
-                    Self::set_state(FSMState::Status);
+                    Self::set_state(FSMState::Verify);
                 } else {
                     Self::set_state(FSMState::Email);
                 }
@@ -165,35 +169,57 @@ impl ScreenLogin {
         Ok(())
     }
 
-    fn handle_status(&mut self, 
-        lcd: &mut dyn LCDDisplayFn, 
-        display_signal: &mut EventBits, 
-        rtc: &Arc<Mutex<dyn RTC + 'static>>
-    ) -> Result<()> {
+    fn handle_code(&mut self, lcd: &mut dyn LCDDisplayFn, display_signal: &mut EventBits, rtc: &Arc<Mutex<dyn RTC + 'static>>) -> Result<()> {
 
-        let email = self.email.get_value()?;
-        let email_passwd = self.email_passwd.get_value()?;
+        self.code.draw(
+            lcd,
+            display_signal,
+            rtc,
+            &Bytes::<DISPLAY_INPUT_MAX_SIZE>::from_str("Login Code"),
+            ScreenParam::<u16>::default(),
+            Some(|_, confirmed| {
+                if confirmed {
+                    Self::set_state(FSMState::Verify);
+                } else {
+                    Self::set_state(FSMState::EmailPasswd);
+                }
+            }),
+        )?;
 
-        let mut cmd = Bytes::<DISPLAY_INPUT_MAX_SIZE>::new();
-        cmd.format(format_args!("AT+SESS=i,{email},{email_passwd}", email = email.as_str(), email_passwd = email_passwd.as_str()));
+        Ok(())
+    }
 
-        let args: Args = Args {
-            raw: cmd.as_str()
-        };
+    /// Logs in once, a code of the second factor is accepted a single time
+    fn handle_verify(&mut self) -> Result<()> {
 
-        match self.config.get_session().set("", args) {
-            Ok(_) => {
-                if let Ok(_) = self.config.get_session().exec("") {
-                    LOGGED.store(true, Ordering::SeqCst);
-                } else {
-                    LOGGED.store(false, Ordering::SeqCst);
+        let email = self.email.get_value()?;
+        let email_passwd = self.email_passwd.get_value()?;
+
+        let session = self.config.get_session();
+        let code = if session.needs_code(email.as_str()) {
+            match self.code.get_value() {
+                Ok(code) => Some(code),
+                Err(_) => {
+                    Self::set_state(FSMState::Code);
+                    return Ok(());
                 }
-            },
-            Err(_) => {
-                LOGGED.store(false, Ordering::SeqCst);
             }
+        } else {
+            None
         };
 
+        let logged = session.login_local(email.as_str(), email_passwd.as_str(), code.as_ref().map(|code| code.as_str()));
+        LOGGED.store(logged, Ordering::SeqCst);
+        self.code = Input::new();
+        Self::set_state(FSMState::Status);
+        Ok(())
+    }
+
+    fn handle_status(&mut self, 
+        lcd: &mut dyn LCDDisplayFn, 
+        display_signal: &mut EventBits, 
+        rtc: &Arc<Mutex<dyn RTC + 'static>>
+    ) -> Result<()> {
 
         let mut text = Bytes::<DISPLAY_INPUT_MAX_SIZE>::new();
         if LOGGED.load(Ordering::SeqCst) {
@@ -38,6 +38,7 @@ use crate::apps::display::time::Time;
 use crate::apps::session::User;
 use crate::apps::signals::display::DisplayFlag;
 use crate::apps::signals::error::ErrorFlag;
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
 use crate::apps::screen_route::auth::{fill_auth_selections, selected_auth_from_selections};
 use crate::drivers::date_time::DateTime;
 use crate::drivers::encrypt::EncryptGeneric;
@@ -47,6 +48,7 @@ use crate::traits::hardware::HardwareFn;
 use crate::traits::lcd_display::LCDDisplayFn;
 use crate::traits::rtc::RTC;
 use crate::traits::screen::{Screen, ScreenParam, ScreenRoute};
+use crate::traits::signal::Signal;
 
 static mut FSM_STATE: FSMState = FSMState::Serial;
 static mut OLD_FSM_STATE: FSMState = FSMState::Serial;
@@ -426,7 +428,7 @@ impl ScreenSetConfig {
         let password_hash = EncryptGeneric::get_sha256(email_passwd.to_bytes())?;
 
         user.set_password(&password_hash.as_str());
-        self.config.get_session().set_user(&user);
+        self.config.get_session().set_user(&user)?;
 
         if wifi_enable {
             let wifi_ssid = self.wifi_ssid.get_value()?;
@@ -453,6 +455,7 @@ impl ScreenSetConfig {
             let date_time = DateTime::new(year, month, wday, mday, hour, minute, second)?;
 
             rtc.lock()?.set_timestamp(date_time.to_timestamp())?;
+            SchedulerSignal::set(SchedulerFlag::ClockChanged.into());
         }
 
         DateTime::set_daylight_saving_time(self.enable_dst.get_value().unwrap_or_default());
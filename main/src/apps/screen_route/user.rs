@@ -31,6 +31,7 @@ use crate::apps::config::Config;
 use crate::apps::display::input::Input;
 use crate::apps::session::User;
 use crate::apps::signals::display::DisplayFlag;
+use crate::drivers::encrypt::EncryptGeneric;
 use crate::traits::lcd_display::LCDDisplayFn;
 use crate::traits::rtc::RTC;
 use crate::traits::screen::{Screen, ScreenParam, ScreenRoute};
@@ -147,8 +148,11 @@ impl ScreenUser {
 
         let mut user = User::default();
         user.set_email(email.as_str());
-        user.set_password(passwd.as_str());
-        Config::shared().get_session().set_user(&user);
+
+        let password_hash = EncryptGeneric::get_sha256(passwd.to_bytes())?;
+
+        user.set_password(&password_hash.as_str());
+        Config::shared().get_session().set_user(&user)?;
         Config::shared().apply_session();
         Config::save()?;
 
@@ -20,45 +20,184 @@
  
 use core::time::Duration;
 
+use alloc::format;
 use alloc::sync::Arc;
 use at_parser_rs::{AtError, AtResult, at_quoted};
 use at_parser_rs::context::AtContext;
 use osal_rs::{access_static_option, log_error, log_info, log_warning};
-use osal_rs::os::{RawMutexGuard, Timer, TimerFn, ToTick};
-use osal_rs::utils::{Bytes, Error, Result};
+use osal_rs::os::{RawMutexGuard, System, SystemFn, Timer, TimerFn, ToTick};
+use osal_rs::os::types::TickType;
+use osal_rs::utils::{Bytes, Error, Result, bytes_to_hex};
 use osal_rs_serde::{Deserialize, Serialize};
 
+use crate::apps::audit::{AuditLog, AuditOutcome, AuditRecord};
 use crate::apps::config::{Config, MUTEX};
+use crate::apps::config::defaults::{DEFAULT_UART_PLAIN_LOGIN, MAX_USERS, PASSWORD_ITERATIONS};
 use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::utils::same_bytes;
 use crate::drivers::encrypt::{EncryptGeneric, SHA256_RESULT_BYTES};
+use crate::drivers::platform::Hardware;
+use crate::traits::hardware::HardwareFn;
+use crate::traits::rx_tx::Source;
 use crate::traits::signal::Signal;
 use crate::traits::state::Initializable;
 use crate::apps::signals::status::{StatusSignal, StatusFlag};
 
+mod lockout;
+mod totp;
+
+use lockout::Lockout;
+pub(super) use totp::Totp;
+
 const APP_TAG: &str = "AppSession";
 
+/// Random bytes of the salt of each password
+const SALT_BYTES: usize = 16;
+
+/// Random bytes of the nonce of each login challenge
+const NONCE_BYTES: usize = 16;
+
+/// Key of the salts handed out for the unknown emails, random at each boot
+static mut DECOY_KEY: Bytes<{SALT_BYTES * 2}> = Bytes::new();
+
 /// Temp user data for update local user
 static mut USER_LOCAL: User = User::new();
 
-/// User currently logged in, None if no user is logged
-static mut USER_LOGGED: Option<User> = None;
+/// Session slot of each source of the parser, the last one is the local display
+const SLOTS: usize = 5;
+const LOCAL_SLOT: usize = SLOTS - 1;
+const SOURCES: [Source; LOCAL_SLOT] = [Source::Uart, Source::Mqtt, Source::Telnet, Source::Http];
+
+/// Idle time after which a session is closed
+const TIMEOUT_SECONDS: u64 = 5 * 60;
 
-/// Temporary user used for login/logout operations
-static mut USER_TMP: User = User::new();
+/// Period of the idle check
+const CHECK_SECONDS: u64 = 10;
 
+static mut SESSIONS: [SourceSession; SLOTS] = [SourceSession::new(); SLOTS];
+
+/// Checks the idle time of every session
 static mut TIMER: Option<Timer> = None;
 
+static mut ACCOUNT: Account = Account;
+static mut PASSWORD: Password = Password;
+
+/// Reason of a refused login
+enum LoginError {
+    /// unknown email or wrong password
+    Invalid,
+
+    /// too many failures, seconds before the next attempt
+    Locked(u32),
+
+    /// right password of a user with the second factor, without its code
+    CodeRequired,
+}
+
+/// What the login hands over to prove the password
+enum Proof {
+    /// SHA-256 of the password, from the plain login and the display
+    Hash(Bytes<{SHA256_RESULT_BYTES * 2}>),
+
+    /// HMAC-SHA256 of the nonce keyed with the stored key of the password
+    Answer {
+        nonce: Bytes<{NONCE_BYTES * 2}>,
+        mac: Bytes<{SHA256_RESULT_BYTES * 2}>,
+    },
+}
+
+/// Login state of a single source, independent from the others
+#[derive(Clone, Copy)]
+struct SourceSession {
+    /// user logged in, None if no user is logged
+    logged: Option<User>,
+
+    /// credentials of the pending login, empty for a logout
+    pending: User,
+
+    /// nonce of the last challenge, empty once used
+    nonce: Bytes<{NONCE_BYTES * 2}>,
+
+    /// the pending password is the answer to the challenge instead of a SHA-256
+    answered: bool,
+
+    /// TOTP code of the pending login, empty if none was given
+    code: Bytes<8>,
+
+    /// tick of the last command, for the idle timeout
+    last_activity: TickType,
+}
+
+impl SourceSession {
+    const fn new() -> Self {
+        Self { logged: None, pending: User::new(), nonce: Bytes::new(), answered: false, code: Bytes::new(), last_activity: 0 }
+    }
+}
+
+/// Fills the buffer with random bytes
+fn random_fill(random: &mut [u8]) {
+    for chunk in random.chunks_mut(size_of::<u64>()) {
+        chunk.copy_from_slice(&Hardware::get_random().to_le_bytes()[..chunk.len()]);
+    }
+}
+
+/// Hex of random bytes, as many as the buffer holds
+fn random_hex<const SIZE: usize>(random: &mut [u8]) -> Bytes<SIZE> {
+    random_fill(random);
+    Bytes::from_str(bytes_to_hex(random).as_str())
+}
+
+/// Rights of a user, each role has the rights of the ones below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Role {
+    /// reads the state and the settings
+    Viewer = 0,
+    /// runs the zones and edits the irrigation
+    Operator = 1,
+    /// edits the system settings and the users
+    Admin = 2,
+}
+
+impl TryFrom<u8> for Role {
+    type Error = ();
+
+    fn try_from(value: u8) -> core::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Role::Viewer),
+            1 => Ok(Role::Operator),
+            2 => Ok(Role::Admin),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The password is kept as the PBKDF2-HMAC-SHA256 key of its SHA-256, the SHA-256 being
+/// what the plain login and the display hand over and the key what the answer to a challenge is keyed with
 #[derive(Clone, Copy)]
 pub(super) struct User {
     email: Bytes<32>,
     password: Bytes<{SHA256_RESULT_BYTES * 2}>,
+    /// hex of the random salt of the password
+    salt: Bytes<{SALT_BYTES * 2}>,
+    /// PBKDF2 iterations, 0 for a bare SHA-256 saved before the salted hashes
+    iterations: u32,
+    role: Role,
+    /// the password has to be replaced before any other command
+    must_change: bool,
+    /// base32 secret of the second factor, empty if disabled
+    totp: Bytes<{totp::SECRET_SIZE}>,
 }
 
 impl Serialize for User {
     fn serialize<S: osal_rs_serde::Serializer>(&self, name: &str, serializer: &mut S) -> core::result::Result<(), S::Error> {
-        serializer.serialize_struct_start(name, 2)?;
+        serializer.serialize_struct_start(name, 7)?;
         serializer.serialize_str("email", self.email.as_str())?;
         serializer.serialize_str("password", self.password.as_str())?;
+        serializer.serialize_str("salt", self.salt.as_str())?;
+        serializer.serialize_u32("iterations", self.iterations)?;
+        serializer.serialize_u8("role", self.role as u8)?;
+        serializer.serialize_u8("change", self.must_change as u8)?;
+        serializer.serialize_str("totp", self.totp.as_str())?;
         serializer.serialize_struct_end()
     }
 }
@@ -68,21 +207,33 @@ impl Deserialize for User {
         deserializer.deserialize_struct_start(name)?;
         let email = deserializer.deserialize_string("email")?;
         let password = deserializer.deserialize_string("password")?;
+        // The passwords saved before the salted hashes are a bare SHA-256
+        let salt = deserializer.deserialize_string("salt").unwrap_or_default();
+        let iterations = deserializer.deserialize_u32("iterations").unwrap_or(0);
+        // The users saved before the roles had all the rights, an unknown role has the fewest
+        let role = match deserializer.deserialize_u8("role") {
+            Ok(value) => Role::try_from(value).unwrap_or(Role::Viewer),
+            Err(_) => Role::Admin,
+        };
+        let must_change = deserializer.deserialize_u8("change").is_ok_and(|value| value != 0);
+        let totp = deserializer.deserialize_string("totp").unwrap_or_default();
         deserializer.deserialize_struct_end()?;
 
         Ok(Self {
             email: Bytes::from_str(&email),
             password: Bytes::from_str(&password),
+            salt: Bytes::from_str(&salt),
+            iterations,
+            role,
+            must_change,
+            totp: Bytes::from_str(&totp),
         })
     }
 }
 
 impl Default for User {
     fn default() -> Self {
-        Self {
-            email: Bytes::new(),
-            password: Bytes::new(),
-        }
+        Self::new()
     }
 }
 
@@ -97,6 +248,16 @@ impl User {
         &self.password
     }
 
+    #[inline]
+    pub fn get_role(&self) -> Role {
+        self.role
+    }
+
+    #[inline]
+    pub fn is_defined(&self) -> bool {
+        self.email.len() > 0
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub fn set_email(&mut self, email: &str) {
@@ -115,13 +276,9 @@ impl AtContext<{Parser::CMD_SIZE}> for User {
     fn exec(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        if unsafe { USER_LOGGED }.is_none() {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE.into())));
-        }
-
         let email = self.email.clone();
 
-        Config::shared().get_session().set_user(self);
+        Config::shared().get_session().set_user(self).map_err(|_| (at_response, AtError::Unhandled("Password hash error")))?;
 
         self.clear();
 
@@ -131,24 +288,17 @@ impl AtContext<{Parser::CMD_SIZE}> for User {
     fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        if unsafe { USER_LOGGED }.is_none() {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE.into())));
-        }
-        Ok(at_cmd_response!(at_response; at_quoted!(self.email.as_str())))
+        Ok(at_cmd_response!(at_response; at_quoted!(self.email.as_str()), self.role as u8))
     }
 
     #[inline]
     fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
-        Ok(at_cmd_response!(at_response; "<email>,<password>"))
+        Ok(at_cmd_response!(at_response; "<email>,<password>[,<role 0-2>]"))
     } 
     
     fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        if unsafe { USER_LOGGED }.is_none() {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE.into())));
-        }
-
         let arg0 = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
         if arg0.len() > 32 {
             return Err((at_response, AtError::Unhandled("email max len 32")));
@@ -160,8 +310,18 @@ impl AtContext<{Parser::CMD_SIZE}> for User {
             return Err((at_response, AtError::Unhandled("password max len 32")));
         }
 
+        let role = match args.get(2) {
+            Some(value) => match value.parse::<u8>() {
+                Ok(role) => Role::try_from(role).map_err(|_| (at_response, AtError::InvalidArgs))?,
+                Err(_) => return Err((at_response, AtError::InvalidArgs)),
+            },
+            // Only for the clients written before the roles, that never pass one
+            None => Role::Admin,
+        };
+
         self.email = Bytes::from_str(arg0.as_ref());
         self.password = EncryptGeneric::get_sha256(arg1.as_bytes()).map_err(|_| (at_response, AtError::InvalidArgs))?;
+        self.role = role;
 
         Ok(at_cmd_response!(at_response; ""))
     }
@@ -178,6 +338,11 @@ impl User {
         Self { 
             email: Bytes::new(),
             password: Bytes::new(),
+            salt: Bytes::new(),
+            iterations: 0,
+            role: Role::Admin,
+            must_change: false,
+            totp: Bytes::new(),
         }
     }
 
@@ -188,14 +353,65 @@ impl User {
     fn clear(&mut self) {
         self.email.clear();
         self.password.clear();
+        self.salt.clear();
+        self.iterations = 0;
+        self.totp.clear();
+    }
+
+    /// Stores the key of the SHA-256 of the password with a new random salt
+    fn set_password_hash(&mut self, hash: &Bytes<{SHA256_RESULT_BYTES * 2}>) -> Result<()> {
+        let salt = random_hex(&mut [0u8; SALT_BYTES]);
+
+        self.password = EncryptGeneric::pbkdf2_sha256(hash.as_str().as_bytes(), salt.as_str().as_bytes(), PASSWORD_ITERATIONS)?;
+        self.salt = salt;
+        self.iterations = PASSWORD_ITERATIONS;
+        Ok(())
+    }
+
+    /// True if the SHA-256 of the password matches the stored one
+    fn check_password(&self, hash: &Bytes<{SHA256_RESULT_BYTES * 2}>) -> bool {
+        if self.iterations == 0 {
+            return same_bytes(self.password.as_raw_bytes(), hash.as_raw_bytes());
+        }
+        EncryptGeneric::pbkdf2_sha256(hash.as_str().as_bytes(), self.salt.as_str().as_bytes(), self.iterations)
+            .is_ok_and(|key| same_bytes(key.as_raw_bytes(), self.password.as_raw_bytes()))
+    }
+
+    /// True if the MAC is the HMAC-SHA256 of the nonce keyed with the stored key
+    fn check_answer(&self, nonce: &Bytes<{NONCE_BYTES * 2}>, mac: &Bytes<{SHA256_RESULT_BYTES * 2}>) -> bool {
+        nonce.len() > 0 && EncryptGeneric::hmac_sha256(self.password.as_str().as_bytes(), nonce.as_str().as_bytes())
+            .is_ok_and(|expected| same_bytes(expected.as_str().to_ascii_lowercase().as_bytes(), mac.as_str().to_ascii_lowercase().as_bytes()))
     }
 
 }
 
 
-#[derive(Serialize, Deserialize, Clone, Copy)]
+#[derive(Serialize, Clone, Copy)]
 pub(super) struct Session {
     users: [User; Session::MAX_USERS],
+    /// the UART accepts the login with the password in clear
+    plain_login: bool,
+}
+
+impl Deserialize for Session {
+    fn deserialize<D: osal_rs_serde::Deserializer>(deserializer: &mut D, name: &str) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_struct_start(name)?;
+        let users = match <[User; Session::MAX_USERS]>::deserialize(deserializer, "users") {
+            Ok(users) => users,
+            // The sessions saved before the user accounts have only the system and the local user
+            Err(_) => {
+                let mut users = [User::new(); Session::MAX_USERS];
+                let legacy = <[User; Session::LEGACY_USERS]>::deserialize(deserializer, "users")?;
+                users[..Session::LEGACY_USERS].copy_from_slice(&legacy);
+                users
+            }
+        };
+        // The sessions saved before the challenge login get the default of the UART plain login
+        let plain_login = bool::deserialize(deserializer, "plain_login").unwrap_or(DEFAULT_UART_PLAIN_LOGIN);
+        deserializer.deserialize_struct_end()?;
+
+        Ok(Self { users, plain_login })
+    }
 }
 
 impl AtContext<{Parser::CMD_SIZE}> for Session {
@@ -203,57 +419,89 @@ impl AtContext<{Parser::CMD_SIZE}> for Session {
     fn exec(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        let user_tmp = unsafe { &*&raw mut USER_TMP };
-        match user_tmp {
-            User{email, password} if email.len() == 0 || password.len() == 0 => {
-                if unsafe { USER_LOGGED }.is_none() {
-                    return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE.into())));
-                }
-                Self::logout();
-                Ok(at_cmd_response!(at_response; ""))
+        let slot = Self::issuer_slot().ok_or((at_response, AtError::InvalidArgs))?;
+        let User{email, password, ..} = unsafe { SESSIONS[slot].pending };
+        if email.len() == 0 || password.len() == 0 {
+            if unsafe { SESSIONS[slot].logged }.is_none() {
+                return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE.into())));
             }
-            User{email, password} if email.len() > 0 && password.len() > 0 => self.login(at_response),
-            User { .. } => Err((at_response, AtError::InvalidArgs))
+            Self::logout(slot);
+            Ok(at_cmd_response!(at_response; ""))
+        } else {
+            self.login(at_response, slot)
         }
-
     }
 
     fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        let logged = unsafe { *&raw const USER_LOGGED };
-        if logged.is_some() { 
-            Ok(at_cmd_response!(at_response; access_static_option!(USER_LOGGED).email.as_str()))
-        } else {
-            Err((at_response, AtError::InvalidArgs))
+        let slot = Self::issuer_slot().ok_or((at_response, AtError::InvalidArgs))?;
+        match unsafe { SESSIONS[slot].logged } {
+            Some(user) => Ok(at_cmd_response!(at_response; user.email.as_str(), user.role as u8, user.must_change as u8)),
+            None => Err((at_response, AtError::InvalidArgs)),
         }
-        
     }
 
     #[inline]
-    /// i = login, o = logout
+    /// c = challenge, r = response, i = plain login (UART only), o = logout, pl = plain login switch
     fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
-        Ok(at_cmd_response!(at_response; "<i|o>,<email>,<password>"))
+        Ok(at_cmd_response!(at_response; "c,<email> | r,<hmac>[,<code>] | i,<email>,<password>[,<code>] | o | pl[,<0|1>]"))
     }
 
     fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
+        let slot = Self::issuer_slot().ok_or((at_response, AtError::InvalidArgs))?;
+        let session = unsafe { &mut *&raw mut SESSIONS[slot] };
+
         let arg0 = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
 
-        if arg0 == "i" { // Login
+        if arg0 == "c" { // Challenge
             let arg1 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
-            let arg2 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?;
-            unsafe {
-                USER_TMP.email = Bytes::from_str(arg1.as_ref());
-                USER_TMP.password = EncryptGeneric::get_sha256(arg2.as_bytes()).map_err(|_| (at_response, AtError::InvalidArgs))?;
+            if arg1.len() > 32 {
+                return Err((at_response, AtError::Unhandled("email max len 32")));
             }
+            let (salt, iterations) = self.challenge_salt(arg1.as_ref()).map_err(|_| (at_response, AtError::Unhandled("Challenge error")))?;
+
+            session.pending.email = Bytes::from_str(arg1.as_ref());
+            (*session.pending.password).fill(0);
+            session.nonce = random_hex(&mut [0u8; NONCE_BYTES]);
+            session.answered = false;
+            session.code.clear();
+
+            return Ok(at_cmd_response!(at_response; session.nonce.as_str(), salt.as_str(), iterations));
+        } else if arg0 == "r" { // Response
+            let arg1 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+            if session.nonce.len() == 0 || arg1.len() != SHA256_RESULT_BYTES * 2 {
+                return Err((at_response, AtError::InvalidArgs));
+            }
+            session.pending.password = Bytes::from_str(arg1.as_ref());
+            session.answered = true;
+            session.code = Bytes::from_str(args.get(2).as_deref().unwrap_or_default());
+        } else if arg0 == "i" { // Login
+            if slot != Self::slot(&Source::Uart) || !self.plain_login {
+                return Err((at_response, AtError::Unhandled(Self::PLAIN_LOGIN_RESPONSE)));
+            }
+            let arg1 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+            let arg2 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?;
+            session.pending.email = Bytes::from_str(arg1.as_ref());
+            session.pending.password = EncryptGeneric::get_sha256(arg2.as_bytes()).map_err(|_| (at_response, AtError::InvalidArgs))?;
+            session.nonce.clear();
+            session.answered = false;
+            session.code = Bytes::from_str(args.get(3).as_deref().unwrap_or_default());
         } else if arg0 == "o" { // Logout
-            unsafe {
-                (*USER_TMP.email).fill(0);
-                (*USER_TMP.password).fill(0);
+            (*session.pending.email).fill(0);
+            (*session.pending.password).fill(0);
+            session.nonce.clear();
+            session.answered = false;
+            session.code.clear();
+        } else if arg0 == "pl" { // Plain login switch
+            if let Some(value) = args.get(1) {
+                let value: u8 = value.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                self.plain_login = value != 0;
+                Config::save().map_err(|_| (at_response, AtError::Unhandled("Save error")))?;
             }
-            
+            return Ok(at_cmd_response!(at_response; self.plain_login as u8));
         } else {
             return Err((at_response, AtError::InvalidArgs));
         }
@@ -276,21 +524,22 @@ impl Initializable for Session {
     fn init(&mut self) -> Result<()> {
         log_info!(APP_TAG, "Init app session");
 
-        if let Ok(timer) = Timer::new("autoreload_timer",
-        Duration::from_mins(5).to_ticks(),
-        false,
+        Lockout::init()?;
+
+        if let Ok(timer) = Timer::new("session_timer",
+        Duration::from_secs(CHECK_SECONDS).to_ticks(),
+        true,
         None,
         |_, _| {
 
-            Self::logout();
-            
-            log_warning!(APP_TAG, "Session timeout, user logged out");
+            Self::expire();
 
             Ok(Arc::new(()))
         }) {
             unsafe {
                 TIMER = Some(timer);
             }
+            access_static_option!(TIMER).start(0);
         } else {
             log_error!(APP_TAG, "Error creating timer");
             return Err(Error::OutOfMemory)
@@ -303,72 +552,277 @@ impl Initializable for Session {
 impl Session {
     pub const AT_CMD: &'static str = "AT+SESS";
     pub const AT_RESP: &'static str = "+SESS: ";
-    pub const MAX_USERS : usize = 2;
+    pub const MAX_USERS : usize = MAX_USERS;
+    /// Users of the sessions saved before the user accounts
+    const LEGACY_USERS: usize = 2;
+    pub const LOCKED_RESPONSE: &'static str = "KO: Login locked";
+    pub const PLAIN_LOGIN_RESPONSE: &'static str = "KO: Plain login disabled";
+    pub const CODE_RESPONSE: &'static str = "KO: Code required";
+
+    /// Slots of the system user and of the local user of the display, the others are free
+    const SYSTEM_USER: usize = 0;
+    const LOCAL_USER: usize = 1;
 
     pub const fn new() -> Self {
-        Self { users: [User::new(); Session::MAX_USERS] }
+        Self { users: [User::new(); Session::MAX_USERS], plain_login: DEFAULT_UART_PLAIN_LOGIN }
     }
 
-    fn login(&self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
-        let user_tmp = unsafe { USER_TMP }.clone();
+    fn login(&mut self, at_response: &'static str, slot: usize) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let session = unsafe { &mut *&raw mut SESSIONS[slot] };
+        let user_tmp = session.pending;
 
         if user_tmp.email.len() == 0 || user_tmp.password.len() == 0 {
             return Err((at_response, AtError::InvalidArgs));
         }
 
-        for User{email: user, password: pwd} in self.users.iter() {
-            if *user == user_tmp.email && pwd.as_raw_bytes() == user_tmp.password.as_raw_bytes() {
-                unsafe { USER_LOGGED = Some(USER_TMP); }
+        // A nonce proves a single login
+        let proof = if session.answered {
+            Proof::Answer { nonce: session.nonce, mac: user_tmp.password }
+        } else {
+            Proof::Hash(user_tmp.password)
+        };
+        session.nonce.clear();
+        let code = session.code;
+        session.code.clear();
+
+        let error = match self.authenticate(slot, user_tmp.email.as_str(), &proof, (code.len() > 0).then_some(code.as_str())) {
+            Ok(user) => {
+                session.logged = Some(user);
+                session.last_activity = System::get_tick_count();
+                Self::update_status();
+
+                return Ok(at_cmd_response!(at_response; user.email));
+            }
+            Err(error) => error,
+        };
+
+        if session.logged.is_none() {
+            (*session.pending.email).fill(0);
+            (*session.pending.password).fill(0);
+            session.answered = false;
+        }
+
+        match error {
+            LoginError::Locked(seconds) => Err((at_response, AtError::UnhandledOwned(format!("{}, retry in {seconds} s", Self::LOCKED_RESPONSE)))),
+            LoginError::Invalid => Err((at_response, AtError::InvalidArgs)),
+            LoginError::CodeRequired => Err((at_response, AtError::Unhandled(Self::CODE_RESPONSE))),
+        }
+    }
 
-                StatusSignal::set(StatusFlag::UserLogged.into());
-                access_static_option!(TIMER).start(0);
+    /// Checks the credentials once the waits of the source and of the user are over,
+    /// a failure that locks the login is written to the audit log.
+    /// A user with the second factor needs its code too, a wrong one counts as a failure
+    fn authenticate(&mut self, slot: usize, email: &str, proof: &Proof, code: Option<&str>) -> core::result::Result<User, LoginError> {
+        let index = self.index_of(email);
+
+        let remaining = Lockout::remaining(slot, index);
+        if remaining > 0 {
+            return Err(LoginError::Locked(remaining));
+        }
 
-                return Ok(at_cmd_response!(at_response; unsafe { USER_TMP.email } ));
+        if let Some(user) = self.find_user(email, proof) {
+            let accepted = match (user.totp.len() > 0, code, index) {
+                (false, _, _) => true,
+                (true, None, _) => return Err(LoginError::CodeRequired),
+                (true, Some(code), Some(index)) => totp::accept(index, user.totp.as_str(), code),
+                (true, Some(_), None) => false,
+            };
+            if accepted {
+                Lockout::success(slot, index);
+                return Ok(user);
             }
         }
 
-        if unsafe { USER_LOGGED }.is_none() {
-            unsafe {
-                (*USER_TMP.email).fill(0);
-                (*USER_TMP.password).fill(0);
+        if Lockout::failure(slot, index) {
+            log_warning!(APP_TAG, "Login locked on slot {slot} after repeated failures");
+            let verb = match proof {
+                Proof::Hash(_) => "i",
+                Proof::Answer { .. } => "r",
+            };
+            AuditLog::shared().add(AuditRecord::event(slot, email, Self::AT_CMD, verb, AuditOutcome::Locked));
+        }
+        Err(LoginError::Invalid)
+    }
+
+    /// Stored user with the credentials, the role is the stored one.
+    /// After a SHA-256 login a bare SHA-256 or a key of another iteration count is derived again
+    /// now that the password is known, an answer to the challenge leaves it as it is
+    fn find_user(&mut self, email: &str, proof: &Proof) -> Option<User> {
+        let index = self.index_of(email)?;
+        let user = &mut self.users[index];
+        let hash = match proof {
+            Proof::Hash(hash) => hash,
+            Proof::Answer { nonce, mac } => return user.check_answer(nonce, mac).then_some(*user),
+        };
+        if !user.check_password(hash) {
+            return None;
+        }
+
+        if user.iterations != PASSWORD_ITERATIONS {
+            if user.set_password_hash(hash).is_ok() && Config::save().is_ok() {
+                log_info!(APP_TAG, "Password hash of user {index} upgraded");
+            } else {
+                log_error!(APP_TAG, "Password hash of user {index} not upgraded");
             }
         }
 
-        Err((at_response, AtError::InvalidArgs))
+        Some(self.users[index])
+    }
+
+    /// Login of the local display, the password in clear and the code of the second factor if any
+    pub fn login_local(&mut self, email: &str, password: &str, code: Option<&str>) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let Ok(password) = EncryptGeneric::get_sha256(password.as_bytes()) else {
+            return false;
+        };
+        let Ok(user) = self.authenticate(LOCAL_SLOT, email, &Proof::Hash(password), code) else {
+            return false;
+        };
+
+        let session = unsafe { &mut *&raw mut SESSIONS[LOCAL_SLOT] };
+        session.logged = Some(user);
+        session.last_activity = System::get_tick_count();
+        Self::update_status();
+        true
     }
 
+    /// True if the stored user with the email logs in with the code of the second factor too
+    pub fn needs_code(&self, email: &str) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        self.index_of(email).is_some_and(|index| self.users[index].totp.len() > 0)
+    }
 
     // Called from the AT parser task (under lock, recursion is safe)
-    // and from the session timeout timer task (unlocked entry point).
-    fn logout() {
+    // and from the session timer task (unlocked entry point).
+    fn logout(slot: usize) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        unsafe {
+            SESSIONS[slot] = SourceSession::new();
+        }
+        Totp::cancel(slot);
+        AuditLog::shared().flush();
+        Self::update_status();
+    }
+
+    /// `UserLogged` while any session is open and the flag of each source while its own is,
+    /// the staged local user is dropped with the last session
+    fn update_status() {
+        let sessions = unsafe { &*&raw const SESSIONS };
+
+        for (source, session) in SOURCES.iter().zip(sessions.iter()) {
+            let source_flag: u32 = StatusFlag::from(source).into();
+            if session.logged.is_some() {
+                StatusSignal::set(source_flag);
+            } else {
+                StatusSignal::clear(source_flag);
+            }
+        }
+
+        if sessions.iter().any(|session| session.logged.is_some()) {
+            StatusSignal::set(StatusFlag::UserLogged.into());
+        } else {
+            User::get_local().clear();
+            StatusSignal::clear(StatusFlag::UserLogged.into());
+            StatusSignal::clear(StatusFlag::SystemCmd.into());
+        }
+    }
+
+    /// Closes the sessions idle for longer than the timeout
+    fn expire() {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let now = System::get_tick_count();
+        let timeout = Duration::from_secs(TIMEOUT_SECONDS).to_ticks();
+        for slot in 0..SLOTS {
+            let session = unsafe { SESSIONS[slot] };
+            if session.logged.is_some() && now.wrapping_sub(session.last_activity) >= timeout {
+                Self::logout(slot);
+                log_warning!(APP_TAG, "Session timeout, user logged out from slot {slot}");
+            }
+        }
+    }
+
+    /// Slot of the source in the sessions and in the audit records
+    #[inline]
+    pub(super) fn slot(source: &Source) -> usize {
+        match source {
+            Source::Uart => 0,
+            Source::Mqtt => 1,
+            Source::Telnet => 2,
+            Source::Http => 3,
+        }
+    }
+
+    /// Slot of the source of the command in execution, None outside the parser
+    #[inline]
+    fn issuer_slot() -> Option<usize> {
+        Parser::get_source().map(|source| Self::slot(&source))
+    }
+
+    pub fn is_logged(source: &Source) -> bool {
+        Self::get_role(source).is_some()
+    }
+
+    /// True while the user logged in on the source still has to replace its password
+    pub fn must_change_password(source: &Source) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { SESSIONS[Self::slot(source)].logged }.is_some_and(|user| user.must_change)
+    }
+
+    /// Role of the user logged in on the source, None if no user is logged
+    pub fn get_role(source: &Source) -> Option<Role> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { SESSIONS[Self::slot(source)].logged }.map(|user| user.role)
+    }
+
+    /// Email of the user logged in on the source, None if no user is logged
+    pub fn get_email(source: &Source) -> Option<Bytes<32>> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { SESSIONS[Self::slot(source)].logged }.map(|user| user.email)
+    }
+
+    pub fn is_local_logged() -> bool {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { SESSIONS[LOCAL_SLOT].logged }.is_some()
+    }
 
-        unsafe { USER_LOGGED = None; }
-        User::get_local().clear();
-        StatusSignal::clear(StatusFlag::UserLogged.into());
-        StatusSignal::clear(StatusFlag::UartCmd.into());
-        StatusSignal::clear(StatusFlag::MqttCmd.into());
-        StatusSignal::clear(StatusFlag::SystemCmd.into());
+    /// Ends the session of the source, used when its connection drops
+    pub fn close(source: &Source) {
+        let slot = Self::slot(source);
+        if unsafe { SESSIONS[slot].logged }.is_some() {
+            Self::logout(slot);
+        }
     }
 
-    pub fn set_user(&mut self, user: &User) {
+    /// Stores the local user, its password is the SHA-256 handed over by the caller
+    pub fn set_user(&mut self, user: &User) -> Result<()> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-        self.users[1] = *user;
+        let mut user = *user;
+        if user.iterations == 0 && user.password.len() > 0 {
+            let hash = user.password;
+            user.set_password_hash(&hash)?;
+        }
+        self.users[Self::LOCAL_USER] = user;
+        Lockout::forget(Self::LOCAL_USER);
+        Totp::forget(Self::LOCAL_USER);
+        Ok(())
     }
 
     pub fn set_user_local(&self) {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-        unsafe { USER_LOCAL = self.users[1]; }
+        unsafe { USER_LOCAL = self.users[Self::LOCAL_USER]; }
     }
 
     pub fn get_user_local(&self) -> User {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-        self.users[1]
+        self.users[Self::LOCAL_USER]
     }
 
     pub fn is_set_user_local(&self) -> bool {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-        self.users[1].email.len() > 0 && self.users[1].password.len() > 0
+        self.users[Self::LOCAL_USER].email.len() > 0 && self.users[Self::LOCAL_USER].password.len() > 0
     }
 
     pub fn set_system_user(&mut self, email: &str, password: &str) -> Result<()> {
@@ -376,14 +830,293 @@ impl Session {
             return Err(Error::Empty);
         }
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-        self.users[0].email = Bytes::from_str(email);
-        self.users[0].password = Bytes::from_str(password);
+        let user = &mut self.users[Self::SYSTEM_USER];
+        user.email = Bytes::from_str(email);
+        user.set_password_hash(&Bytes::from_str(password))?;
+        user.role = Role::Admin;
+        // The factory password is known to everyone who built the firmware
+        user.must_change = true;
         Ok(())
     }
 
+    /// Salt and iterations the client of a challenge derives the key with.
+    /// An unknown email gets a salt keyed on the email, the same at every challenge,
+    /// so that the reply does not tell whether the user exists
+    fn challenge_salt(&self, email: &str) -> Result<(Bytes<{SALT_BYTES * 2}>, u32)> {
+        if let Some(index) = self.index_of(email) {
+            let user = &self.users[index];
+            return Ok((user.salt, user.iterations));
+        }
+
+        let key = unsafe { &mut *&raw mut DECOY_KEY };
+        if key.len() == 0 {
+            *key = random_hex(&mut [0u8; SALT_BYTES]);
+        }
+        let mac = EncryptGeneric::hmac_sha256(key.as_str().as_bytes(), email.as_bytes())?;
+        Ok((Bytes::from_str(&mac.as_str()[..SALT_BYTES * 2]), PASSWORD_ITERATIONS))
+    }
+
+    /// Index of the stored user with the email
+    fn index_of(&self, email: &str) -> Option<usize> {
+        self.users.iter().position(|user| user.is_defined() && user.email.as_str() == email)
+    }
+
+    /// Stores the user in the first free slot after the system and local ones
+    fn add_user(&mut self, user: User) -> core::result::Result<usize, &'static str> {
+        if self.index_of(user.email.as_str()).is_some() {
+            return Err("User already defined");
+        }
+        let index = (Self::LOCAL_USER + 1..Self::MAX_USERS)
+            .find(|&index| !self.users[index].is_defined())
+            .ok_or("Users full")?;
+        self.users[index] = user;
+        Ok(index)
+    }
+
+    /// Removes the user and closes its sessions, the system user is kept
+    fn remove_user(&mut self, index: usize) -> core::result::Result<(), &'static str> {
+        if index == Self::SYSTEM_USER {
+            return Err("System user not removable");
+        }
+        if !self.users[index].is_defined() {
+            return Err("User not defined");
+        }
+
+        let email = self.users[index].email;
+        for slot in 0..SLOTS {
+            if unsafe { SESSIONS[slot].logged }.is_some_and(|user| user.email == email) {
+                Self::logout(slot);
+            }
+        }
+        self.users[index] = User::new();
+        Lockout::forget(index);
+        Totp::forget(index);
+        Ok(())
+    }
+
+    /// Replaces the password of the user logged in on the slot after checking the old one
+    fn change_password(&mut self, slot: usize, old: &str, new: &str) -> core::result::Result<(), &'static str> {
+        let logged = unsafe { SESSIONS[slot].logged }.ok_or(Parser::NOT_LOGGED_RESPONSE)?;
+        let index = self.index_of(logged.email.as_str()).ok_or("User not defined")?;
+
+        let old = EncryptGeneric::get_sha256(old.as_bytes()).map_err(|_| "Invalid password")?;
+        let new = EncryptGeneric::get_sha256(new.as_bytes()).map_err(|_| "Invalid password")?;
+        let user = &mut self.users[index];
+        if !user.check_password(&old) {
+            return Err("Wrong password");
+        }
+        if user.check_password(&new) {
+            return Err("Same password");
+        }
+
+        user.set_password_hash(&new).map_err(|_| "Password hash error")?;
+        user.must_change = false;
+
+        // The other sessions of the user are released too
+        Self::update_logged(user);
+        Ok(())
+    }
+
+    /// Copies the stored user into every session it is logged in
+    fn update_logged(user: &User) {
+        for session in unsafe { (&mut *&raw mut SESSIONS).iter_mut() } {
+            if session.logged.is_some_and(|logged| logged.email == user.email) {
+                session.logged = Some(*user);
+            }
+        }
+    }
+
+    /// Restarts the idle time of the session of the source
+    pub fn reset_timer(source: &Source) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe {
+            SESSIONS[Self::slot(source)].last_activity = System::get_tick_count();
+        }
+    }
+}
+
+/// Management of the stored users, AT+ACC
+pub(super) struct Account;
+
+impl AtContext<{Parser::CMD_SIZE}> for Account {
+
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let used = Config::shared().get_session().users.iter().filter(|user| user.is_defined()).count();
+        Ok(at_cmd_response!(at_response; used, Session::MAX_USERS))
+    }
+
+    #[inline]
+    /// rd = read, ad = add, dl = delete, ot = reset of the second factor
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "rd,<index> | ad,<email>,<password>[,<role 0-2>] | dl,<index> | ot,<index>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let session = Config::shared().get_session();
+        let index = args.get(1)
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|&index| index < Session::MAX_USERS);
+
+        match cmd.as_ref() {
+            "rd" => { // read, one user per line
+                let index = index.ok_or((at_response, AtError::InvalidArgs))?;
+                let user = session.users[index];
+                if !user.is_defined() {
+                    return Err((at_response, AtError::Unhandled("User not defined")));
+                }
+                return Ok(at_cmd_response!(at_response; index, at_quoted!(user.email.as_str()), user.role as u8, user.must_change as u8, (user.totp.len() > 0) as u8));
+            }
+            "ad" => { // add
+                let email = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+                if email.len() == 0 || email.len() > 32 {
+                    return Err((at_response, AtError::Unhandled("email max len 32")));
+                }
+                let password = args.get(2).ok_or((at_response, AtError::InvalidArgs))?;
+                if password.len() == 0 || password.len() > 32 {
+                    return Err((at_response, AtError::Unhandled("password max len 32")));
+                }
+                let role = match args.get(3) {
+                    Some(value) => match value.parse::<u8>() {
+                        Ok(role) => Role::try_from(role).map_err(|_| (at_response, AtError::InvalidArgs))?,
+                        Err(_) => return Err((at_response, AtError::InvalidArgs)),
+                    },
+                    None => Role::Viewer,
+                };
+
+                let mut user = User {
+                    email: Bytes::from_str(email.as_ref()),
+                    role,
+                    ..User::new()
+                };
+                let hash = EncryptGeneric::get_sha256(password.as_bytes()).map_err(|_| (at_response, AtError::InvalidArgs))?;
+                user.set_password_hash(&hash).map_err(|_| (at_response, AtError::Unhandled("Password hash error")))?;
+                let index = session.add_user(user).map_err(|e| (at_response, AtError::Unhandled(e)))?;
+                Config::save().map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+                return Ok(at_cmd_response!(at_response; index));
+            }
+            "dl" => { // delete
+                let index = index.ok_or((at_response, AtError::InvalidArgs))?;
+                session.remove_user(index).map_err(|e| (at_response, AtError::Unhandled(e)))?;
+                Config::save().map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            }
+            "ot" => { // reset of the second factor, for a user who lost the authenticator
+                let index = index.ok_or((at_response, AtError::InvalidArgs))?;
+                let user = &mut session.users[index];
+                if !user.is_defined() {
+                    return Err((at_response, AtError::Unhandled("User not defined")));
+                }
+                user.totp.clear();
+                Session::update_logged(user);
+                Totp::forget(index);
+                Config::save().map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl Account {
+    pub const AT_CMD: &'static str = "AT+ACC";
+    pub const AT_RESP: &'static str = "+ACC: ";
+
+    pub fn get() -> &'static mut Account {
+        unsafe { &mut *&raw mut ACCOUNT }
+    }
+}
+
+/// Password change of the user logged in on the source, AT+PWD
+pub(super) struct Password;
+
+impl AtContext<{Parser::CMD_SIZE}> for Password {
+
     #[inline]
-    pub fn reset_timer() {
-        access_static_option!(TIMER).reset(0);
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<old password>,<new password>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let old = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+        let new = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+        if new.len() == 0 || new.len() > 32 {
+            return Err((at_response, AtError::Unhandled("password max len 32")));
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let slot = Session::issuer_slot().ok_or((at_response, AtError::InvalidArgs))?;
+        Config::shared().get_session().change_password(slot, old.as_ref(), new.as_ref())
+            .map_err(|e| (at_response, AtError::Unhandled(e)))?;
+        Config::save().map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl Password {
+    pub const AT_CMD: &'static str = "AT+PWD";
+    pub const AT_RESP: &'static str = "+PWD: ";
+
+    pub fn get() -> &'static mut Password {
+        unsafe { &mut *&raw mut PASSWORD }
     }
 }
 
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    use cjson_binding::{from_json, to_json};
+
+    /// SHA-256 of "password"
+    const PASSWORD_HASH: &str = "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8";
+
+    /// Session of config.json as saved by the firmware before the salted hashes, the roles,
+    /// the second factor and the user accounts
+    const OLD_SESSION: &str = r#"{"users":[{"email":"admin@hhg","password":"5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8"},{"email":"local@hhg","password":"5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8"}]}"#;
+
+    #[test]
+    fn old_sessions_keep_their_users() {
+        let Ok(loaded) = from_json::<Session>(OLD_SESSION) else { panic!("old session not loaded") };
+
+        for (index, email) in [(Session::SYSTEM_USER, "admin@hhg"), (Session::LOCAL_USER, "local@hhg")] {
+            let user = loaded.users[index];
+            assert_eq!(user.email.as_str(), email);
+            assert_eq!(user.password.as_str(), PASSWORD_HASH);
+            assert_eq!((user.iterations, user.role, user.must_change), (0, Role::Admin, false));
+            assert_eq!(user.totp.len(), 0);
+        }
+        assert!(loaded.users[Session::LEGACY_USERS..].iter().all(|user| !user.is_defined()));
+        assert_eq!(loaded.plain_login, DEFAULT_UART_PLAIN_LOGIN);
+    }
+
+    #[test]
+    fn unknown_roles_have_the_fewest_rights() {
+        let json = format!(r#"{{"email":"user@hhg","password":"{PASSWORD_HASH}","role":7}}"#);
+
+        let Ok(user) = from_json::<User>(&json) else { panic!("user not loaded") };
+
+        assert_eq!(user.role, Role::Viewer);
+        assert_eq!(Role::try_from(3), Err(()));
+    }
+
+    #[test]
+    fn sessions_round_trip() {
+        let mut session = Session::new();
+        session.users[2] = User { email: Bytes::from_str("viewer@hhg"), password: Bytes::from_str(PASSWORD_HASH), role: Role::Viewer, ..User::new() };
+        session.plain_login = !DEFAULT_UART_PLAIN_LOGIN;
+        let Ok(json) = to_json(&session) else { panic!("session not serialized") };
+
+        let Ok(loaded) = from_json::<Session>(&json) else { panic!("session not loaded") };
+
+        assert_eq!(loaded.users[2].email.as_str(), "viewer@hhg");
+        assert_eq!(loaded.users[2].role, Role::Viewer);
+        assert_eq!(loaded.plain_login, !DEFAULT_UART_PLAIN_LOGIN);
+    }
+}
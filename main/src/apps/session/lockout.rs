@@ -0,0 +1,200 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+///! Failed logins of each source and of each user. Every failure doubles the wait before
+///! the next attempt, after `MAX_FAILURES` the login is locked for `LOCKOUT_SECONDS` and
+///! every further failure locks it again. The waits are saved, a reboot does not clear them.
+
+use core::time::Duration;
+
+use osal_rs::{access_static_option, log_warning};
+use osal_rs::os::{RawMutexGuard, System, SystemFn, ToTick};
+use osal_rs::os::types::TickType;
+use osal_rs::utils::Result;
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::config::MUTEX;
+use crate::apps::config::defaults::MAX_USERS;
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::FS_DATA_DIR;
+
+use super::SLOTS;
+
+const APP_TAG: &str = "AppLockout";
+
+/// Failures before the lockout
+const MAX_FAILURES: u8 = 5;
+
+/// Wait after the first failure, doubled by each of the next ones
+const BACKOFF_SECONDS: u32 = 1;
+
+const LOCKOUT_SECONDS: u32 = 15 * 60;
+
+static mut SHARED: Lockout = Lockout::new();
+
+/// Tick until which the logins of each source and of each user are refused
+static mut SOURCES_UNTIL: [TickType; SLOTS] = [0; SLOTS];
+static mut USERS_UNTIL: [TickType; MAX_USERS] = [0; MAX_USERS];
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+struct Attempts {
+    /// failed logins since the last successful one
+    failures: u8,
+
+    /// seconds of the wait left when saved
+    seconds: u32,
+}
+
+impl Attempts {
+    const fn new() -> Self {
+        Self { failures: 0, seconds: 0 }
+    }
+}
+
+/// Saved state of the failed logins, indexed by session slot and by user
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub(super) struct Lockout {
+    sources: [Attempts; SLOTS],
+    users: [Attempts; MAX_USERS],
+}
+
+impl Default for Lockout {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lockout {
+    const FILE_NAME: &'static str = "lockout.json";
+
+    const fn new() -> Self {
+        Self { sources: [Attempts::new(); SLOTS], users: [Attempts::new(); MAX_USERS] }
+    }
+
+    /// Loads the saved waits, they start again from the boot
+    pub(super) fn init() -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let shared = unsafe { &mut *&raw mut SHARED };
+        *shared = deserialize_file::<Lockout>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_DATA_DIR, Lockout::FILE_NAME)?;
+
+        let now = System::get_tick_count();
+        let sources_until = unsafe { &mut *&raw mut SOURCES_UNTIL };
+        let users_until = unsafe { &mut *&raw mut USERS_UNTIL };
+        for (attempts, until) in shared.sources.iter().zip(sources_until.iter_mut()).chain(shared.users.iter().zip(users_until.iter_mut())) {
+            *until = now.wrapping_add(Duration::from_secs(attempts.seconds as u64).to_ticks());
+        }
+
+        Ok(())
+    }
+
+    /// Seconds before the source may try again, the user too when the email is stored
+    pub(super) fn remaining(slot: usize, user: Option<usize>) -> u32 {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let source = Self::left(unsafe { SOURCES_UNTIL[slot] });
+        let user = user.map_or(0, |index| Self::left(unsafe { USERS_UNTIL[index] }));
+        source.max(user)
+    }
+
+    /// Counts a failed login, true when it locks the source or the user
+    pub(super) fn failure(slot: usize, user: Option<usize>) -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let shared = unsafe { &mut *&raw mut SHARED };
+        let mut locked = Self::fail(&mut shared.sources[slot], unsafe { &mut *&raw mut SOURCES_UNTIL[slot] });
+        if let Some(index) = user {
+            locked |= Self::fail(&mut shared.users[index], unsafe { &mut *&raw mut USERS_UNTIL[index] });
+        }
+
+        Self::save();
+        locked
+    }
+
+    /// Clears the failures of the source and of the user after a successful login
+    pub(super) fn success(slot: usize, user: Option<usize>) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let shared = unsafe { &mut *&raw mut SHARED };
+        let mut changed = Self::clear(&mut shared.sources[slot], unsafe { &mut *&raw mut SOURCES_UNTIL[slot] });
+        if let Some(index) = user {
+            changed |= Self::clear(&mut shared.users[index], unsafe { &mut *&raw mut USERS_UNTIL[index] });
+        }
+
+        if changed {
+            Self::save();
+        }
+    }
+
+    /// Drops the failures of a removed user, its slot goes to the next one
+    pub(super) fn forget(user: usize) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let shared = unsafe { &mut *&raw mut SHARED };
+        if Self::clear(&mut shared.users[user], unsafe { &mut *&raw mut USERS_UNTIL[user] }) {
+            Self::save();
+        }
+    }
+
+    fn fail(attempts: &mut Attempts, until: &mut TickType) -> bool {
+        attempts.failures = attempts.failures.saturating_add(1).min(MAX_FAILURES);
+
+        let seconds = if attempts.failures >= MAX_FAILURES {
+            LOCKOUT_SECONDS
+        } else {
+            BACKOFF_SECONDS << (attempts.failures - 1)
+        };
+        *until = System::get_tick_count().wrapping_add(Duration::from_secs(seconds as u64).to_ticks());
+
+        attempts.failures >= MAX_FAILURES
+    }
+
+    fn clear(attempts: &mut Attempts, until: &mut TickType) -> bool {
+        let changed = attempts.failures > 0;
+        *attempts = Attempts::new();
+        *until = 0;
+        changed
+    }
+
+    /// Seconds left before the tick, rounded up, 0 once passed
+    fn left(until: TickType) -> u32 {
+        let ticks = until.wrapping_sub(System::get_tick_count());
+        if ticks == 0 || ticks > TickType::MAX / 2 {
+            return 0;
+        }
+        let second = Duration::from_secs(1).to_ticks();
+        ticks.div_ceil(second) as u32
+    }
+
+    fn save() {
+        let shared = unsafe { &mut *&raw mut SHARED };
+        for (attempts, until) in shared.sources.iter_mut().zip(unsafe { SOURCES_UNTIL }) {
+            attempts.seconds = Self::left(until);
+        }
+        for (attempts, until) in shared.users.iter_mut().zip(unsafe { USERS_UNTIL }) {
+            attempts.seconds = Self::left(until);
+        }
+
+        if let Err(e) = serialize_file(unsafe { &*&raw const MUTEX }, APP_TAG, FS_DATA_DIR, Lockout::FILE_NAME, unsafe { &*&raw const SHARED }) {
+            log_warning!(APP_TAG, "Failed to save the lockout state: {e}");
+        }
+    }
+}
@@ -0,0 +1,500 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+///! Second factor of the admins: RFC 6238 time-based one-time passwords, the HOTP of
+///! RFC 4226 over the 30 s steps of the RTC. Neither the SHA of the hardware nor the
+///! wrapper of mbedtls has SHA-1, so it is computed here together with its HMAC.
+
+use alloc::format;
+use alloc::sync::Arc;
+use at_parser_rs::{AtError, AtResult};
+use at_parser_rs::context::AtContext;
+use osal_rs::{access_static_option, log_info, log_warning};
+use osal_rs::os::{Mutex, MutexFn, RawMutexGuard};
+use osal_rs::utils::Bytes;
+
+use crate::apps::audit::{AuditLog, AuditOutcome, AuditRecord};
+use crate::apps::config::{Config, MUTEX};
+use crate::apps::config::defaults::MAX_USERS;
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::traits::rtc::RTC;
+
+use super::{LoginError, Role, SESSIONS, Session, random_fill};
+use super::lockout::Lockout;
+
+const APP_TAG: &str = "AppTotp";
+
+/// Random bytes of each secret
+const SECRET_BYTES: usize = 16;
+
+/// Base32 characters of a secret, without padding
+pub(super) const SECRET_SIZE: usize = (SECRET_BYTES * 8).div_ceil(5);
+
+const STEP_SECONDS: i64 = 30;
+
+/// Six digits codes
+const CODE_MODULO: u32 = 1_000_000;
+const CODE_SIZE: usize = 6;
+
+/// Steps of clock drift accepted on each side of the current one
+const DRIFT_STEPS: i64 = 1;
+
+/// No account name but the issuer, the URI has to fit the QR code of the LCD
+const URI_PREFIX: &str = "otpauth://totp/HHG?secret=";
+pub(in crate::apps) const URI_SIZE: usize = URI_PREFIX.len() + SECRET_SIZE;
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+const SHA1_BLOCK: usize = 64;
+const SHA1_DIGEST: usize = 20;
+
+/// Time source of the codes, set once the hardware is ready
+static mut CLOCK: Option<Arc<Mutex<dyn RTC + 'static>>> = None;
+
+/// Last step accepted for each user, a code is never accepted twice
+static mut LAST_STEPS: [i64; MAX_USERS] = [-1; MAX_USERS];
+
+/// Secret waiting for its first code, one at a time as the LCD shows its QR code
+static mut ENROLLMENT: Option<Enrollment> = None;
+
+static mut TOTP: Totp = Totp;
+
+#[derive(Clone, Copy)]
+struct Enrollment {
+    /// session slot and user that asked for it
+    slot: usize,
+    email: Bytes<32>,
+    secret: Bytes<SECRET_SIZE>,
+    /// already handed to the display
+    shown: bool,
+}
+
+struct Sha1 {
+    state: [u32; 5],
+    block: [u8; SHA1_BLOCK],
+    used: usize,
+    length: u64,
+}
+
+impl Sha1 {
+    const fn new() -> Self {
+        Self {
+            state: [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0],
+            block: [0; SHA1_BLOCK],
+            used: 0,
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+        while !data.is_empty() {
+            let take = (SHA1_BLOCK - self.used).min(data.len());
+            self.block[self.used..self.used + take].copy_from_slice(&data[..take]);
+            self.used += take;
+            data = &data[take..];
+            if self.used == SHA1_BLOCK {
+                self.compress();
+                self.used = 0;
+            }
+        }
+    }
+
+    fn finish(mut self) -> [u8; SHA1_DIGEST] {
+        let bits = self.length * 8;
+        self.update(&[0x80]);
+        while self.used != SHA1_BLOCK - size_of::<u64>() {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+
+        let mut digest = [0u8; SHA1_DIGEST];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self) {
+        let mut w = [0u32; 80];
+        for (word, chunk) in w.iter_mut().zip(self.block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..w.len() {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+fn hmac_sha1(key: &[u8], data: &[u8]) -> [u8; SHA1_DIGEST] {
+    let mut block = [0u8; SHA1_BLOCK];
+    if key.len() > SHA1_BLOCK {
+        let mut hash = Sha1::new();
+        hash.update(key);
+        block[..SHA1_DIGEST].copy_from_slice(&hash.finish());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha1::new();
+    inner.update(&block.map(|byte| byte ^ 0x36));
+    inner.update(data);
+
+    let mut outer = Sha1::new();
+    outer.update(&block.map(|byte| byte ^ 0x5c));
+    outer.update(&inner.finish());
+    outer.finish()
+}
+
+/// RFC 4226 dynamic truncation of the HMAC-SHA1 of the counter, 31 bits before the digits are taken
+fn truncate(secret: &[u8], counter: u64) -> u32 {
+    let mac = hmac_sha1(secret, &counter.to_be_bytes());
+    let offset = (mac[SHA1_DIGEST - 1] & 0x0f) as usize;
+    u32::from_be_bytes([mac[offset], mac[offset + 1], mac[offset + 2], mac[offset + 3]]) & 0x7fff_ffff
+}
+
+/// RFC 4226 code of the counter
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    truncate(secret, counter) % CODE_MODULO
+}
+
+/// RFC 4648 base32 without padding, the format of the authenticator apps
+fn base32_encode<const SIZE: usize>(bytes: &[u8]) -> Bytes<SIZE> {
+    let mut text = [0u8; SIZE];
+    let mut len = 0;
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &byte in bytes {
+        buffer = ((buffer << 8) | byte as u32) & 0x1fff;
+        bits += 8;
+        while bits >= 5 && len < SIZE {
+            bits -= 5;
+            text[len] = BASE32[(buffer >> bits) as usize & 0x1f];
+            len += 1;
+        }
+    }
+    if bits > 0 && len < SIZE {
+        text[len] = BASE32[(buffer << (5 - bits)) as usize & 0x1f];
+        len += 1;
+    }
+    Bytes::from_str(core::str::from_utf8(&text[..len]).unwrap_or_default())
+}
+
+/// Bytes written, None for a character outside the alphabet or a buffer too short
+fn base32_decode(text: &str, bytes: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for character in text.bytes() {
+        let value = BASE32.iter().position(|&c| c == character.to_ascii_uppercase())? as u32;
+        buffer = ((buffer << 5) | value) & 0x1fff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            *bytes.get_mut(len)? = (buffer >> bits) as u8;
+            len += 1;
+        }
+    }
+    Some(len)
+}
+
+/// Step of the code when it is the one of the time or of the steps next to it
+fn verify(secret: &str, code: &str, timestamp: i64) -> Option<i64> {
+    let mut key = [0u8; SHA1_BLOCK];
+    let len = base32_decode(secret, &mut key)?;
+    if code.len() != CODE_SIZE {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+
+    let step = timestamp.div_euclid(STEP_SECONDS);
+    (step - DRIFT_STEPS..=step + DRIFT_STEPS).find(|&step| step >= 0 && hotp(&key[..len], step as u64) == code)
+}
+
+/// True if the code is right now and newer than the last one accepted for the user
+pub(super) fn accept(index: usize, secret: &str, code: &str) -> bool {
+    let Some(timestamp) = (unsafe { (*&raw const CLOCK).as_ref() }).and_then(|rtc| rtc.lock().ok()?.get_timestamp().ok()) else {
+        return false;
+    };
+
+    take(unsafe { &mut *&raw mut LAST_STEPS[index] }, secret, code, timestamp)
+}
+
+/// True if the code is right at the time and its step is newer than the last one, which it becomes
+fn take(last: &mut i64, secret: &str, code: &str, timestamp: i64) -> bool {
+    match verify(secret, code, timestamp) {
+        Some(step) if step > *last => {
+            *last = step;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Code of the logged user checked as a login: refused while the source or the user wait,
+/// a wrong one is a failed login of both and a failure that locks them is audited
+fn confirm(slot: usize, index: usize, email: &str, verb: &str, secret: &str, code: &str) -> core::result::Result<(), LoginError> {
+    let remaining = Lockout::remaining(slot, Some(index));
+    if remaining > 0 {
+        return Err(LoginError::Locked(remaining));
+    }
+
+    if accept(index, secret, code) {
+        Lockout::success(slot, Some(index));
+        return Ok(());
+    }
+
+    if Lockout::failure(slot, Some(index)) {
+        log_warning!(APP_TAG, "TOTP of user {index} locked after repeated wrong codes");
+        AuditLog::shared().add(AuditRecord::event(slot, email, Totp::AT_CMD, verb, AuditOutcome::Locked));
+    }
+    Err(LoginError::Invalid)
+}
+
+/// Second factor of the user logged in on the source, AT+OTP
+pub(in crate::apps) struct Totp;
+
+impl AtContext<{Parser::CMD_SIZE}> for Totp {
+
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let slot = Session::issuer_slot().ok_or((at_response, AtError::InvalidArgs))?;
+        let logged = unsafe { SESSIONS[slot].logged }.ok_or((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)))?;
+        let session = Config::shared().get_session();
+        let enabled = session.index_of(logged.email.as_str()).is_some_and(|index| session.users[index].totp.len() > 0);
+        let enrolling = unsafe { *&raw const ENROLLMENT }.is_some_and(|enrollment| enrollment.slot == slot);
+
+        Ok(at_cmd_response!(at_response; enabled as u8, enrolling as u8))
+    }
+
+    #[inline]
+    /// en = enroll, cf = confirm, dl = disable
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "en | cf,<code> | dl,<code>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let slot = Session::issuer_slot().ok_or((at_response, AtError::InvalidArgs))?;
+        let logged = unsafe { SESSIONS[slot].logged }.ok_or((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)))?;
+        let session = Config::shared().get_session();
+        let index = session.index_of(logged.email.as_str()).ok_or((at_response, AtError::Unhandled("User not defined")))?;
+
+        match cmd.as_ref() {
+            "en" => { // enroll, the secret is used once confirmed
+                if logged.role != Role::Admin {
+                    return Err((at_response, AtError::Unhandled("Admins only")));
+                }
+                let mut random = [0u8; SECRET_BYTES];
+                random_fill(&mut random);
+                let secret = base32_encode(&random);
+                unsafe {
+                    ENROLLMENT = Some(Enrollment { slot, email: logged.email, secret, shown: false });
+                }
+                log_info!(APP_TAG, "TOTP enrollment of user {index} started");
+                return Ok(at_cmd_response!(at_response; secret.as_str()));
+            }
+            "cf" => { // confirm with the first code
+                let code = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+                let enrollment = unsafe { *&raw const ENROLLMENT }
+                    .filter(|enrollment| enrollment.slot == slot && enrollment.email == logged.email)
+                    .ok_or((at_response, AtError::Unhandled("No enrollment")))?;
+                match confirm(slot, index, logged.email.as_str(), "cf", enrollment.secret.as_str(), code.as_ref()) {
+                    Ok(()) => {}
+                    Err(LoginError::Locked(seconds)) => return Err((at_response, AtError::UnhandledOwned(format!("{}, retry in {seconds} s", Session::LOCKED_RESPONSE)))),
+                    Err(_) => return Err((at_response, AtError::Unhandled("Wrong code"))),
+                }
+                unsafe {
+                    ENROLLMENT = None;
+                }
+                session.users[index].totp = enrollment.secret;
+                Session::update_logged(&session.users[index]);
+                log_info!(APP_TAG, "TOTP of user {index} enabled");
+            }
+            "dl" => { // disable, with a code of the secret in use
+                let code = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+                let secret = session.users[index].totp;
+                if secret.len() == 0 {
+                    return Err((at_response, AtError::Unhandled("TOTP not enabled")));
+                }
+                match confirm(slot, index, logged.email.as_str(), "dl", secret.as_str(), code.as_ref()) {
+                    Ok(()) => {}
+                    Err(LoginError::Locked(seconds)) => return Err((at_response, AtError::UnhandledOwned(format!("{}, retry in {seconds} s", Session::LOCKED_RESPONSE)))),
+                    Err(_) => return Err((at_response, AtError::Unhandled("Wrong code"))),
+                }
+                session.users[index].totp.clear();
+                Session::update_logged(&session.users[index]);
+                log_info!(APP_TAG, "TOTP of user {index} disabled");
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Config::save().map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl Totp {
+    pub const AT_CMD: &'static str = "AT+OTP";
+    pub const AT_RESP: &'static str = "+OTP: ";
+
+    pub fn get() -> &'static mut Totp {
+        unsafe { &mut *&raw mut TOTP }
+    }
+
+    pub(in crate::apps) fn set_rtc(rtc: Arc<Mutex<dyn RTC + 'static>>) {
+        unsafe {
+            CLOCK = Some(rtc);
+        }
+    }
+
+    /// True once for each enrollment, when the display has to show its QR code
+    pub(in crate::apps) fn take_display() -> bool {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        match unsafe { (*&raw mut ENROLLMENT).as_mut() } {
+            Some(enrollment) if !enrollment.shown => {
+                enrollment.shown = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// otpauth URI of the enrollment waiting for its first code
+    pub(in crate::apps) fn enrollment_uri() -> Option<Bytes<URI_SIZE>> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { *&raw const ENROLLMENT }.map(|enrollment| {
+            let mut uri = Bytes::from_str(URI_PREFIX);
+            uri.append_str(enrollment.secret.as_str());
+            uri
+        })
+    }
+
+    /// Drops the enrollment started on the slot, its session is over
+    pub(super) fn cancel(slot: usize) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe {
+            if (*&raw const ENROLLMENT).is_some_and(|enrollment| enrollment.slot == slot) {
+                ENROLLMENT = None;
+            }
+        }
+    }
+
+    /// Forgets the codes accepted for the user of the slot, removed or replaced
+    pub(super) fn forget(index: usize) {
+        unsafe {
+            LAST_STEPS[index] = -1;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    /// Secret of the test vectors of RFC 4226 and RFC 6238 (SHA-1)
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+    const RFC_SECRET_BASE32: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn hotp_matches_rfc4226() {
+        let codes = [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+        for (counter, code) in codes.into_iter().enumerate() {
+            assert_eq!(hotp(RFC_SECRET, counter as u64), code, "counter {counter}");
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc6238() {
+        let vectors = [
+            (59, 94287082),
+            (1111111109, 7081804),
+            (1111111111, 14050471),
+            (1234567890, 89005924),
+            (2000000000, 69279037),
+        ];
+        for (timestamp, code) in vectors {
+            let step = (timestamp / STEP_SECONDS) as u64;
+            assert_eq!(truncate(RFC_SECRET, step) % 100_000_000, code, "time {timestamp}");
+            assert_eq!(hotp(RFC_SECRET, step), code % CODE_MODULO, "time {timestamp}");
+        }
+    }
+
+    #[test]
+    fn base32_round_trip() {
+        let encoded: Bytes<32> = base32_encode(RFC_SECRET);
+        assert_eq!(encoded.as_str(), RFC_SECRET_BASE32);
+
+        let random: [u8; SECRET_BYTES] = core::array::from_fn(|i| (i as u8).wrapping_mul(37) ^ 0xa5);
+        let secret: Bytes<SECRET_SIZE> = base32_encode(&random);
+        assert_eq!(secret.len(), SECRET_SIZE);
+        let mut decoded = [0u8; SHA1_BLOCK];
+        assert_eq!(base32_decode(secret.as_str(), &mut decoded), Some(SECRET_BYTES));
+        assert_eq!(decoded[..SECRET_BYTES], random);
+
+        assert_eq!(base32_decode(&RFC_SECRET_BASE32.to_ascii_lowercase(), &mut decoded), Some(RFC_SECRET.len()));
+        assert_eq!(&decoded[..RFC_SECRET.len()], RFC_SECRET);
+        assert_eq!(base32_decode("GEZD1", &mut decoded), None);
+    }
+
+    #[test]
+    fn verify_accepts_one_step_of_drift() {
+        // 287082 is the code of the step 1, from 30 s to 59 s
+        assert_eq!(verify(RFC_SECRET_BASE32, "287082", 59), Some(1));
+        assert_eq!(verify(RFC_SECRET_BASE32, "287082", 0), Some(1));
+        assert_eq!(verify(RFC_SECRET_BASE32, "287082", 89), Some(1));
+        assert_eq!(verify(RFC_SECRET_BASE32, "287082", 90), None);
+        assert_eq!(verify(RFC_SECRET_BASE32, "28708", 59), None);
+        assert_eq!(verify(RFC_SECRET_BASE32, "000000", 59), None);
+    }
+
+    #[test]
+    fn used_steps_are_refused() {
+        let mut last = -1;
+        assert!(take(&mut last, RFC_SECRET_BASE32, "287082", 59));
+        assert!(!take(&mut last, RFC_SECRET_BASE32, "287082", 59));
+        // an older step still in the drift window
+        assert!(!take(&mut last, RFC_SECRET_BASE32, "755224", 59));
+        assert!(take(&mut last, RFC_SECRET_BASE32, "359152", 59));
+        assert_eq!(last, 2);
+    }
+}
@@ -20,6 +20,7 @@
 
 pub(super) mod display;
 pub(super) mod error;
+pub(super) mod scheduler;
 pub(super) mod status;
 
 
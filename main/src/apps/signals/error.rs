@@ -35,6 +35,8 @@ pub(in crate::apps) enum ErrorFlag {
     Display = 0x04,
     #[allow(unused)]
     DisplayHeader = 0x08,
+    PumpLockout = 0x10,
+    Mqtt = 0x20,
 }
 
 impl From<u32> for ErrorFlag {
@@ -44,6 +46,8 @@ impl From<u32> for ErrorFlag {
             0x01 => NTP,
             0x02 => DateTime,
             0x04 => Display,
+            0x10 => PumpLockout,
+            0x20 => Mqtt,
             _ => None, // Default case, can be adjusted as needed
         }
     }
@@ -64,6 +68,8 @@ impl Display for ErrorFlag {
             DateTime => "DateTime",
             Display => "Display",
             DisplayHeader => "DisplayHeader",
+            PumpLockout => "PumpLockout",
+            Mqtt => "Mqtt",
         };
         write!(f, "{}", s)
     }
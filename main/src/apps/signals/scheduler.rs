@@ -0,0 +1,57 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+
+///! Scheduler signal, wakes the main FSM before the next due start.
+
+use crate::define_signal;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(in crate::apps) enum SchedulerFlag {
+    None = 0x00,
+    ScheduleChanged = 0x01,
+    ClockChanged = 0x02,
+    Request = 0x04,
+}
+
+impl SchedulerFlag {
+    /// The next start of the schedules must be computed again
+    pub(in crate::apps) const RESCHEDULE: u32 = SchedulerFlag::ScheduleChanged as u32 | SchedulerFlag::ClockChanged as u32;
+}
+
+impl From<u32> for SchedulerFlag {
+    fn from(value: u32) -> Self {
+        use SchedulerFlag::*;
+        match value {
+            0x01 => ScheduleChanged,
+            0x02 => ClockChanged,
+            0x04 => Request,
+            _ => None, // Default case, can be adjusted as needed
+        }
+    }
+}
+
+impl From<SchedulerFlag> for u32 {
+    fn from(flag: SchedulerFlag) -> Self {
+        flag as u32
+    }
+}
+
+define_signal!(SchedulerSignal, SCHEDULER_SIGNAL);
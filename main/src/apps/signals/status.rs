@@ -39,7 +39,11 @@ pub(in crate::apps) enum StatusFlag {
     Ready = 0x80,
     Error = 0x01_00,
     Reset = 0x02_00,
+    Winterize = 0x04_00,
+    Winterized = 0x08_00,
     
+    HttpCmd = 0x00_04_00_00,
+    TelnetCmd = 0x00_08_00_00,
     SystemCmd = 0x00_10_00_00,
     MqttCmd = 0x00_20_00_00,
     UartCmd = 0x00_40_00_00,
@@ -61,7 +65,11 @@ impl From<u32> for StatusFlag {
             0x80 => Ready,
             0x01_00 => Error,
             0x02_00 => Reset,
+            0x04_00 => Winterize,
+            0x08_00 => Winterized,
             
+            0x00_04_00_00 => HttpCmd,
+            0x00_08_00_00 => TelnetCmd,
             0x00_10_00_00 => SystemCmd,
             0x00_20_00_00 => MqttCmd,
             0x00_40_00_00 => UartCmd, 
@@ -83,6 +91,8 @@ impl From<&Source> for StatusFlag {
         match source {
             Source::Mqtt => MqttCmd,
             Source::Uart => UartCmd,
+            Source::Telnet => TelnetCmd,
+            Source::Http => HttpCmd,
         }
     }
 }
@@ -103,6 +113,10 @@ impl StatusFlag {
             Ready => Bytes::from("Ready"),
             Error => Bytes::from("Error"),
             Reset => Bytes::from("Reset"),
+            Winterize => Bytes::from("Winterize"),
+            Winterized => Bytes::from("Winterized"),
+            HttpCmd => Bytes::from("HttpCmd"),
+            TelnetCmd => Bytes::from("TelnetCmd"),
             SystemCmd => Bytes::from("SystemCmd"),
             MqttCmd => Bytes::from("MqttCmd"),
             UartCmd => Bytes::from("UartCmd"),
@@ -22,30 +22,273 @@
 
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use osal_rs::log_info;
-use osal_rs::utils::Result;
+use osal_rs::{log_error, log_info, log_warning};
+use osal_rs::utils::{OsalRsBool, Result};
 
+use crate::apps::events::{Event, Events};
+use crate::apps::sprinkler::budget::BudgetController;
 use crate::apps::sprinkler::commons::Status;
-use crate::apps::sprinkler::schedule::ScheduleController;
-use crate::apps::sprinkler::zone::ZoneController;
+use crate::apps::sprinkler::dosing::{DosingController, DosingMode};
+use crate::apps::sprinkler::group::{GroupController, ZoneTarget};
+use crate::apps::sprinkler::history::{HistoryController, RunRecord};
+use crate::apps::sprinkler::manual::ManualController;
+use crate::apps::sprinkler::profile::{Profile, ProfileController};
+use crate::apps::sprinkler::protection::{LockoutReason, ProtectionController};
+use crate::apps::sprinkler::schedule::{Due, ScheduleController, ScheduleZone, Timetable};
+use crate::apps::sprinkler::statistics::{SkipReason, StatisticsController};
+use crate::apps::sprinkler::winterization::WinterizationController;
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
 use crate::drivers::date_time::DateTime;
+use crate::drivers::platform::GpioPeripheral;
+use crate::traits::flow_meter::FlowMeter as FlowMeterFn;
+use crate::traits::pump_sensors::PumpSensors as PumpSensorsFn;
+use crate::traits::relays::Relays as RelaysFn;
 use crate::traits::state::Initializable;
 
 mod commons;
 pub(in crate::apps) mod zone;
+pub(in crate::apps) mod group;
+pub(in crate::apps) mod profile;
+pub(in crate::apps) mod dosing;
 pub(in crate::apps) mod schedule;
+pub(in crate::apps) mod statistics;
+pub(in crate::apps) mod history;
+pub(in crate::apps) mod protection;
+pub(in crate::apps) mod budget;
+pub(in crate::apps) mod winterization;
+pub(in crate::apps) mod program;
+pub(in crate::apps) mod manual;
 
 const APP_TAG: &str = "AppSprinkler";
+const MILLILITERS_PER_LITER: u64 = 1_000;
+const MICROLITERS_PER_MILLILITER: u64 = 1_000;
 
 static DISBURSEMENT_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
 
 /// Index of the schedule returned by `query`, set via `AT+SPK=select,<index>`
 static SELECTED_SCHEDULE: AtomicUsize = AtomicUsize::new(0);
 
-#[derive(Debug)]
+/// State of a zone valve while the schedule entry that contains it is in execution
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum Valve {
+    #[default]
+    Idle,
+
+    /// waiting a free slot, see `GroupController::get_max_open_valves`
+    Waiting,
+
+    /// open since timestamp
+    Open(i64),
+
+    /// closed since timestamp between two cycles, see `Profile::cycle_minutes`
+    Soaking(i64),
+}
+
+/// What a valve of the entry in execution does at a step, see `plan`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Action {
+    /// nothing changes
+    Keep,
+
+    /// open the waiting or soaked valve
+    Open,
+
+    /// the soak is over, wait a free slot
+    Wait,
+
+    /// close the valve at the end of a cycle
+    Soak,
+
+    /// close the valve, time or volume reached
+    Close,
+
+    /// close the valve before its target, weekly budget reached
+    Shorten,
+
+    /// drop the valve never opened
+    Skip(SkipReason),
+}
+
+/// State of a valve and of its zone at a step, see `plan`
+#[derive(Debug, Default, Copy, Clone)]
+struct ValveInput {
+    valve: Valve,
+
+    /// time or volume of the open valve reached
+    completed: bool,
+
+    /// weekly budget of the zone or of the controller reached
+    over_budget: bool,
+
+    /// opened at least once in the entry in execution
+    started: bool,
+
+    /// watering by volume without flow meter nor flow rate
+    unmeasured: bool,
+
+    /// seconds of watering before a soak, 0 without cycles
+    cycle_seconds: i64,
+
+    /// seconds with the valve closed between two cycles
+    soak_seconds: i64,
+}
+
+/// Decide the step of each valve of the entry in execution: the open ones close when completed or over budget and
+/// soak at the end of a cycle, the soaking ones wait again after the soak, then the waiting ones open in relay order
+/// while less than `max_open_valves` are open, counting the slots freed in the same step
+fn plan(inputs: &[ValveInput; ZoneController::SIZE], max_open_valves: usize, timestamp: i64) -> [Action; ZoneController::SIZE] {
+    let mut actions = [Action::Keep; ZoneController::SIZE];
+    let mut waiting = [false; ZoneController::SIZE];
+    let mut open_valves = 0;
+
+    for (idx, input) in inputs.iter().enumerate() {
+        actions[idx] = match input.valve {
+            Valve::Idle => Action::Keep,
+            Valve::Open(_) if input.completed => Action::Close,
+            Valve::Open(_) if input.over_budget => Action::Shorten,
+            Valve::Open(since) if input.cycle_seconds > 0 && timestamp - since >= input.cycle_seconds => Action::Soak,
+            Valve::Open(_) => {
+                open_valves += 1;
+                Action::Keep
+            }
+            Valve::Soaking(_) if input.over_budget => Action::Shorten,
+            Valve::Soaking(since) if timestamp - since >= input.soak_seconds => {
+                waiting[idx] = true;
+                Action::Wait
+            }
+            Valve::Soaking(_) => Action::Keep,
+            Valve::Waiting if input.over_budget => if input.started { Action::Shorten } else { Action::Skip(SkipReason::Budget) },
+            Valve::Waiting if input.unmeasured => Action::Skip(SkipReason::Flow),
+            Valve::Waiting => {
+                waiting[idx] = true;
+                Action::Keep
+            }
+        };
+    }
+
+    for idx in (0..ZoneController::SIZE).filter(|idx| waiting[*idx]) {
+        if open_valves >= max_open_valves {
+            break;
+        }
+        actions[idx] = Action::Open;
+        open_valves += 1;
+    }
+
+    actions
+}
+
+/// Schedule in execution, entries are watered one after the other, the valves of a group entry concurrently
+#[derive(Debug, Copy, Clone)]
+struct Run {
+
+    /// index of the running schedule, `ManualController::SCHEDULE` for a manual run
+    schedule: usize,
+
+    /// zones or zone groups with watering time or volume, sorted by zone weight
+    zones: [Option<ScheduleZone>; ZoneController::SIZE],
+
+    /// position in `zones` of the next entry to water
+    position: usize,
+
+    /// entry in execution
+    entry: ScheduleZone,
+
+    /// valves of the entry in execution indexed by relay number
+    valves: [Valve; ZoneController::SIZE],
+
+    /// month (1-12) of the start, selects the seasonal factor of the profiles
+    month: u8,
+
+    /// target of each valve of the entry in execution, seconds by time or milliliters by volume
+    target: [u64; ZoneController::SIZE],
+
+    /// timestamp of the first opening of each valve
+    started: [i64; ZoneController::SIZE],
+
+    /// seconds watered by each valve in the previous cycles
+    watered: [i64; ZoneController::SIZE],
+
+    /// milliliters delivered by each valve
+    delivered: [u64; ZoneController::SIZE],
+
+    /// flow meter pulses at the last step
+    pulses: u32,
+
+    /// fertilizer microliters pumped for each valve
+    dosed: [u64; ZoneController::SIZE],
+
+    /// open valves that requested the dosing pump at the last step
+    dosing: [bool; ZoneController::SIZE],
+
+    /// timestamp of the last step
+    timestamp: i64,
+
+    /// timestamp of the last flow detected with valves open
+    flow_at: i64,
+
+    /// flow meter pulses at the last flow detected
+    flow_pulses: u32,
+}
+
+impl Run {
+    #[inline]
+    fn open_valves(&self) -> usize {
+        self.valves.iter().filter(|valve| matches!(valve, Valve::Open(_))).count()
+    }
+
+    #[inline]
+    fn is_idle(&self) -> bool {
+        self.valves.iter().all(|valve| *valve == Valve::Idle)
+    }
+
+    /// Seconds watered by the valve in all its cycles
+    #[inline]
+    fn elapsed(&self, idx: usize, timestamp: i64) -> i64 {
+        match self.valves[idx] {
+            Valve::Open(since) => self.watered[idx] + (timestamp - since).max(0),
+            _ => self.watered[idx],
+        }
+    }
+}
+
+/// Step of the winterization blowout, zones are blown one after the other with a pause between them
+#[derive(Debug, Copy, Clone)]
+struct Blowout {
+
+    /// index of the zone blown
+    zone: usize,
+
+    /// valve open, otherwise pause after the zone
+    open: bool,
+
+    /// timestamp of the start of the step
+    since: i64,
+}
+
 pub(in crate::apps) struct Sprinkler {
     schedule_controller: &'static mut ScheduleController,
     zone_comntroller: &'static mut ZoneController,
+    group_controller: &'static mut GroupController,
+    statistics_controller: &'static mut StatisticsController,
+    history_controller: &'static mut HistoryController,
+    dosing_controller: &'static mut DosingController,
+    protection_controller: &'static mut ProtectionController,
+    winterization_controller: &'static mut WinterizationController,
+    profile_controller: &'static mut ProfileController,
+    budget_controller: &'static mut BudgetController,
+    manual_controller: &'static mut ManualController,
+    relays: Option<&'static dyn RelaysFn>,
+    flow_meter: Option<&'static dyn FlowMeterFn>,
+    pump_sensors: Option<&'static dyn PumpSensorsFn>,
+    run: Option<Run>,
+
+    /// state of the dosing pump
+    pump: bool,
+
+    blowout: Option<Blowout>,
+
+    /// next start time of each schedule
+    timetable: Timetable,
 }
 
 impl Initializable for Sprinkler {
@@ -54,6 +297,14 @@ impl Initializable for Sprinkler {
         
         self.schedule_controller.init()?;
         self.zone_comntroller.init()?;
+        self.group_controller.init()?;
+        self.statistics_controller.init()?;
+        self.history_controller.init()?;
+        self.dosing_controller.init()?;
+        self.protection_controller.init()?;
+        self.winterization_controller.init()?;
+        self.profile_controller.init()?;
+        self.budget_controller.init()?;
 
 
 
@@ -77,22 +328,795 @@ impl Sprinkler {
     pub(in crate::apps) fn new() -> Self {
         Self {
             schedule_controller: ScheduleController::shared(),
-            zone_comntroller: ZoneController::shared()
+            zone_comntroller: ZoneController::shared(),
+            group_controller: GroupController::shared(),
+            statistics_controller: StatisticsController::shared(),
+            history_controller: HistoryController::shared(),
+            dosing_controller: DosingController::shared(),
+            protection_controller: ProtectionController::shared(),
+            winterization_controller: WinterizationController::shared(),
+            profile_controller: ProfileController::shared(),
+            budget_controller: BudgetController::shared(),
+            manual_controller: ManualController::shared(),
+            relays: None,
+            flow_meter: None,
+            pump_sensors: None,
+            run: None,
+            pump: false,
+            blowout: None,
+            timetable: Timetable::new(),
         }
     }
 
+    #[inline]
+    pub(in crate::apps) fn set_relays(&mut self, relays: &'static dyn RelaysFn) {
+        self.relays = Some(relays);
+    }
+
+    #[inline]
+    pub(in crate::apps) fn set_flow_meter(&mut self, flow_meter: &'static dyn FlowMeterFn) {
+        self.flow_meter = Some(flow_meter);
+    }
+
+    #[inline]
+    pub(in crate::apps) fn set_pump_sensors(&mut self, pump_sensors: &'static dyn PumpSensorsFn) {
+        self.pump_sensors = Some(pump_sensors);
+    }
+
+    /// Compute again the next start of the schedules at the next check, after a change of the schedules or of the clock
+    #[inline]
+    pub(in crate::apps) fn reschedule(&mut self) {
+        self.timetable.reschedule();
+    }
+
+    /// Seconds of idle from `timestamp` to the next due start of the schedules, see `Timetable::get_sleep_seconds`
+    #[inline]
+    pub(in crate::apps) fn get_sleep_seconds(&self, timestamp: i64) -> i64 {
+        self.timetable.get_sleep_seconds(timestamp)
+    }
+
+    /// Schedule or winterization blowout in execution, the run engine must be stepped every tick
+    #[inline]
+    pub(in crate::apps) fn is_running(&self) -> bool {
+        self.run.is_some() || self.blowout.is_some()
+    }
+
+    /// Start the schedules whose start time is due, each start time is handled once even if the clock goes back,
+    /// then advance the running schedule
     pub(in crate::apps) fn check(&mut self, now: DateTime) {
-        if DISBURSEMENT_IN_PROGRESS.load(Ordering::Relaxed) {
+        let timestamp = now.to_timestamp();
+
+        self.budget_controller.set_timestamp(timestamp);
+
+        self.timetable.update(self.schedule_controller.into_iter().map(|schedule| &*schedule), timestamp);
+
+        self.serve_manual(now);
+
+        let in_progress = DISBURSEMENT_IN_PROGRESS.load(Ordering::Relaxed);
+        let locked = self.protection_controller.is_locked(timestamp);
+
+        for (idx, schedule) in self.schedule_controller.into_iter().enumerate() {
+            match self.timetable.take(idx, schedule, timestamp) {
+                None => continue,
+                Some(Due::Missed) => {
+                    log_warning!(APP_TAG, "Schedule {idx} start missed");
+                    continue;
+                }
+                Some(Due::Start) => {}
+            }
+
+            if in_progress || self.run.is_some() {
+                log_warning!(APP_TAG, "Schedule {idx} skipped, watering in progress");
+                for zone in schedule.zones.iter().flatten() {
+                    for (relay, selected) in self.group_controller.relays(zone.target).into_iter().enumerate() {
+                        if selected {
+                            self.statistics_controller.record_skip(ZoneRelay::from(relay as u8), SkipReason::Busy);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            if locked {
+                log_warning!(APP_TAG, "Schedule {idx} skipped, pump locked out");
+                for zone in schedule.zones.iter().flatten() {
+                    for (relay, selected) in self.group_controller.relays(zone.target).into_iter().enumerate() {
+                        if selected {
+                            self.statistics_controller.record_skip(ZoneRelay::from(relay as u8), SkipReason::Lockout);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            // entries without minutes take the watering time of the zone profiles
+            let mut zones = schedule.zones;
+            // a group weights as its lighter zone
+            zones.sort_unstable_by_key(|zone| match zone {
+                Some(zone) => self.group_controller.relays(zone.target).into_iter().enumerate()
+                    .filter(|(_, selected)| *selected)
+                    .filter_map(|(relay, _)| self.zone_comntroller.get(ZoneRelay::from(relay as u8)))
+                    .map(|zone| zone.weight as u16)
+                    .min()
+                    .unwrap_or(0),
+                None => u16::MAX,
+            });
+
+            log_info!(APP_TAG, "Schedule {idx} started");
+            Events::post(Event::ScheduleStart { schedule: idx as u8 });
+            schedule.status = Status::RUN;
+            self.start(idx, zones, now);
+            break;
+        }
+
+        self.step(timestamp);
+
+        // skips of schedules and manual runs that did not start, a run saves in `finish`
+        if self.run.is_none() {
+            self.statistics_controller.flush();
+        }
+    }
+
+    /// Serve the manual requests received over AT: stop of the watering, close of single valves and start of a manual run
+    fn serve_manual(&mut self, now: DateTime) {
+        let timestamp = now.to_timestamp();
+
+        if self.manual_controller.take_stop_all_request() {
+            log_info!(APP_TAG, "Watering stopped on request");
+            self.stop(timestamp);
+        }
+
+        let stop = self.manual_controller.take_stop_request();
+        if let Some(mut run) = self.run.filter(|_| stop != 0) {
+            for idx in (0..ZoneController::SIZE).filter(|idx| stop & 1 << idx != 0) {
+                match run.valves[idx] {
+                    Valve::Open(_) | Valve::Soaking(_) => {
+                        log_info!(APP_TAG, "Zone {} closed on request", ZoneRelay::from(idx as u8));
+                        self.close(&mut run, idx, timestamp);
+                    }
+                    Valve::Waiting => run.valves[idx] = Valve::Idle,
+                    Valve::Idle => {}
+                }
+            }
+            self.run = Some(run);
+        }
+
+        let Some((zone_relay, minutes)) = self.manual_controller.take_start_request() else {
+            return;
+        };
+
+        if DISBURSEMENT_IN_PROGRESS.load(Ordering::Relaxed) || self.run.is_some() {
+            log_warning!(APP_TAG, "Manual run of zone {zone_relay} skipped, watering in progress");
+            self.statistics_controller.record_skip(zone_relay, SkipReason::Busy);
             return;
         }
 
-        for schedule in self.schedule_controller.into_iter() {
-            if schedule.executable(&now) {
-                DISBURSEMENT_IN_PROGRESS.store(true, Ordering::Relaxed);
-                schedule.status = Status::RUN;
+        if self.protection_controller.is_locked(timestamp) {
+            log_warning!(APP_TAG, "Manual run of zone {zone_relay} skipped, pump locked out");
+            self.statistics_controller.record_skip(zone_relay, SkipReason::Lockout);
+            return;
+        }
+
+        let mut zones = [None; ZoneController::SIZE];
+        zones[0] = Some(ScheduleZone { target: ZoneTarget::Zone(zone_relay), minutes, liters: 0 });
+
+        log_info!(APP_TAG, "Manual run of zone {zone_relay} started");
+        self.start(ManualController::SCHEDULE, zones, now);
+    }
+
+    fn start(&mut self, schedule: usize, zones: [Option<ScheduleZone>; ZoneController::SIZE], now: DateTime) {
+        let timestamp = now.to_timestamp();
+
+        DISBURSEMENT_IN_PROGRESS.store(true, Ordering::Relaxed);
+        self.run = Some(Run {
+            schedule,
+            zones,
+            position: 0,
+            entry: ScheduleZone::default(),
+            valves: [Valve::Idle; ZoneController::SIZE],
+            month: now.month,
+            target: [0; ZoneController::SIZE],
+            started: [0; ZoneController::SIZE],
+            watered: [0; ZoneController::SIZE],
+            delivered: [0; ZoneController::SIZE],
+            pulses: self.flow_meter.map_or(0, |flow_meter| flow_meter.get_pulses()),
+            dosed: [0; ZoneController::SIZE],
+            dosing: [false; ZoneController::SIZE],
+            timestamp,
+            flow_at: timestamp,
+            flow_pulses: 0,
+        });
+    }
+
+    /// Advance the running schedule: close the valves whose time or volume is reached, open the waiting ones
+    /// without exceeding the max open valves, move to the next entry when all are closed and drive the dosing pump
+    fn step(&mut self, timestamp: i64) {
+        let Some(mut run) = self.run else {
+            return;
+        };
+
+        let max_open_valves = self.group_controller.get_max_open_valves() as usize;
+
+        self.measure(&mut run, timestamp);
+        self.dose(&mut run, timestamp);
+
+        if let Some(reason) = self.protect(&mut run, timestamp) {
+            self.run = Some(run);
+            self.stop(timestamp);
+            self.protection_controller.lock(reason, timestamp);
+            return;
+        }
+
+        loop {
+            let inputs = core::array::from_fn(|idx| self.valve_input(&run, idx, timestamp));
+            let actions = plan(&inputs, max_open_valves, timestamp);
+
+            // the valves are closed before the others open, never more than the max are open
+            for (idx, action) in actions.into_iter().enumerate() {
+                let zone_relay = ZoneRelay::from(idx as u8);
+                match action {
+                    Action::Keep | Action::Open => {}
+                    Action::Wait => run.valves[idx] = Valve::Waiting,
+                    Action::Soak => self.soak(&mut run, idx, timestamp),
+                    Action::Close => self.close(&mut run, idx, timestamp),
+                    Action::Shorten => {
+                        log_warning!(APP_TAG, "Zone {zone_relay} shortened, weekly budget reached");
+                        self.close(&mut run, idx, timestamp);
+                    }
+                    Action::Skip(reason) => {
+                        match reason {
+                            SkipReason::Budget => log_warning!(APP_TAG, "Zone {zone_relay} skipped, weekly budget reached"),
+                            _ => log_warning!(APP_TAG, "Zone {zone_relay} skipped, volume requested without flow meter or flow rate"),
+                        }
+                        self.statistics_controller.record_skip(zone_relay, reason);
+                        run.valves[idx] = Valve::Idle;
+                    }
+                }
+            }
+
+            for idx in (0..ZoneController::SIZE).filter(|idx| actions[*idx] == Action::Open) {
+                let zone_relay = ZoneRelay::from(idx as u8);
+                if self.set_zone(zone_relay, true) {
+                    if run.started[idx] == 0 {
+                        run.started[idx] = timestamp;
+                        Events::post(Event::ZoneStart {
+                            zone: idx as u8,
+                            target: if run.entry.liters > 0 { run.entry.liters } else { run.target[idx].div_ceil(DateTime::SECONDS_PER_MINUTE as u64) as u16 },
+                            liters: run.entry.liters > 0,
+                        });
+                    }
+                    run.valves[idx] = Valve::Open(timestamp);
+                } else {
+                    log_warning!(APP_TAG, "Zone {zone_relay} skipped, relay not switched");
+                    self.statistics_controller.record_skip(zone_relay, SkipReason::Relay);
+                    run.valves[idx] = Valve::Idle;
+                }
+            }
+
+            if !run.is_idle() {
                 break;
             }
+
+            let Some(entry) = run.zones.get(run.position).copied().flatten() else {
+                self.finish(run.schedule);
+                return;
+            };
+
+            run.position += 1;
+            run.entry = entry;
+            for (idx, selected) in self.group_controller.relays(entry.target).into_iter().enumerate() {
+                if !selected {
+                    continue;
+                }
+
+                run.target[idx] = self.target(&run, idx);
+                run.started[idx] = 0;
+                run.watered[idx] = 0;
+                run.delivered[idx] = 0;
+                run.dosed[idx] = 0;
+                if run.target[idx] > 0 {
+                    run.valves[idx] = Valve::Waiting;
+                } else {
+                    log_warning!(APP_TAG, "Zone {} skipped, no watering time", ZoneRelay::from(idx as u8));
+                }
+            }
+        }
+
+        self.set_dosing(&mut run, timestamp);
+
+        self.run = Some(run);
+    }
+
+    /// State of the valve and of its zone read by `plan`
+    fn valve_input(&self, run: &Run, idx: usize, timestamp: i64) -> ValveInput {
+        let valve = run.valves[idx];
+        let profile = self.profile(idx);
+        let flow_rate = self.zone_comntroller.get(ZoneRelay::from(idx as u8)).map_or(0, |zone| zone.flow_rate);
+
+        ValveInput {
+            valve,
+            completed: matches!(valve, Valve::Open(_)) && self.is_completed(run, idx, run.elapsed(idx, timestamp)),
+            over_budget: valve != Valve::Idle && self.is_over_budget(run, idx, timestamp),
+            started: run.started[idx] != 0,
+            unmeasured: run.entry.liters > 0 && !self.is_metered() && flow_rate == 0,
+            cycle_seconds: profile.map_or(0, |profile| profile.cycle_minutes as i64 * DateTime::SECONDS_PER_MINUTE),
+            soak_seconds: profile.map_or(0, |profile| profile.soak_minutes as i64 * DateTime::SECONDS_PER_MINUTE),
+        }
+    }
+
+    /// Profile of the zone, None if not assigned or not defined anymore
+    #[inline]
+    fn profile(&self, idx: usize) -> Option<Profile> {
+        self.zone_comntroller.get(ZoneRelay::from(idx as u8))
+            .and_then(|zone| zone.profile)
+            .and_then(|id| self.profile_controller.get(id))
+    }
+
+    /// Target of the zone for the entry in execution scaled by the seasonal factor of its profile,
+    /// entries by time without minutes take the watering time of the profile,
+    /// manual runs are not scaled and fall back to `ManualController::DEFAULT_MINUTES`
+    fn target(&self, run: &Run, idx: usize) -> u64 {
+        let manual = run.schedule == ManualController::SCHEDULE;
+        let profile = self.profile(idx);
+        let percent = if manual {
+            100
+        } else {
+            profile.map_or(100, |profile| profile.get_seasonal(run.month)) as u64
+        };
+
+        let target = if run.entry.liters > 0 {
+            run.entry.liters as u64 * MILLILITERS_PER_LITER
+        } else {
+            let minutes = match (run.entry.minutes, profile.map_or(0, |profile| profile.minutes)) {
+                (0, 0) if manual => ManualController::DEFAULT_MINUTES,
+                (0, minutes) => minutes,
+                (minutes, _) => minutes,
+            };
+            minutes as u64 * DateTime::SECONDS_PER_MINUTE as u64
+        };
+
+        target * percent / 100
+    }
+
+    /// Weekly budget of the zone or of the controller reached, counting the valves of the entry in execution
+    fn is_over_budget(&self, run: &Run, idx: usize, timestamp: i64) -> bool {
+        let mut seconds = [0; ZoneController::SIZE];
+        let mut milliliters = [0; ZoneController::SIZE];
+        for valve in 0..ZoneController::SIZE {
+            if run.valves[valve] != Valve::Idle {
+                seconds[valve] = run.elapsed(valve, timestamp) as u64;
+                milliliters[valve] = run.delivered[valve];
+            }
+        }
+
+        self.budget_controller.is_exceeded(ZoneRelay::from(idx as u8), timestamp, &seconds, &milliliters)
+    }
+
+    #[inline]
+    fn is_metered(&self) -> bool {
+        self.flow_meter.is_some() && self.zone_comntroller.get_pulses_per_liter() > 0
+    }
+
+    /// Update the volume delivered by the open valves, the flow meter pulses are split
+    /// between the open valves by nominal flow rate, without flow meter the nominal flow rate is used
+    fn measure(&self, run: &mut Run, timestamp: i64) {
+        let flow_rate = |idx: usize| self.zone_comntroller.get(ZoneRelay::from(idx as u8)).map_or(0, |zone| zone.flow_rate as u64);
+
+        match self.flow_meter {
+            Some(flow_meter) if self.is_metered() => {
+                let pulses = flow_meter.get_pulses();
+                let milliliters = pulses.wrapping_sub(run.pulses) as u64 * MILLILITERS_PER_LITER / self.zone_comntroller.get_pulses_per_liter() as u64;
+                run.pulses = pulses;
+
+                let open = |idx: &usize| matches!(run.valves[*idx], Valve::Open(_));
+                let total_flow_rate: u64 = (0..ZoneController::SIZE).filter(open).map(flow_rate).sum();
+                let open_valves = run.open_valves() as u64;
+
+                for idx in (0..ZoneController::SIZE).filter(open) {
+                    run.delivered[idx] += if total_flow_rate > 0 {
+                        milliliters * flow_rate(idx) / total_flow_rate
+                    } else {
+                        milliliters / open_valves
+                    };
+                }
+            }
+            _ => {
+                for idx in 0..ZoneController::SIZE {
+                    if matches!(run.valves[idx], Valve::Open(_)) {
+                        let seconds = run.elapsed(idx, timestamp) as u64;
+                        run.delivered[idx] = seconds * flow_rate(idx) * MILLILITERS_PER_LITER / DateTime::SECONDS_PER_HOUR as u64;
+                    }
+                }
+            }
+        }
+    }
+
+    /// A valve is completed when its time is elapsed or, watering by volume, when the target volume or the max duration is reached
+    fn is_completed(&self, run: &Run, idx: usize, elapsed: i64) -> bool {
+        if run.entry.liters == 0 {
+            return elapsed >= run.target[idx] as i64;
+        }
+
+        let zone_relay = ZoneRelay::from(idx as u8);
+        let max_minutes = self.zone_comntroller.get(zone_relay).map_or(0, |zone| zone.max_minutes);
+        if elapsed >= max_minutes as i64 * DateTime::SECONDS_PER_MINUTE {
+            log_warning!(APP_TAG, "Zone {zone_relay} stopped, max duration reached");
+            return true;
+        }
+
+        run.delivered[idx] >= run.target[idx]
+    }
+
+    /// Dry run and overheating detection while valves are open, the dry run needs the flow meter or the pressure switch
+    fn protect(&self, run: &mut Run, timestamp: i64) -> Option<LockoutReason> {
+        if run.open_valves() == 0 {
+            run.flow_at = timestamp;
+            run.flow_pulses = self.flow_meter.map_or(0, |flow_meter| flow_meter.get_pulses());
+            return None;
+        }
+
+        if self.protection_controller.has_thermal_switch() && self.pump_sensors.is_some_and(|pump_sensors| pump_sensors.is_over_temperature()) {
+            log_error!(APP_TAG, "Pump over temperature");
+            return Some(LockoutReason::OverTemperature);
+        }
+
+        let flowing = match (self.flow_meter, self.pump_sensors) {
+            (Some(flow_meter), _) if self.is_metered() => {
+                let pulses = flow_meter.get_pulses();
+                let flowing = pulses != run.flow_pulses;
+                run.flow_pulses = pulses;
+                flowing
+            }
+            (_, Some(pump_sensors)) if self.protection_controller.has_pressure_switch() => pump_sensors.is_pressure_ok(),
+            _ => return None,
+        };
+
+        let dry_run_seconds = self.protection_controller.get_dry_run_seconds() as i64;
+        if flowing || dry_run_seconds == 0 {
+            run.flow_at = timestamp;
+            None
+        } else if timestamp - run.flow_at >= dry_run_seconds {
+            log_error!(APP_TAG, "Pump dry run, no flow for {} seconds", timestamp - run.flow_at);
+            Some(LockoutReason::DryRun)
+        } else {
+            None
+        }
+    }
+
+    /// Account the fertilizer pumped since the last step to the valves that requested it
+    fn dose(&self, run: &mut Run, timestamp: i64) {
+        let seconds = (timestamp - run.timestamp).max(0) as u64;
+        run.timestamp = timestamp;
+
+        let requests = run.dosing.iter().filter(|dosing| **dosing).count() as u64;
+        if seconds == 0 || requests == 0 {
+            return;
+        }
+
+        let microliters = seconds * self.dosing_controller.get_pump_rate() as u64 * MICROLITERS_PER_MILLILITER / DateTime::SECONDS_PER_MINUTE as u64;
+        for idx in 0..ZoneController::SIZE {
+            if run.dosing[idx] {
+                run.dosed[idx] += microliters / requests;
+            }
+        }
+    }
+
+    /// Switch the dosing pump on while at least one open valve needs fertilizer
+    fn set_dosing(&mut self, run: &mut Run, timestamp: i64) {
+        for idx in 0..ZoneController::SIZE {
+            run.dosing[idx] = match run.valves[idx] {
+                Valve::Open(_) => self.is_dosing(run, idx, run.elapsed(idx, timestamp)),
+                _ => false,
+            };
+        }
+
+        self.set_pump(run.dosing.iter().any(|dosing| *dosing));
+    }
+
+    /// Fertilizer is off during the flush at the end of the valve run, by ratio the pump
+    /// follows the delivered water, by duty cycle it follows the on/off cycle
+    fn is_dosing(&self, run: &Run, idx: usize, elapsed: i64) -> bool {
+        let dosing = self.dosing_controller.get(ZoneRelay::from(idx as u8));
+
+        if dosing.mode == DosingMode::Off || self.remaining(run, idx, elapsed) <= dosing.flush_minutes as i64 * DateTime::SECONDS_PER_MINUTE {
+            return false;
+        }
+
+        match dosing.mode {
+            DosingMode::Off => false,
+            DosingMode::Ratio => self.dosing_controller.get_pump_rate() > 0 && run.dosed[idx] < run.delivered[idx] * dosing.ratio as u64,
+            DosingMode::Duty => dosing.is_duty_on(elapsed),
+        }
+    }
+
+    /// Seconds to the end of the valve run, watering by volume estimated by the average flow so far
+    fn remaining(&self, run: &Run, idx: usize, elapsed: i64) -> i64 {
+        if run.entry.liters == 0 {
+            return run.target[idx] as i64 - elapsed;
+        }
+
+        let max_minutes = self.zone_comntroller.get(ZoneRelay::from(idx as u8)).map_or(0, |zone| zone.max_minutes);
+        let remaining = max_minutes as i64 * DateTime::SECONDS_PER_MINUTE - elapsed;
+
+        let delivered = run.delivered[idx];
+        if delivered == 0 || elapsed <= 0 {
+            return remaining;
+        }
+
+        remaining.min((run.target[idx].saturating_sub(delivered) * elapsed as u64 / delivered) as i64)
+    }
+
+    fn set_pump(&mut self, state: bool) {
+        if self.pump == state {
+            return;
+        }
+
+        let Some(relays) = self.relays else {
+            return;
+        };
+
+        if matches!(relays.set_relay_state(GpioPeripheral::DosingPump, state), OsalRsBool::True) {
+            log_info!(APP_TAG, "Dosing pump {}", if state { "on" } else { "off" });
+            self.pump = state;
+        } else {
+            log_warning!(APP_TAG, "Dosing pump not switched");
+        }
+    }
+
+    /// Close the valve for the soak time, the watering restarts from the waiting state
+    fn soak(&mut self, run: &mut Run, idx: usize, timestamp: i64) {
+        let zone_relay = ZoneRelay::from(idx as u8);
+        log_info!(APP_TAG, "Zone {zone_relay} soaking");
+
+        self.set_zone(zone_relay, false);
+        run.watered[idx] = run.elapsed(idx, timestamp);
+        run.valves[idx] = Valve::Soaking(timestamp);
+    }
+
+    /// Close the valve, open or soaking, and record the run of all its cycles
+    fn close(&mut self, run: &mut Run, idx: usize, timestamp: i64) {
+        let zone_relay = ZoneRelay::from(idx as u8);
+        if matches!(run.valves[idx], Valve::Open(_)) {
+            self.set_zone(zone_relay, false);
+        }
+        run.watered[idx] = run.elapsed(idx, timestamp);
+        run.valves[idx] = Valve::Idle;
+        Events::post(Event::ZoneStop { zone: idx as u8 });
+
+        let started_at = run.started[idx];
+        let seconds = run.watered[idx] as u32;
+        let dosing = self.dosing_controller.get(zone_relay).mode;
+        let flow_rate = self.zone_comntroller.get(zone_relay).map_or(0, |zone| zone.flow_rate);
+        let delivered_liters = if self.is_metered() || flow_rate > 0 {
+            Some((run.delivered[idx] / MILLILITERS_PER_LITER) as u32)
+        } else {
+            None
+        };
+
+        self.budget_controller.record(zone_relay, timestamp, seconds, run.delivered[idx]);
+        self.statistics_controller.record_run(zone_relay, seconds.div_ceil(DateTime::SECONDS_PER_MINUTE as u32), started_at, delivered_liters);
+        self.history_controller.add(RunRecord {
+            timestamp: started_at,
+            schedule: run.schedule as u8,
+            zone_relay,
+            seconds,
+            target_liters: run.entry.liters,
+            delivered_liters: delivered_liters.map(|liters| liters.min(u16::MAX as u32) as u16),
+            dosing,
+            fertilizer_ml: (run.dosed[idx] / MICROLITERS_PER_MILLILITER) as u32,
+        });
+    }
+
+    /// Close the open valves and end the running schedule
+    fn stop(&mut self, timestamp: i64) {
+        let Some(mut run) = self.run else {
+            return;
+        };
+
+        for idx in 0..ZoneController::SIZE {
+            if matches!(run.valves[idx], Valve::Open(_) | Valve::Soaking(_)) {
+                self.close(&mut run, idx, timestamp);
+            }
+        }
+        self.finish(run.schedule);
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_winterized(&self) -> bool {
+        self.winterization_controller.is_winterized()
+    }
+
+    /// Stop the watering and disable the schedules, the active ones are restored by `end_winterization`
+    pub(in crate::apps) fn begin_winterization(&mut self, now: DateTime) -> Result<()> {
+        log_info!(APP_TAG, "Winterization started");
+
+        self.stop(now.to_timestamp());
+
+        if !self.winterization_controller.is_winterized() {
+            let schedules = self.schedule_controller.get_active();
+            self.schedule_controller.set_active(0)?;
+            self.winterization_controller.set_winterized(true, schedules)?;
+        }
+
+        self.blowout = None;
+        Ok(())
+    }
+
+    /// Advance the blowout, each zone is opened for the blow time then closed for the pause,
+    /// true when every zone has been blown
+    pub(in crate::apps) fn winterize(&mut self, now: DateTime) -> bool {
+        let timestamp = now.to_timestamp();
+
+        let Some(mut blowout) = self.blowout else {
+            self.blowout = Some(Blowout { zone: 0, open: self.blow(0), since: timestamp });
+            return false;
+        };
+
+        if blowout.open {
+            if timestamp - blowout.since >= self.winterization_controller.get_blow_seconds() as i64 {
+                self.set_zone(ZoneRelay::from(blowout.zone as u8), false);
+                blowout.open = false;
+                blowout.since = timestamp;
+            }
+        } else if timestamp - blowout.since >= self.winterization_controller.get_pause_seconds() as i64 {
+            blowout.zone += 1;
+            if blowout.zone >= ZoneController::SIZE {
+                log_info!(APP_TAG, "Winterization completed");
+                self.blowout = None;
+                return true;
+            }
+            blowout.open = self.blow(blowout.zone);
+            blowout.since = timestamp;
+        }
+
+        self.blowout = Some(blowout);
+        false
+    }
+
+    fn blow(&mut self, zone: usize) -> bool {
+        let zone_relay = ZoneRelay::from(zone as u8);
+        log_info!(APP_TAG, "Blowout of zone {zone_relay}");
+
+        let open = self.set_zone(zone_relay, true);
+        if !open {
+            log_warning!(APP_TAG, "Zone {zone_relay} not blown, relay not switched");
         }
+        open
+    }
+
+    /// Close the valve left open by an interrupted blowout and restore the schedules active before the winterization
+    pub(in crate::apps) fn end_winterization(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Winterization ended");
+
+        if let Some(Blowout { zone, open: true, .. }) = self.blowout.take() {
+            self.set_zone(ZoneRelay::from(zone as u8), false);
+        }
+
+        self.schedule_controller.set_active(self.winterization_controller.get_schedules())?;
+        self.winterization_controller.set_winterized(false, 0)
+    }
+
+    fn finish(&mut self, schedule: usize) {
+        if schedule == ManualController::SCHEDULE {
+            log_info!(APP_TAG, "Manual run completed");
+        } else {
+            log_info!(APP_TAG, "Schedule {schedule} completed");
+            Events::post(Event::ScheduleEnd { schedule: schedule as u8 });
+        }
+
+        // the schedule could have been edited over AT during the run
+        if let Some(schedule) = self.schedule_controller.get_mut(schedule) {
+            if schedule.status == Status::RUN {
+                schedule.status = Status::ACTIVE;
+            }
+        }
+        self.set_pump(false);
+        self.run = None;
+        DISBURSEMENT_IN_PROGRESS.store(false, Ordering::Relaxed);
+
+        self.statistics_controller.flush();
+        self.history_controller.flush();
+        self.budget_controller.flush();
     }
 
-}
\ No newline at end of file
+    fn set_zone(&mut self, zone_relay: ZoneRelay, state: bool) -> bool {
+        let Some(relays) = self.relays else {
+            return false;
+        };
+
+        if !matches!(relays.set_relay_state(zone_relay.into(), state), OsalRsBool::True) {
+            return false;
+        }
+
+        if let Some(zone) = self.zone_comntroller.get_mut(zone_relay) {
+            zone.status = if state { Status::RUN } else { Status::UNACTIVE };
+        }
+
+        true
+    }
+
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_750_000_000;
+
+    fn waiting() -> ValveInput {
+        ValveInput { valve: Valve::Waiting, ..Default::default() }
+    }
+
+    fn open(since: i64) -> ValveInput {
+        ValveInput { valve: Valve::Open(since), started: true, ..Default::default() }
+    }
+
+    #[test]
+    fn waiting_valves_open_up_to_the_limit() {
+        let inputs = [waiting(), ValveInput::default(), waiting(), waiting()];
+
+        assert_eq!(plan(&inputs, 1, NOW), [Action::Open, Action::Keep, Action::Keep, Action::Keep]);
+        assert_eq!(plan(&inputs, 2, NOW), [Action::Open, Action::Keep, Action::Open, Action::Keep]);
+        assert_eq!(plan(&inputs, 4, NOW), [Action::Open, Action::Keep, Action::Open, Action::Open]);
+    }
+
+    #[test]
+    fn open_valves_count_against_the_limit() {
+        let inputs = [open(NOW - 60), waiting(), open(NOW - 30), waiting()];
+
+        assert_eq!(plan(&inputs, 2, NOW), [Action::Keep; ZoneController::SIZE]);
+        assert_eq!(plan(&inputs, 3, NOW), [Action::Keep, Action::Open, Action::Keep, Action::Keep]);
+    }
+
+    #[test]
+    fn group_larger_than_the_limit_is_watered_in_turns() {
+        let mut inputs = [open(NOW - 600), open(NOW - 600), waiting(), waiting()];
+        assert_eq!(plan(&inputs, 2, NOW), [Action::Keep; ZoneController::SIZE]);
+
+        // the slot freed by a completed valve is taken in the same step
+        inputs[1].completed = true;
+        assert_eq!(plan(&inputs, 2, NOW), [Action::Keep, Action::Close, Action::Open, Action::Keep]);
+
+        inputs[0].completed = true;
+        assert_eq!(plan(&inputs, 2, NOW), [Action::Close, Action::Close, Action::Open, Action::Open]);
+    }
+
+    #[test]
+    fn cycle_end_frees_a_slot_for_a_waiting_valve() {
+        let mut inputs = [open(NOW - 300), waiting(), ValveInput::default(), ValveInput::default()];
+        inputs[0].cycle_seconds = 300;
+        inputs[0].soak_seconds = 600;
+
+        assert_eq!(plan(&inputs, 1, NOW - 1), [Action::Keep; ZoneController::SIZE]);
+        assert_eq!(plan(&inputs, 1, NOW), [Action::Soak, Action::Open, Action::Keep, Action::Keep]);
+    }
+
+    #[test]
+    fn soaked_valve_waits_a_free_slot() {
+        let soaking = ValveInput { valve: Valve::Soaking(NOW - 600), started: true, soak_seconds: 600, ..Default::default() };
+
+        assert_eq!(plan(&[soaking, ValveInput::default(), ValveInput::default(), ValveInput::default()], 1, NOW - 1), [Action::Keep; ZoneController::SIZE]);
+        assert_eq!(plan(&[soaking, ValveInput::default(), ValveInput::default(), ValveInput::default()], 1, NOW), [Action::Open, Action::Keep, Action::Keep, Action::Keep]);
+        assert_eq!(plan(&[soaking, open(NOW - 60), ValveInput::default(), ValveInput::default()], 1, NOW), [Action::Wait, Action::Keep, Action::Keep, Action::Keep]);
+    }
+
+    #[test]
+    fn budget_and_flow_drop_valves_without_taking_a_slot() {
+        let over_budget = ValveInput { over_budget: true, ..waiting() };
+        let soaked_over_budget = ValveInput { valve: Valve::Waiting, over_budget: true, started: true, ..Default::default() };
+        let unmeasured = ValveInput { unmeasured: true, ..waiting() };
+        let inputs = [over_budget, soaked_over_budget, unmeasured, waiting()];
+
+        assert_eq!(plan(&inputs, 1, NOW), [Action::Skip(SkipReason::Budget), Action::Shorten, Action::Skip(SkipReason::Flow), Action::Open]);
+    }
+
+    #[test]
+    fn budget_shortens_open_and_soaking_valves() {
+        let open_over_budget = ValveInput { over_budget: true, ..open(NOW - 60) };
+        let soaking_over_budget = ValveInput { valve: Valve::Soaking(NOW - 60), over_budget: true, started: true, soak_seconds: 600, ..Default::default() };
+        let completed = ValveInput { completed: true, over_budget: true, ..open(NOW - 60) };
+
+        assert_eq!(plan(&[open_over_budget, soaking_over_budget, completed, ValveInput::default()], 1, NOW),
+            [Action::Shorten, Action::Shorten, Action::Close, Action::Keep]);
+    }
+}
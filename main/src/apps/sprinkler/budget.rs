@@ -0,0 +1,323 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_info, log_warning};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::date_time::DateTime;
+use crate::drivers::platform::{FS_CONFIG_DIR, FS_DATA_DIR};
+use crate::traits::state::Initializable;
+
+static mut SHARED: BudgetController = BudgetController {
+    config: BudgetConfig::new(),
+    usage: BudgetUsage::new(),
+    timestamp: 0,
+    dirty: false,
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+const APP_TAG: &str = "BudgetController";
+const MILLILITERS_PER_LITER: u64 = 1_000;
+
+/// Weekly limit, 0 for no limit
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(in crate::apps) struct BudgetLimit {
+
+    /// watering minutes in the last 7 days
+    minutes: u16,
+
+    /// delivered liters in the last 7 days, measured by the flow meter or estimated by the zone flow rate
+    liters: u32,
+}
+
+impl BudgetLimit {
+    const fn new() -> Self {
+        Self {
+            minutes: 0,
+            liters: 0,
+        }
+    }
+
+    #[inline]
+    fn is_exceeded(&self, seconds: u64, milliliters: u64) -> bool {
+        (self.minutes > 0 && seconds >= self.minutes as u64 * DateTime::SECONDS_PER_MINUTE as u64)
+            || (self.liters > 0 && milliliters >= self.liters as u64 * MILLILITERS_PER_LITER)
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct BudgetConfig {
+    zones: [BudgetLimit; ZoneController::SIZE],
+
+    /// limit of the whole controller, all zones together
+    total: BudgetLimit,
+}
+
+impl BudgetConfig {
+    const fn new() -> Self {
+        Self {
+            zones: [BudgetLimit::new(); ZoneController::SIZE],
+            total: BudgetLimit::new(),
+        }
+    }
+}
+
+/// Consumption of a day, the runs are accounted to the day they end
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+struct DayUsage {
+
+    /// days since epoch, timestamp / `DateTime::SECONDS_PER_DAY`
+    day: i64,
+
+    /// watering seconds of each zone
+    seconds: [u32; ZoneController::SIZE],
+
+    /// delivered milliliters of each zone
+    milliliters: [u32; ZoneController::SIZE],
+}
+
+impl DayUsage {
+    const fn new() -> Self {
+        Self {
+            day: 0,
+            seconds: [0; ZoneController::SIZE],
+            milliliters: [0; ZoneController::SIZE],
+        }
+    }
+}
+
+/// Rolling window of the last `BudgetController::DAYS` days, indexed by day modulo `DAYS`
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct BudgetUsage {
+    days: [DayUsage; BudgetController::DAYS],
+}
+
+impl BudgetUsage {
+    const fn new() -> Self {
+        Self {
+            days: [DayUsage::new(); BudgetController::DAYS],
+        }
+    }
+
+    /// Seconds and milliliters of the zone in the window ending at `timestamp`
+    fn get(&self, idx: usize, timestamp: i64) -> (u64, u64) {
+        let today = timestamp.div_euclid(DateTime::SECONDS_PER_DAY);
+
+        self.days.iter()
+            .filter(|usage| usage.day > today - BudgetController::DAYS as i64 && usage.day <= today)
+            .fold((0, 0), |(seconds, milliliters), usage| {
+                (seconds + usage.seconds[idx] as u64, milliliters + usage.milliliters[idx] as u64)
+            })
+    }
+}
+
+pub(in crate::apps) struct BudgetController {
+    config: BudgetConfig,
+    usage: BudgetUsage,
+
+    /// timestamp of the last check of the run engine, end of the window read by `rd`
+    timestamp: i64,
+
+    /// runs recorded since the last save of the usage, see `flush`
+    dirty: bool,
+}
+
+impl Initializable for BudgetController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init Budget");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.config = deserialize_file::<BudgetConfig>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_CONFIG_DIR, BudgetController::FILE_NAME)?;
+        self.usage = deserialize_file::<BudgetUsage>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_DATA_DIR, BudgetController::USAGE_FILE_NAME)?;
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for BudgetController {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        response.format(format_args!("{},{}", self.config.total.minutes, self.config.total.liters));
+        for (idx, limit) in self.config.zones.iter().enumerate() {
+            response.format(format_args!("\r\n{},{},{}", idx, limit.minutes, limit.liters));
+        }
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// tt = total, mn = minutes, lt = liters, rd = read usage, rs = reset usage, sv = save
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<zone_relay|tt>,<mn|lt>,<value> | <zone_relay|tt>,rd | rs | sv"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let first = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match first.as_ref() {
+            "rs" => { // reset usage
+                self.usage = BudgetUsage::new();
+                self.dirty = false;
+                serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, BudgetController::USAGE_FILE_NAME, &self.usage).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+                return Ok(at_cmd_response!(at_response; ""));
+            }
+            "sv" => { // save
+                serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, BudgetController::FILE_NAME, &self.config).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+                return Ok(at_cmd_response!(at_response; ""));
+            }
+            _ => {}
+        }
+
+        // None for the whole controller
+        let zone = if first.as_ref() == "tt" {
+            None
+        } else {
+            let idx: usize = first.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            if idx >= ZoneController::SIZE {
+                return Err((at_response, AtError::InvalidArgs));
+            }
+            Some(idx)
+        };
+        let cmd = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+
+        if cmd.as_ref() == "rd" { // read usage
+            let (seconds, milliliters) = match zone {
+                Some(idx) => self.usage.get(idx, self.timestamp),
+                None => self.get_total(self.timestamp),
+            };
+            let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+            response.format(format_args!("{},{},{}", first.as_ref(), seconds.div_ceil(DateTime::SECONDS_PER_MINUTE as u64), milliliters / MILLILITERS_PER_LITER));
+            return Ok((at_response, response));
+        }
+
+        let limit = match zone {
+            Some(idx) => &mut self.config.zones[idx],
+            None => &mut self.config.total,
+        };
+
+        match cmd.as_ref() {
+            "mn" => { // minutes
+                limit.minutes = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            }
+            "lt" => { // liters
+                limit.liters = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl BudgetController {
+    /// Days of the rolling window
+    pub(in crate::apps) const DAYS: usize = 7;
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+BDG";
+    pub(in crate::apps) const AT_RESP: &'static str = "+BDG: ";
+    const FILE_NAME: &'static str = "budget.json";
+    const USAGE_FILE_NAME: &'static str = "usage.json";
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn set_timestamp(&mut self, timestamp: i64) {
+        self.timestamp = timestamp;
+    }
+
+    fn get_total(&self, timestamp: i64) -> (u64, u64) {
+        (0..ZoneController::SIZE)
+            .map(|idx| self.usage.get(idx, timestamp))
+            .fold((0, 0), |(seconds, milliliters), (s, m)| (seconds + s, milliliters + m))
+    }
+
+    /// Limit of the zone or of the controller reached in the window ending at `timestamp`, counting
+    /// the seconds and the milliliters of the runs in progress not yet recorded, indexed by relay number
+    pub(in crate::apps) fn is_exceeded(&self, zone_relay: ZoneRelay, timestamp: i64, seconds: &[u64; ZoneController::SIZE], milliliters: &[u64; ZoneController::SIZE]) -> bool {
+        let idx = <ZoneRelay as Into<u8>>::into(zone_relay) as usize;
+
+        let (used_seconds, used_milliliters) = self.usage.get(idx, timestamp);
+        if self.config.zones[idx].is_exceeded(used_seconds + seconds[idx], used_milliliters + milliliters[idx]) {
+            return true;
+        }
+
+        let (used_seconds, used_milliliters) = self.get_total(timestamp);
+        self.config.total.is_exceeded(used_seconds + seconds.iter().sum::<u64>(), used_milliliters + milliliters.iter().sum::<u64>())
+    }
+
+    /// Account a completed run to the day of `timestamp`, the window is saved by `flush`
+    pub(in crate::apps) fn record(&mut self, zone_relay: ZoneRelay, timestamp: i64, seconds: u32, milliliters: u64) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let idx = <ZoneRelay as Into<u8>>::into(zone_relay) as usize;
+        let day = timestamp.div_euclid(DateTime::SECONDS_PER_DAY);
+
+        let usage = &mut self.usage.days[day.rem_euclid(BudgetController::DAYS as i64) as usize];
+        if usage.day != day {
+            *usage = DayUsage::new();
+            usage.day = day;
+        }
+        usage.seconds[idx] = usage.seconds[idx].saturating_add(seconds);
+        usage.milliliters[idx] = usage.milliliters[idx].saturating_add(milliliters.min(u32::MAX as u64) as u32);
+        self.dirty = true;
+    }
+
+    /// Save the usage window if runs were recorded since the last save, called once at the end of a run
+    pub(in crate::apps) fn flush(&mut self) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        if let Err(e) = serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, BudgetController::USAGE_FILE_NAME, &self.usage) {
+            log_warning!(APP_TAG, "Failed to save budget usage: {e}");
+        }
+    }
+}
@@ -0,0 +1,263 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_info};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::FS_CONFIG_DIR;
+use crate::traits::state::Initializable;
+
+static mut SHARED: DosingController = DosingController {
+    zones: [ZoneDosing::new(); ZoneController::SIZE],
+    pump_rate: 0,
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+const APP_TAG: &str = "DosingController";
+
+/// How the dosing pump runs while the zone is watered
+#[repr(u8)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(in crate::apps) enum DosingMode {
+    #[default]
+    Off,
+
+    /// fertilizer milliliters for each liter of delivered water
+    Ratio,
+
+    /// fixed on/off cycle of the pump
+    Duty,
+}
+
+impl From<u8> for DosingMode {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => DosingMode::Ratio,
+            2 => DosingMode::Duty,
+            _ => DosingMode::Off,
+        }
+    }
+}
+
+impl Serialize for DosingMode {
+    #[inline]
+    fn serialize<S: Serializer>(&self, name: &str, serializer: &mut S) -> Result<(), S::Error> {
+        Ok(serializer.serialize_u8(name, *self as u8)?)
+    }
+}
+
+impl Deserialize for DosingMode {
+    #[inline]
+    fn deserialize<D: Deserializer>(deserializer: &mut D, name: &str) -> Result<Self, D::Error> {
+        Ok(DosingMode::from(deserializer.deserialize_u8(name)?))
+    }
+}
+
+/// Fertigation of a zone
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(in crate::apps) struct ZoneDosing {
+    pub(in crate::apps) mode: DosingMode,
+
+    /// fertilizer milliliters per liter of water, used by `DosingMode::Ratio`
+    pub(in crate::apps) ratio: u16,
+
+    /// pump on seconds of the cycle, used by `DosingMode::Duty`
+    pub(in crate::apps) on_seconds: u16,
+
+    /// pump off seconds of the cycle, used by `DosingMode::Duty`
+    pub(in crate::apps) off_seconds: u16,
+
+    /// minutes at the end of the zone run watered without fertilizer
+    pub(in crate::apps) flush_minutes: u8,
+}
+
+impl ZoneDosing {
+    const fn new() -> Self {
+        Self {
+            mode: DosingMode::Off,
+            ratio: 0,
+            on_seconds: 0,
+            off_seconds: 0,
+            flush_minutes: 0,
+        }
+    }
+
+    /// Pump state requested by the duty cycle after `elapsed` seconds of watering
+    #[inline]
+    pub(in crate::apps) fn is_duty_on(&self, elapsed: i64) -> bool {
+        let period = self.on_seconds as i64 + self.off_seconds as i64;
+        period > 0 && elapsed.rem_euclid(period) < self.on_seconds as i64
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct DosingController {
+    zones: [ZoneDosing; ZoneController::SIZE],
+
+    /// dosing pump flow in milliliters per minute, 0 if unknown
+    pump_rate: u16,
+}
+
+impl Initializable for DosingController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init Dosing");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = deserialize_file::<DosingController>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_CONFIG_DIR, DosingController::FILE_NAME)?;
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for DosingController {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        response.format(format_args!("{}", self.pump_rate));
+        for (idx, zone) in self.zones.iter().enumerate() {
+            response.format(format_args!("\r\n{},{},{},{},{},{}",
+                idx, zone.mode as u8, zone.ratio, zone.on_seconds, zone.off_seconds, zone.flush_minutes));
+        }
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// of = off, rt = ratio, dc = duty cycle, fl = flush minutes, pr = pump rate, sv = save
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<zone_relay>,<of|rt|dc|fl>[,<value>[,<value>]] | pr,<value> | sv"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let first = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match first.as_ref() {
+            "pr" => { // pump rate
+                self.pump_rate = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                return Ok(at_cmd_response!(at_response; ""));
+            }
+            "sv" => { // save
+                serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, DosingController::FILE_NAME, unsafe {&*&raw const SHARED}).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+                return Ok(at_cmd_response!(at_response; ""));
+            }
+            _ => {}
+        }
+
+        let idx: usize = first.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+        let cmd = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+        let zone = self.zones.get_mut(idx).ok_or((at_response, AtError::InvalidArgs))?;
+
+        match cmd.as_ref() {
+            "of" => { // off
+                zone.mode = DosingMode::Off;
+            }
+            "rt" => { // ratio
+                let ratio: u16 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if ratio == 0 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                zone.mode = DosingMode::Ratio;
+                zone.ratio = ratio;
+            }
+            "dc" => { // duty cycle
+                let on_seconds: u16 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let off_seconds: u16 = args.get(3).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if on_seconds == 0 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                zone.mode = DosingMode::Duty;
+                zone.on_seconds = on_seconds;
+                zone.off_seconds = off_seconds;
+            }
+            "fl" => { // flush minutes
+                zone.flush_minutes = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl DosingController {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+DOS";
+    pub(in crate::apps) const AT_RESP: &'static str = "+DOS: ";
+    const FILE_NAME: &'static str = "dosing.json";
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get(&self, zone_relay: ZoneRelay) -> &ZoneDosing {
+        &self.zones[<ZoneRelay as Into<u8>>::into(zone_relay) as usize]
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_pump_rate(&self) -> u16 {
+        self.pump_rate
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_valid(&self) -> bool {
+        self.zones.iter().all(|zone| match zone.mode {
+            DosingMode::Off => true,
+            DosingMode::Ratio => zone.ratio > 0,
+            DosingMode::Duty => zone.on_seconds > 0,
+        })
+    }
+
+    /// Replace the fertigation of all zones and persist
+    pub(in crate::apps) fn replace(&mut self, other: &Self) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = *other;
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, DosingController::FILE_NAME, self)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_info};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Error, Result};
+use osal_rs_serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::FS_CONFIG_DIR;
+use crate::traits::state::Initializable;
+
+static mut SHARED: GroupController = GroupController {
+    groups: [ZoneGroup::new(); GroupController::SIZE],
+    max_open_valves: GroupController::MAX_OPEN_VALVES_DEFAULT,
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+const APP_TAG: &str = "GroupController";
+
+/// Target of a schedule entry, a single zone or a group of zones watered together
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(in crate::apps) enum ZoneTarget {
+    Zone(ZoneRelay),
+    Group(u8),
+}
+
+impl Default for ZoneTarget {
+    #[inline]
+    fn default() -> Self {
+        ZoneTarget::Zone(ZoneRelay::default())
+    }
+}
+
+impl ZoneTarget {
+    /// Serialized value flag of groups, zones keep the relay number so old files stay valid
+    const GROUP_FLAG: u8 = 0x10;
+
+    #[inline]
+    pub(in crate::apps) fn is_valid(&self) -> bool {
+        match self {
+            ZoneTarget::Zone(_) => true,
+            ZoneTarget::Group(group) => (*group as usize) < GroupController::SIZE,
+        }
+    }
+}
+
+impl From<u8> for ZoneTarget {
+    fn from(value: u8) -> Self {
+        if value & ZoneTarget::GROUP_FLAG != 0 {
+            ZoneTarget::Group(value & !ZoneTarget::GROUP_FLAG)
+        } else {
+            ZoneTarget::Zone(ZoneRelay::from(value))
+        }
+    }
+}
+
+impl From<ZoneTarget> for u8 {
+    fn from(value: ZoneTarget) -> Self {
+        match value {
+            ZoneTarget::Zone(zone_relay) => zone_relay.into(),
+            ZoneTarget::Group(group) => ZoneTarget::GROUP_FLAG | group,
+        }
+    }
+}
+
+impl From<ZoneRelay> for ZoneTarget {
+    #[inline]
+    fn from(value: ZoneRelay) -> Self {
+        ZoneTarget::Zone(value)
+    }
+}
+
+/// AT form: `<zone_relay>` for a zone, `g<group>` for a group
+impl Display for ZoneTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ZoneTarget::Zone(zone_relay) => write!(f, "{}", <ZoneRelay as Into<u8>>::into(*zone_relay)),
+            ZoneTarget::Group(group) => write!(f, "g{group}"),
+        }
+    }
+}
+
+impl FromStr for ZoneTarget {
+    type Err = Error<'static>;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let target = match s.strip_prefix('g') {
+            Some(group) => ZoneTarget::Group(group.parse().map_err(|_| Error::NotFound)?),
+            None => {
+                let zone_relay: u8 = s.parse().map_err(|_| Error::NotFound)?;
+                if zone_relay as usize >= ZoneController::SIZE {
+                    return Err(Error::NotFound);
+                }
+                ZoneTarget::Zone(ZoneRelay::from(zone_relay))
+            }
+        };
+
+        if target.is_valid() { Ok(target) } else { Err(Error::NotFound) }
+    }
+}
+
+impl Serialize for ZoneTarget {
+    #[inline]
+    fn serialize<S: Serializer>(&self, name: &str, serializer: &mut S) -> Result<(), S::Error> {
+        Ok(serializer.serialize_u8(name, (*self).into())?)
+    }
+}
+
+impl Deserialize for ZoneTarget {
+    #[inline]
+    fn deserialize<D: Deserializer>(deserializer: &mut D, name: &str) -> Result<Self, D::Error> {
+        Ok(ZoneTarget::from(deserializer.deserialize_u8(name)?))
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(in crate::apps) struct ZoneGroup {
+
+    /// bitmask of the zone relays in the group, bit n is ZoneRelay n
+    pub(in crate::apps) relays: u8,
+}
+
+impl ZoneGroup {
+    const fn new() -> Self {
+        Self {
+            relays: 0
+        }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn contains(&self, zone_relay: ZoneRelay) -> bool {
+        self.relays & (1 << <ZoneRelay as Into<u8>>::into(zone_relay)) != 0
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct GroupController {
+    groups: [ZoneGroup; GroupController::SIZE],
+
+    /// max number of valves open at the same time, pump capacity
+    max_open_valves: u8,
+}
+
+impl Initializable for GroupController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init Group");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = deserialize_file::<GroupController>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_CONFIG_DIR, GroupController::FILE_NAME)?;
+
+        if self.max_open_valves == 0 {
+            self.max_open_valves = GroupController::MAX_OPEN_VALVES_DEFAULT;
+        }
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for GroupController {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        response.format(format_args!("{}", self.max_open_valves));
+        for (idx, group) in self.groups.iter().enumerate() {
+            response.format(format_args!("\r\n{},{}", idx, group.relays));
+        }
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// rl = relays bitmask, mx = max open valves, sv = save
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "rl,<group>,<relays> | mx,<value> | sv"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match cmd.as_ref() {
+            "rl" => { // relays bitmask
+                let idx: usize = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let relays: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if relays >= 1 << ZoneController::SIZE {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                let group = self.groups.get_mut(idx).ok_or((at_response, AtError::InvalidArgs))?;
+                group.relays = relays;
+            }
+            "mx" => { // max open valves
+                let value: u8 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value == 0 || value as usize > ZoneController::SIZE {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                self.max_open_valves = value;
+            }
+            "sv" => { // save
+                serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, GroupController::FILE_NAME, unsafe {&*&raw const SHARED}).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl GroupController {
+    pub(in crate::apps) const SIZE: usize = 4;
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+ZGR";
+    pub(in crate::apps) const AT_RESP: &'static str = "+ZGR: ";
+    const FILE_NAME: &'static str = "groups.json";
+    const MAX_OPEN_VALVES_DEFAULT: u8 = 1;
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_max_open_valves(&self) -> u8 {
+        self.max_open_valves
+    }
+
+    /// Relays watered by a schedule entry, indexed by relay number
+    pub(in crate::apps) fn relays(&self, target: ZoneTarget) -> [bool; ZoneController::SIZE] {
+        let mut ret = [false; ZoneController::SIZE];
+        match target {
+            ZoneTarget::Zone(zone_relay) => ret[<ZoneRelay as Into<u8>>::into(zone_relay) as usize] = true,
+            ZoneTarget::Group(group) => if let Some(group) = self.groups.get(group as usize) {
+                for (idx, relay) in ret.iter_mut().enumerate() {
+                    *relay = group.contains(ZoneRelay::from(idx as u8));
+                }
+            }
+        }
+        ret
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_valid(&self) -> bool {
+        self.max_open_valves > 0 && self.max_open_valves as usize <= ZoneController::SIZE
+            && self.groups.iter().all(|group| group.relays < 1 << ZoneController::SIZE)
+    }
+
+    /// Replace all groups and persist
+    pub(in crate::apps) fn replace(&mut self, other: &Self) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = *other;
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, GroupController::FILE_NAME, self)?;
+        Ok(())
+    }
+}
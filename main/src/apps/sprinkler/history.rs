@@ -0,0 +1,228 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_info, log_warning};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::sprinkler::dosing::DosingMode;
+use crate::apps::sprinkler::zone::ZoneRelay;
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::FS_DATA_DIR;
+use crate::traits::state::Initializable;
+
+static mut SHARED: HistoryController = HistoryController {
+    records: [RunRecord::new(); HistoryController::SIZE],
+    next: 0,
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+/// Records added since the last save, see `HistoryController::flush`
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+const APP_TAG: &str = "HistoryController";
+
+/// Completed watering of a zone
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(in crate::apps) struct RunRecord {
+
+    /// timestamp of valve opening, 0 for an empty record
+    pub(in crate::apps) timestamp: i64,
+
+    /// index of the schedule, `ManualController::SCHEDULE` for a manual run
+    pub(in crate::apps) schedule: u8,
+
+    /// relay number of the zone
+    pub(in crate::apps) zone_relay: ZoneRelay,
+
+    /// valve open time in seconds
+    pub(in crate::apps) seconds: u32,
+
+    /// target volume in liters, 0 for watering by time
+    pub(in crate::apps) target_liters: u16,
+
+    /// delivered volume in liters, measured by the flow meter or estimated by the nominal flow rate, None if unknown
+    pub(in crate::apps) delivered_liters: Option<u16>,
+
+    /// fertigation of the zone during the run
+    pub(in crate::apps) dosing: DosingMode,
+
+    /// fertilizer milliliters pumped during the run
+    pub(in crate::apps) fertilizer_ml: u32,
+}
+
+impl RunRecord {
+    pub(in crate::apps) const fn new() -> Self {
+        Self {
+            timestamp: 0,
+            schedule: 0,
+            zone_relay: ZoneRelay::Relay0,
+            seconds: 0,
+            target_liters: 0,
+            delivered_liters: None,
+            dosing: DosingMode::Off,
+            fertilizer_ml: 0,
+        }
+    }
+}
+
+/// Ring of the last run records, oldest are overwritten
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct HistoryController {
+    records: [RunRecord; HistoryController::SIZE],
+
+    /// position of the next record to write
+    next: u8,
+}
+
+impl Default for HistoryController {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            records: [RunRecord::new(); HistoryController::SIZE],
+            next: 0,
+        }
+    }
+}
+
+impl Initializable for HistoryController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init History");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = deserialize_file::<HistoryController>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_DATA_DIR, HistoryController::FILE_NAME)?;
+
+        if self.next as usize >= HistoryController::SIZE {
+            self.next = 0;
+        }
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for HistoryController {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        Ok(at_cmd_response!(at_response; self.len()))
+    }
+
+    #[inline]
+    /// rs = reset
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<record> | rs"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let arg = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if arg.as_ref() == "rs" { // reset
+            *self = HistoryController::default();
+            serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, HistoryController::FILE_NAME, unsafe {&*&raw const SHARED}).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            return Ok(at_cmd_response!(at_response; ""));
+        }
+
+        let idx: usize = arg.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+        let record = self.get(idx).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        response.format(format_args!("{},{},{},{},{},{},",
+            idx,
+            record.timestamp,
+            record.schedule,
+            <ZoneRelay as Into<u8>>::into(record.zone_relay),
+            record.seconds,
+            record.target_liters,
+        ));
+        if let Some(delivered_liters) = record.delivered_liters {
+            response.format(format_args!("{delivered_liters}"));
+        }
+        response.format(format_args!(",{},{}", record.dosing as u8, record.fertilizer_ml));
+
+        Ok((at_response, response))
+    }
+}
+
+impl HistoryController {
+    pub(in crate::apps) const SIZE: usize = 16;
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+HIST";
+    pub(in crate::apps) const AT_RESP: &'static str = "+HIST: ";
+    const FILE_NAME: &'static str = "history.json";
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    /// Number of records stored
+    pub(in crate::apps) fn len(&self) -> usize {
+        self.records.iter().filter(|record| record.timestamp != 0).count()
+    }
+
+    /// Record by age, 0 is the newest
+    pub(in crate::apps) fn get(&self, idx: usize) -> Option<&RunRecord> {
+        if idx >= self.len() {
+            return None;
+        }
+        let position = (self.next as usize + HistoryController::SIZE - 1 - idx) % HistoryController::SIZE;
+        self.records.get(position)
+    }
+
+    pub(in crate::apps) fn add(&mut self, record: RunRecord) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.records[self.next as usize] = record;
+        self.next = ((self.next as usize + 1) % HistoryController::SIZE) as u8;
+
+        DIRTY.store(true, Ordering::Relaxed);
+    }
+
+    /// Save the records added since the last save, called once at the end of a run
+    pub(in crate::apps) fn flush(&self) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if !DIRTY.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        if let Err(e) = serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, HistoryController::FILE_NAME, self) {
+            log_warning!(APP_TAG, "Failed to save history: {e}");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::utils::Bytes;
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
+use crate::apps::signals::status::{StatusFlag, StatusSignal};
+use crate::apps::sprinkler::DISBURSEMENT_IN_PROGRESS;
+use crate::apps::sprinkler::schedule::ScheduleController;
+use crate::apps::sprinkler::winterization::WinterizationController;
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+use crate::traits::signal::Signal;
+
+static mut SHARED: ManualController = ManualController;
+
+/// Relay of the manual run requested over AT, `NO_ZONE` if none, served by the main FSM
+static START_ZONE: AtomicU8 = AtomicU8::new(ManualController::NO_ZONE);
+
+/// Watering minutes of the requested manual run, 0 for the watering time of the zone profile
+static START_MINUTES: AtomicU8 = AtomicU8::new(0);
+
+/// Bitmask of the valves to close requested over AT, bit n is relay n
+static STOP_REQUEST: AtomicU8 = AtomicU8::new(0);
+
+/// Stop of the whole watering requested over AT
+static STOP_ALL_REQUEST: AtomicBool = AtomicBool::new(false);
+
+/// Manual run of a single zone outside the schedules, it goes through the same run engine
+/// so the budget, the dry run and overheating protection and the history apply
+pub(in crate::apps) struct ManualController;
+
+impl AtContext<{Parser::CMD_SIZE}> for ManualController {
+    fn exec(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        if !DISBURSEMENT_IN_PROGRESS.load(Ordering::Relaxed) {
+            return Err((at_response, AtError::Unhandled("No watering in progress")));
+        }
+
+        STOP_ALL_REQUEST.store(true, Ordering::Relaxed);
+        SchedulerSignal::set(SchedulerFlag::Request.into());
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let open = ZoneController::shared().get_open();
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        for idx in 0..ZoneController::SIZE {
+            if idx > 0 {
+                response.append_str(",");
+            }
+            response.append_str(if open & 1 << idx != 0 { "1" } else { "0" });
+        }
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// <minutes> = 0 close the zone, without minutes the watering time of the zone profile
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<zone_relay>[,<minutes>]"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let zone: u8 = args.get(0).ok_or((at_response, AtError::InvalidArgs))?
+            .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+        if zone as usize >= ZoneController::SIZE {
+            return Err((at_response, AtError::InvalidArgs));
+        }
+
+        let minutes: Option<u8> = match args.get(1) {
+            Some(value) => Some(value.parse().map_err(|_| (at_response, AtError::InvalidArgs))?),
+            None => None,
+        };
+
+        let in_progress = DISBURSEMENT_IN_PROGRESS.load(Ordering::Relaxed);
+
+        if minutes == Some(0) {
+            if !in_progress {
+                return Err((at_response, AtError::Unhandled("No watering in progress")));
+            }
+
+            STOP_REQUEST.fetch_or(1 << zone, Ordering::Relaxed);
+            SchedulerSignal::set(SchedulerFlag::Request.into());
+
+            return Ok(at_cmd_response!(at_response; ""));
+        }
+
+        if StatusFlag::Winterize.check_signal(StatusSignal::get()) || WinterizationController::shared().is_winterized() {
+            return Err((at_response, AtError::Unhandled("Winterized")));
+        }
+
+        if in_progress || START_ZONE.load(Ordering::Relaxed) != ManualController::NO_ZONE {
+            return Err((at_response, AtError::Unhandled("Watering in progress")));
+        }
+
+        let max_minutes = ZoneController::shared().get(ZoneRelay::from(zone)).map_or(0, |zone| zone.max_minutes);
+        if minutes.is_some_and(|minutes| minutes > max_minutes) {
+            return Err((at_response, AtError::Unhandled("Over max duration")));
+        }
+
+        START_MINUTES.store(minutes.unwrap_or(0), Ordering::Relaxed);
+        START_ZONE.store(zone, Ordering::Relaxed);
+        SchedulerSignal::set(SchedulerFlag::Request.into());
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl ManualController {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+RUN";
+    pub(in crate::apps) const AT_RESP: &'static str = "+RUN: ";
+
+    /// Schedule index of the manual runs in the run engine and in the history
+    pub(in crate::apps) const SCHEDULE: usize = ScheduleController::SIZE;
+
+    /// Watering minutes of a manual run without minutes on a zone without profile
+    pub(in crate::apps) const DEFAULT_MINUTES: u8 = 10;
+
+    const NO_ZONE: u8 = u8::MAX;
+
+    #[inline]
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    /// Relay and minutes of the requested manual run
+    pub(in crate::apps) fn take_start_request(&self) -> Option<(ZoneRelay, u8)> {
+        match START_ZONE.swap(ManualController::NO_ZONE, Ordering::Relaxed) {
+            ManualController::NO_ZONE => None,
+            zone => Some((ZoneRelay::from(zone), START_MINUTES.load(Ordering::Relaxed))),
+        }
+    }
+
+    /// Bitmask of the valves to close, bit n is relay n
+    #[inline]
+    pub(in crate::apps) fn take_stop_request(&self) -> u8 {
+        STOP_REQUEST.swap(0, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(in crate::apps) fn take_stop_all_request(&self) -> bool {
+        STOP_ALL_REQUEST.swap(false, Ordering::Relaxed)
+    }
+}
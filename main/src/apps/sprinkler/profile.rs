@@ -0,0 +1,318 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+use at_parser_rs::at_quoted as quoted;
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_info};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::DISPLAY_INPUT_MAX_SIZE;
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::sprinkler::zone::ZoneController;
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::FS_CONFIG_DIR;
+use crate::traits::state::Initializable;
+
+static mut SHARED: ProfileController = ProfileController {
+    profiles: [None; ProfileController::USER_SIZE],
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+const APP_TAG: &str = "ProfileController";
+
+/// Built-in profiles, read only, ids from 0 to `ProfileController::PRESETS - 1`
+const PRESETS: [(&str, Profile); ProfileController::PRESETS] = [
+    ("Lawn", Profile::preset(20, 10, 30, [30, 40, 60, 80, 100, 120, 140, 130, 100, 70, 40, 30], 20, 35)),
+    ("Drip", Profile::preset(30, 0, 0, [30, 30, 50, 70, 100, 120, 130, 120, 90, 60, 40, 30], 25, 40)),
+    ("Shrubs", Profile::preset(15, 0, 0, [20, 20, 40, 70, 100, 120, 130, 120, 90, 60, 30, 20], 15, 30)),
+    ("Pots", Profile::preset(5, 3, 10, [40, 40, 60, 80, 100, 130, 150, 140, 100, 70, 50, 40], 30, 50)),
+];
+
+/// Plant or irrigation profile shared by the zones that reference it
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(in crate::apps) struct Profile {
+
+    /// description of the profile
+    pub(in crate::apps) description: Bytes<DISPLAY_INPUT_MAX_SIZE>,
+
+    /// watering time in minutes of the schedule entries without minutes
+    pub(in crate::apps) minutes: u8,
+
+    /// max minutes of watering before a soak, 0 waters without cycles
+    pub(in crate::apps) cycle_minutes: u8,
+
+    /// minutes with the valve closed between two cycles
+    pub(in crate::apps) soak_minutes: u8,
+
+    /// percent of the watering time or volume for each month, January first
+    pub(in crate::apps) seasonal: [u8; 12],
+
+    /// soil moisture percent under which the zone needs water
+    pub(in crate::apps) moisture_min: u8,
+
+    /// soil moisture percent over which the zone doesn't need water
+    pub(in crate::apps) moisture_max: u8,
+}
+
+impl Default for Profile {
+    #[inline]
+    fn default() -> Self {
+        Self::preset(Profile::MINUTES_DEFAULT, 0, 0, [Profile::SEASONAL_DEFAULT; 12], 0, 100)
+    }
+}
+
+impl Profile {
+    const MINUTES_DEFAULT: u8 = 10;
+    const SEASONAL_DEFAULT: u8 = 100;
+
+    /// Max seasonal percent, keeps the scaled watering time within the zone max duration
+    pub(in crate::apps) const SEASONAL_MAX: u8 = 250;
+
+    const fn preset(minutes: u8, cycle_minutes: u8, soak_minutes: u8, seasonal: [u8; 12], moisture_min: u8, moisture_max: u8) -> Self {
+        Self {
+            description: Bytes::new(),
+            minutes,
+            cycle_minutes,
+            soak_minutes,
+            seasonal,
+            moisture_min,
+            moisture_max,
+        }
+    }
+
+    /// Seasonal percent of `month` (1-12)
+    #[inline]
+    pub(in crate::apps) fn get_seasonal(&self, month: u8) -> u8 {
+        self.seasonal.get(month.wrapping_sub(1) as usize).copied().unwrap_or(Profile::SEASONAL_DEFAULT)
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.description.is_empty()
+            && self.seasonal.iter().all(|percent| *percent <= Profile::SEASONAL_MAX)
+            && self.moisture_min <= self.moisture_max && self.moisture_max <= 100
+    }
+}
+
+/// User profiles, the built-in ones are not stored
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct ProfileController {
+    profiles: [Option<Profile>; ProfileController::USER_SIZE],
+}
+
+impl Initializable for ProfileController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init Profile");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = deserialize_file::<ProfileController>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_CONFIG_DIR, ProfileController::FILE_NAME)?;
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for ProfileController {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        for id in 0..ProfileController::SIZE {
+            if self.get(id as u8).is_some() {
+                response.format(format_args!("{}{}", if response.is_empty() { "" } else { "," }, id));
+            }
+        }
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// rd = read, ds = description, mn = minutes, cs = cycle and soak, sn = seasonal, ms = moisture, cp = copy, dl = delete, sv = save
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<id>,<rd|ds|mn|cs|sn|ms|cp|dl>[,<value>[,<value>]] | sv"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let first = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if first.as_ref() == "sv" { // save
+            serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, ProfileController::FILE_NAME, unsafe {&*&raw const SHARED}).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            return Ok(at_cmd_response!(at_response; ""));
+        }
+
+        let id: u8 = first.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+        let cmd = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+
+        if id as usize >= ProfileController::SIZE {
+            return Err((at_response, AtError::InvalidArgs));
+        }
+
+        match cmd.as_ref() {
+            "rd" => { // read
+                let profile = self.get(id).ok_or((at_response, AtError::Unhandled("Profile not defined")))?;
+                let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+                response.format(format_args!("{},{},{},{},{},{},{}",
+                    id, profile.minutes, profile.cycle_minutes, profile.soak_minutes, profile.moisture_min, profile.moisture_max, quoted!(profile.description.as_str())));
+                return Ok((at_response, response));
+            }
+            "sn" if args.get(2).is_none() => { // read seasonal
+                let profile = self.get(id).ok_or((at_response, AtError::Unhandled("Profile not defined")))?;
+                let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+                for (idx, percent) in profile.seasonal.iter().enumerate() {
+                    response.format(format_args!("{}{}", if idx == 0 { "" } else { "," }, percent));
+                }
+                return Ok((at_response, response));
+            }
+            _ => {}
+        }
+
+        let slot = (id as usize).checked_sub(ProfileController::PRESETS)
+            .ok_or((at_response, AtError::Unhandled("Read only profile")))?;
+
+        match cmd.as_ref() {
+            "cp" => { // copy
+                let source: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let source = self.get(source).ok_or((at_response, AtError::Unhandled("Profile not defined")))?;
+                self.profiles[slot] = Some(source);
+                return Ok(at_cmd_response!(at_response; ""));
+            }
+            "dl" => { // delete
+                if ZoneController::shared().is_profile_used(id) {
+                    return Err((at_response, AtError::Unhandled("Profile in use")));
+                }
+                self.profiles[slot] = None;
+                return Ok(at_cmd_response!(at_response; ""));
+            }
+            "ds" => { // description, creates the profile
+                let value = args.get(2).ok_or((at_response, AtError::InvalidArgs))?;
+                if value.is_empty() {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                if value.len() > DISPLAY_INPUT_MAX_SIZE {
+                    return Err((at_response, AtError::Unhandled("description max len exceeded")));
+                }
+                self.profiles[slot].get_or_insert_with(Profile::default).description = Bytes::from_str(value.as_ref());
+                return Ok(at_cmd_response!(at_response; ""));
+            }
+            _ => {}
+        }
+
+        let profile = self.profiles[slot].as_mut().ok_or((at_response, AtError::Unhandled("Profile not defined")))?;
+
+        match cmd.as_ref() {
+            "mn" => { // minutes
+                profile.minutes = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            }
+            "cs" => { // cycle and soak
+                let cycle_minutes: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let soak_minutes: u8 = args.get(3).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                profile.cycle_minutes = cycle_minutes;
+                profile.soak_minutes = soak_minutes;
+            }
+            "sn" => { // seasonal
+                let month: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let percent: u8 = args.get(3).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if !(1..=12).contains(&month) || percent > Profile::SEASONAL_MAX {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                profile.seasonal[month as usize - 1] = percent;
+            }
+            "ms" => { // moisture
+                let moisture_min: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let moisture_max: u8 = args.get(3).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if moisture_min > moisture_max || moisture_max > 100 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                profile.moisture_min = moisture_min;
+                profile.moisture_max = moisture_max;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl ProfileController {
+    /// Built-in profiles
+    pub(in crate::apps) const PRESETS: usize = 4;
+
+    /// User profiles, ids from `PRESETS` to `SIZE - 1`
+    pub(in crate::apps) const USER_SIZE: usize = 4;
+    pub(in crate::apps) const SIZE: usize = ProfileController::PRESETS + ProfileController::USER_SIZE;
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+PRF";
+    pub(in crate::apps) const AT_RESP: &'static str = "+PRF: ";
+    const FILE_NAME: &'static str = "profiles.json";
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    /// Built-in or user profile, None if not defined
+    pub(in crate::apps) fn get(&self, id: u8) -> Option<Profile> {
+        let id = id as usize;
+        if id < ProfileController::PRESETS {
+            let (description, profile) = PRESETS[id];
+            return Some(Profile { description: Bytes::from_str(description), ..profile });
+        }
+
+        self.profiles.get(id - ProfileController::PRESETS).copied().flatten()
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_valid(&self) -> bool {
+        self.profiles.iter().flatten().all(Profile::is_valid)
+    }
+
+    /// Replace the user profiles and persist
+    pub(in crate::apps) fn replace(&mut self, other: &Self) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = *other;
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, ProfileController::FILE_NAME, self)?;
+        Ok(())
+    }
+}
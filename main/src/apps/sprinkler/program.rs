@@ -0,0 +1,269 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::sync::atomic::Ordering;
+
+use alloc::string::String;
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use cjson_binding::{from_json, to_json};
+use osal_rs::{log_info, log_warning};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::sprinkler::DISBURSEMENT_IN_PROGRESS;
+use crate::apps::sprinkler::dosing::DosingController;
+use crate::apps::sprinkler::group::GroupController;
+use crate::apps::sprinkler::profile::ProfileController;
+use crate::apps::sprinkler::schedule::ScheduleController;
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+
+static mut PROGRAM_HANDLER: ProgramHandler = ProgramHandler;
+
+/// JSON document built by `ex`, read chunk by chunk with `rd`
+static mut EXPORT_TMP: Option<String> = None;
+
+/// JSON document received chunk by chunk with `im` until `exec` applies it, with the number of chunks received
+static mut IMPORT_TMP: Option<(usize, String)> = None;
+
+const APP_TAG: &str = "ProgramHandler";
+
+/// Whole garden program: zones, zone profiles, zone groups, fertigation and schedules
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct Program {
+    zones: ZoneController,
+    profiles: ProfileController,
+    groups: GroupController,
+    dosing: DosingController,
+    schedules: ScheduleController,
+}
+
+pub(in crate::apps) struct ProgramHandler;
+
+impl AtContext<{Parser::CMD_SIZE}> for ProgramHandler {
+    fn exec(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        if DISBURSEMENT_IN_PROGRESS.load(Ordering::Relaxed) {
+            return Err((at_response, AtError::Unhandled("Watering in progress")));
+        }
+
+        let (_, json) = unsafe { (*&raw mut IMPORT_TMP).take() }
+            .ok_or((at_response, AtError::Unhandled("No import in progress")))?;
+
+        let program = from_json::<Program>(&json).map_err(|_| (at_response, AtError::Unhandled("Invalid JSON")))?;
+
+        if !program.zones.is_valid() || !program.profiles.is_valid() || !program.groups.is_valid() || !program.dosing.is_valid() || !program.schedules.is_valid() {
+            return Err((at_response, AtError::Unhandled("Invalid program")));
+        }
+
+        // every zone profile must be defined by the program
+        let profiles_defined = (0..ZoneController::SIZE)
+            .filter_map(|idx| program.zones.get(ZoneRelay::from(idx as u8)).and_then(|zone| zone.profile))
+            .all(|id| program.profiles.get(id).is_some());
+        if !profiles_defined {
+            return Err((at_response, AtError::Unhandled("Invalid program")));
+        }
+
+        let old = ProgramHandler::current();
+        let pick = |imported: bool| if imported { &program } else { &old };
+
+        ProgramHandler::apply(&mut [
+            &mut |imported| ProfileController::shared().replace(&pick(imported).profiles),
+            &mut |imported| ZoneController::shared().replace(&pick(imported).zones),
+            &mut |imported| GroupController::shared().replace(&pick(imported).groups),
+            &mut |imported| DosingController::shared().replace(&pick(imported).dosing),
+            &mut |imported| ScheduleController::shared().replace(&pick(imported).schedules),
+        ]).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+
+        log_info!(APP_TAG, "Program imported");
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+
+    #[inline]
+    /// ex = export, rd = read chunk, bg = begin import, im = import chunk
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<ex|bg> | rd,<chunk> | im,<chunk>,<data>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        match cmd.as_ref() {
+            "ex" => { // export
+                let json = to_json(&ProgramHandler::current()).map_err(|_| (at_response, AtError::Unhandled("Impossible export")))?;
+                let chunks = json.len().div_ceil(ProgramHandler::CHUNK_SIZE);
+
+                unsafe {
+                    EXPORT_TMP = Some(json);
+                }
+
+                Ok(at_cmd_response!(at_response; chunks))
+            }
+            "rd" => { // read chunk
+                let chunk: usize = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+
+                let json = unsafe { (*&raw const EXPORT_TMP).as_ref() }
+                    .ok_or((at_response, AtError::Unhandled("No export in progress")))?;
+
+                let start = ProgramHandler::char_boundary(json, chunk * ProgramHandler::CHUNK_SIZE);
+                let end = ProgramHandler::char_boundary(json, (chunk + 1) * ProgramHandler::CHUNK_SIZE);
+                if start >= json.len() {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+
+                let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+                response.format(format_args!("{},{}", chunk, &json[start..end]));
+
+                Ok((at_response, response))
+            }
+            "bg" => { // begin import
+                unsafe {
+                    IMPORT_TMP = Some((0, String::new()));
+                }
+
+                Ok(at_cmd_response!(at_response; ""))
+            }
+            "im" => { // import chunk
+                let chunk: usize = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let data = args.get(2).ok_or((at_response, AtError::InvalidArgs))?;
+
+                let (received, json) = unsafe { (*&raw mut IMPORT_TMP).as_mut() }
+                    .ok_or((at_response, AtError::Unhandled("No import in progress")))?;
+
+                if chunk != *received {
+                    return Err((at_response, AtError::Unhandled("Unexpected chunk")));
+                }
+
+                if json.len() + data.len() > ProgramHandler::JSON_MAX_SIZE {
+                    unsafe {
+                        IMPORT_TMP = None;
+                    }
+                    return Err((at_response, AtError::Unhandled("Program too large")));
+                }
+
+                json.push_str(data.as_ref());
+                *received += 1;
+
+                Ok(at_cmd_response!(at_response; ""))
+            }
+            _ => Err((at_response, AtError::InvalidArgs)),
+        }
+    }
+}
+
+impl ProgramHandler {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+PRG";
+    pub(in crate::apps) const AT_RESP: &'static str = "+PRG: ";
+
+    /// Bytes of JSON for each response line, leaves room in `Parser::CMD_SIZE` for the chunk index
+    const CHUNK_SIZE: usize = 64;
+    const JSON_MAX_SIZE: usize = 4_096;
+
+    pub(in crate::apps) fn get() -> &'static mut ProgramHandler {
+        unsafe { &mut *&raw mut PROGRAM_HANDLER }
+    }
+
+    /// Program of the controllers in use
+    fn current() -> Program {
+        Program {
+            zones: *ZoneController::shared(),
+            profiles: *ProfileController::shared(),
+            groups: *GroupController::shared(),
+            dosing: *DosingController::shared(),
+            schedules: ScheduleController::shared().snapshot(),
+        }
+    }
+
+    /// Runs the replaces of the controllers in order, each one called with true saves the imported part.
+    /// A failed save calls again with false the replaces done and the failed one, last first,
+    /// so RAM and flash keep the old program instead of a mix of the two
+    fn apply(replaces: &mut [&mut dyn FnMut(bool) -> Result<()>]) -> Result<()> {
+        for done in 0..replaces.len() {
+            if let Err(e) = replaces[done](true) {
+                for replace in replaces[..=done].iter_mut().rev() {
+                    if let Err(e) = replace(false) {
+                        log_warning!(APP_TAG, "Failed to restore the program: {e}");
+                    }
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Move back `index` to the nearest char boundary, to not split a multi-byte char between chunks
+    fn char_boundary(json: &str, index: usize) -> usize {
+        let mut index = index.min(json.len());
+        while !json.is_char_boundary(index) {
+            index -= 1;
+        }
+        index
+    }
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use osal_rs::utils::Error;
+
+    #[test]
+    fn a_failed_save_puts_back_the_old_program() {
+        // part and program of each replace call, in the order of the calls
+        let calls = RefCell::new(Vec::new());
+        let replace = |part: u8, imported: bool, fails: bool| {
+            calls.borrow_mut().push((part, imported));
+            if fails && imported { Err(Error::OutOfMemory) } else { Ok(()) }
+        };
+
+        let result = ProgramHandler::apply(&mut [
+            &mut |imported| replace(0, imported, false),
+            &mut |imported| replace(1, imported, false),
+            &mut |imported| replace(2, imported, true),
+            &mut |imported| replace(3, imported, false),
+        ]);
+
+        assert!(result.is_err());
+        assert_eq!(calls.into_inner(), [(0, true), (1, true), (2, true), (2, false), (1, false), (0, false)]);
+    }
+
+    #[test]
+    fn a_program_is_saved_whole() {
+        let calls = RefCell::new(Vec::new());
+        let replace = |part: u8, imported: bool| -> Result<()> {
+            calls.borrow_mut().push((part, imported));
+            Ok(())
+        };
+
+        let result = ProgramHandler::apply(&mut [
+            &mut |imported| replace(0, imported),
+            &mut |imported| replace(1, imported),
+        ]);
+
+        assert!(result.is_ok());
+        assert_eq!(calls.into_inner(), [(0, true), (1, true)]);
+    }
+}
@@ -0,0 +1,299 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_error, log_info};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::signals::error::{ErrorFlag, ErrorSignal};
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::date_time::DateTime;
+use crate::drivers::platform::{FS_CONFIG_DIR, FS_DATA_DIR};
+use crate::traits::signal::Signal;
+use crate::traits::state::Initializable;
+
+static mut SHARED: ProtectionController = ProtectionController {
+    config: ProtectionConfig::new(),
+    lockout: Lockout::new(),
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+const APP_TAG: &str = "ProtectionController";
+
+/// Why the pump has been locked out
+#[repr(u8)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(in crate::apps) enum LockoutReason {
+    #[default]
+    None,
+
+    /// pump on with no flow or no pressure
+    DryRun,
+
+    /// thermal protector of the pump open
+    OverTemperature,
+}
+
+impl From<u8> for LockoutReason {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => LockoutReason::DryRun,
+            2 => LockoutReason::OverTemperature,
+            _ => LockoutReason::None,
+        }
+    }
+}
+
+impl Serialize for LockoutReason {
+    #[inline]
+    fn serialize<S: Serializer>(&self, name: &str, serializer: &mut S) -> Result<(), S::Error> {
+        Ok(serializer.serialize_u8(name, *self as u8)?)
+    }
+}
+
+impl Deserialize for LockoutReason {
+    #[inline]
+    fn deserialize<D: Deserializer>(deserializer: &mut D, name: &str) -> Result<Self, D::Error> {
+        Ok(LockoutReason::from(deserializer.deserialize_u8(name)?))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct ProtectionConfig {
+
+    /// seconds with valves open and no flow before the run is stopped, 0 disables the dry run protection
+    dry_run_seconds: u16,
+
+    /// minutes the pump stays locked out after a trip
+    cooldown_minutes: u16,
+
+    /// no pressure on the pressure switch is a dry run, used when the flow meter is not installed
+    pressure_switch: bool,
+
+    /// the thermal protector of the pump is wired
+    thermal_switch: bool,
+}
+
+impl Default for ProtectionConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtectionConfig {
+    const DRY_RUN_SECONDS_DEFAULT: u16 = 60;
+    const COOLDOWN_MINUTES_DEFAULT: u16 = 30;
+
+    const fn new() -> Self {
+        Self {
+            dry_run_seconds: ProtectionConfig::DRY_RUN_SECONDS_DEFAULT,
+            cooldown_minutes: ProtectionConfig::COOLDOWN_MINUTES_DEFAULT,
+            pressure_switch: false,
+            thermal_switch: false,
+        }
+    }
+}
+
+/// Lockout state, persisted so that a reboot does not restart a dry pump
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct Lockout {
+    reason: LockoutReason,
+
+    /// timestamp of the end of the cooldown
+    until: i64,
+}
+
+impl Lockout {
+    const fn new() -> Self {
+        Self {
+            reason: LockoutReason::None,
+            until: 0,
+        }
+    }
+}
+
+pub(in crate::apps) struct ProtectionController {
+    config: ProtectionConfig,
+    lockout: Lockout,
+}
+
+impl Initializable for ProtectionController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init Protection");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.config = deserialize_file::<ProtectionConfig>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_CONFIG_DIR, ProtectionController::FILE_NAME)?;
+        self.lockout = deserialize_file::<Lockout>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_DATA_DIR, ProtectionController::LOCKOUT_FILE_NAME)?;
+
+        if self.lockout.reason != LockoutReason::None {
+            log_error!(APP_TAG, "Pump locked out until {}", self.lockout.until);
+            ErrorSignal::set(ErrorFlag::PumpLockout.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for ProtectionController {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        response.format(format_args!("{},{},{},{},{},{}",
+            self.config.dry_run_seconds,
+            self.config.cooldown_minutes,
+            self.config.pressure_switch as u8,
+            self.config.thermal_switch as u8,
+            self.lockout.reason as u8,
+            self.lockout.until,
+        ));
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// dr = dry run seconds, cd = cooldown minutes, ps = pressure switch, th = thermal switch, ul = unlock, sv = save
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<dr|cd|ps|th>,<value> | ul | sv"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match cmd.as_ref() {
+            "dr" => { // dry run seconds
+                self.config.dry_run_seconds = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            }
+            "cd" => { // cooldown minutes
+                self.config.cooldown_minutes = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            }
+            "ps" => { // pressure switch
+                let value: u8 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                self.config.pressure_switch = value != 0;
+            }
+            "th" => { // thermal switch
+                let value: u8 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                self.config.thermal_switch = value != 0;
+            }
+            "ul" => { // unlock
+                self.unlock().map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            }
+            "sv" => { // save
+                serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, ProtectionController::FILE_NAME, &self.config).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl ProtectionController {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+PMP";
+    pub(in crate::apps) const AT_RESP: &'static str = "+PMP: ";
+    const FILE_NAME: &'static str = "protection.json";
+    const LOCKOUT_FILE_NAME: &'static str = "lockout.json";
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_dry_run_seconds(&self) -> u16 {
+        self.config.dry_run_seconds
+    }
+
+    #[inline]
+    pub(in crate::apps) fn has_pressure_switch(&self) -> bool {
+        self.config.pressure_switch
+    }
+
+    #[inline]
+    pub(in crate::apps) fn has_thermal_switch(&self) -> bool {
+        self.config.thermal_switch
+    }
+
+    /// Locked out pump, the lockout expired at `timestamp` is released
+    pub(in crate::apps) fn is_locked(&mut self, timestamp: i64) -> bool {
+        if self.lockout.reason == LockoutReason::None {
+            return false;
+        }
+
+        if timestamp < self.lockout.until {
+            return true;
+        }
+
+        log_info!(APP_TAG, "Pump cooldown completed");
+        if let Err(e) = self.unlock() {
+            log_error!(APP_TAG, "Error saving lockout: {e}");
+        }
+        false
+    }
+
+    /// Lock out the pump for the cooldown and raise the pump lockout error
+    pub(in crate::apps) fn lock(&mut self, reason: LockoutReason, timestamp: i64) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.lockout = Lockout {
+            reason,
+            until: timestamp + self.config.cooldown_minutes as i64 * DateTime::SECONDS_PER_MINUTE,
+        };
+
+        log_error!(APP_TAG, "Pump locked out ({}) until {}", reason as u8, self.lockout.until);
+        ErrorSignal::set(ErrorFlag::PumpLockout.into());
+
+        if let Err(e) = serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, ProtectionController::LOCKOUT_FILE_NAME, &self.lockout) {
+            log_error!(APP_TAG, "Error saving lockout: {e}");
+        }
+    }
+
+    fn unlock(&mut self) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.lockout = Lockout::new();
+        ErrorSignal::clear(ErrorFlag::PumpLockout.into());
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, ProtectionController::LOCKOUT_FILE_NAME, &self.lockout)?;
+        Ok(())
+    }
+}
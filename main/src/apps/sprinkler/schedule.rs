@@ -18,17 +18,19 @@
  *
  ***************************************************************************/
 
+use at_parser_rs::at_quoted as quoted;
 use at_parser_rs::{Args, AtError, AtResult};
 use at_parser_rs::context::AtContext;
 use osal_rs::{access_static_option, log_info};
 use osal_rs::os::{RawMutex, RawMutexGuard};
-use osal_rs::utils::{Bytes, Result};
-use osal_rs_serde::{Deserialize, Serialize};
+use osal_rs::utils::{Bytes, Error, Result};
+use osal_rs_serde::{Deserialize, Deserializer, Serialize};
 
 use crate::apps::DISPLAY_INPUT_MAX_SIZE;
 use crate::apps::parser::{Parser, at_cmd_response};
-use crate::apps::signals::status::{StatusFlag, StatusSignal};
-use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
+use crate::apps::sprinkler::group::ZoneTarget;
+use crate::apps::sprinkler::zone::ZoneController;
 use crate::apps::utils::{deserialize_file, serialize_file};
 use crate::drivers::date_time::DateTime;
 use crate::drivers::platform::FS_CONFIG_DIR;
@@ -46,8 +48,8 @@ static mut SHARED: ScheduleController = ScheduleController { schedules: [
 
 static mut MUTEX: Option<RawMutex> = None;
 
-/// Temporary schedule data used to stage changes from `set` until `exec` persists them
-static mut SCHEDULE_TMP: (usize, Schedule) = (0, Schedule::new());
+/// Selected schedule index and its copy used to stage changes from `set` until `exec` commits them, None if no schedule is selected
+static mut SCHEDULE_TMP: Option<(usize, Schedule)> = None;
 
 const APP_TAG: &str = "SchedulerController";
 
@@ -64,6 +66,8 @@ pub(in crate::apps) enum Day {
 } 
 
 impl Day {
+    pub(in crate::apps) const ALL: u8 = 0x7F;
+
     fn map(value: u8) -> [Option<Self>; 7] {
         use Day::*;
         
@@ -141,6 +145,8 @@ impl From<Month> for u8 {
 }
 
 impl Month {
+    pub(in crate::apps) const ALL: u16 = 0x0FFF;
+
     fn map(value: u16) -> [Option<Self>; 12] {
         use Month::*;
         
@@ -174,6 +180,47 @@ impl Month {
 
 
 
+/// Zone or zone group watered by a schedule, by time or by volume
+#[derive(Debug, Default, Copy, Clone, Serialize, PartialEq, Eq)]
+pub(in crate::apps) struct ScheduleZone {
+
+    /// zone or zone group
+    pub target: ZoneTarget,
+
+    /// watering time in minutes, used when liters is 0, 0 for the watering time of the zone profile
+    pub minutes: u8,
+
+    /// target volume in liters for each zone, 0 for watering by time
+    pub liters: u16,
+}
+
+impl ScheduleZone {
+    /// Max target volume, keeps `AT+SCH?` response within `Parser::CMD_SIZE`
+    pub(in crate::apps) const MAX_LITERS: u16 = 9_999;
+}
+
+impl Deserialize for ScheduleZone {
+    fn deserialize<D: Deserializer>(deserializer: &mut D, name: &str) -> core::result::Result<Self, D::Error> {
+        if deserializer.deserialize_struct_start(name).is_ok() {
+            if let Ok(target) = ZoneTarget::deserialize(deserializer, "target") {
+                let minutes = deserializer.deserialize_u8("minutes").unwrap_or(0);
+                // The entries saved before the watering by volume are all by time
+                let liters = deserializer.deserialize_u16("liters").unwrap_or(0);
+                deserializer.deserialize_struct_end()?;
+
+                return Ok(Self { target, minutes, liters });
+            }
+            deserializer.deserialize_struct_end()?;
+        }
+
+        // The entries saved before the zone groups are a (zone relay, minutes) pair,
+        // the relay number is the serialized value of its ZoneTarget
+        let (target, minutes) = <(ZoneTarget, u8)>::deserialize(deserializer, name)?;
+
+        Ok(Self { target, minutes, liters: 0 })
+    }
+}
+
 #[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub(in crate::apps) struct Schedule {
 
@@ -192,8 +239,8 @@ pub(in crate::apps) struct Schedule {
     /// description 
     pub description: Bytes<DISPLAY_INPUT_MAX_SIZE>,
 
-    /// zones associated to the schedule and watering time in minutes
-    pub zones: [Option<(ZoneRelay, u8)>; ZoneController::SIZE],
+    /// zones or zone groups associated to the schedule with watering time or volume
+    pub zones: [Option<ScheduleZone>; ZoneController::SIZE],
 
     /// status of the schedule
     pub status: Status
@@ -202,6 +249,9 @@ pub(in crate::apps) struct Schedule {
 impl Schedule {
     pub(super) const NOT_SET: u8 = 0x00;
 
+    /// Days searched by `next_start`, a year plus a week covers every month and weekday combination
+    const MAX_DAYS_AHEAD: i64 = 372;
+
     pub(super) const fn new() -> Self {
         Self {
             minute: 0,
@@ -219,61 +269,168 @@ impl Schedule {
         }
     }
 
-    fn is_modified(tmp: &Self) -> bool {
-        static EMPTY: Schedule = Schedule::new();
-        EMPTY != *tmp 
+    /// Check fields ranges and that each zone or group is assigned at most once
+    pub(in crate::apps) fn is_valid(&self) -> bool {
+        if self.minute > 60 || self.hour > 24 || self.days > Day::ALL || self.month > Month::ALL || self.status == Status::RUN {
+            return false;
+        }
+
+        self.zones.iter().enumerate().all(|(idx, zone)| match zone {
+            Some(zone) => zone.target.is_valid() && zone.liters <= ScheduleZone::MAX_LITERS
+                && !self.zones[idx + 1..].iter().any(|z| matches!(z, Some(z) if z.target == zone.target)),
+            None => true,
+        })
+    }
+
+    #[inline]
+    fn is_modified(&self, tmp: &Self) -> bool {
+        *self != *tmp
     }
 
-    pub(in super) fn executable(&self, now: &DateTime) -> bool {
-        if self.status == Status::RUN {
-            return false;
+    /// Month and weekday of `date` allowed by the schedule
+    fn is_day(&self, date: &DateTime) -> bool {
+        let month = self.month == Schedule::NOT_SET as u16
+            || Month::map(self.month).iter().flatten().any(|m| <Month as Into<u8>>::into(*m) + 1 == date.month);
+        let day = self.days == Schedule::NOT_SET
+            || Day::map(self.days).iter().flatten().any(|d| <Day as Into<u8>>::into(*d) == date.wday);
+
+        month && day
+    }
+
+    /// First start at or after the minute of `timestamp`, None if the schedule is not active or has no
+    /// start in the next `MAX_DAYS_AHEAD` days. Only depends on `timestamp`, so it can be driven by a virtual clock
+    pub(in crate::apps) fn next_start(&self, timestamp: i64) -> Option<i64> {
+        if self.status == Status::UNACTIVE {
+            return None;
         }
 
-        let mut check = [true; 4];
+        let from = timestamp - timestamp.rem_euclid(DateTime::SECONDS_PER_MINUTE);
+        let today = from - from.rem_euclid(DateTime::SECONDS_PER_DAY);
 
-        if self.month != Schedule::NOT_SET as u16 {
-            let months = Month::map(self.month);
-            for month in months.iter() {
-                check[0] = false;
-                if let Some(m) = month {
-                    if <Month as Into<u8>>::into(*m) == now.month {
-                        check[0] = true;
-                        break;
-                    }
-                }
+        for day in 0..=Schedule::MAX_DAYS_AHEAD {
+            let midnight = today + day * DateTime::SECONDS_PER_DAY;
+            if !self.is_day(&DateTime::from_timestamp(midnight).ok()?) {
+                continue;
             }
-        } else {
-            check[0] = true;
-        }
 
-        if self.days != Schedule::NOT_SET {
-            let days = Day::map(self.days);
-            for day in days.iter() {
-                check[1] = false;
-                if let Some(d) = day {
-                    if <Day as Into<u8>>::into(*d) == now.wday {
-                        check[1] = true;
-                        break;
+            let hours = match self.hour {
+                Schedule::NOT_SET => 0..24,
+                hour => hour as i64 - 1..hour as i64,
+            };
+            for hour in hours {
+                let minutes = match self.minute {
+                    Schedule::NOT_SET => 0..60,
+                    minute => minute as i64 - 1..minute as i64,
+                };
+                for minute in minutes {
+                    let start = midnight + hour * DateTime::SECONDS_PER_HOUR + minute * DateTime::SECONDS_PER_MINUTE;
+                    if start >= from {
+                        return Some(start);
                     }
                 }
             }
-        } else {
-            check[1] = true;
         }
 
-        if self.hour != Schedule::NOT_SET {
-            check[2] = self.hour - 1 == now.hour;
-        } else {
-            check[2] = true;
+        None
+    }
+}
+
+/// Start time of a schedule reached by the clock, see `Timetable::take`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(in crate::apps) enum Due {
+    /// within the minute of the start time
+    Start,
+
+    /// the clock jumped forward over the start time
+    Missed,
+}
+
+/// Next start time of each schedule, a start time is handled exactly once even if the clock goes back.
+/// Only depends on the schedules and on the timestamps it is given, so it can be driven by a virtual clock
+#[derive(Debug, Copy, Clone)]
+pub(in crate::apps) struct Timetable {
+
+    /// start time of the last start or skip of each schedule
+    handled: [i64; ScheduleController::SIZE],
+
+    /// next start time of each schedule, None if not active
+    due: [Option<i64>; ScheduleController::SIZE],
+
+    /// `due` is up to date with the schedules and the clock, see `reschedule`
+    scheduled: bool,
+
+    /// `handled` starts from the minute of the first update, see `update`
+    booted: bool,
+}
+
+impl Default for Timetable {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timetable {
+    /// Longest sleep while no schedule is due, bounds the drift between the system tick and the RTC
+    pub(in crate::apps) const MAX_SLEEP_SECONDS: i64 = 3_600;
+
+    pub(in crate::apps) const fn new() -> Self {
+        Self {
+            handled: [0; ScheduleController::SIZE],
+            due: [None; ScheduleController::SIZE],
+            scheduled: false,
+            booted: false,
         }
+    }
+
+    /// Compute again the next start of the schedules at the next `update`, after a change of the schedules or of the clock
+    #[inline]
+    pub(in crate::apps) fn reschedule(&mut self) {
+        self.scheduled = false;
+    }
 
-        if self.minute != Schedule::NOT_SET {
-            check[3] = self.minute - 1 == now.minute;
+    /// Next start of each schedule from `timestamp` if a `reschedule` was requested, the start times already handled are skipped.
+    /// The start times up to the minute of the first update are handled, a reboot in the minute of a start does not start it again
+    pub(in crate::apps) fn update<'a>(&mut self, schedules: impl Iterator<Item = &'a Schedule>, timestamp: i64) {
+        if self.scheduled {
+            return;
+        }
+
+        if !self.booted {
+            self.handled = [timestamp - timestamp.rem_euclid(DateTime::SECONDS_PER_MINUTE); ScheduleController::SIZE];
+            self.booted = true;
+        }
+
+        for (idx, schedule) in schedules.enumerate().take(ScheduleController::SIZE) {
+            self.due[idx] = schedule.next_start(timestamp.max(self.handled[idx] + DateTime::SECONDS_PER_MINUTE));
+        }
+        self.scheduled = true;
+    }
+
+    /// Take the start time of the schedule if reached at `timestamp`, the next one becomes due
+    pub(in crate::apps) fn take(&mut self, idx: usize, schedule: &Schedule, timestamp: i64) -> Option<Due> {
+        let due = self.due[idx].filter(|due| *due <= timestamp)?;
+
+        self.handled[idx] = due;
+        self.due[idx] = schedule.next_start(due + DateTime::SECONDS_PER_MINUTE);
+
+        if timestamp - due >= DateTime::SECONDS_PER_MINUTE {
+            Some(Due::Missed)
         } else {
-            check[3] = true;
+            Some(Due::Start)
         }
+    }
+
+    /// Next due start of the schedules, None if no schedule is active
+    #[inline]
+    pub(in crate::apps) fn get_next_start(&self) -> Option<i64> {
+        self.due.iter().flatten().min().copied()
+    }
 
-        check.iter().all(|&x| x)
+    /// Seconds of sleep from `timestamp` to the next due start, at most `MAX_SLEEP_SECONDS`
+    pub(in crate::apps) fn get_sleep_seconds(&self, timestamp: i64) -> i64 {
+        self.get_next_start()
+            .map_or(Timetable::MAX_SLEEP_SECONDS, |start| (start - timestamp).clamp(0, Timetable::MAX_SLEEP_SECONDS))
     }
 }
 
@@ -317,130 +474,181 @@ impl AtContext<{Parser::CMD_SIZE}> for ScheduleController {
     fn exec(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
+        let (index, schedule_tmp) = unsafe { *&raw const SCHEDULE_TMP }
+            .ok_or((at_response, AtError::Unhandled("No schedule selected")))?;
+
+        let schedule = self.schedules.get_mut(index)
+            .ok_or((at_response, AtError::InvalidArgs))?;
 
-        if !Schedule::is_modified(unsafe { &(*&raw const SCHEDULE_TMP).1 }) {
+        if !schedule.is_modified(&schedule_tmp) {
             return Err((at_response, AtError::Unhandled("No modify applied")));
         }
-
-        let (index, schedule_tmp) = unsafe { &mut *&raw mut SCHEDULE_TMP };
-
-        let schedule = self.schedules.get_mut(*index)
-            .ok_or((at_response, AtError::InvalidArgs))?;
-        *schedule = *schedule_tmp;
+        // a running schedule is staged as RUN, st cannot set it
+        let mut checked = schedule_tmp;
+        if checked.status == Status::RUN && schedule.status == Status::RUN {
+            checked.status = Status::ACTIVE;
+        }
+        if !checked.is_valid() {
+            return Err((at_response, AtError::Unhandled("Invalid schedule")));
+        }
+        *schedule = schedule_tmp;
 
         unsafe {
-            SCHEDULE_TMP = (0, Schedule::new());
+            SCHEDULE_TMP = None;
         }
 
+        SchedulerSignal::set(SchedulerFlag::ScheduleChanged.into());
+
         Ok(at_cmd_response!(at_response; ""))
     }
 
+    /// Response layout, fits `Parser::CMD_SIZE` also with every field at its max length:
+    /// `<idx>,<mi>,<hr>,<dy>,<mo>,<st>,"<description>"` then one `<target>,<minutes>` or `<target>,<liters>L` line for each zone
     fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-        
-        let (index, schedule) = unsafe {
-            &*&raw mut SCHEDULE_TMP
-        };
-        
 
-        let mut zones_descr = Bytes::<100>::new();
-        zones_descr.append_str("[");
-        for zone in schedule.zones {
-            match zone {
-                Some((zone_relay, watering_time)) => {
-                    let mut format = Bytes::<16>::new();
-                    format.format(format_args!("{zone_relay}={watering_time}, "));
-                    zones_descr.append_as_sync_str(&format);
-                }
-                None => zones_descr.append_str("None, "),
-            };
-        }
-        zones_descr.pop_char();
-        zones_descr.pop_char();
-        zones_descr.append_str("]");
+        let (index, schedule) = unsafe { *&raw const SCHEDULE_TMP }
+            .ok_or((at_response, AtError::Unhandled("No schedule selected")))?;
 
-        let mut response = Bytes::<{ Parser::CMD_SIZE }>::new();
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
         response.format(format_args!("{},{},{},{},{},{},{}",
             index,
             schedule.minute,
             schedule.hour,
             schedule.days,
             schedule.month,
-            schedule.description,
-            zones_descr,
+            <Status as Into<u8>>::into(schedule.status),
+            quoted!(schedule.description.as_str()),
         ));
 
+        for zone in schedule.zones.iter().flatten() {
+            if zone.liters > 0 {
+                response.format(format_args!("\r\n{},{}L", zone.target, zone.liters));
+            } else {
+                response.format(format_args!("\r\n{},{}", zone.target, zone.minutes));
+            }
+        }
+
         Ok((at_response, response))
     }
 
     #[inline]
-    /// mi = minute, hr = hour, dy = days, mo = month, ds = description, zn = zone, st = status, sv = save
+    /// sl = select, mi = minute, hr = hour, dy = days, mo = month, ds = description, zn = zone by time, zv = zone by volume, rz = remove zone, st = status, en = enable, cl = clear, sv = save
     fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
-        Ok(at_cmd_response!(at_response; "<idx>,<mi|hr|dy|mo|ds|zn|st>,<value> | sv"))
+        Ok(at_cmd_response!(at_response; "<idx>,<mi|hr|dy|mo|ds|zn|zv|rz|st|en>,<value> | sl | cl | sv"))
     }
 
-    #[allow(unused_assignments)]
     fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
-
         let idx: usize = args.get(0).ok_or((at_response, AtError::InvalidArgs))?
             .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
         let cmd = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
 
-        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-
+        if cmd.as_ref() == "en" { // enable, on the live schedule without touching the staged changes
+            let value: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            if value > 1 {
+                return Err((at_response, AtError::InvalidArgs));
+            }
+            self.set_enabled(idx, value == 1).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            return Ok(at_cmd_response!(at_response; ""));
+        }
 
-        let schedule = unsafe {
-            &mut *&raw mut SCHEDULE_TMP
-        };
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        schedule.0 = idx;
+        let live = *self.schedules.get(idx).ok_or((at_response, AtError::InvalidArgs))?;
 
-        match cmd.as_ref() {
-            "mi" => // minute
-                schedule.1.minute = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
-                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?,
+        let schedule_tmp = unsafe { &mut *&raw mut SCHEDULE_TMP };
 
-            "hr" => // hour
-                schedule.1.hour = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
-                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?,
+        // select the schedule discarding staged changes of any other one
+        if cmd.as_ref() == "sl" || !matches!(schedule_tmp, Some((index, _)) if *index == idx) {
+            *schedule_tmp = Some((idx, live));
+        }
 
-            "dy" => // days (bitmask, see Day)
-                schedule.1.days = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
-                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?,
+        let Some((_, schedule)) = schedule_tmp else {
+            return Err((at_response, AtError::InvalidArgs));
+        };
 
-            "mo" => // month (bitmask, see Month)
-                schedule.1.month = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
-                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?,
+        match cmd.as_ref() {
+            "sl" => {} // select
 
+            "mi" => { // minute
+                let value: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value > 60 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                schedule.minute = value;
+            }
+            "hr" => { // hour
+                let value: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value > 24 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                schedule.hour = value;
+            }
+            "dy" => { // days (bitmask, see Day)
+                let value: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value > Day::ALL {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                schedule.days = value;
+            }
+            "mo" => { // month (bitmask, see Month)
+                let value: u16 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value > Month::ALL {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                schedule.month = value;
+            }
             "ds" => { // description
                 let value = args.get(2).ok_or((at_response, AtError::InvalidArgs))?;
                 if value.len() > DISPLAY_INPUT_MAX_SIZE {
                     return Err((at_response, AtError::Unhandled("description max len exceeded")));
                 }
-                schedule.1.description = Bytes::from_str(value.as_ref());
+                schedule.description = Bytes::from_str(value.as_ref());
             }
-            "zn" => { // zone relay + watering time in minutes
-                let zone_relay: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
-                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
-                let zone_relay = ZoneRelay::from(zone_relay);
-                let minutes: u8 = args.get(3).ok_or((at_response, AtError::InvalidArgs))?
+            "zn" | "zv" => { // zone relay or zone group + watering time in minutes or volume in liters
+                let target: ZoneTarget = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
                     .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                let value = args.get(3).ok_or((at_response, AtError::InvalidArgs))?;
+
+                let zone = if cmd.as_ref() == "zn" {
+                    ScheduleZone { target, minutes: value.parse().map_err(|_| (at_response, AtError::InvalidArgs))?, liters: 0 }
+                } else {
+                    let liters: u16 = value.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                    if liters > ScheduleZone::MAX_LITERS {
+                        return Err((at_response, AtError::InvalidArgs));
+                    }
+                    ScheduleZone { target, minutes: 0, liters }
+                };
 
-                let position = schedule.1.zones.iter().position(|z| matches!(z, Some((relay, _)) if *relay == zone_relay))
-                    .or_else(|| schedule.1.zones.iter().position(|z| z.is_none()))
+                let position = schedule.zones.iter().position(|z| matches!(z, Some(z) if z.target == target))
+                    .or_else(|| schedule.zones.iter().position(|z| z.is_none()))
                     .ok_or((at_response, AtError::InvalidArgs))?;
-                schedule.1.zones[position] = Some((zone_relay, minutes));
+                schedule.zones[position] = Some(zone);
+            }
+            "rz" => { // remove zone relay or zone group
+                let target: ZoneTarget = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+
+                let position = schedule.zones.iter().position(|z| matches!(z, Some(z) if z.target == target))
+                    .ok_or((at_response, AtError::Unhandled("Zone not assigned")))?;
+                schedule.zones[position] = None;
             }
-            "st" => { // status
+            "st" => { // status, RUN is set only by the run engine
                 let value: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
                     .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
-                schedule.1.status = Status::from(value);
+                if value > 1 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                schedule.status = Status::from(value);
+            }
+            "cl" => { // clear
+                *schedule = Schedule::new();
+                schedule.description.format(format_args!("Schedule {idx}"));
             }
             "sv" => {// save
                 serialize_file(unsafe {&*&raw const MUTEX},  APP_TAG, FS_CONFIG_DIR, ScheduleController::FILE_NAME, unsafe {&*&raw const SHARED}).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
@@ -472,4 +680,247 @@ impl ScheduleController {
         unsafe { &mut *&raw mut SHARED }
     }
 
+    #[inline]
+    pub(in crate::apps) fn is_valid(&self) -> bool {
+        self.schedules.iter().all(Schedule::is_valid)
+    }
+
+    /// Copy of all schedules with the running ones reported as `ACTIVE`
+    pub(in crate::apps) fn snapshot(&self) -> Self {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let mut ret = *self;
+        for schedule in ret.schedules.iter_mut() {
+            if schedule.status == Status::RUN {
+                schedule.status = Status::ACTIVE;
+            }
+        }
+        ret
+    }
+
+    /// Replace all schedules, discard staged changes and persist
+    pub(in crate::apps) fn replace(&mut self, other: &Self) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = *other;
+
+        unsafe {
+            SCHEDULE_TMP = None;
+        }
+
+        SchedulerSignal::set(SchedulerFlag::ScheduleChanged.into());
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, ScheduleController::FILE_NAME, self)?;
+        Ok(())
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get(&self, index: usize) -> Option<&Schedule> {
+        self.schedules.get(index)
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_mut(&mut self, index: usize) -> Option<&mut Schedule> {
+        self.schedules.get_mut(index)
+    }
+
+    /// Bitmask of the active or running schedules, bit n is schedule n
+    pub(in crate::apps) fn get_active(&self) -> u8 {
+        self.schedules.iter().enumerate()
+            .filter(|(_, schedule)| schedule.status != Status::UNACTIVE)
+            .fold(0, |mask, (idx, _)| mask | 1 << idx)
+    }
+
+    /// Activate or deactivate one schedule and persist, a running one stays running when activated
+    pub(in crate::apps) fn set_enabled(&mut self, idx: usize, enabled: bool) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let schedule = self.schedules.get_mut(idx).ok_or(Error::NotFound)?;
+        schedule.status = match (enabled, schedule.status) {
+            (true, Status::RUN) => Status::RUN,
+            (true, _) => Status::ACTIVE,
+            (false, _) => Status::UNACTIVE,
+        };
+
+        SchedulerSignal::set(SchedulerFlag::ScheduleChanged.into());
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, ScheduleController::FILE_NAME, self)?;
+        Ok(())
+    }
+
+    /// Activate the schedules in the bitmask, deactivate the others and persist
+    pub(in crate::apps) fn set_active(&mut self, mask: u8) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        for (idx, schedule) in self.schedules.iter_mut().enumerate() {
+            schedule.status = if mask & 1 << idx != 0 { Status::ACTIVE } else { Status::UNACTIVE };
+        }
+
+        SchedulerSignal::set(SchedulerFlag::ScheduleChanged.into());
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, ScheduleController::FILE_NAME, self)?;
+        Ok(())
+    }
+
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    use cjson_binding::{from_json, to_json};
+
+    use crate::apps::sprinkler::zone::ZoneRelay;
+
+    /// schedules.json as saved by the firmware before the zone groups and the watering by volume,
+    /// the zones are (zone relay, minutes) pairs
+    const OLD_SCHEDULES: &str = r#"{"schedules":[{"minute":0,"hour":0,"days":0,"month":0,"description":"","zones":[null,null,null,null],"status":0},{"minute":31,"hour":7,"days":127,"month":0,"description":"Lawn","zones":[[2,15],null,[0,5],null],"status":1},{"minute":0,"hour":0,"days":0,"month":0,"description":"","zones":[null,null,null,null],"status":0},{"minute":0,"hour":0,"days":0,"month":0,"description":"","zones":[null,null,null,null],"status":0}]}"#;
+
+    #[test]
+    fn old_schedules_keep_their_zones() {
+        let Ok(loaded) = from_json::<ScheduleController>(OLD_SCHEDULES) else { panic!("old schedules not loaded") };
+
+        let schedule = loaded.schedules[1];
+        assert_eq!((schedule.minute, schedule.hour, schedule.days), (31, 7, Day::ALL));
+        assert_eq!(schedule.description.as_str(), "Lawn");
+        assert_eq!(schedule.status, Status::ACTIVE);
+        assert_eq!(schedule.zones, [
+            Some(ScheduleZone { target: ZoneTarget::Zone(ZoneRelay::Relay2), minutes: 15, liters: 0 }),
+            None,
+            Some(ScheduleZone { target: ZoneTarget::Zone(ZoneRelay::Relay0), minutes: 5, liters: 0 }),
+            None,
+        ]);
+        assert!(schedule.is_valid());
+    }
+
+    #[test]
+    fn schedules_round_trip() {
+        let mut controller = ScheduleController::default();
+        controller.schedules[0].status = Status::ACTIVE;
+        controller.schedules[0].zones = [
+            Some(ScheduleZone { target: ZoneTarget::Group(1), minutes: 0, liters: 120 }),
+            Some(ScheduleZone { target: ZoneTarget::Zone(ZoneRelay::Relay3), minutes: 20, liters: 0 }),
+            None,
+            None,
+        ];
+        let Ok(json) = to_json(&controller) else { panic!("schedules not serialized") };
+
+        let Ok(loaded) = from_json::<ScheduleController>(&json) else { panic!("schedules not loaded") };
+
+        assert_eq!(loaded.schedules[0], controller.schedules[0]);
+    }
+
+    /// Timestamp of a date of the virtual clock
+    fn at(year: i32, month: u8, mday: u8, hour: u8, minute: u8, second: u8) -> i64 {
+        let Ok(date) = DateTime::new(year, month, 0, mday, hour, minute, second) else { panic!("invalid date") };
+        date.to_timestamp()
+    }
+
+    /// Active schedule at 07:30 of the days and months in the masks
+    fn schedule(days: u8, month: u16) -> Schedule {
+        Schedule { minute: 31, hour: 8, days, month, status: Status::ACTIVE, ..Schedule::new() }
+    }
+
+    #[test]
+    fn next_start_rolls_over_to_the_allowed_month() {
+        let march = schedule(Schedule::NOT_SET, Month::March as u16);
+        assert_eq!(march.next_start(at(2026, 2, 27, 12, 0, 0)), Some(at(2026, 3, 1, 7, 30, 0)));
+        assert_eq!(march.next_start(at(2026, 3, 31, 7, 31, 0)), Some(at(2027, 3, 1, 7, 30, 0)));
+
+        let january = schedule(Schedule::NOT_SET, Month::January as u16);
+        assert_eq!(january.next_start(at(2025, 12, 31, 23, 59, 59)), Some(at(2026, 1, 1, 7, 30, 0)));
+
+        let december = schedule(Schedule::NOT_SET, Month::December as u16);
+        assert_eq!(december.next_start(at(2026, 12, 31, 7, 30, 0)), Some(at(2026, 12, 31, 7, 30, 0)));
+        assert_eq!(december.next_start(at(2026, 12, 31, 7, 31, 0)), Some(at(2027, 12, 1, 7, 30, 0)));
+    }
+
+    #[test]
+    fn next_start_follows_the_day_of_week_mask() {
+        // 2026-10-18 is a Sunday
+        let sunday = at(2026, 10, 18, 12, 0, 0);
+        assert_eq!(DateTime::from_timestamp(sunday).map(|date| date.wday).ok(), Some(0));
+
+        let monday_thursday = schedule(Day::Monday as u8 | Day::Thursday as u8, Schedule::NOT_SET as u16);
+        assert_eq!(monday_thursday.next_start(sunday), Some(at(2026, 10, 19, 7, 30, 0)));
+        assert_eq!(monday_thursday.next_start(at(2026, 10, 19, 7, 30, 59)), Some(at(2026, 10, 19, 7, 30, 0)));
+        assert_eq!(monday_thursday.next_start(at(2026, 10, 19, 7, 31, 0)), Some(at(2026, 10, 22, 7, 30, 0)));
+
+        let saturday_in_march = schedule(Day::Saturday as u8, Month::March as u16);
+        assert_eq!(saturday_in_march.next_start(sunday), Some(at(2027, 3, 6, 7, 30, 0)));
+
+        let every_minute = Schedule { status: Status::ACTIVE, ..Schedule::new() };
+        assert_eq!(every_minute.next_start(at(2026, 10, 18, 12, 0, 30)), Some(at(2026, 10, 18, 12, 0, 0)));
+
+        assert_eq!(Schedule::new().next_start(sunday), None);
+    }
+
+    #[test]
+    fn timetable_starts_each_start_time_once() {
+        let schedules = [schedule(Schedule::NOT_SET, Schedule::NOT_SET as u16), Schedule::new(), Schedule::new(), Schedule::new()];
+        let mut timetable = Timetable::new();
+
+        timetable.update(schedules.iter(), at(2026, 10, 18, 7, 0, 0));
+        assert_eq!(timetable.get_next_start(), Some(at(2026, 10, 18, 7, 30, 0)));
+        assert_eq!(timetable.take(0, &schedules[0], at(2026, 10, 18, 7, 29, 59)), None);
+        assert_eq!(timetable.take(0, &schedules[0], at(2026, 10, 18, 7, 30, 20)), Some(Due::Start));
+        assert_eq!(timetable.take(0, &schedules[0], at(2026, 10, 18, 7, 30, 40)), None);
+        assert_eq!(timetable.get_next_start(), Some(at(2026, 10, 19, 7, 30, 0)));
+
+        // the clock goes back before the start time already handled
+        timetable.reschedule();
+        timetable.update(schedules.iter(), at(2026, 10, 18, 7, 10, 0));
+        assert_eq!(timetable.get_next_start(), Some(at(2026, 10, 19, 7, 30, 0)));
+    }
+
+    #[test]
+    fn timetable_misses_a_start_time_jumped_over() {
+        let schedules = [schedule(Schedule::NOT_SET, Schedule::NOT_SET as u16), Schedule::new(), Schedule::new(), Schedule::new()];
+        let mut timetable = Timetable::new();
+
+        timetable.update(schedules.iter(), at(2026, 10, 18, 7, 0, 0));
+        assert_eq!(timetable.take(0, &schedules[0], at(2026, 10, 18, 9, 0, 0)), Some(Due::Missed));
+        assert_eq!(timetable.get_next_start(), Some(at(2026, 10, 19, 7, 30, 0)));
+
+        // a jump of days misses one start time at each check, until the next one is ahead
+        for day in 19..=21 {
+            assert_eq!(timetable.take(0, &schedules[0], at(2026, 10, 21, 9, 0, 0)), Some(Due::Missed));
+            assert_eq!(timetable.get_next_start(), Some(at(2026, 10, day + 1, 7, 30, 0)));
+        }
+        assert_eq!(timetable.take(0, &schedules[0], at(2026, 10, 21, 9, 0, 0)), None);
+
+        // after a reschedule the start times in the past are not due anymore
+        timetable.reschedule();
+        timetable.update(schedules.iter(), at(2026, 10, 21, 9, 0, 0));
+        assert_eq!(timetable.get_next_start(), Some(at(2026, 10, 22, 7, 30, 0)));
+    }
+
+    #[test]
+    fn timetable_skips_the_start_time_of_the_boot_minute() {
+        let schedules = [schedule(Schedule::NOT_SET, Schedule::NOT_SET as u16), Schedule::new(), Schedule::new(), Schedule::new()];
+        let mut timetable = Timetable::new();
+
+        // rebooted 20 s after the start of 07:30
+        timetable.update(schedules.iter(), at(2026, 10, 18, 7, 30, 20));
+        assert_eq!(timetable.get_next_start(), Some(at(2026, 10, 19, 7, 30, 0)));
+        assert_eq!(timetable.take(0, &schedules[0], at(2026, 10, 18, 7, 30, 40)), None);
+    }
+
+    #[test]
+    fn sleep_is_capped() {
+        let schedules = [schedule(Schedule::NOT_SET, Schedule::NOT_SET as u16), Schedule::new(), Schedule::new(), Schedule::new()];
+        let mut timetable = Timetable::new();
+        assert_eq!(timetable.get_sleep_seconds(at(2026, 10, 18, 7, 0, 0)), Timetable::MAX_SLEEP_SECONDS);
+
+        timetable.update(schedules.iter(), at(2026, 10, 18, 7, 0, 0));
+        assert_eq!(timetable.get_sleep_seconds(at(2026, 10, 18, 7, 0, 0)), 30 * DateTime::SECONDS_PER_MINUTE);
+        assert_eq!(timetable.get_sleep_seconds(at(2026, 10, 18, 5, 0, 0)), Timetable::MAX_SLEEP_SECONDS);
+        assert_eq!(timetable.get_sleep_seconds(at(2026, 10, 18, 7, 30, 10)), 0);
+
+        let inactive = [Schedule::new(); ScheduleController::SIZE];
+        let mut timetable = Timetable::new();
+        timetable.update(inactive.iter(), at(2026, 10, 18, 7, 0, 0));
+        assert_eq!(timetable.get_next_start(), None);
+        assert_eq!(timetable.get_sleep_seconds(at(2026, 10, 18, 7, 0, 0)), Timetable::MAX_SLEEP_SECONDS);
+    }
 }
@@ -0,0 +1,248 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_info, log_warning};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::sprinkler::zone::{ZoneController, ZoneRelay};
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::FS_DATA_DIR;
+use crate::traits::state::Initializable;
+use ZoneRelay::*;
+
+static mut SHARED: StatisticsController = StatisticsController { zones: [
+    ZoneStatistics::new(Relay0),
+    ZoneStatistics::new(Relay1),
+    ZoneStatistics::new(Relay2),
+    ZoneStatistics::new(Relay3)
+]};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+/// Runs or skips recorded since the last save, see `StatisticsController::flush`
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+const APP_TAG: &str = "StatisticsController";
+
+/// Reason why a zone was not watered when its schedule was due
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(in crate::apps) enum SkipReason {
+    /// another schedule was already watering
+    Busy,
+    /// the relay driver refused to open the valve
+    Relay,
+    /// watering by volume without flow meter nor nominal flow rate
+    Flow,
+    /// pump locked out after a dry run or an overheating
+    Lockout,
+    /// weekly budget of the zone or of the controller reached
+    Budget,
+}
+
+impl SkipReason {
+    pub(in crate::apps) const SIZE: usize = 5;
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(in crate::apps) struct ZoneStatistics {
+
+    /// relay number associated to the zone
+    pub(in crate::apps) zone_relay: ZoneRelay,
+
+    /// number of completed runs
+    pub(in crate::apps) runs: u32,
+
+    /// total watering time in minutes
+    pub(in crate::apps) run_minutes: u32,
+
+    /// timestamp of the last completed run, 0 if never run
+    pub(in crate::apps) last_run: i64,
+
+    /// total delivered liters, only updated when a measure is available
+    pub(in crate::apps) liters: u32,
+
+    /// skipped runs counters indexed by SkipReason
+    pub(in crate::apps) skips: [u16; SkipReason::SIZE],
+}
+
+impl ZoneStatistics {
+    const fn new(zone_relay: ZoneRelay) -> Self {
+        Self {
+            zone_relay,
+            runs: 0,
+            run_minutes: 0,
+            last_run: 0,
+            liters: 0,
+            skips: [0; SkipReason::SIZE],
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct StatisticsController {
+    zones: [ZoneStatistics; ZoneController::SIZE]
+}
+
+impl Initializable for StatisticsController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init Statistics");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        *self = deserialize_file::<StatisticsController>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_DATA_DIR, StatisticsController::FILE_NAME)?;
+
+        // file created from defaults has every zone on Relay0
+        for (idx, zone) in self.zones.iter_mut().enumerate() {
+            zone.zone_relay = ZoneRelay::from(idx as u8);
+        }
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for StatisticsController {
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        for zone in self.zones.iter() {
+            response.format(format_args!("{},{},{}\r\n",
+                <ZoneRelay as Into<u8>>::into(zone.zone_relay), zone.runs, zone.run_minutes));
+        }
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// rd = read details, rs = reset
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<zone_relay>,<rd|rs>"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let zone_relay: u8 = args.get(0).ok_or((at_response, AtError::InvalidArgs))?
+            .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+        if zone_relay as usize >= ZoneController::SIZE {
+            return Err((at_response, AtError::InvalidArgs));
+        }
+        let zone_relay = ZoneRelay::from(zone_relay);
+        let cmd = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let zone = self.zones.iter_mut().find(|zone| zone.zone_relay == zone_relay)
+            .ok_or((at_response, AtError::InvalidArgs))?;
+
+        match cmd.as_ref() {
+            "rd" => { // read details
+                let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+                response.format(format_args!("{},{},{},{},{}",
+                    <ZoneRelay as Into<u8>>::into(zone.zone_relay), zone.runs, zone.run_minutes, zone.last_run, zone.liters));
+                for skip in zone.skips.iter() {
+                    response.format(format_args!(",{skip}"));
+                }
+                return Ok((at_response, response));
+            }
+            "rs" => { // reset
+                *zone = ZoneStatistics::new(zone_relay);
+                serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, StatisticsController::FILE_NAME, unsafe {&*&raw const SHARED}).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl StatisticsController {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+STAT";
+    pub(in crate::apps) const AT_RESP: &'static str = "+STAT: ";
+    const FILE_NAME: &'static str = "statistics.json";
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get(&self, zone_relay: ZoneRelay) -> Option<&ZoneStatistics> {
+        self.zones.iter().find(|zone| zone.zone_relay == zone_relay)
+    }
+
+    /// Account a completed run, `liters` is None when the delivered volume is not measured
+    pub(in crate::apps) fn record_run(&mut self, zone_relay: ZoneRelay, minutes: u32, timestamp: i64, liters: Option<u32>) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if let Some(zone) = self.zones.iter_mut().find(|zone| zone.zone_relay == zone_relay) {
+            zone.runs = zone.runs.saturating_add(1);
+            zone.run_minutes = zone.run_minutes.saturating_add(minutes);
+            zone.last_run = timestamp;
+            if let Some(liters) = liters {
+                zone.liters = zone.liters.saturating_add(liters);
+            }
+        }
+
+        DIRTY.store(true, Ordering::Relaxed);
+    }
+
+    pub(in crate::apps) fn record_skip(&mut self, zone_relay: ZoneRelay, reason: SkipReason) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if let Some(zone) = self.zones.iter_mut().find(|zone| zone.zone_relay == zone_relay) {
+            let skip = &mut zone.skips[reason as usize];
+            *skip = skip.saturating_add(1);
+        }
+
+        DIRTY.store(true, Ordering::Relaxed);
+    }
+
+    /// Save the runs and skips recorded since the last save, called once at the end of a run
+    /// instead of after each zone to spare the flash
+    pub(in crate::apps) fn flush(&self) {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if !DIRTY.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        if let Err(e) = serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, StatisticsController::FILE_NAME, self) {
+            log_warning!(APP_TAG, "Failed to save statistics: {e}");
+        }
+    }
+}
@@ -0,0 +1,252 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use at_parser_rs::context::AtContext;
+use at_parser_rs::{Args, AtError, AtResult};
+use osal_rs::{access_static_option, log_info};
+use osal_rs::os::{RawMutex, RawMutexGuard};
+use osal_rs::utils::{Bytes, Result};
+use osal_rs_serde::{Deserialize, Serialize};
+
+use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
+use crate::apps::signals::status::{StatusFlag, StatusSignal};
+use crate::apps::sprinkler::DISBURSEMENT_IN_PROGRESS;
+use crate::apps::utils::{deserialize_file, serialize_file};
+use crate::drivers::platform::{FS_CONFIG_DIR, FS_DATA_DIR};
+use crate::traits::signal::Signal;
+use crate::traits::state::Initializable;
+
+static mut SHARED: WinterizationController = WinterizationController {
+    config: WinterizationConfig::new(),
+    state: WinterizationState::new(),
+};
+
+static mut MUTEX: Option<RawMutex> = None;
+
+/// Blowout sequence requested over AT, served by the main FSM
+static START_REQUEST: AtomicBool = AtomicBool::new(false);
+
+/// Exit from winterization requested over AT, served by the main FSM
+static ENABLE_REQUEST: AtomicBool = AtomicBool::new(false);
+
+const APP_TAG: &str = "WinterizationController";
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct WinterizationConfig {
+
+    /// seconds each zone stays open while the air is blown
+    blow_seconds: u16,
+
+    /// seconds between two zones to let the compressor recover
+    pause_seconds: u16,
+}
+
+impl Default for WinterizationConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WinterizationConfig {
+    const BLOW_SECONDS_DEFAULT: u16 = 60;
+    const PAUSE_SECONDS_DEFAULT: u16 = 30;
+
+    const fn new() -> Self {
+        Self {
+            blow_seconds: WinterizationConfig::BLOW_SECONDS_DEFAULT,
+            pause_seconds: WinterizationConfig::PAUSE_SECONDS_DEFAULT,
+        }
+    }
+}
+
+/// Persisted so that the controller stays winterized across reboots
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(in crate::apps) struct WinterizationState {
+    winterized: bool,
+
+    /// bitmask of the schedules active before the winterization, restored on exit
+    schedules: u8,
+}
+
+impl WinterizationState {
+    const fn new() -> Self {
+        Self {
+            winterized: false,
+            schedules: 0,
+        }
+    }
+}
+
+pub(in crate::apps) struct WinterizationController {
+    config: WinterizationConfig,
+    state: WinterizationState,
+}
+
+impl Initializable for WinterizationController {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init Winterization");
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.config = deserialize_file::<WinterizationConfig>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_CONFIG_DIR, WinterizationController::FILE_NAME)?;
+        self.state = deserialize_file::<WinterizationState>(unsafe { &*&raw const MUTEX }, APP_TAG, FS_DATA_DIR, WinterizationController::STATE_FILE_NAME)?;
+
+        Ok(())
+    }
+}
+
+impl AtContext<{Parser::CMD_SIZE}> for WinterizationController {
+    fn exec(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        if StatusFlag::Winterize.check_signal(StatusSignal::get()) {
+            return Err((at_response, AtError::Unhandled("Winterization in progress")));
+        }
+
+        if DISBURSEMENT_IN_PROGRESS.load(Ordering::Relaxed) {
+            return Err((at_response, AtError::Unhandled("Watering in progress")));
+        }
+
+        START_REQUEST.store(true, Ordering::Relaxed);
+        SchedulerSignal::set(SchedulerFlag::Request.into());
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+
+    fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        let state = if StatusFlag::Winterize.check_signal(StatusSignal::get()) {
+            1
+        } else if self.state.winterized {
+            2
+        } else {
+            0
+        };
+
+        let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+        response.format(format_args!("{},{},{}", state, self.config.blow_seconds, self.config.pause_seconds));
+
+        Ok((at_response, response))
+    }
+
+    #[inline]
+    /// bt = blow time, ps = pause, en = enable, sv = save
+    fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        Ok(at_cmd_response!(at_response; "<bt|ps>,<seconds> | en | sv"))
+    }
+
+    fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
+        let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        match cmd.as_ref() {
+            "bt" => { // blow time
+                let value: u16 = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value == 0 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                self.config.blow_seconds = value;
+            }
+            "ps" => { // pause
+                self.config.pause_seconds = args.get(1).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+            }
+            "en" => { // enable, leave the winterization
+                if !self.state.winterized {
+                    return Err((at_response, AtError::Unhandled("Not winterized")));
+                }
+                ENABLE_REQUEST.store(true, Ordering::Relaxed);
+                SchedulerSignal::set(SchedulerFlag::Request.into());
+            }
+            "sv" => { // save
+                serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, WinterizationController::FILE_NAME, &self.config).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
+            }
+            _ => return Err((at_response, AtError::InvalidArgs)),
+        }
+
+        Ok(at_cmd_response!(at_response; ""))
+    }
+}
+
+impl WinterizationController {
+    pub(in crate::apps) const AT_CMD: &'static str = "AT+WNT";
+    pub(in crate::apps) const AT_RESP: &'static str = "+WNT: ";
+    const FILE_NAME: &'static str = "winterization.json";
+    const STATE_FILE_NAME: &'static str = "winterized.json";
+
+    pub(in crate::apps) fn shared() -> &'static mut Self {
+        unsafe {
+            if (*&raw const MUTEX).is_none() {
+                MUTEX = match RawMutex::new() {
+                    Ok(mutex) => Some(mutex),
+                    Err(_) =>  panic!("MUTEX is not initialized",),
+                }
+            }
+        }
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+        unsafe { &mut *&raw mut SHARED }
+    }
+
+    #[inline]
+    pub(in crate::apps) fn take_start_request(&self) -> bool {
+        START_REQUEST.swap(false, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(in crate::apps) fn take_enable_request(&self) -> bool {
+        ENABLE_REQUEST.swap(false, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_winterized(&self) -> bool {
+        self.state.winterized
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_blow_seconds(&self) -> u16 {
+        self.config.blow_seconds
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_pause_seconds(&self) -> u16 {
+        self.config.pause_seconds
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_schedules(&self) -> u8 {
+        self.state.schedules
+    }
+
+    /// Persist the winterization with the schedules to restore on exit
+    pub(in crate::apps) fn set_winterized(&mut self, winterized: bool, schedules: u8) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.state = WinterizationState { winterized, schedules };
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_DATA_DIR, WinterizationController::STATE_FILE_NAME, &self.state)?;
+        Ok(())
+    }
+}
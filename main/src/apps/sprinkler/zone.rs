@@ -33,12 +33,11 @@ use osal_rs_serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::apps::DISPLAY_INPUT_MAX_SIZE;
 use crate::apps::parser::{Parser, at_cmd_response};
-use crate::apps::signals::status::{StatusFlag, StatusSignal};
 use crate::apps::utils::{deserialize_file, serialize_file};
 use crate::drivers::platform::{FS_CONFIG_DIR, GpioPeripheral};
-use crate::traits::signal::Signal;
 use crate::traits::state::Initializable;
 use super::commons::Status;
+use super::profile::ProfileController;
 use ZoneRelay::*;
 
 static mut SHARED: ZoneController = ZoneController { zones: [
@@ -46,7 +45,7 @@ static mut SHARED: ZoneController = ZoneController { zones: [
     Zone::new(Relay1),
     Zone::new(Relay2),
     Zone::new(Relay3)
-]};
+], pulses_per_liter: 0 };
 
 static mut MUTEX: Option<RawMutex> = None;
 
@@ -133,7 +132,7 @@ impl Deserialize for ZoneRelay {
     }
 }
 
-#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Copy, Clone, Serialize, PartialEq, Eq)]
 pub(in crate::apps) struct Zone {
 
     /// description of zone
@@ -145,10 +144,36 @@ pub(in crate::apps) struct Zone {
     /// for manage order of execution lighter is first then weightier
     pub(in crate::apps) weight: u8,
 
+    /// nominal flow rate in liters per hour, 0 if unknown
+    pub(in crate::apps) flow_rate: u16,
+
+    /// safety cap in minutes of the runs by volume
+    pub(in crate::apps) max_minutes: u8,
+
+    /// plant or irrigation profile, see `ProfileController`
+    pub(in crate::apps) profile: Option<u8>,
+
     /// status of the zone
     pub(in crate::apps) status: Status
 }
 
+impl Deserialize for Zone {
+    fn deserialize<D: Deserializer>(deserializer: &mut D, name: &str) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct_start(name)?;
+        let description = Bytes::deserialize(deserializer, "description")?;
+        let zone_relay = ZoneRelay::deserialize(deserializer, "zone_relay")?;
+        let weight = deserializer.deserialize_u8("weight")?;
+        // The zones saved before the watering by volume and the profiles have no flow meter data and no profile
+        let flow_rate = deserializer.deserialize_u16("flow_rate").unwrap_or(0);
+        let max_minutes = deserializer.deserialize_u8("max_minutes").unwrap_or(Zone::MAX_MINUTES_DEFAULT);
+        let profile = Option::<u8>::deserialize(deserializer, "profile").unwrap_or(None);
+        let status = Status::deserialize(deserializer, "status")?;
+        deserializer.deserialize_struct_end()?;
+
+        Ok(Self { description, zone_relay, weight, flow_rate, max_minutes, profile, status })
+    }
+}
+
 impl Display for Zone {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
             write!(f, "{}", self.description.as_str()
@@ -158,11 +183,16 @@ impl Display for Zone {
 
 
 impl Zone {
+    const MAX_MINUTES_DEFAULT: u8 = 60;
+
     const fn new(zone_relay: ZoneRelay) -> Self {
         Self {
             description: Bytes::new(),
             zone_relay,
             weight: 0,
+            flow_rate: 0,
+            max_minutes: Zone::MAX_MINUTES_DEFAULT,
+            profile: None,
             status: Status::UNACTIVE
         }
     } 
@@ -173,12 +203,46 @@ impl Zone {
     }
 }
 
-#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Serialize)]
 pub(in crate::apps) struct ZoneController {
-    zones: [Zone; ZoneController::SIZE]
+    zones: [Zone; ZoneController::SIZE],
+
+    /// flow meter pulses for each liter, 0 if the flow meter is not installed
+    pulses_per_liter: u16,
+}
+
+impl Deserialize for ZoneController {
+    fn deserialize<D: Deserializer>(deserializer: &mut D, name: &str) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct_start(name)?;
+        let zones = <[Zone; ZoneController::SIZE]>::deserialize(deserializer, "zones")?;
+        let pulses_per_liter = deserializer.deserialize_u16("pulses_per_liter").unwrap_or(0);
+        deserializer.deserialize_struct_end()?;
+
+        Ok(Self { zones, pulses_per_liter })
+    }
 }
 
 
+impl Default for ZoneController {
+    fn default() -> Self {
+        let mut zones = [
+            Zone::new(Relay0),
+            Zone::new(Relay1),
+            Zone::new(Relay2),
+            Zone::new(Relay3)
+        ];
+        for zone in zones.iter_mut() {
+            zone.description = Bytes::from_str(zone.zone_relay.into());
+            zone.weight = zone.zone_relay.into();
+        }
+
+        Self {
+            zones,
+            pulses_per_liter: 0
+        }
+    }
+}
+
 impl Initializable for ZoneController {
     fn init(&mut self) -> Result<()> {
         log_info!(APP_TAG, "Init Zone");
@@ -204,20 +268,19 @@ impl AtContext<{Parser::CMD_SIZE}> for ZoneController {
     fn exec(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
 
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
-
         if !Zone::is_modified(unsafe { &*&raw const ZONE_TMP}) {
             return Err((at_response, AtError::Unhandled("No modify applied")));
         }
 
-        let Zone{zone_relay, description: descr, weight, ..} = unsafe { &mut *&raw mut ZONE_TMP };
+        let Zone{zone_relay, description: descr, weight, flow_rate, max_minutes, profile, ..} = unsafe { &mut *&raw mut ZONE_TMP };
 
         let zone = self.zones.iter_mut().find(|zone| zone.zone_relay == *zone_relay)
             .ok_or((at_response, AtError::InvalidArgs))?;
         zone.weight = *weight;
         zone.description = *descr;
+        zone.flow_rate = *flow_rate;
+        zone.max_minutes = *max_minutes;
+        zone.profile = *profile;
 
         unsafe {
             ZONE_TMP = Zone::new(Relay0);
@@ -228,9 +291,6 @@ impl AtContext<{Parser::CMD_SIZE}> for ZoneController {
 
     fn query(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
         let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
 
         let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
         for zone in self.zones.iter() {
@@ -242,26 +302,44 @@ impl AtContext<{Parser::CMD_SIZE}> for ZoneController {
     }
 
     #[inline]
-    /// wt = weight, ds = description, sv = save
+    /// wt = weight, ds = description, fr = flow rate, mx = max minutes, pf = profile, rd = read, fm = flow meter pulses per liter, sv = save
     fn test(&mut self, at_response: &'static str) -> AtResult<'_, {Parser::CMD_SIZE}> {
-        Ok(at_cmd_response!(at_response; "<zone_relay>,<wt|ds>,<value> | sv"))
+        Ok(at_cmd_response!(at_response; "<zone_relay>,<wt|ds|fr|mx>,<value> | <zone_relay>,pf[,<value>] | <zone_relay>,rd | fm[,<value>] | sv"))
     }
 
     fn set(&mut self, at_response: &'static str, args: Args) -> AtResult<'_, {Parser::CMD_SIZE}> {
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
+        let first = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
+
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        if first.as_ref() == "fm" { // flow meter pulses per liter
+            return match args.get(1) {
+                Some(value) => {
+                    self.pulses_per_liter = value.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                    Ok(at_cmd_response!(at_response; ""))
+                }
+                None => Ok(at_cmd_response!(at_response; self.pulses_per_liter)),
+            };
         }
 
-        let zone_relay: u8 = args.get(0).ok_or((at_response, AtError::InvalidArgs))?
-            .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+        let zone_relay: u8 = first.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
         let zone_relay = ZoneRelay::from(zone_relay);
         let cmd = args.get(1).ok_or((at_response, AtError::InvalidArgs))?;
 
-        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
-
         let zone = self.zones.iter().find(|zone| zone.zone_relay == zone_relay)
             .ok_or((at_response, AtError::InvalidArgs))?;
 
+        if cmd.as_ref() == "rd" { // read
+            let mut response = Bytes::<{Parser::CMD_SIZE}>::new();
+            response.format(format_args!("{},{},{},{},",
+                <ZoneRelay as Into<u8>>::into(zone.zone_relay), zone.weight, zone.flow_rate, zone.max_minutes));
+            if let Some(profile) = zone.profile {
+                response.format(format_args!("{profile}"));
+            }
+            response.format(format_args!(",{}", quoted!(zone.description.as_str())));
+            return Ok((at_response, response));
+        }
+
         unsafe {
             ZONE_TMP = *zone;
         }
@@ -283,6 +361,38 @@ impl AtContext<{Parser::CMD_SIZE}> for ZoneController {
                     ZONE_TMP.description = Bytes::from_str(value.as_ref());
                 }
             }
+            "fr" => { // flow rate
+                let value: u16 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                unsafe {
+                    ZONE_TMP.flow_rate = value;
+                }
+            }
+            "mx" => { // max minutes
+                let value: u8 = args.get(2).ok_or((at_response, AtError::InvalidArgs))?
+                    .parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                if value == 0 {
+                    return Err((at_response, AtError::InvalidArgs));
+                }
+                unsafe {
+                    ZONE_TMP.max_minutes = value;
+                }
+            }
+            "pf" => { // profile, none without value
+                let value = match args.get(2) {
+                    Some(value) => {
+                        let id: u8 = value.parse().map_err(|_| (at_response, AtError::InvalidArgs))?;
+                        if ProfileController::shared().get(id).is_none() {
+                            return Err((at_response, AtError::Unhandled("Profile not defined")));
+                        }
+                        Some(id)
+                    }
+                    None => None,
+                };
+                unsafe {
+                    ZONE_TMP.profile = value;
+                }
+            }
             "sv" => {
                 serialize_file(unsafe {&*&raw const MUTEX},  APP_TAG, FS_CONFIG_DIR, ZoneController::FILE_NAME, unsafe {&*&raw const SHARED}).map_err(|_| (at_response, AtError::Unhandled("Impossible save")))?;
             }
@@ -315,4 +425,93 @@ impl ZoneController {
         unsafe { &mut *&raw mut SHARED }
     }
 
+    /// Every relay must be present once in its own slot
+    pub(in crate::apps) fn is_valid(&self) -> bool {
+        self.zones.iter().enumerate().all(|(idx, zone)| <ZoneRelay as Into<u8>>::into(zone.zone_relay) as usize == idx && zone.max_minutes > 0)
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_pulses_per_liter(&self) -> u16 {
+        self.pulses_per_liter
+    }
+
+    /// Replace all zones, discard staged changes and persist
+    pub(in crate::apps) fn replace(&mut self, other: &Self) -> Result<()> {
+        let _lock = RawMutexGuard::acquire(access_static_option!(MUTEX));
+
+        self.zones = other.zones;
+        self.pulses_per_liter = other.pulses_per_liter;
+        for zone in self.zones.iter_mut() {
+            zone.status = Status::UNACTIVE;
+        }
+
+        unsafe {
+            ZONE_TMP = Zone::new(Relay0);
+        }
+
+        serialize_file(unsafe {&*&raw const MUTEX}, APP_TAG, FS_CONFIG_DIR, ZoneController::FILE_NAME, self)?;
+        Ok(())
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_profile_used(&self, id: u8) -> bool {
+        self.zones.iter().any(|zone| zone.profile == Some(id))
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get(&self, zone_relay: ZoneRelay) -> Option<&Zone> {
+        self.zones.iter().find(|zone| zone.zone_relay == zone_relay)
+    }
+
+    #[inline]
+    pub(in crate::apps) fn get_mut(&mut self, zone_relay: ZoneRelay) -> Option<&mut Zone> {
+        self.zones.iter_mut().find(|zone| zone.zone_relay == zone_relay)
+    }
+
+    /// Bitmask of the open valves, bit n is relay n
+    pub(in crate::apps) fn get_open(&self) -> u8 {
+        self.zones.iter()
+            .filter(|zone| zone.status == Status::RUN)
+            .fold(0, |mask, zone| mask | 1 << <ZoneRelay as Into<u8>>::into(zone.zone_relay))
+    }
+
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    use cjson_binding::{from_json, to_json};
+
+    /// zones.json as saved by the firmware before the watering by volume and the profiles
+    const OLD_ZONES: &str = r#"{"zones":[{"description":"Garden","zone_relay":0,"weight":3,"status":0},{"description":"Garden","zone_relay":1,"weight":2,"status":0},{"description":"Garden","zone_relay":2,"weight":1,"status":1},{"description":"Garden","zone_relay":3,"weight":0,"status":0}]}"#;
+
+    #[test]
+    fn old_zones_get_the_new_defaults() {
+        let Ok(loaded) = from_json::<ZoneController>(OLD_ZONES) else { panic!("old zones not loaded") };
+
+        for (idx, zone) in loaded.zones.iter().enumerate() {
+            assert_eq!(zone.description.as_str(), "Garden");
+            assert_eq!(zone.zone_relay, ZoneRelay::from(idx as u8));
+            assert_eq!(zone.weight, 3 - idx as u8);
+            assert_eq!((zone.flow_rate, zone.max_minutes, zone.profile), (0, Zone::MAX_MINUTES_DEFAULT, None));
+        }
+        assert_eq!(loaded.zones[2].status, Status::ACTIVE);
+        assert_eq!(loaded.pulses_per_liter, 0);
+    }
+
+    #[test]
+    fn zones_round_trip() {
+        let mut controller = ZoneController::default();
+        controller.zones[1].flow_rate = 480;
+        controller.zones[1].max_minutes = 25;
+        controller.zones[1].profile = Some(2);
+        controller.pulses_per_liter = 450;
+        let Ok(json) = to_json(&controller) else { panic!("zones not serialized") };
+
+        let Ok(loaded) = from_json::<ZoneController>(&json) else { panic!("zones not loaded") };
+
+        assert_eq!(loaded.zones, controller.zones);
+        assert_eq!(loaded.pulses_per_liter, 450);
+    }
 }
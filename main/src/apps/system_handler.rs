@@ -22,8 +22,9 @@ use at_parser_rs::{AtError, AtResult};
 use at_parser_rs::context::AtContext;
 
 use crate::apps::parser::{Parser, at_cmd_response};
+use crate::apps::permission::Form;
 use crate::apps::signals::error::ErrorSignal;
-use crate::apps::signals::status::{StatusFlag, StatusSignal};
+use crate::apps::signals::status::StatusSignal;
 use crate::drivers::error::HardwareErrorSignal;
 use crate::drivers::filesystem::Filesystem;
 use crate::drivers::platform::Hardware;
@@ -48,9 +49,6 @@ impl AtContext<{Parser::CMD_SIZE}> for SystemHandler {
     }
 
     fn set(&mut self, at_response: &'static str, args: at_parser_rs::Args) -> AtResult<'_, { Parser::CMD_SIZE }> {
-        if StatusSignal::get() & <StatusFlag as Into<u32>>::into(StatusFlag::UserLogged) == 0 {
-            return Err((at_response, AtError::Unhandled(Parser::NOT_LOGGED_RESPONSE)));
-        }
         let cmd = args.get(0).ok_or((at_response, AtError::InvalidArgs))?;
         match cmd.as_ref() {
             "rs" => 
@@ -75,7 +73,12 @@ impl SystemHandler {
     pub const AT_CMD: &'static str = "AT+SYS";
     pub const AT_RESP: &'static str = "+SYS: ";
 
-    pub(in crate::apps) fn get() -> &'static mut SystemHandler {
+    /// True for the set lines that reboot or erase the device, `verb` is their first argument
+    pub(in crate::apps) fn resets(cmd: &str, form: Form, verb: Option<&str>) -> bool {
+        cmd == Self::AT_CMD && form == Form::Set && matches!(verb, Some("rs" | "fr"))
+    }
+
+        pub(in crate::apps) fn get() -> &'static mut SystemHandler {
         unsafe { &mut *&raw mut SYSTEM_HANDLER }
     }
 }
\ No newline at end of file
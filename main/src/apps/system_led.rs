@@ -31,6 +31,7 @@ use crate::traits::rgb_led::{Color, RgbLed as _};
 use crate::traits::signal::Signal;
 use crate::traits::state::Initializable;
 
+use crate::apps::signals::error::{ErrorFlag, ErrorSignal};
 use crate::apps::signals::status::{StatusFlag::*, StatusSignal};
 
 macro_rules! blink_led {
@@ -61,6 +62,7 @@ static BLINK: AtomicBool = AtomicBool::new(false);
 const COLOR_RED: Color = Color::new(255, 0, 0);
 const COLOR_ORANGE: Color = Color::new(255, 165, 0);
 const COLOR_GREEN: Color = Color::new(0, 255, 0);
+const COLOR_BLUE: Color = Color::new(0, 0, 255);
 const COLOR_OFF: Color = Color::new(0, 0, 0);
 
  pub struct SystemLed{
@@ -85,6 +87,10 @@ const COLOR_OFF: Color = Color::new(0, 0, 0);
                     
                     blink_led!(rgb_led, COLOR_ORANGE);
 
+                } else if Ready.check_signal(status) && ErrorSignal::get() & <ErrorFlag as Into<u32>>::into(ErrorFlag::PumpLockout) != 0 {
+
+                    blink_led!(rgb_led, COLOR_RED);
+
                 } else if Ready.check_signal(status) {
 
                     Self::handle_ready(&rgb_led);
@@ -93,6 +99,14 @@ const COLOR_OFF: Color = Color::new(0, 0, 0);
                     
                     blink_led!(rgb_led, COLOR_RED);
 
+                } else if Winterize.check_signal(status) {
+
+                    blink_led!(rgb_led, COLOR_BLUE);
+
+                } else if Winterized.check_signal(status) {
+
+                    rgb_led.set_color(&COLOR_BLUE);
+
                 } else {
 
                    rgb_led.set_color(&COLOR_OFF);
@@ -0,0 +1,249 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+#![allow(dead_code)]
+
+///! TCP transport of the AT parser: a `telnet` or `nc` client sends command lines
+///! and gets back the responses and the URCs it subscribed. One client at a time,
+///! the session it opens with AT+SESS ends with the connection, which is dropped
+///! after the configured idle time.
+
+use core::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use core::time::Duration;
+
+use osal_rs::{log_error, log_info, log_warning};
+use osal_rs::os::{System, SystemFn, Thread, ThreadFn, ToTick};
+use osal_rs::os::types::{StackType, TickType};
+use osal_rs::utils::Result;
+
+use crate::apps::config::{Config, TelnetConfig};
+use crate::apps::session::Session;
+use crate::drivers::network::Network;
+use crate::drivers::platform::ThreadPriority;
+use crate::traits::rx_tx::{OnReceive, SetOnReceive, SetTransmit, Source};
+use crate::traits::state::Initializable;
+
+const APP_TAG: &str = "AppTelnet";
+const THREAD_NAME: &str = "app_telnet_trd";
+const STACK_SIZE: StackType = 1_536;
+const TICK_INTERVAL_MS: u64 = 50;
+const RETRY_SECONDS: u64 = 5;
+const WRITE_RETRIES: u8 = 20;
+const WRITE_RETRY_MS: u64 = 10;
+
+const RX_BUFFER_SIZE: usize = 128;
+
+/// Telnet protocol bytes, the negotiations are dropped and never answered
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const WILL: u8 = 251;
+const DONT: u8 = 254;
+
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+static RESTART: AtomicBool = AtomicBool::new(false);
+
+/// Port of the running listener, 0 when stopped
+static PORT: AtomicU16 = AtomicU16::new(0);
+
+static mut ON_RECEIVE: Option<&'static dyn OnReceive> = None;
+
+/// Strips the telnet commands from the received bytes, a raw TCP client is not affected
+#[derive(Clone, Copy, Default)]
+enum Filter {
+    #[default]
+    Data,
+    Command,
+    Option,
+    Subnegotiation,
+    SubnegotiationCommand,
+}
+
+impl Filter {
+    fn next(&mut self, byte: u8) -> Option<u8> {
+        use Filter::*;
+        let (state, out) = match (*self, byte) {
+            (Data, IAC) => (Command, None),
+            (Data, 0) => (Data, None), // telnet sends CR NUL for a bare carriage return
+            (Data, byte) => (Data, Some(byte)),
+            (Command, IAC) => (Data, Some(IAC)),
+            (Command, WILL..=DONT) => (Option, None),
+            (Command, SB) => (Subnegotiation, None),
+            (Command, _) | (Option, _) => (Data, None),
+            (Subnegotiation, IAC) => (SubnegotiationCommand, None),
+            (Subnegotiation, _) => (Subnegotiation, None),
+            (SubnegotiationCommand, SE) => (Data, None),
+            (SubnegotiationCommand, _) => (Subnegotiation, None),
+        };
+        *self = state;
+        out
+    }
+}
+
+pub(in crate::apps) struct Telnet(Thread);
+
+impl Initializable for Telnet {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init app telnet");
+
+        self.0 = self.0.spawn_simple(move || {
+            loop {
+                RESTART.store(false, Ordering::SeqCst);
+
+                let config = Config::shared().get_telnet_config();
+                if !config.is_enabled() || !Network::is_link_up() || !Network::dhcp_supplied_address() {
+                    System::delay_with_to_tick(Duration::from_secs(1));
+                    continue;
+                }
+
+                if let Err(e) = Network::tcp_listen(config.get_port()) {
+                    log_error!(APP_TAG, "Listen on port {} failed: {e}", config.get_port());
+                    System::delay_with_to_tick(Duration::from_secs(RETRY_SECONDS));
+                    continue;
+                }
+                log_info!(APP_TAG, "Listening on port {}", config.get_port());
+                PORT.store(config.get_port(), Ordering::SeqCst);
+
+                Self::serve(&config);
+
+                Self::disconnect();
+                Network::tcp_unlisten(PORT.swap(0, Ordering::SeqCst));
+                log_info!(APP_TAG, "Stopped");
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+impl SetOnReceive<'static> for Telnet {
+    fn set_on_receive(&mut self, on_receive: &'static dyn OnReceive) {
+        unsafe {
+            ON_RECEIVE = Some(on_receive);
+        }
+    }
+}
+
+impl SetTransmit for Telnet {
+    /// Waits a little for room in the send buffer, a stalled client loses the data
+    fn transmit(&self, data: &[u8]) -> usize {
+        if !CONNECTED.load(Ordering::SeqCst) {
+            return 0;
+        }
+
+        let port = PORT.load(Ordering::SeqCst);
+
+        for _ in 0..WRITE_RETRIES {
+            match Network::tcp_server_write(port, data) {
+                Ok(len) => return len,
+                Err(_) if Network::tcp_server_is_connected(port) => System::delay_with_to_tick(Duration::from_millis(WRITE_RETRY_MS)),
+                Err(_) => return 0,
+            }
+        }
+
+        log_warning!(APP_TAG, "Client too slow, {} bytes dropped", data.len());
+        0
+    }
+}
+
+impl Telnet {
+    pub(in crate::apps) fn new() -> Self {
+        Self(Thread::new_with_to_priority(THREAD_NAME, STACK_SIZE, ThreadPriority::Normal))
+    }
+
+    #[inline]
+    pub(in crate::apps) fn is_connected() -> bool {
+        CONNECTED.load(Ordering::SeqCst)
+    }
+
+    /// Drops the client and listens again with the updated configuration
+    #[inline]
+    pub(in crate::apps) fn restart() {
+        RESTART.store(true, Ordering::SeqCst);
+    }
+
+    /// Serves the clients until a restart is requested or the link goes down
+    fn serve(config: &TelnetConfig) {
+        let idle = Duration::from_secs(config.get_idle() as u64).to_ticks();
+        let mut last_rx: TickType = 0;
+        let mut filter = Filter::default();
+
+        while !RESTART.load(Ordering::SeqCst) && Network::is_link_up() {
+            if Network::tcp_accept(config.get_port()) {
+                // The previous client may have been reset without being noticed yet
+                Session::close(&Source::Telnet);
+
+                log_info!(APP_TAG, "Client connected");
+                CONNECTED.store(true, Ordering::SeqCst);
+                last_rx = System::get_tick_count();
+                filter = Filter::default();
+            }
+
+            if CONNECTED.load(Ordering::SeqCst) {
+                match Self::receive(&mut filter) {
+                    Ok(0) if idle > 0 && System::get_tick_count().wrapping_sub(last_rx) >= idle => {
+                        log_info!(APP_TAG, "Client idle, disconnected");
+                        Self::disconnect();
+                    }
+                    Ok(0) => {}
+                    Ok(_) => last_rx = System::get_tick_count(),
+                    Err(_) => {
+                        log_info!(APP_TAG, "Client disconnected");
+                        Self::disconnect();
+                    }
+                }
+            }
+
+            System::delay_with_to_tick(Duration::from_millis(TICK_INTERVAL_MS));
+        }
+    }
+
+    /// Hands the received bytes to the parser, returns how many were read
+    fn receive(filter: &mut Filter) -> Result<usize> {
+        let mut buffer = [0u8; RX_BUFFER_SIZE];
+        let len = Network::tcp_server_read(PORT.load(Ordering::SeqCst), &mut buffer)?;
+
+        let mut data = [0u8; RX_BUFFER_SIZE];
+        let mut data_len = 0;
+        for &byte in &buffer[..len] {
+            if let Some(byte) = filter.next(byte) {
+                data[data_len] = byte;
+                data_len += 1;
+            }
+        }
+
+        if data_len > 0 {
+            if let Some(on_receive) = unsafe { *&raw const ON_RECEIVE } {
+                if let Err(e) = on_receive.on_receive(Source::Telnet, &data[..data_len]) {
+                    log_warning!(APP_TAG, "Command dropped: {e}");
+                }
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Closes the client and the session it opened
+    fn disconnect() {
+        CONNECTED.store(false, Ordering::SeqCst);
+        Network::tcp_server_close(PORT.load(Ordering::SeqCst));
+        Session::close(&Source::Telnet);
+    }
+}
@@ -32,7 +32,8 @@ use crate::drivers::filesystem::flags::{CREAT, RDWR, TRUNC, WRONLY};
 use crate::drivers::filesystem::{FileBytes, Filesystem};
 use crate::drivers::platform::FS_SEPARATOR_DIR;
 
-
+/// Suffix of the files that failed to load, kept before the defaults are saved
+const BACKUP_SUFFIX: &str = ".bak";
 
 pub(in crate::apps) fn deserialize_file<T>(mutex: &'static Option<RawMutex>, app_tag: &str, dir: &str, name: &str) -> Result<T> 
 where 
@@ -97,7 +98,14 @@ where
             Ok(t)
         }
         Err(e) => {
-            log_warning!(app_tag, "Using default config values err: {e}");
+            // Keep the unreadable file for a manual recovery instead of losing it with the defaults
+            let mut backup_name = file_name.clone();
+            backup_name.append_str(BACKUP_SUFFIX);
+            match Filesystem::rename_with_as_sync_str(&file_name, &backup_name) {
+                Ok(_) => log_error!(app_tag, "File:{file_name} not loaded, moved to {backup_name}, using defaults err: {e}"),
+                Err(rename_err) => log_error!(app_tag, "File:{file_name} not loaded and not moved - {rename_err}, using defaults err: {e}"),
+            }
+
             let ret = T::default();
 
             serialize_file(mutex, app_tag, dir, name, &ret)?;
@@ -144,4 +152,9 @@ where
             log_info!(app_tag, "Saved successfully");
             Ok(t)
         })
-}
\ No newline at end of file
+}
+
+/// Compares in a time that depends only on the lengths, a secret is not guessed byte by byte
+pub(in crate::apps) fn same_bytes(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
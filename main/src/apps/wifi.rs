@@ -18,14 +18,18 @@
  *
  ***************************************************************************/
 
+use core::sync::atomic::{AtomicI8, Ordering};
+
 use alloc::sync::Arc;
 use osal_rs::os::{Mutex, MutexFn};
 use osal_rs::{log_info};
 use osal_rs::utils::{Bytes, Result};
 
 use crate::apps::config::Config;
+use crate::apps::events::{Event, Events};
 use crate::apps::signals::display::DisplaySignal;
 use crate::apps::signals::error::{ErrorFlag, ErrorSignal};
+use crate::apps::signals::scheduler::{SchedulerFlag, SchedulerSignal};
 use crate::drivers::date_time::DateTime;
 use crate::drivers::network::Network;
 use crate::set_app_error;
@@ -40,6 +44,9 @@ const APP_TAG: &str = "AppWifi";
 static mut STATUS: WifiStatus = Disabled;
 static mut IP_ADDR: Bytes<IPV6_ADDR_LEN> = Bytes::new();
 
+/// Last signal strength reported by the wifi driver in dBm, 0 if unknown
+static RSSI: AtomicI8 = AtomicI8::new(0);
+
 macro_rules! ntp_sync {
     ($tag:expr, $config:expr) => {
         {
@@ -89,6 +96,8 @@ impl OnWifiChangeStatus for Wifi {
             STATUS = status;
         }
 
+        Events::post(Event::Wifi(status));
+
         let config = Config::shared();
 
         match status {
@@ -109,6 +118,7 @@ impl OnWifiChangeStatus for Wifi {
                             let dt = DateTime::from_timestamp(timestamp);
                             set_app_error!(dt.clone(), ErrorFlag::NTP);
                             set_app_error!(rtc.lock().unwrap().set_timestamp(timestamp), ErrorFlag::NTP);
+                            SchedulerSignal::set(SchedulerFlag::ClockChanged.into());
                             log_info!(APP_TAG, "NTP time (UTC): {}", dt.unwrap());
                         },
                         None => {
@@ -127,6 +137,7 @@ impl OnWifiChangeStatus for Wifi {
     }
 
     fn on_rssi_change(&self, rssi: RSSIStatus) {
+        RSSI.store(rssi.into(), Ordering::Relaxed);
         DisplaySignal::set((rssi.to_bites() as u32) << 6);
     }
 }
@@ -149,4 +160,10 @@ impl Wifi {
     pub(in crate::apps) fn get_ip_address() -> Bytes<IPV6_ADDR_LEN> {
         unsafe { (*&raw const IP_ADDR).clone() }
     }
+
+    /// Signal strength bucket in dBm, 0 if unknown
+    #[inline]
+    pub(in crate::apps) fn get_rssi() -> i8 {
+        RSSI.load(Ordering::Relaxed)
+    }
 }
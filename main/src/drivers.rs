@@ -4,10 +4,12 @@ mod encoder;
 pub mod encrypt;
 pub mod error;
 pub mod filesystem;
+mod flow_meter;
 pub mod gpio;
 mod i2c;
 mod lcd_sh1106;
 pub mod network;
+mod pump_sensors;
 mod relays;
 pub mod rgb_led;
 mod rtc;
@@ -18,9 +20,15 @@ pub(super) mod wifi;
 #[cfg(feature = "pico")]
 mod pico;
 
+#[cfg(feature = "host")]
+mod host;
+
 #[cfg(feature = "pico")]
 use crate::drivers::pico as plt;
 
+#[cfg(feature = "host")]
+use crate::drivers::host as plt;
+
 pub mod platform {
     pub(crate) use crate::drivers::plt::flash::*;
     pub(crate) use crate::drivers::plt::gpio::*;
@@ -24,7 +24,7 @@ use alloc::{str, vec::Vec};
 use osal_rs::log_info;
 use osal_rs::utils::{Error, Result, Bytes};
 use crate::traits::state::Initializable;
-use crate::drivers::pico::mbedtls::ENCRYPT_FN;
+use crate::drivers::plt::ENCRYPT_FN;
 
 const APP_TAG: &str = "Encrypt";
 pub const SHA256_RESULT_BYTES: usize = 32;
@@ -34,6 +34,8 @@ pub(in crate::drivers) struct EncryptFn {
     pub(in crate::drivers) aes_encrypt: fn(handler: *mut c_void, key: &[u8], iv: &[u8], plain: &[u8]) -> Result<Vec<u8>>,
     pub(in crate::drivers) aes_decrypt: fn(handler: *mut c_void, key: &[u8], iv: &[u8], cipher: &[u8]) -> Result<Vec<u8>>,
     pub(in crate::drivers) get_sha256: fn(data: &[u8]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>>,
+    pub(in crate::drivers) pbkdf2_sha256: fn(password: &[u8], salt: &[u8], iterations: u32) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>>,
+    pub(in crate::drivers) hmac_sha256: fn(key: &[u8], data: &[u8]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>>,
     pub(in crate::drivers) drop: fn(*mut c_void),
 }
 
@@ -87,6 +89,18 @@ impl<'a, const KEY_SIZE: usize, const IV_SIZE: usize> Encrypt<'a, KEY_SIZE, IV_S
         (ENCRYPT_FN.get_sha256)(data)
     }
 
+    /// PBKDF2-HMAC-SHA256 key of the password, hex encoded
+    #[inline]
+    pub fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+        (ENCRYPT_FN.pbkdf2_sha256)(password, salt, iterations)
+    }
+
+    /// HMAC-SHA256 of the data, hex encoded
+    #[inline]
+    pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+        (ENCRYPT_FN.hmac_sha256)(key, data)
+    }
+
     #[inline]
     pub fn drop(&mut self) {
         log_info!(APP_TAG, "Free encrypt");
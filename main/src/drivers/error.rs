@@ -42,6 +42,8 @@ pub enum HardwareErrorFlag {
     Rtc = 0x200,
     Uart = 0x400,
     Wifi = 0x800,
+    FlowMeter = 0x1000,
+    PumpSensors = 0x2000,
 }
 
 impl From<u32> for HardwareErrorFlag {
@@ -60,6 +62,8 @@ impl From<u32> for HardwareErrorFlag {
             0x200 => Rtc,
             0x400 => Uart,
             0x800 => Wifi,
+            0x1000 => FlowMeter,
+            0x2000 => PumpSensors,
             _ => panic!("Invalid hardware flag value: {}", value),
         }
     }
@@ -87,6 +91,8 @@ impl Display for HardwareErrorFlag {
             Rtc => "RTC error",
             Uart => "UART error",
             Wifi => "WiFi error",
+            FlowMeter => "Flow meter error",
+            PumpSensors => "Pump sensors error",
         };
         write!(f, "{}", description)
     }
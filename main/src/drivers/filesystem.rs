@@ -33,8 +33,8 @@ use core::ptr::null_mut;
 use core::str::from_utf8;
 
 use crate::drivers::encrypt::{Encrypt, EncryptGeneric};
-use crate::drivers::pico::flash::{FILESYSTEM_FN, FILE_FN, DIR_FN, FS_SEPARATOR_DIR};
-use crate::drivers::pico::hardware::Hardware;
+use crate::drivers::platform::{FS_SEPARATOR_DIR, Hardware};
+use crate::drivers::plt::flash::{FILESYSTEM_FN, FILE_FN, DIR_FN};
 use crate::traits::hardware::HardwareFn;
 use crate::traits::state::Initializable;
 
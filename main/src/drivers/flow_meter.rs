@@ -0,0 +1,73 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use osal_rs::{log_error, log_info};
+use osal_rs::utils::{Error, OsalRsBool, Result};
+
+use crate::drivers::gpio::{Gpio, InterruptType};
+use crate::drivers::platform::GpioPeripheral;
+use crate::traits::flow_meter::FlowMeter as FlowMeterFn;
+use crate::traits::state::Initializable;
+
+const APP_TAG: &str = "FlowMeter";
+
+static PULSES: AtomicU32 = AtomicU32::new(0);
+
+pub struct FlowMeter {
+    gpio_ref: GpioPeripheral,
+}
+
+extern "C" fn flow_meter_isr() {
+    PULSES.fetch_add(1, Ordering::Relaxed);
+}
+
+impl Initializable for FlowMeter {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init flow meter");
+
+        let mut gpio = Gpio::shared();
+
+        if gpio.set_interrupt(&self.gpio_ref, InterruptType::FallingEdge, true, flow_meter_isr) == OsalRsBool::False {
+            log_error!(APP_TAG, "Error setting flow meter interrupt");
+            return Err(Error::NotFound);
+        }
+
+        Ok(())
+    }
+}
+
+impl FlowMeterFn for FlowMeter {
+
+    #[inline]
+    fn get_pulses(&self) -> u32 {
+        PULSES.load(Ordering::Relaxed)
+    }
+}
+
+impl FlowMeter {
+    #[inline]
+    pub fn shared() -> Self {
+        Self {
+            gpio_ref: GpioPeripheral::FlowMeter,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+///! Drivers of a POSIX host: the filesystem is a directory, the network uses the std sockets,
+///! the GPIO and I2C only keep the written values. The apps run unchanged for the tests.
+
+pub(super) mod encrypt;
+pub(super) mod flash;
+pub(super) mod gpio;
+pub(super) mod hardware;
+pub(super) mod i2c;
+pub(super) mod network;
+pub(super) mod rtc;
+pub(super) mod timer;
+pub(super) mod uart;
+pub(super) mod wifi;
+
+pub(super) use encrypt::ENCRYPT_FN;
+pub(super) use network::NETWORK_FN;
+pub(super) use rtc::RTC_FN;
+pub(super) use timer::TIMER_FN;
+pub(super) use wifi::WIFI_FN;
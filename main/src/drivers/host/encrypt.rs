@@ -0,0 +1,82 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::ffi::c_void;
+use core::ops::DerefMut;
+use core::ptr::null_mut;
+
+use alloc::vec::Vec;
+use osal_rs::utils::{bytes_to_hex_into_slice, Bytes, Error, Result};
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::drivers::encrypt::{EncryptFn, SHA256_RESULT_BYTES};
+
+/// SHA-256, HMAC and PBKDF2 of the RustCrypto crates, the file encryption is not available
+pub(in crate::drivers) const ENCRYPT_FN: EncryptFn = EncryptFn {
+    init,
+    aes_encrypt,
+    aes_decrypt,
+    get_sha256,
+    pbkdf2_sha256,
+    hmac_sha256,
+    drop,
+};
+
+fn to_hex(digest: &[u8; SHA256_RESULT_BYTES]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+    let mut ret = Bytes::<{SHA256_RESULT_BYTES * 2}>::new();
+
+    if bytes_to_hex_into_slice(digest, ret.deref_mut()) != SHA256_RESULT_BYTES * 2 {
+        return Err(Error::Unhandled("Failed to convert digest to hex string"));
+    }
+
+    Ok(ret)
+}
+
+fn init() -> Result<*mut c_void> {
+    Ok(null_mut())
+}
+
+fn aes_encrypt(_: *mut c_void, _key: &[u8], _iv: &[u8], _plain: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::Unhandled("AES not available on the host, build without the encryption feature"))
+}
+
+fn aes_decrypt(_: *mut c_void, _key: &[u8], _iv: &[u8], _cipher: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::Unhandled("AES not available on the host, build without the encryption feature"))
+}
+
+fn get_sha256(data: &[u8]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+    to_hex(&Sha256::digest(data).into())
+}
+
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+    let mut key = [0u8; SHA256_RESULT_BYTES];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut key);
+    to_hex(&key)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|_| Error::InvalidType)?;
+    mac.update(data);
+    to_hex(&mac.finalize().into_bytes().into())
+}
+
+fn drop(_: *mut c_void) {}
@@ -0,0 +1,400 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::ffi::{c_int, c_void};
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use osal_rs::utils::{Error, Result};
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::drivers::filesystem::{DirFn, FileFn, FilesystemFn, flags};
+use lfs_errors::*;
+
+pub(crate) const FS_SEPARATOR_DIR: &str = "/";
+pub(crate) const FS_CONFIG_DIR: &str = "/etc";
+pub(crate) const FS_DATA_DIR: &str = "/var";
+pub(crate) const FS_LOG_DIR: &str = "/var/log";
+
+/// Directory holding the files, HHG_FS_ROOT or hhg-fs in the temporary directory
+const FS_ROOT_ENV: &str = "HHG_FS_ROOT";
+const FS_ROOT_NAME: &str = "hhg-fs";
+
+/// Same geometry of the flash partition, only reported by stat_fs
+const BLOCK_SIZE: u32 = 4_096;
+const BLOCK_COUNT: u32 = 256;
+
+const TYPE_REG: u8 = 1;
+const TYPE_DIR: u8 = 2;
+
+/// The littlefs codes, so the callers handle the errors as on the target
+#[allow(dead_code)]
+pub(crate) mod lfs_errors {
+    pub const LFS_ERR_OK: i32 =0;    // No error
+    pub const LFS_ERR_IO: i32 =-5;   // Error during device operation
+    pub const LFS_ERR_CORRUPT: i32 =-84;  // Corrupted
+    pub const LFS_ERR_NOENT: i32 =-2;   // No directory entry
+    pub const LFS_ERR_EXIST: i32 =-17;  // Entry already exists
+    pub const LFS_ERR_NOTDIR: i32 =-20;  // Entry is not a dir
+    pub const LFS_ERR_ISDIR: i32 =-21;  // Entry is a dir
+    pub const LFS_ERR_NOTEMPTY: i32 =-39;  // Dir is not empty
+    pub const LFS_ERR_BADF: i32 =-9;   // Bad file number
+    pub const LFS_ERR_FBIG: i32 =-27;  // File too large
+    pub const LFS_ERR_INVAL: i32 =-22;  // Invalid parameter
+    pub const LFS_ERR_NOSPC: i32 =-28;  // No space left on device
+    pub const LFS_ERR_NOMEM: i32 =-12;  // No more memory available
+    pub const LFS_ERR_NOATTR: i32 =-61;  // No data/attr available
+    pub const LFS_ERR_NAMETOOLONG: i32 =-36;  // File name too long
+}
+
+/// Attributes of the paths, littlefs keeps them in the metadata
+static ATTRS: Mutex<BTreeMap<(String, u8), Vec<u8>>> = Mutex::new(BTreeMap::new());
+
+/// Entries of an open directory, read one at a time like lfs_dir_read
+struct DirHandle {
+    entries: Vec<(u8, u32, String)>,
+    position: usize,
+}
+
+pub(in crate::drivers) const FILE_FN: FileFn = FileFn {
+    
+    open: file_open,
+
+    write: file_write,
+
+    read: file_read,
+
+    rewind: file_rewind,
+
+    seek: file_seek,
+
+    tell: file_tell,
+
+    truncate: file_truncate,
+
+    flush: file_flush,
+
+    size: file_size,
+
+    close: file_close,
+};
+
+pub(in crate::drivers) const DIR_FN: DirFn = DirFn {
+    read: dir_read,
+    seek: dir_seek,
+    tell: dir_tell,
+    rewind: dir_rewind,
+    close: dir_close,
+};
+
+pub(in crate::drivers) const FILESYSTEM_FN: FilesystemFn = FilesystemFn {
+    mount: filesystem_mount,
+
+    umount: filesystem_umount,
+
+    open: filesystem_open,
+
+    remove: filesystem_remove,
+
+    rename: filesystem_rename,
+
+    stat_fs: filesystem_stat_fs,
+
+    stat: filesystem_stat,
+
+    get_attr: filesystem_get_attr,
+
+    set_attr: filesystem_set_attr,
+
+    remove_attr: filesystem_removeattr,
+
+    mkdir: filesystem_mkdir,
+
+    open_dir: filesystem_open_dir,
+
+    err_msg: filesystem_err_msg,
+};
+
+fn root() -> PathBuf {
+    env::var_os(FS_ROOT_ENV).map(PathBuf::from).unwrap_or_else(|| env::temp_dir().join(FS_ROOT_NAME))
+}
+
+/// Host path of an absolute path of the filesystem
+fn host_path(path: &str) -> PathBuf {
+    root().join(path.trim_start_matches(FS_SEPARATOR_DIR))
+}
+
+fn lfs_error(e: io::Error) -> Error<'static> {
+    Error::ReturnWithCode(match e.kind() {
+        ErrorKind::NotFound => LFS_ERR_NOENT,
+        ErrorKind::AlreadyExists => LFS_ERR_EXIST,
+        ErrorKind::InvalidInput => LFS_ERR_INVAL,
+        _ => LFS_ERR_IO,
+    })
+}
+
+/// The handler is the pointer of the boxed file returned by filesystem_open
+fn file<'a>(handler: *mut c_void) -> Result<&'a mut fs::File> {
+    if handler.is_null() {
+        return Err(Error::ReturnWithCode(LFS_ERR_BADF));
+    }
+    Ok(unsafe { &mut *(handler as *mut fs::File) })
+}
+
+fn dir<'a>(handler: *mut c_void) -> Result<&'a mut DirHandle> {
+    if handler.is_null() {
+        return Err(Error::ReturnWithCode(LFS_ERR_BADF));
+    }
+    Ok(unsafe { &mut *(handler as *mut DirHandle) })
+}
+
+fn open(path: &str, open_flags: i32) -> Result<fs::File> {
+    let access = open_flags & flags::RDWR;
+    OpenOptions::new()
+        .read(access != flags::WRONLY)
+        .write(access != flags::RDONLY)
+        .create(open_flags & flags::CREAT != 0 && open_flags & flags::EXCL == 0)
+        .create_new(open_flags & flags::CREAT != 0 && open_flags & flags::EXCL != 0)
+        .truncate(open_flags & flags::TRUNC != 0)
+        .append(open_flags & flags::APPEND != 0)
+        .open(host_path(path))
+        .map_err(lfs_error)
+}
+
+fn file_open(path: &str, flags: i32) -> Result<()> {
+    open(path, flags).map(|_| ())
+}
+
+fn file_write(handler: *mut c_void, buffer: &[u8]) -> Result<isize> {
+    file(handler)?.write_all(buffer).map_err(lfs_error)?;
+    Ok(buffer.len() as isize)
+}
+
+fn file_read(handler: *mut c_void) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    file(handler)?.read_to_end(&mut buffer).map_err(lfs_error)?;
+    Ok(buffer)
+}
+
+fn file_rewind(handler: *mut c_void) -> Result<()> {
+    file(handler)?.rewind().map_err(lfs_error)
+}
+
+fn file_seek(handler: *mut c_void, offset: i32, whence: i32) -> Result<isize> {
+    let from = match whence {
+        0 => SeekFrom::Start(offset.max(0) as u64),
+        1 => SeekFrom::Current(offset as i64),
+        2 => SeekFrom::End(offset as i64),
+        _ => return Err(Error::ReturnWithCode(LFS_ERR_INVAL)),
+    };
+    let position = file(handler)?.seek(from).map_err(lfs_error)?;
+    Ok(position as isize)
+}
+
+fn file_tell(handler: *mut c_void) -> Result<isize> {
+    let position = file(handler)?.stream_position().map_err(lfs_error)?;
+    Ok(position as isize)
+}
+
+fn file_truncate(handler: *mut c_void, size: u32) -> Result<()> {
+    file(handler)?.set_len(size as u64).map_err(lfs_error)
+}
+
+fn file_flush(handler: *mut c_void) -> Result<()> {
+    file(handler)?.flush().map_err(lfs_error)
+}
+
+fn file_size(handler: *mut c_void) -> Result<isize> {
+    let metadata = file(handler)?.metadata().map_err(lfs_error)?;
+    Ok(metadata.len() as isize)
+}
+
+fn file_close(handler: *mut c_void) -> Result<()> {
+    file(handler)?;
+    drop(unsafe { Box::from_raw(handler as *mut fs::File) });
+    Ok(())
+}
+
+/// Copies the name with its terminator, cut to the buffer
+fn copy_name(name: &str, buffer: &mut [u8]) {
+    let len = name.len().min(buffer.len().saturating_sub(1));
+    buffer[..len].copy_from_slice(&name.as_bytes()[..len]);
+    if let Some(terminator) = buffer.get_mut(len) {
+        *terminator = 0;
+    }
+}
+
+/// 1 for an entry, 0 at the end, as lfs_dir_read
+fn dir_read(handler: *mut c_void, type_: &mut u8, size: &mut u32, name: &mut [u8]) -> c_int {
+    let Ok(dir) = dir(handler) else {
+        return LFS_ERR_BADF;
+    };
+    let Some((entry_type, entry_size, entry_name)) = dir.entries.get(dir.position) else {
+        return 0;
+    };
+
+    *type_ = *entry_type;
+    *size = *entry_size;
+    copy_name(entry_name, name);
+    dir.position += 1;
+    1
+}
+
+fn dir_seek(handler: *mut c_void, offset: u32) -> Result<()> {
+    dir(handler)?.position = offset as usize;
+    Ok(())
+}
+
+fn dir_tell(handler: *mut c_void) -> Result<i32> {
+    Ok(dir(handler)?.position as i32)
+}
+
+fn dir_rewind(handler: *mut c_void) -> Result<()> {
+    dir(handler)?.position = 0;
+    Ok(())
+}
+
+fn dir_close(handler: *mut c_void) -> Result<()> {
+    dir(handler)?;
+    drop(unsafe { Box::from_raw(handler as *mut DirHandle) });
+    Ok(())
+}
+
+/// Creates the root directory, a host directory never needs a format
+fn filesystem_mount(_format: bool) -> Result<()> {
+    fs::create_dir_all(root()).map_err(lfs_error)
+}
+
+fn filesystem_umount() -> Result<()> {
+    Ok(())
+}
+
+fn filesystem_open(path: &str, flags: i32) -> Result<*mut c_void> {
+    let file = open(path, flags)?;
+    Ok(Box::into_raw(Box::new(file)) as *mut c_void)
+}
+
+fn filesystem_remove(path: &str) -> Result<()> {
+    let path = host_path(path);
+    if path.is_dir() {
+        fs::remove_dir(path).map_err(|e| match e.kind() {
+            ErrorKind::DirectoryNotEmpty => Error::ReturnWithCode(LFS_ERR_NOTEMPTY),
+            _ => lfs_error(e),
+        })
+    } else {
+        fs::remove_file(path).map_err(lfs_error)
+    }
+}
+
+fn filesystem_rename(old_path: &str, new_path: &str) -> Result<()> {
+    fs::rename(host_path(old_path), host_path(new_path)).map_err(lfs_error)
+}
+
+fn filesystem_stat_fs(block_size: &mut u32, block_count: &mut u32, blocks_used: &mut u32) -> Result<()> {
+    fn used(path: PathBuf) -> u64 {
+        fs::read_dir(path).into_iter().flatten().flatten().map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => used(entry.path()),
+            Ok(metadata) => metadata.len().div_ceil(BLOCK_SIZE as u64),
+            Err(_) => 0,
+        }).sum()
+    }
+
+    *block_size = BLOCK_SIZE;
+    *block_count = BLOCK_COUNT;
+    *blocks_used = (used(root()) as u32).min(BLOCK_COUNT);
+    Ok(())
+}
+
+fn filesystem_stat(path: &str, type_: &mut u8, size: &mut u32, name: &mut [u8]) -> Result<i32> {
+    let metadata = fs::metadata(host_path(path)).map_err(lfs_error)?;
+
+    *type_ = if metadata.is_dir() { TYPE_DIR } else { TYPE_REG };
+    *size = metadata.len() as u32;
+    copy_name(path.rsplit(FS_SEPARATOR_DIR).next().unwrap_or(path), name);
+    Ok(LFS_ERR_OK)
+}
+
+fn filesystem_get_attr(path: &str, type_: u8, buffer: &mut [u8]) -> Result<i32> {
+    let attrs = ATTRS.lock().map_err(|_| Error::ReturnWithCode(LFS_ERR_IO))?;
+    let value = attrs.get(&(String::from(path), type_)).ok_or(Error::ReturnWithCode(LFS_ERR_NOATTR))?;
+
+    let len = value.len().min(buffer.len());
+    buffer[..len].copy_from_slice(&value[..len]);
+    Ok(value.len() as i32)
+}
+
+fn filesystem_set_attr(path: &str, type_: u8, buffer: &[u8]) -> Result<()> {
+    fs::metadata(host_path(path)).map_err(lfs_error)?;
+    ATTRS.lock().map_err(|_| Error::ReturnWithCode(LFS_ERR_IO))?.insert((String::from(path), type_), buffer.to_vec());
+    Ok(())
+}
+
+fn filesystem_removeattr(path: &str, type_: u8) -> Result<()> {
+    ATTRS.lock().map_err(|_| Error::ReturnWithCode(LFS_ERR_IO))?.remove(&(String::from(path), type_));
+    Ok(())
+}
+
+fn filesystem_mkdir(path: &str) -> Result<()> {
+    fs::create_dir(host_path(path)).map_err(lfs_error)
+}
+
+fn filesystem_open_dir(path: &str) -> Result<*mut c_void> {
+    let mut entries = Vec::from([(TYPE_DIR, 0, String::from(".")), (TYPE_DIR, 0, String::from(".."))]);
+    for entry in fs::read_dir(host_path(path)).map_err(lfs_error)? {
+        let entry = entry.map_err(lfs_error)?;
+        let metadata = entry.metadata().map_err(lfs_error)?;
+        entries.push((
+            if metadata.is_dir() { TYPE_DIR } else { TYPE_REG },
+            metadata.len() as u32,
+            entry.file_name().to_string_lossy().into_owned(),
+        ));
+    }
+    entries[2..].sort_by(|a, b| a.2.cmp(&b.2));
+
+    Ok(Box::into_raw(Box::new(DirHandle { entries, position: 0 })) as *mut c_void)
+}
+
+fn filesystem_err_msg(err: i32) -> &'static str {
+    match err {
+        LFS_ERR_OK => "No error",
+        LFS_ERR_IO => "Error during device operation",
+        LFS_ERR_CORRUPT => "Corrupted",
+        LFS_ERR_NOENT => "No directory entry",
+        LFS_ERR_EXIST => "Entry already exists",
+        LFS_ERR_NOTDIR => "Entry is not a dir",
+        LFS_ERR_ISDIR => "Entry is a dir",
+        LFS_ERR_NOTEMPTY => "Dir is not empty",
+        LFS_ERR_BADF => "Bad file number",
+        LFS_ERR_FBIG => "File too large",
+        LFS_ERR_INVAL => "Invalid parameter",
+        LFS_ERR_NOSPC => "No space left on device",
+        LFS_ERR_NOMEM => "No more memory available",
+        LFS_ERR_NOATTR => "No data/attr available",
+        LFS_ERR_NAMETOOLONG => "File name too long",
+        _ => "Unknown error",
+    }
+}
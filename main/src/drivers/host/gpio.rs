@@ -0,0 +1,237 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::str::FromStr;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use alloc::str;
+
+use osal_rs::utils::{AsSyncStr, Error, OsalRsBool, Ptr, Result};
+
+use std::sync::Mutex;
+
+use crate::drivers::gpio::GpioConfigs;
+use crate::drivers::gpio::{GpioFn, GpioConfig, GpioInputType, InterruptCallback, InterruptType::{self, *}, GpioType};
+use GpioPeripheral::*;
+
+pub(crate) const GPIO_CONFIG_SIZE: usize = 17;
+
+const PINS: usize = 32;
+
+/// Raw ADC value of the internal sensor at about 27 °C
+const INTERNAL_TEMP_RAW: u32 = 876;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum GpioPeripheral {
+    NoUsed,
+    EncoderCCW,
+    EncoderCW,
+    EncoderBtn,
+    Btn,
+    LedRed,
+    LedGreen,
+    LedBlue,
+    Cyw43Led,
+    InternalTemp,
+    Relay0,
+    Relay1,
+    Relay2,
+    Relay3,
+    FlowMeter,
+    DosingPump,
+    PressureSwitch,
+    PumpThermal,
+}
+ 
+impl AsSyncStr for GpioPeripheral {
+    fn as_str(&self) -> &str {
+        match self {
+            NoUsed => "NoUsed",
+            EncoderCCW => "EncoderCCw",
+            EncoderCW => "EncoderCW",
+            EncoderBtn => "EncoderBtn",
+            Btn => "Btn",
+            LedRed => "LedRed",
+            LedGreen => "LedGreen",
+            LedBlue => "LedBlue",
+            Cyw43Led => "Cyw43Led",
+            InternalTemp => "InternalTemp",
+            Relay0 => "Relay1",
+            Relay1 => "Relay2",
+            Relay2 => "Relay3",
+            Relay3 => "Relay4",
+            FlowMeter => "FlowMeter",
+            DosingPump => "DosingPump",
+            PressureSwitch => "PressureSwitch",
+            PumpThermal => "PumpThermal",
+        }
+    }
+}
+
+impl FromStr for GpioPeripheral {
+    type Err = Error<'static>;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        match s {
+            "NoUsed" => Ok(NoUsed),
+            "EncoderCCw" => Ok(EncoderCCW),
+            "EncoderCW" => Ok(EncoderCW),
+            "EncoderBtn" => Ok(EncoderBtn),
+            "Btn" => Ok(Btn),
+            "LedRed" => Ok(LedRed),
+            "LedGreen" => Ok(LedGreen),
+            "LedBlue" => Ok(LedBlue),
+            "Cyw43Led" => Ok(Cyw43Led),
+            "InternalTemp" => Ok(InternalTemp),
+            "Relay1" => Ok(Relay0),
+            "Relay2" => Ok(Relay1),
+            "Relay3" => Ok(Relay2),
+            "Relay4" => Ok(Relay3),
+            "FlowMeter" => Ok(FlowMeter),
+            "DosingPump" => Ok(DosingPump),
+            "PressureSwitch" => Ok(PressureSwitch),
+            "PumpThermal" => Ok(PumpThermal),
+            _ => Err(Error::NotFound)
+        }
+    }
+}
+
+ 
+pub(crate) static mut GPIO_CONFIGS: GpioConfigs<'static, GPIO_CONFIG_SIZE> = GpioConfigs::new_with_array([
+        Some(GpioConfig::new(&EncoderCCW, GpioType::Input(None, 20, GpioInputType::PullDown, 0))),
+        Some(GpioConfig::new(&EncoderCW, GpioType::Input(None, 21, GpioInputType::PullDown, 0))),
+        Some(GpioConfig::new(&EncoderBtn, GpioType::Input(None, 19, GpioInputType::PullUp, 0))),
+        Some(GpioConfig::new(&Btn, GpioType::Input(None, 18, GpioInputType::PullUp, 0))),
+        Some(GpioConfig::new(&LedRed, GpioType::OutputPWM(None, 13, 0))),
+        Some(GpioConfig::new(&LedGreen, GpioType::OutputPWM(None, 14, 0))),
+        Some(GpioConfig::new(&LedBlue, GpioType::OutputPWM(None, 15, 0))),
+        Some(GpioConfig::new(&Cyw43Led, GpioType::Output(None, 0, 0))),
+        Some(GpioConfig::new(&InternalTemp, GpioType::InputAnalog(None, 0, 4, 0))),
+        Some(GpioConfig::new(&Relay0, GpioType::Output(None, 6, 0))),
+        Some(GpioConfig::new(&Relay1, GpioType::Output(None, 7, 0))),
+        Some(GpioConfig::new(&Relay2, GpioType::Output(None, 8, 0))),
+        Some(GpioConfig::new(&Relay3, GpioType::Output(None, 9, 0))),
+        Some(GpioConfig::new(&FlowMeter, GpioType::Input(None, 22, GpioInputType::PullUp, 0))),
+        Some(GpioConfig::new(&DosingPump, GpioType::Output(None, 10, 0))),
+        Some(GpioConfig::new(&PressureSwitch, GpioType::Input(None, 11, GpioInputType::PullUp, 0))),
+        Some(GpioConfig::new(&PumpThermal, GpioType::Input(None, 12, GpioInputType::PullUp, 0))),
+]);
+
+/// Level of each pin, the inputs are driven by the tests with set_input()
+static LEVELS: [AtomicU32; PINS] = [const { AtomicU32::new(0) }; PINS];
+
+static ENABLED: [AtomicBool; PINS] = [const { AtomicBool::new(false) }; PINS];
+
+static INTERRUPTS: Mutex<[Option<(InterruptType, InterruptCallback)>; PINS]> = Mutex::new([const { None }; PINS]);
+
+pub(in crate::drivers) const GPIO_FN : GpioFn = GpioFn {
+    init: None,
+    input: Some(input),
+    input_analog: None,
+    output: Some(output),
+    output_pwm: Some(output),
+    peripheral: None,
+    deinit: None,
+    read: Some(read),
+    write: Some(write),
+    set_pwm: Some(write),
+    set_interrupt: Some(set_interrupt),
+    enable_interrupt: Some(enable_interrupt)
+};
+
+/// Drives an input pin, the interrupt of the pin runs on the matching edge or level
+#[allow(dead_code)]
+pub(crate) fn set_input(pin: u32, level: bool) {
+    let Some(current) = LEVELS.get(pin as usize) else {
+        return;
+    };
+    let previous = current.swap(level as u32, Ordering::SeqCst) != 0;
+
+    let interrupt = INTERRUPTS.lock().ok().and_then(|interrupts| interrupts[pin as usize].clone());
+    let Some((irq_type, callback)) = interrupt else {
+        return;
+    };
+    if !ENABLED[pin as usize].load(Ordering::SeqCst) {
+        return;
+    }
+
+    let fire = match irq_type {
+        RisingEdge => !previous && level,
+        FallingEdge => previous && !level,
+        BothEdge => previous != level,
+        HighLevel => level,
+        LowLevel => !level,
+    };
+    if fire {
+        callback();
+    }
+}
+
+fn input(_: &GpioConfig, _: Option<Ptr>, pin: u32, input_type: GpioInputType, _default_value: u32) -> Result<()> {
+    let level = LEVELS.get(pin as usize).ok_or(Error::InvalidType)?;
+    level.store((input_type == GpioInputType::PullUp) as u32, Ordering::SeqCst);
+    Ok(())
+}
+
+fn output(_: &GpioConfig, _: Option<Ptr>, pin: u32, default_value: u32) -> Result<()> {
+    let level = LEVELS.get(pin as usize).ok_or(Error::InvalidType)?;
+    level.store(default_value, Ordering::SeqCst);
+    Ok(())
+}
+
+fn read(config: &GpioConfig, _: Option<Ptr>, input: u32) -> Result<u32> {
+    if config.get_name() == InternalTemp.as_str() {
+        return Ok(INTERNAL_TEMP_RAW);
+    }
+    let level = LEVELS.get(input as usize).ok_or(Error::InvalidType)?;
+    Ok(level.load(Ordering::SeqCst))
+}
+
+fn write(_: &GpioConfig, _: Option<Ptr>, pin: u32, state: u32) -> OsalRsBool {
+    match LEVELS.get(pin as usize) {
+        Some(level) => {
+            level.store(state, Ordering::SeqCst);
+            OsalRsBool::True
+        }
+        None => OsalRsBool::False,
+    }
+}
+
+fn set_interrupt(_: &GpioConfig, _: Option<Ptr>, pin: u32, irq_type: InterruptType, callback: InterruptCallback, enable: bool) -> OsalRsBool { 
+    let Ok(mut interrupts) = INTERRUPTS.lock() else {
+        return OsalRsBool::False;
+    };
+    let Some(interrupt) = interrupts.get_mut(pin as usize) else {
+        return OsalRsBool::False;
+    };
+    *interrupt = Some((irq_type, callback));
+    ENABLED[pin as usize].store(enable, Ordering::SeqCst);
+    OsalRsBool::True
+}
+
+fn enable_interrupt(_: &GpioConfig, _: Option<Ptr>, pin: u32, enable: bool) -> OsalRsBool {
+    match ENABLED.get(pin as usize) {
+        Some(enabled) => {
+            enabled.store(enable, Ordering::SeqCst);
+            OsalRsBool::True
+        }
+        None => OsalRsBool::False,
+    }
+}
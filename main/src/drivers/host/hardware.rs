@@ -0,0 +1,309 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::hash::{BuildHasher, Hasher};
+
+use alloc::sync::Arc;
+use osal_rs::log_info;
+use osal_rs::os::types::UBaseType;
+use osal_rs::os::{Mutex, MutexFn, System, SystemFn, ToPriority};
+use osal_rs::utils::{Error, OsalRsBool, Result};
+
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::io::Read;
+use std::process;
+
+use crate::drivers::button::Button;
+use crate::drivers::encoder::Encoder;
+use crate::drivers::error::{HardwareErrorSignal, HardwareErrorFlag};
+use crate::drivers::filesystem::Filesystem;
+use crate::drivers::flow_meter::FlowMeter;
+use crate::drivers::i2c::I2C;
+use crate::drivers::pump_sensors::PumpSensors;
+use crate::drivers::relays::Relays;
+use crate::drivers::rtc::RTC;
+use crate::drivers::uart::Uart;
+use crate::drivers::gpio::Gpio;
+use crate::drivers::platform::{GpioPeripheral, I2C_BAUDRATE, I2C0_INSTANCE, I2C1_INSTANCE, LCDDisplay};
+use crate::drivers::plt::flash::{FS_CONFIG_DIR, FS_DATA_DIR, FS_LOG_DIR};
+use crate::drivers::plt::flash::lfs_errors::LFS_ERR_EXIST;
+use crate::drivers::wifi::Wifi;
+
+use crate::set_hardware_error;
+use crate::traits::relays::Relays as RelaysFn;
+use crate::traits::flow_meter::FlowMeter as FlowMeterFn;
+use crate::traits::pump_sensors::PumpSensors as PumpSensorsFn;
+use crate::traits::button::{OnClickable, SetClickable as ButtonOnClickable};
+use crate::traits::encoder::{OnRotatableAndClickable as EncoderOnRotatableAndClickable, SetRotatableAndClickable};
+use crate::traits::hardware::HardwareFn;
+use crate::traits::rtc::RTC as RTCFn;
+use crate::traits::rx_tx::{OnReceive, SetOnReceive, SetTransmit};
+use crate::traits::state::Initializable;
+use crate::traits::wifi::{OnWifiChangeStatus, SetOnWifiChangeStatus};
+
+
+const APP_TAG: &str = "Hardware";
+
+/// Called by `reset` before the reboot, lets the apps save what they keep in RAM
+static mut ON_RESET: Option<fn()> = None;
+
+/// Identifier of the host board, the key of the files is derived from it
+const UNIQUE_ID: [u8; 8] = *b"hhg-host";
+
+const RANDOM_SOURCE: &str = "/dev/urandom";
+
+#[allow(dead_code)]
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ThreadPriority {
+    None = 0,
+    Idle = 1,
+    Low = 4,
+    BelowNormal = 8,
+    Normal = 12,
+    AboveNormal = 16,
+    BelowHigh = 19,
+    High = 23,
+    AboveHigh = 27,
+    Realtime = 31,
+}
+
+impl ToPriority for ThreadPriority {
+    #[inline]
+    fn to_priority(&self) -> UBaseType {
+        *self as UBaseType
+    }
+}
+
+#[allow(unused)]
+impl ThreadPriority {
+    pub fn from_priority(priority: UBaseType) -> Self {
+        use ThreadPriority::*;
+        match priority {
+            1 => Idle,
+            2..=4 => Low,
+            5..=8 => BelowNormal,
+            9..=12 => Normal,
+            13..=16 => AboveNormal,
+            17..=19 => BelowHigh,
+            20..=23 => High,
+            24..=27 => AboveHigh,
+            28..=31 => Realtime,
+            _ => None,
+        }
+    }
+}
+
+/// Same drivers of the board over the host backends, the encoder and the button
+/// need the hardware timers and are left uninitialized
+pub(crate) struct Hardware {
+    uart: Uart,
+    encoder: Encoder,
+    button: Button,
+    relays: Relays,
+    flow_meter: FlowMeter,
+    pump_sensors: PumpSensors,
+    display: LCDDisplay,
+    i2c0: I2C<{I2C0_INSTANCE}, {I2C_BAUDRATE}>,
+    i2c1: I2C<{I2C1_INSTANCE}, {I2C_BAUDRATE}>,
+    wifi: Wifi,
+    rtc: Arc<Mutex<RTC>>,
+}
+
+impl Initializable for Hardware {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init hardware");
+
+        HardwareErrorSignal::init()?;
+
+        set_hardware_error!(self.wifi.init(), HardwareErrorFlag::Wifi);
+
+        set_hardware_error!(Gpio::shared().init(), HardwareErrorFlag::Gpio);
+
+        set_hardware_error!(self.uart.init(), HardwareErrorFlag::Uart);
+        
+        set_hardware_error!(self.relays.init(), HardwareErrorFlag::Relays);
+
+        set_hardware_error!(self.flow_meter.init(), HardwareErrorFlag::FlowMeter);
+
+        set_hardware_error!(self.pump_sensors.init(), HardwareErrorFlag::PumpSensors);
+
+        set_hardware_error!(self.i2c0.init(), HardwareErrorFlag::I2C);
+        
+        set_hardware_error!(self.i2c1.init(), HardwareErrorFlag::I2C);
+        
+        let mut rtc = self.rtc.lock()?;
+        rtc.set_i2c(self.i2c0.clone());
+        set_hardware_error!(rtc.init(), HardwareErrorFlag::Rtc);
+
+        self.display.set_i2c(self.i2c1.clone());
+        set_hardware_error!(self.display.init(), HardwareErrorFlag::Display);
+        
+        set_hardware_error!(self.init_fs(), HardwareErrorFlag::Filesystem);
+
+        log_info!(APP_TAG, "Hardware initialized successfully heap_free:{}", System::get_free_heap_size());
+        Ok(())
+    } 
+}
+
+
+impl RelaysFn for Hardware {
+
+    #[inline]
+    fn set_relay_state(&self, relay_index: GpioPeripheral, state: bool) -> OsalRsBool {
+        self.relays.set_relay_state(relay_index, state)
+    }
+}
+
+impl FlowMeterFn for Hardware {
+
+    #[inline]
+    fn get_pulses(&self) -> u32 {
+        self.flow_meter.get_pulses()
+    }
+}
+
+impl PumpSensorsFn for Hardware {
+
+    #[inline]
+    fn is_pressure_ok(&self) -> bool {
+        self.pump_sensors.is_pressure_ok()
+    }
+
+    #[inline]
+    fn is_over_temperature(&self) -> bool {
+        self.pump_sensors.is_over_temperature()
+    }
+}
+
+impl SetOnWifiChangeStatus<'static> for Hardware {
+
+    #[inline]
+    fn set_on_wifi_change_status(&mut self, on_wifi_change_status: &'static dyn OnWifiChangeStatus) {
+        self.wifi.set_on_wifi_change_status(on_wifi_change_status);
+    }
+}
+
+impl SetOnReceive<'static> for Hardware {
+
+    #[inline]
+    fn set_on_receive(&mut self, on_receive: &'static dyn OnReceive) {
+        self.uart.add_listener(on_receive);
+    }
+}
+
+impl SetTransmit for Hardware {
+
+    #[inline]
+    fn transmit(&self, data: &[u8]) -> usize {
+        self.uart.transmit(data)
+    }
+}
+
+impl HardwareFn<'static> for Hardware {
+
+    #[inline]
+    fn set_button_handler(&mut self, clickable: &'static dyn OnClickable) {
+        self.button.set_on_click(clickable);
+    }
+
+    #[inline]
+    fn set_encoder_handler(&mut self, rotable_and_clickable: &'static dyn EncoderOnRotatableAndClickable) {
+        self.encoder.set_on_rotate_and_click(rotable_and_clickable);
+    }
+    
+    fn get_temperature(&self) -> f32 {
+        let raw_value = Gpio::shared().read(&GpioPeripheral::InternalTemp).unwrap_or(0);
+        Self::temperature_conversion(raw_value)
+    }
+
+    #[inline]
+    fn get_unique_id() -> [u8; 8] {
+        UNIQUE_ID
+    }
+
+    /// The entropy of the kernel, the random keys of the std hasher if it cannot be read
+    fn get_random() -> u64 {
+        let mut bytes = [0u8; 8];
+        match fs::File::open(RANDOM_SOURCE).and_then(|mut source| source.read_exact(&mut bytes)) {
+            Ok(_) => u64::from_ne_bytes(bytes),
+            Err(_) => RandomState::new().build_hasher().finish(),
+        }
+    }
+
+    #[inline]
+    fn get_rtc(&self) -> Arc<Mutex<dyn RTCFn + 'static>> {
+        self.rtc.clone()
+    }
+
+}
+
+impl Hardware {
+    pub fn new() -> Self {        
+
+        let i2c1 =  I2C::new_with_address(LCDDisplay::I2C_ADDRESS);
+
+        Self { 
+            uart: Uart::shared(),
+            encoder: Encoder::shared(),
+            button: Button::shared(),
+            relays: Relays::shared(),
+            flow_meter: FlowMeter::shared(),
+            pump_sensors: PumpSensors::shared(),
+            display: LCDDisplay::new(),
+            i2c0: I2C::new(),
+            i2c1: i2c1,
+            wifi: Wifi::shared(),
+            rtc: Mutex::new_arc(RTC::shared()),
+            
+        }
+    }
+
+    #[inline]
+    pub fn get_lcd_display(&mut self) -> LCDDisplay {
+        self.display.clone()
+    }
+
+    pub fn init_fs(&self) -> Result<()> {
+        Filesystem::mount(true)?;
+
+        for dir in [FS_CONFIG_DIR, FS_DATA_DIR, FS_LOG_DIR] {
+            match Filesystem::mkdir(dir) {
+                Ok(_) => log_info!(APP_TAG, "Created {dir} directory"),
+                Err(Error::ReturnWithCode(LFS_ERR_EXIST)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_on_reset(on_reset: fn()) {
+        unsafe { ON_RESET = Some(on_reset); }
+    }
+
+    pub fn reset() -> ! {
+        if let Some(on_reset) = unsafe { *&raw const ON_RESET } {
+            on_reset();
+        }
+        process::exit(0)
+    }
+}
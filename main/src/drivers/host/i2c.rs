@@ -0,0 +1,65 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::ffi::c_void;
+use core::ptr::null_mut;
+
+use alloc::vec::Vec;
+use osal_rs::utils::Result;
+
+use crate::drivers::i2c::I2CFn;
+
+pub(crate) const I2C0_INSTANCE: u8 = 0;
+pub(crate) const I2C1_INSTANCE: u8 = 1;
+pub(crate) const I2C_BAUDRATE: u32 = 100_000;
+
+/// A bus without devices: the writes are dropped and the reads return zeros
+pub(in crate::drivers) static I2C_FN: I2CFn = I2CFn {
+    init,
+    write,
+    write_dma: write,
+    read,
+    write_and_read,
+    scan_i2c,
+    drop
+};
+
+fn scan_i2c(_: *mut c_void) -> Result<Vec<u8>> {
+    Ok(Vec::new())
+}
+
+fn init(_i2c_instance: u8, _baudrate: u32) -> Result<*mut c_void> {
+    Ok(null_mut())
+}
+
+fn write(_: *mut c_void, _address: u8, _data: &[u8]) -> Result<()> {
+    Ok(())
+}
+
+fn read(_: *mut c_void, _address: u8, buffer: &mut [u8]) -> Result<()> {
+    buffer.fill(0);
+    Ok(())
+}
+
+fn write_and_read(instance: *mut c_void, address: u8, data: &[u8], buffer: &mut [u8]) -> (Result<()>, Result<()>) {
+    (write(instance, address, data), read(instance, address, buffer))
+}
+
+fn drop(_: *mut c_void) {}
@@ -0,0 +1,283 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::time::Duration;
+
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use osal_rs::utils::{Bytes, Error, Result};
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+
+use crate::drivers::network::{IP4Addr, NetworkFn};
+use crate::traits::network::{IPV6_ADDR_LEN, IpAddress};
+
+/// Same semantic of the lwIP backend over the sockets of the host: non blocking reads,
+/// one outgoing connection and one client at a time for each listening port
+pub(in crate::drivers) static NETWORK_FN: NetworkFn = NetworkFn {
+    dhcp_get_ip_address,
+    dhcp_get_binary_ip_address,
+    dhcp_supplied_address,
+    dns_resolve_addrress,
+    ntp_request,
+    is_link_up,
+    tcp_connect,
+    tcp_write,
+    tcp_read,
+    tcp_close,
+    tcp_is_connected,
+    tcp_listen,
+    tcp_accept,
+    tcp_server_write,
+    tcp_server_read,
+    tcp_server_close,
+    tcp_server_is_connected,
+    tcp_unlisten,
+};
+
+const LOCAL_ADDRESS: Ipv4Addr = Ipv4Addr::LOCALHOST;
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Wait of a read on an empty connection, so the read does not block the caller
+const READ_POLL: Duration = Duration::from_millis(1);
+const NTP_DELTA: u64 = 2_208_988_800; // Seconds between 1900 and 1970
+
+static mut IP_ADDRES_FOUND: Option<IP4Addr> = None;
+
+/// Outgoing connection opened by tcp_connect()
+static TCP_CLIENT: Mutex<Option<TcpStream>> = Mutex::new(None);
+
+/// Listener opened by tcp_listen() and the client it accepted
+struct TcpServer {
+    port: u16,
+    listener: TcpListener,
+    client: Option<TcpStream>,
+}
+
+const TCP_SERVERS_SIZE: usize = 2;
+
+static TCP_SERVERS: Mutex<Vec<TcpServer>> = Mutex::new(Vec::new());
+
+/// The first octet in the most significant byte, as printed by IP4Addr
+fn to_ip4_addr(address: Ipv4Addr) -> IP4Addr {
+    IP4Addr { addr: u32::from_be_bytes(address.octets()) }
+}
+
+fn to_socket_addr(ipaddr: &dyn IpAddress, port: u16) -> SocketAddr {
+    let ipaddr = unsafe { &*(ipaddr as *const dyn IpAddress as *const IP4Addr) };
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::from(ipaddr.addr.to_be_bytes()), port))
+}
+
+fn dhcp_get_ip_address() -> Bytes<IPV6_ADDR_LEN> {
+    Bytes::from_str(&LOCAL_ADDRESS.to_string())
+}
+
+#[inline]
+fn dhcp_get_binary_ip_address() -> u32 {
+    to_ip4_addr(LOCAL_ADDRESS).addr
+}
+
+fn dhcp_supplied_address() -> bool {
+    true
+}
+
+fn dns_resolve_addrress<'a>(hostname: &Bytes<64>) -> Result<&'a dyn IpAddress> {
+    let address = (hostname.as_str(), 0)
+        .to_socket_addrs()
+        .map_err(|_| Error::Empty)?
+        .find_map(|address| match address {
+            SocketAddr::V4(address) => Some(*address.ip()),
+            SocketAddr::V6(_) => None,
+        })
+        .ok_or(Error::Empty)?;
+
+    let found = &raw mut IP_ADDRES_FOUND;
+    let ipaddr = unsafe { (*found).insert(to_ip4_addr(address)) };
+    Ok(ipaddr)
+}
+
+fn ntp_request(ipaddr_dest: &'static dyn IpAddress, port: u16, msg_len: u16) -> Result<i64> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|_| Error::NullPtr)?;
+    socket.set_read_timeout(Some(TIMEOUT)).map_err(|_| Error::NullPtr)?;
+
+    let mut req = vec![0u8; (msg_len as usize).max(48)];
+    req[0] = 0x1b;
+    socket.send_to(&req, to_socket_addr(ipaddr_dest, port)).map_err(|_| Error::Unhandled("NTP send failed"))?;
+
+    let len = socket.recv(&mut req).map_err(|_| Error::Empty)?;
+    let mode = req[0] & 0x7;
+    let stratum = req[1];
+    if len < 48 || mode != 0x4 || stratum == 0 {
+        return Err(Error::Empty);
+    }
+
+    let seconds_since_1900 = u32::from_be_bytes([req[40], req[41], req[42], req[43]]) as u64;
+    Ok(seconds_since_1900.saturating_sub(NTP_DELTA) as i64)
+}
+
+fn is_link_up() -> bool {
+    true
+}
+
+/// Prepares a new stream: short reads, bounded writes and no delay for the small packets
+fn setup(stream: &TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(READ_POLL)).map_err(|_| Error::Unhandled("TCP setup failed"))?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(|_| Error::Unhandled("TCP setup failed"))?;
+    stream.set_nodelay(true).map_err(|_| Error::Unhandled("TCP setup failed"))
+}
+
+fn write(connection: &mut Option<TcpStream>, data: &[u8]) -> Result<usize> {
+    let stream = connection.as_mut().ok_or(Error::Unhandled("TCP not connected"))?;
+
+    if stream.write_all(data).is_err() {
+        *connection = None;
+        return Err(Error::Unhandled("TCP not connected"));
+    }
+
+    Ok(data.len())
+}
+
+/// 0 when nothing was received, an error once the peer closed the connection
+fn read(connection: &mut Option<TcpStream>, buffer: &mut [u8]) -> Result<usize> {
+    let stream = connection.as_mut().ok_or(Error::Unhandled("TCP not connected"))?;
+
+    match stream.read(buffer) {
+        Ok(0) if !buffer.is_empty() => {
+            *connection = None;
+            Err(Error::Unhandled("TCP not connected"))
+        }
+        Ok(len) => Ok(len),
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => Ok(0),
+        Err(_) => {
+            *connection = None;
+            Err(Error::Unhandled("TCP not connected"))
+        }
+    }
+}
+
+/// Peeks the connection, as lwIP notices a closed peer without a read
+fn is_connected(connection: &mut Option<TcpStream>) -> bool {
+    let Some(stream) = connection.as_ref() else {
+        return false;
+    };
+
+    let connected = match stream.peek(&mut [0u8; 1]) {
+        Ok(len) => len > 0,
+        Err(e) => matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted),
+    };
+    if !connected {
+        *connection = None;
+    }
+    connected
+}
+
+fn tcp_connect(ipaddr_dest: &dyn IpAddress, port: u16) -> Result<()> {
+    let mut client = TCP_CLIENT.lock().map_err(|_| Error::NullPtr)?;
+    *client = None;
+
+    let stream = TcpStream::connect_timeout(&to_socket_addr(ipaddr_dest, port), TIMEOUT)
+        .map_err(|_| Error::Unhandled("TCP connect failed"))?;
+    setup(&stream)?;
+    *client = Some(stream);
+    Ok(())
+}
+
+fn tcp_write(data: &[u8]) -> Result<usize> {
+    write(&mut *TCP_CLIENT.lock().map_err(|_| Error::NullPtr)?, data)
+}
+
+fn tcp_read(buffer: &mut [u8]) -> Result<usize> {
+    read(&mut *TCP_CLIENT.lock().map_err(|_| Error::NullPtr)?, buffer)
+}
+
+fn tcp_close() {
+    if let Ok(mut client) = TCP_CLIENT.lock() {
+        *client = None;
+    }
+}
+
+fn tcp_is_connected() -> bool {
+    TCP_CLIENT.lock().is_ok_and(|mut client| is_connected(&mut client))
+}
+
+/// Runs f on the server of the port, None if the port is not listening
+fn with_server<T>(port: u16, f: impl FnOnce(&mut TcpServer) -> T) -> Option<T> {
+    let mut servers = TCP_SERVERS.lock().ok()?;
+    servers.iter_mut().find(|server| port != 0 && server.port == port).map(f)
+}
+
+fn tcp_listen(port: u16) -> Result<()> {
+
+    if port == 0 {
+        return Err(Error::InvalidType);
+    }
+
+    tcp_unlisten(port);
+
+    let mut servers = TCP_SERVERS.lock().map_err(|_| Error::NullPtr)?;
+    if servers.len() >= TCP_SERVERS_SIZE {
+        return Err(Error::Unhandled("No TCP listener available"));
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).map_err(|_| Error::Unhandled("TCP listen failed"))?;
+    listener.set_nonblocking(true).map_err(|_| Error::Unhandled("TCP listen failed"))?;
+    servers.push(TcpServer { port, listener, client: None });
+    Ok(())
+}
+
+/// Returns true once for every client accepted since the previous call, the clients
+/// connecting while another one is served are refused
+fn tcp_accept(port: u16) -> bool {
+    with_server(port, |server| {
+        let mut accepted = false;
+        while let Ok((stream, _)) = server.listener.accept() {
+            if server.client.is_some() || accepted || setup(&stream).is_err() {
+                continue; // dropped, the peer sees the connection closed
+            }
+            server.client = Some(stream);
+            accepted = true;
+        }
+        accepted
+    }).unwrap_or(false)
+}
+
+fn tcp_server_write(port: u16, data: &[u8]) -> Result<usize> {
+    with_server(port, |server| write(&mut server.client, data)).unwrap_or(Err(Error::Unhandled("TCP not listening")))
+}
+
+fn tcp_server_read(port: u16, buffer: &mut [u8]) -> Result<usize> {
+    with_server(port, |server| read(&mut server.client, buffer)).unwrap_or(Err(Error::Unhandled("TCP not listening")))
+}
+
+fn tcp_server_close(port: u16) {
+    with_server(port, |server| server.client = None);
+}
+
+fn tcp_server_is_connected(port: u16) -> bool {
+    with_server(port, |server| is_connected(&mut server.client)).unwrap_or(false)
+}
+
+fn tcp_unlisten(port: u16) {
+    if let Ok(mut servers) = TCP_SERVERS.lock() {
+        servers.retain(|server| port == 0 || server.port != port);
+    }
+}
@@ -0,0 +1,67 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::sync::atomic::{AtomicI64, Ordering};
+
+use osal_rs::utils::Result;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::drivers::i2c::I2C;
+use crate::drivers::rtc::RTCFn;
+use crate::drivers::platform::{I2C_BAUDRATE, I2C0_INSTANCE};
+
+/// Seconds added to the clock of the host, the system timer and the RTC share it
+static OFFSET: AtomicI64 = AtomicI64::new(0);
+
+pub(crate) const RTC_FN: RTCFn = RTCFn {
+    init,
+    set_timestamp,
+    get_timestamp,
+    set_rtc_timestamp,
+    get_rtc_timestamp
+};
+
+fn host_timestamp() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+fn init(_i2c: &mut I2C<{I2C0_INSTANCE}, {I2C_BAUDRATE}>) -> Result<()> {
+    Ok(())
+}
+
+#[inline]
+fn set_timestamp(_i2c: &I2C<{I2C0_INSTANCE}, {I2C_BAUDRATE}>, timestamp: u64) {
+    OFFSET.store(timestamp as i64 - host_timestamp(), Ordering::SeqCst);
+}
+
+#[inline]
+fn get_timestamp(_i2c: &I2C<{I2C0_INSTANCE}, {I2C_BAUDRATE}>) -> u64 {
+    (host_timestamp() + OFFSET.load(Ordering::SeqCst)) as u64
+}
+
+fn set_rtc_timestamp(i2c: &I2C<{I2C0_INSTANCE}, {I2C_BAUDRATE}>, timestamp: i64) -> Result<()> {
+    set_timestamp(i2c, timestamp as u64);
+    Ok(())
+}
+
+fn get_rtc_timestamp(i2c: &I2C<{I2C0_INSTANCE}, {I2C_BAUDRATE}>) -> Result<i64> {
+    Ok(get_timestamp(i2c) as i64)
+}
@@ -0,0 +1,38 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::any::Any;
+use core::ffi::c_void;
+
+use osal_rs::utils::{Error, Result};
+
+use crate::drivers::timer::{Timer, TimerFn};
+
+/// No hardware timers on the host, the encoder and the button are not available
+pub(in crate::drivers) const TIMER_FN: TimerFn = TimerFn {
+    add_repeating_ms,
+    cancel
+};
+
+fn add_repeating_ms (_delay_ms: i32, _user_data: &dyn Any, _callback: extern "C" fn(*mut c_void)) -> Result<Timer> {
+    Err(Error::Unhandled("No hardware timer on the host"))
+}
+
+fn cancel (_timer: Timer) {}
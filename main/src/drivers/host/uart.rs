@@ -0,0 +1,61 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::ptr::null_mut;
+
+use osal_rs::utils::Result;
+
+use std::io::{self, Write};
+
+use crate::drivers::uart::{UartConfig, UartDataBits, UartFlowControl, UartFn, UartParity, UartStopBits};
+
+/// The console is the standard output, the tests hand the received lines to the listener
+pub(in crate::drivers) static mut UART_FN: UartFn = UartFn {
+    init,
+    transmit,
+    add_listener: None,
+    deinit,
+};
+
+pub(in crate::drivers) static mut UART_CONFIG: UartConfig = UartConfig {
+    name : &"Uart",
+    base: null_mut(),
+    baudrate: 115200,
+    data_bits: UartDataBits::Eight,
+    stop_bits: UartStopBits::One,
+    parity: UartParity::None,
+    flow_control: UartFlowControl::None,
+};
+
+fn init(_: &UartConfig) -> Result<()> {
+    Ok(())
+}
+
+fn transmit(data: &[u8]) -> usize {
+    let mut stdout = io::stdout().lock();
+    match stdout.write_all(data).and_then(|_| stdout.flush()) {
+        Ok(_) => data.len(),
+        Err(_) => 0,
+    }
+}
+
+fn deinit(_: &UartConfig) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,62 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use core::ffi::c_void;
+use core::ptr::null_mut;
+
+use osal_rs::utils::Result;
+
+use crate::drivers::wifi::{Auth, LinkStatus::{self, *}, WifiFn};
+
+/// The network of the host is always connected, the credentials are ignored
+pub(crate) const WIFI_FN: WifiFn = WifiFn {
+    init,
+    enable_sta_mode,
+    disable_sta_mode,
+    connect,
+    link_status,
+    get_rssi,
+    drop,
+};
+
+/// Signal reported to the apps, a good link
+const RSSI: i32 = -50;
+
+fn init(_country_code: u32) -> Result<*mut c_void> {
+    Ok(null_mut())
+}
+
+fn enable_sta_mode(_: *mut c_void) {}
+
+fn disable_sta_mode(_: *mut c_void) {}
+
+fn connect(_: *mut c_void, _ssid: &str, _password: &str, _auth: Auth) -> Result<i32> {
+    Ok(0)
+}
+
+fn link_status(_: *mut c_void) -> LinkStatus {
+    Up
+}
+
+fn get_rssi(_: *mut c_void) -> Result<i32> {
+    Ok(RSSI)
+}
+
+fn drop(_: *mut c_void) {}
@@ -23,7 +23,7 @@ use core::fmt::Display;
 
 use osal_rs::utils::{Bytes, Error, Result};
 
-use crate::drivers::plt::lwip::NETWORK_FN;
+use crate::drivers::plt::NETWORK_FN;
 use crate::traits::network::{IPV6_ADDR_LEN, IpAddress};
 
 
@@ -140,6 +140,18 @@ pub(in crate::drivers) struct NetworkFn<'a> {
     pub(in crate::drivers) dns_resolve_addrress: fn(hostname: &Bytes<64>) -> Result<&'a dyn IpAddress>,
     pub(in crate::drivers) ntp_request: fn(ipaddr_dest: &'a dyn IpAddress, port: u16, msg_len: u16) -> Result<i64>,
     pub(in crate::drivers) is_link_up: fn() -> bool,
+    pub(in crate::drivers) tcp_connect: fn(ipaddr_dest: &'a dyn IpAddress, port: u16) -> Result<()>,
+    pub(in crate::drivers) tcp_write: fn(data: &[u8]) -> Result<usize>,
+    pub(in crate::drivers) tcp_read: fn(buffer: &mut [u8]) -> Result<usize>,
+    pub(in crate::drivers) tcp_close: fn(),
+    pub(in crate::drivers) tcp_is_connected: fn() -> bool,
+    pub(in crate::drivers) tcp_listen: fn(port: u16) -> Result<()>,
+    pub(in crate::drivers) tcp_accept: fn(port: u16) -> bool,
+    pub(in crate::drivers) tcp_server_write: fn(port: u16, data: &[u8]) -> Result<usize>,
+    pub(in crate::drivers) tcp_server_read: fn(port: u16, buffer: &mut [u8]) -> Result<usize>,
+    pub(in crate::drivers) tcp_server_close: fn(port: u16),
+    pub(in crate::drivers) tcp_server_is_connected: fn(port: u16) -> bool,
+    pub(in crate::drivers) tcp_unlisten: fn(port: u16),
 }
 
 #[allow(dead_code)]
@@ -185,6 +197,75 @@ impl Network {
         (NETWORK_FN.is_link_up)()
     }
 
+    /// Opens the TCP client connection, an already open one is closed first
+    #[inline]
+    pub fn tcp_connect(ipaddr_dest: &'static dyn IpAddress, port: u16) -> Result<()> {
+        (NETWORK_FN.tcp_connect)(ipaddr_dest, port)
+    }
+
+    /// Queues the whole buffer or nothing, so a packet is never split between two writers
+    #[inline]
+    pub fn tcp_write(data: &[u8]) -> Result<usize> {
+        (NETWORK_FN.tcp_write)(data)
+    }
+
+    /// Non blocking read of the received bytes, fails once the connection is closed and drained
+    #[inline]
+    pub fn tcp_read(buffer: &mut [u8]) -> Result<usize> {
+        (NETWORK_FN.tcp_read)(buffer)
+    }
+
+    #[inline]
+    pub fn tcp_close() {
+        (NETWORK_FN.tcp_close)()
+    }
+
+    #[inline]
+    pub fn tcp_is_connected() -> bool {
+        (NETWORK_FN.tcp_is_connected)()
+    }
+
+    /// Opens a TCP listener on every local address, one client at a time is accepted on each port
+    #[inline]
+    pub fn tcp_listen(port: u16) -> Result<()> {
+        (NETWORK_FN.tcp_listen)(port)
+    }
+
+    /// True once for each client accepted on the port since the previous call
+    #[inline]
+    pub fn tcp_accept(port: u16) -> bool {
+        (NETWORK_FN.tcp_accept)(port)
+    }
+
+    /// Same semantic as tcp_write(), on the client accepted on the port
+    #[inline]
+    pub fn tcp_server_write(port: u16, data: &[u8]) -> Result<usize> {
+        (NETWORK_FN.tcp_server_write)(port, data)
+    }
+
+    /// Same semantic as tcp_read(), on the client accepted on the port
+    #[inline]
+    pub fn tcp_server_read(port: u16, buffer: &mut [u8]) -> Result<usize> {
+        (NETWORK_FN.tcp_server_read)(port, buffer)
+    }
+
+    /// Drops the client accepted on the port, the listener keeps running
+    #[inline]
+    pub fn tcp_server_close(port: u16) {
+        (NETWORK_FN.tcp_server_close)(port)
+    }
+
+    #[inline]
+    pub fn tcp_server_is_connected(port: u16) -> bool {
+        (NETWORK_FN.tcp_server_is_connected)(port)
+    }
+
+    /// Closes the client and the listener of the port
+    #[inline]
+    pub fn tcp_unlisten(port: u16) {
+        (NETWORK_FN.tcp_unlisten)(port)
+    }
+
 
 
 }
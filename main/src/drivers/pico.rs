@@ -30,6 +30,12 @@ pub(super) mod rtc_ds3231;
 pub(super) mod uart;
 pub(super) mod wifi_cyw43;
 
+pub(super) use hw_timer::TIMER_FN;
+pub(super) use lwip::NETWORK_FN;
+pub(super) use mbedtls::ENCRYPT_FN;
+pub(super) use rtc_ds3231::RTC_FN;
+pub(super) use wifi_cyw43::WIFI_FN;
+
 use core::ffi::c_char;
 use osal_rs::os::types::ThreadHandle;
 
@@ -259,6 +259,20 @@ pub(super) struct udp_pcb {
     pub recv_arg: *mut c_void,
 }
 
+type tcp_connected_fn = extern "C" fn(arg: *mut c_void, tpcb: *mut tcp_pcb, err: i8) -> i8;
+
+type tcp_recv_fn = extern "C" fn(arg: *mut c_void, tpcb: *mut tcp_pcb, p: *mut pbuf, err: i8) -> i8;
+
+type tcp_err_fn = extern "C" fn(arg: *mut c_void, err: i8);
+
+type tcp_accept_fn = extern "C" fn(arg: *mut c_void, newpcb: *mut tcp_pcb, err: i8) -> i8;
+
+/// The TCP protocol control block, only handled through pointers
+#[repr(C)]
+pub(super) struct tcp_pcb {
+    _private: [u8; 0],
+}
+
 #[allow(dead_code)]
 unsafe extern "C" {
     pub(super) fn hhg_gpio_init(gpio: u32);
@@ -324,6 +338,20 @@ unsafe extern "C" {
     pub(super) fn hhg_dns_gethostbyname(hostname: *const c_char, addr: *mut ip_addr, dns_found_callback: extern "C" fn(name: *const c_char, ipaddr: *const ip_addr, callback_arg: *mut c_void), callback_arg: *mut c_void) -> c_char;
     pub(super) fn hhg_udp_sendto(buf: *mut udp_pcb, p: *mut pbuf, ipaddr: *const ip_addr, port: u16) -> i8;
     pub(super) fn hhg_udp_recv(pcb: *mut udp_pcb, recv: udp_recv_fn ,  recv_arg: *mut c_void);
+    pub(super) fn hhg_tcp_new_ip_type(_type: c_uchar) -> *mut tcp_pcb;
+    pub(super) fn hhg_tcp_arg(pcb: *mut tcp_pcb, arg: *mut c_void);
+    pub(super) fn hhg_tcp_recv(pcb: *mut tcp_pcb, recv: Option<tcp_recv_fn>);
+    pub(super) fn hhg_tcp_err(pcb: *mut tcp_pcb, err: Option<tcp_err_fn>);
+    pub(super) fn hhg_tcp_connect(pcb: *mut tcp_pcb, ipaddr: *const ip_addr, port: u16, connected: tcp_connected_fn) -> i8;
+    pub(super) fn hhg_tcp_sndbuf(pcb: *const tcp_pcb) -> u16;
+    pub(super) fn hhg_tcp_write(pcb: *mut tcp_pcb, data: *const c_void, len: u16) -> i8;
+    pub(super) fn hhg_tcp_output(pcb: *mut tcp_pcb) -> i8;
+    pub(super) fn hhg_tcp_recved(pcb: *mut tcp_pcb, len: u16);
+    pub(super) fn hhg_tcp_close(pcb: *mut tcp_pcb) -> i8;
+    pub(super) fn hhg_tcp_abort(pcb: *mut tcp_pcb);
+    pub(super) fn hhg_tcp_bind_any(pcb: *mut tcp_pcb, port: u16) -> i8;
+    pub(super) fn hhg_tcp_listen(pcb: *mut tcp_pcb) -> *mut tcp_pcb;
+    pub(super) fn hhg_tcp_accept(pcb: *mut tcp_pcb, accept: Option<tcp_accept_fn>);
 
     pub(super) fn hhg_i2c_instance(i2c_num: u8) -> *mut c_void;
     pub(super) fn hhg_i2c_init(i2c: *mut c_void, baudrate: c_uint) -> c_uint;
@@ -387,12 +415,15 @@ unsafe extern "C" {
     pub(super) fn hhg_flash_errmsg(err: c_int) -> *const c_char;
 
     pub(super) fn hhg_get_unique_id(id_buffer: *mut u8);
+    pub(super) fn hhg_get_rand_64() -> u64;
 
     pub(super) fn hhg_mbedtls_aes_init() -> *mut c_void;
     pub(super) fn hhg_mbedtls_aes_setkey_enc(aes: *mut c_void, key: *const u8, keybits: u32) -> i32;
     pub(super) fn hhg_mbedtls_aes_crypt_cbc(aes: *mut c_void, mode: i32, length: usize, iv: *mut u8, input: *const u8, output: *mut u8) -> i32;
     pub(super) fn hhg_mbedtls_aes_setkey_dec(aes: *mut c_void, key: *const u8, keybits: u32) -> i32;
     pub(super) fn hhg_mbedtls_aes_free(aes: *mut c_void);
+    pub(super) fn hhg_mbedtls_pbkdf2_sha256(password: *const u8, password_len: usize, salt: *const u8, salt_len: usize, iterations: u32, output: *mut u8, output_len: u32) -> i32;
+    pub(super) fn hhg_mbedtls_hmac_sha256(key: *const u8, key_len: usize, input: *const u8, input_len: usize, output: *mut u8) -> i32;
 
     pub(super) fn hhg_pico_sha256_start_blocking(state: *mut *mut c_void, use_dma: bool) -> c_int;
     pub(super) fn hhg_pico_sha256_update_blocking(state: *mut c_void, data: *const c_uchar, data_size_bytes: usize);
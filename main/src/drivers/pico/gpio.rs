@@ -30,7 +30,7 @@ use crate::drivers::gpio::{GpioFn, GpioConfig, GpioInputType, InterruptCallback,
 use GpioPeripheral::*;
 use crate::drivers::plt::ffi::hhg_adc_select_input;
 
-pub(crate) const GPIO_CONFIG_SIZE: usize = 13;
+pub(crate) const GPIO_CONFIG_SIZE: usize = 17;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub(crate) enum GpioPeripheral {
@@ -48,6 +48,10 @@ pub(crate) enum GpioPeripheral {
     Relay1,
     Relay2,
     Relay3,
+    FlowMeter,
+    DosingPump,
+    PressureSwitch,
+    PumpThermal,
 }
  
 impl AsSyncStr for GpioPeripheral {
@@ -67,6 +71,10 @@ impl AsSyncStr for GpioPeripheral {
             Relay1 => "Relay2",
             Relay2 => "Relay3",
             Relay3 => "Relay4",
+            FlowMeter => "FlowMeter",
+            DosingPump => "DosingPump",
+            PressureSwitch => "PressureSwitch",
+            PumpThermal => "PumpThermal",
         }
     }
 }
@@ -90,6 +98,10 @@ impl FromStr for GpioPeripheral {
             "Relay2" => Ok(Relay1),
             "Relay3" => Ok(Relay2),
             "Relay4" => Ok(Relay3),
+            "FlowMeter" => Ok(FlowMeter),
+            "DosingPump" => Ok(DosingPump),
+            "PressureSwitch" => Ok(PressureSwitch),
+            "PumpThermal" => Ok(PumpThermal),
             _ => Err(Error::NotFound)
         }
     }
@@ -110,6 +122,10 @@ pub(crate) static mut GPIO_CONFIGS: GpioConfigs<'static, GPIO_CONFIG_SIZE> = Gpi
         Some(GpioConfig::new(&Relay1, GpioType::Output(None, 7, 0))),
         Some(GpioConfig::new(&Relay2, GpioType::Output(None, 8, 0))),
         Some(GpioConfig::new(&Relay3, GpioType::Output(None, 9, 0))),
+        Some(GpioConfig::new(&FlowMeter, GpioType::Input(None, 22, GpioInputType::PullUp, 0))),
+        Some(GpioConfig::new(&DosingPump, GpioType::Output(None, 10, 0))),
+        Some(GpioConfig::new(&PressureSwitch, GpioType::Input(None, 11, GpioInputType::PullUp, 0))),
+        Some(GpioConfig::new(&PumpThermal, GpioType::Input(None, 12, GpioInputType::PullUp, 0))),
 ]);
 
 
@@ -31,8 +31,10 @@ use crate::drivers::date_time::DateTime;
 use crate::drivers::encoder::Encoder;
 use crate::drivers::error::{HardwareErrorSignal, HardwareErrorFlag};
 use crate::drivers::filesystem::{Filesystem, FsStat};
+use crate::drivers::flow_meter::FlowMeter;
 use crate::drivers::i2c::I2C;
-use crate::drivers::pico::ffi::{hhg_get_unique_id};
+use crate::drivers::pico::ffi::{hhg_get_rand_64, hhg_get_unique_id};
+use crate::drivers::pump_sensors::PumpSensors;
 use crate::drivers::relays::Relays;
 use crate::drivers::rgb_led::RgbLed;
 use crate::drivers::rtc::RTC;
@@ -45,6 +47,8 @@ use crate::drivers::wifi::Wifi;
 
 use crate::set_hardware_error;
 use crate::traits::relays::Relays as RelaysFn;
+use crate::traits::flow_meter::FlowMeter as FlowMeterFn;
+use crate::traits::pump_sensors::PumpSensors as PumpSensorsFn;
 use crate::traits::button::{OnClickable, SetClickable as ButtonOnClickable};
 use crate::traits::encoder::{OnRotatableAndClickable as EncoderOnRotatableAndClickable, SetRotatableAndClickable};
 use crate::traits::hardware::HardwareFn;
@@ -57,6 +61,9 @@ use crate::traits::wifi::{OnWifiChangeStatus, SetOnWifiChangeStatus};
 const APP_TAG: &str = "Hardware";
 const PPB_BASE: usize = 0xe0000000;
 
+/// Called by `reset` before the reboot, lets the apps save what they keep in RAM
+static mut ON_RESET: Option<fn()> = None;
+
 #[allow(dead_code)]
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -104,6 +111,8 @@ pub(crate) struct Hardware {
     encoder: Encoder,
     button: Button,
     relays: Relays,
+    flow_meter: FlowMeter,
+    pump_sensors: PumpSensors,
     display: LCDDisplay,
     i2c0: I2C<{I2C0_INSTANCE}, {I2C_BAUDRATE}>,
     i2c1: I2C<{I2C1_INSTANCE}, {I2C_BAUDRATE}>,
@@ -125,6 +134,10 @@ impl Initializable for Hardware {
         
         set_hardware_error!(self.relays.init(), HardwareErrorFlag::Relays);
 
+        set_hardware_error!(self.flow_meter.init(), HardwareErrorFlag::FlowMeter);
+
+        set_hardware_error!(self.pump_sensors.init(), HardwareErrorFlag::PumpSensors);
+
         set_hardware_error!(self.encoder.init(), HardwareErrorFlag::Encoder);
 
         set_hardware_error!(self.button.init(), HardwareErrorFlag::Button);
@@ -170,6 +183,27 @@ impl RelaysFn for Hardware {
     }
 }
 
+impl FlowMeterFn for Hardware {
+
+    #[inline]
+    fn get_pulses(&self) -> u32 {
+        self.flow_meter.get_pulses()
+    }
+}
+
+impl PumpSensorsFn for Hardware {
+
+    #[inline]
+    fn is_pressure_ok(&self) -> bool {
+        self.pump_sensors.is_pressure_ok()
+    }
+
+    #[inline]
+    fn is_over_temperature(&self) -> bool {
+        self.pump_sensors.is_over_temperature()
+    }
+}
+
 impl SetOnWifiChangeStatus<'static> for Hardware {
 
     #[inline]
@@ -227,6 +261,11 @@ impl HardwareFn<'static> for Hardware {
         id_buffer
     }
 
+    #[inline]
+    fn get_random() -> u64 {
+        unsafe { hhg_get_rand_64() }
+    }
+
     #[inline]
     fn get_rtc(&self) -> Arc<Mutex<dyn RTCFn + 'static>> {
         self.rtc.clone()
@@ -244,6 +283,8 @@ impl Hardware {
             encoder: Encoder::shared(),
             button: Button::shared(),
             relays: Relays::shared(),
+            flow_meter: FlowMeter::shared(),
+            pump_sensors: PumpSensors::shared(),
             display: LCDDisplay::new(),
             i2c0: I2C::new(),
             i2c1: i2c1,
@@ -304,7 +345,14 @@ impl Hardware {
         Ok(())
     }
 
+    pub fn set_on_reset(on_reset: fn()) {
+        unsafe { ON_RESET = Some(on_reset); }
+    }
+
     pub fn reset() -> ! {
+        if let Some(on_reset) = unsafe { *&raw const ON_RESET } {
+            on_reset();
+        }
         unsafe {
             let aircr_register = (PPB_BASE + 0x0ED0C) as *mut u32;
             write_volatile(aircr_register, 0x5FA0004);
@@ -19,24 +19,83 @@
  ***************************************************************************/
 
 use core::ffi::{c_char, c_void};
-use core::ptr::null_mut;
+use core::ptr::{copy, copy_nonoverlapping, null_mut};
 use core::slice::{from_raw_parts, from_raw_parts_mut};
 use osal_rs::os::{System, SystemFn};
 use osal_rs::utils::{Bytes, Error, Result};
 use crate::drivers::network::{IP4Addr, NetworkFn};
-use crate::drivers::pico::ffi::{lwip_ip_addr_type::IPADDR_TYPE_V4, hhg_cyw43_arch_lwip_begin, hhg_cyw43_arch_lwip_end, hhg_cyw43_arch_poll, hhg_dns_gethostbyname, hhg_pbuf_alloc, hhg_pbuf_copy_partial, hhg_pbuf_free, hhg_pbuf_get_at, hhg_udp_new_ip_type, hhg_udp_recv, hhg_udp_sendto, ip_addr, pbuf, udp_pcb, hhg_dhcp_get_binary_ip_address, hhg_dhcp_get_ip_address, hhg_dhcp_supplied_address, hhg_netif_is_link_up};
+use crate::drivers::pico::ffi::{lwip_ip_addr_type::IPADDR_TYPE_V4, err_enum, hhg_cyw43_arch_lwip_begin, hhg_cyw43_arch_lwip_end, hhg_cyw43_arch_poll, hhg_dns_gethostbyname, hhg_pbuf_alloc, hhg_pbuf_copy_partial, hhg_pbuf_free, hhg_pbuf_get_at, hhg_udp_new_ip_type, hhg_udp_recv, hhg_udp_sendto, ip_addr, pbuf, udp_pcb, hhg_dhcp_get_binary_ip_address, hhg_dhcp_get_ip_address, hhg_dhcp_supplied_address, hhg_netif_is_link_up, tcp_pcb, hhg_tcp_new_ip_type, hhg_tcp_arg, hhg_tcp_recv, hhg_tcp_err, hhg_tcp_connect, hhg_tcp_sndbuf, hhg_tcp_write, hhg_tcp_output, hhg_tcp_recved, hhg_tcp_close, hhg_tcp_abort, hhg_tcp_bind_any, hhg_tcp_listen, hhg_tcp_accept};
 use crate::traits::network::{IPV6_ADDR_LEN, IpAddress};
 
 static mut IP_ADDRES_FOUND: Option<IP4Addr> = None;
 static mut TIMESTAMP: i64 = 0;
 
+const TCP_RX_SIZE: usize = 1_024;
+
+/// State of a single TCP connection, handed to the lwIP callbacks as argument
+struct TcpConnection {
+    pcb: *mut tcp_pcb,
+    connected: bool,
+    rx: [u8; TCP_RX_SIZE],
+    rx_len: usize,
+}
+
+impl TcpConnection {
+    const fn new() -> Self {
+        Self {
+            pcb: null_mut(),
+            connected: false,
+            rx: [0; TCP_RX_SIZE],
+            rx_len: 0,
+        }
+    }
+}
+
+/// Outgoing connection opened by tcp_connect()
+static mut TCP_CLIENT: TcpConnection = TcpConnection::new();
+
+/// Listener opened by tcp_listen() and the client it accepted, a slot is free when port is 0
+struct TcpServer {
+    port: u16,
+    listen: *mut tcp_pcb,
+    accepted: bool,
+    client: TcpConnection,
+}
+
+impl TcpServer {
+    const fn new() -> Self {
+        Self {
+            port: 0,
+            listen: null_mut(),
+            accepted: false,
+            client: TcpConnection::new(),
+        }
+    }
+}
+
+const TCP_SERVERS_SIZE: usize = 2;
+
+static mut TCP_SERVERS: [TcpServer; TCP_SERVERS_SIZE] = [const { TcpServer::new() }; TCP_SERVERS_SIZE];
+
 pub(in crate::drivers) static NETWORK_FN: NetworkFn = NetworkFn {
     dhcp_get_ip_address,
     dhcp_get_binary_ip_address,
     dhcp_supplied_address,
     dns_resolve_addrress,
     ntp_request,
-    is_link_up
+    is_link_up,
+    tcp_connect,
+    tcp_write,
+    tcp_read,
+    tcp_close,
+    tcp_is_connected,
+    tcp_listen,
+    tcp_accept,
+    tcp_server_write,
+    tcp_server_read,
+    tcp_server_close,
+    tcp_server_is_connected,
+    tcp_unlisten,
 };
 
 fn dhcp_get_ip_address() -> Bytes<IPV6_ADDR_LEN> {
@@ -253,4 +312,343 @@ fn ntp_request(ipaddr_dest: &'static dyn IpAddress, port: u16, msg_len: u16) ->
 
 fn is_link_up() -> bool {
     unsafe { hhg_netif_is_link_up() == 1 }
-}
\ No newline at end of file
+}
+
+extern "C" fn tcp_connected_callback(arg: *mut c_void, _: *mut tcp_pcb, err: i8) -> i8 {
+    let connection = unsafe { &mut *(arg as *mut TcpConnection) };
+    connection.connected = err == err_enum::ERR_OK as i8;
+    err_enum::ERR_OK as i8
+}
+
+extern "C" fn tcp_recv_callback(arg: *mut c_void, tpcb: *mut tcp_pcb, p: *mut pbuf, _: i8) -> i8 {
+
+    let connection = unsafe { &mut *(arg as *mut TcpConnection) };
+
+    if p.is_null() {
+        // Connection closed by the remote host, the pcb is released by close()
+        connection.connected = false;
+        return err_enum::ERR_OK as i8;
+    }
+
+    unsafe {
+        let tot_len = (*p).tot_len as usize;
+        if connection.rx_len + tot_len > TCP_RX_SIZE {
+            // Keep the pbuf, lwIP delivers it again once the buffer has been read
+            return err_enum::ERR_MEM as i8;
+        }
+
+        hhg_pbuf_copy_partial(p, connection.rx.as_mut_ptr().add(connection.rx_len) as *mut c_void, tot_len as u16, 0);
+        connection.rx_len += tot_len;
+
+        hhg_tcp_recved(tpcb, tot_len as u16);
+        hhg_pbuf_free(p);
+    }
+
+    err_enum::ERR_OK as i8
+}
+
+extern "C" fn tcp_err_callback(arg: *mut c_void, _: i8) {
+    // The pcb has already been freed by lwIP
+    let connection = unsafe { &mut *(arg as *mut TcpConnection) };
+    connection.pcb = null_mut();
+    connection.connected = false;
+}
+
+extern "C" fn tcp_accept_callback(arg: *mut c_void, newpcb: *mut tcp_pcb, err: i8) -> i8 {
+
+    if err != err_enum::ERR_OK as i8 || newpcb.is_null() {
+        return err_enum::ERR_VAL as i8;
+    }
+
+    let server = unsafe { &mut *(arg as *mut TcpServer) };
+    if !server.client.pcb.is_null() {
+        // Only one client at a time, the others are refused
+        unsafe {
+            hhg_tcp_abort(newpcb);
+        }
+        return err_enum::ERR_ABRT as i8;
+    }
+
+    server.client.pcb = newpcb;
+    server.client.connected = true;
+    server.client.rx_len = 0;
+    server.accepted = true;
+
+    unsafe {
+        hhg_tcp_arg(newpcb, &raw mut server.client as *mut c_void);
+        hhg_tcp_recv(newpcb, Some(tcp_recv_callback));
+        hhg_tcp_err(newpcb, Some(tcp_err_callback));
+    }
+
+    err_enum::ERR_OK as i8
+}
+
+fn write(connection: &mut TcpConnection, data: &[u8]) -> Result<usize> {
+
+    if data.len() > u16::MAX as usize {
+        return Err(Error::InvalidQueueSize);
+    }
+
+    unsafe {
+        hhg_cyw43_arch_lwip_begin();
+
+        if connection.pcb.is_null() || !connection.connected {
+            hhg_cyw43_arch_lwip_end();
+            return Err(Error::Unhandled("TCP not connected"));
+        }
+
+        if (hhg_tcp_sndbuf(connection.pcb) as usize) < data.len() {
+            hhg_cyw43_arch_lwip_end();
+            return Err(Error::Unhandled("TCP send buffer full"));
+        }
+
+        let ret = hhg_tcp_write(connection.pcb, data.as_ptr() as *const c_void, data.len() as u16);
+        if ret < 0 {
+            hhg_cyw43_arch_lwip_end();
+            return Err(Error::ReturnWithCode(ret as i32));
+        }
+
+        hhg_tcp_output(connection.pcb);
+        hhg_cyw43_arch_lwip_end();
+    }
+
+    Ok(data.len())
+}
+
+fn read(connection: &mut TcpConnection, buffer: &mut [u8]) -> Result<usize> {
+
+    unsafe {
+        hhg_cyw43_arch_lwip_begin();
+
+        let len = buffer.len().min(connection.rx_len);
+        if len == 0 && !connection.connected {
+            hhg_cyw43_arch_lwip_end();
+            return Err(Error::Unhandled("TCP not connected"));
+        }
+
+        let rx = connection.rx.as_mut_ptr();
+        copy_nonoverlapping(rx, buffer.as_mut_ptr(), len);
+        copy(rx.add(len), rx, connection.rx_len - len);
+        connection.rx_len -= len;
+
+        hhg_cyw43_arch_lwip_end();
+
+        Ok(len)
+    }
+}
+
+fn close(connection: &mut TcpConnection) {
+
+    unsafe {
+        hhg_cyw43_arch_lwip_begin();
+
+        if !connection.pcb.is_null() {
+            // Detach the callbacks first, a late error must not touch the next connection
+            hhg_tcp_recv(connection.pcb, None);
+            hhg_tcp_err(connection.pcb, None);
+            if hhg_tcp_close(connection.pcb) != err_enum::ERR_OK as i8 {
+                hhg_tcp_abort(connection.pcb);
+            }
+        }
+
+        connection.pcb = null_mut();
+        connection.connected = false;
+        connection.rx_len = 0;
+
+        hhg_cyw43_arch_lwip_end();
+    }
+}
+
+fn tcp_connect(ipaddr_dest: &dyn IpAddress, port: u16) -> Result<()> {
+
+    tcp_close();
+
+    let client = unsafe { &mut *&raw mut TCP_CLIENT };
+
+    unsafe {
+        hhg_cyw43_arch_lwip_begin();
+    }
+
+    let pcb = unsafe { hhg_tcp_new_ip_type(IPADDR_TYPE_V4) };
+    if pcb.is_null() {
+        unsafe {
+            hhg_cyw43_arch_lwip_end();
+        }
+        return Err(Error::NullPtr);
+    }
+
+    let ipaddr = unsafe { &*(ipaddr_dest as *const dyn IpAddress as *const IP4Addr) };
+
+    let ret = unsafe {
+        client.pcb = pcb;
+        hhg_tcp_arg(pcb, client as *mut TcpConnection as *mut c_void);
+        hhg_tcp_recv(pcb, Some(tcp_recv_callback));
+        hhg_tcp_err(pcb, Some(tcp_err_callback));
+        hhg_tcp_connect(pcb, ipaddr, port, tcp_connected_callback)
+    };
+    if ret < 0 {
+        unsafe {
+            hhg_tcp_err(pcb, None);
+            hhg_tcp_abort(pcb);
+            client.pcb = null_mut();
+            hhg_cyw43_arch_lwip_end();
+        }
+        return Err(Error::ReturnWithCode(ret as i32));
+    }
+
+    unsafe {
+        hhg_cyw43_arch_lwip_end();
+    }
+
+    const TIMEOUT_MS: u32 = 5000;
+    const POLL_INTERVAL_MS: u32 = 10;
+    let max_attempts = TIMEOUT_MS / POLL_INTERVAL_MS;
+
+    for _ in 0..max_attempts {
+        unsafe { hhg_cyw43_arch_poll(); }
+
+        if client.connected || client.pcb.is_null() {
+            break;
+        }
+
+        System::delay(POLL_INTERVAL_MS);
+    }
+
+    if client.connected {
+        Ok(())
+    } else {
+        tcp_close();
+        Err(Error::Unhandled("TCP connect failed"))
+    }
+}
+
+#[inline]
+fn tcp_write(data: &[u8]) -> Result<usize> {
+    write(unsafe { &mut *&raw mut TCP_CLIENT }, data)
+}
+
+#[inline]
+fn tcp_read(buffer: &mut [u8]) -> Result<usize> {
+    read(unsafe { &mut *&raw mut TCP_CLIENT }, buffer)
+}
+
+#[inline]
+fn tcp_close() {
+    close(unsafe { &mut *&raw mut TCP_CLIENT })
+}
+
+#[inline]
+fn tcp_is_connected() -> bool {
+    unsafe { TCP_CLIENT.connected }
+}
+
+/// Slot of the listener on the port, None if the port is not listening
+fn server(port: u16) -> Option<&'static mut TcpServer> {
+    unsafe { (*&raw mut TCP_SERVERS).iter_mut().find(|server| port != 0 && server.port == port) }
+}
+
+fn tcp_listen(port: u16) -> Result<()> {
+
+    if port == 0 {
+        return Err(Error::InvalidType);
+    }
+
+    tcp_unlisten(port);
+
+    let server = unsafe { (*&raw mut TCP_SERVERS).iter_mut().find(|server| server.port == 0) }
+        .ok_or(Error::Unhandled("No TCP listener available"))?;
+
+    unsafe {
+        hhg_cyw43_arch_lwip_begin();
+
+        let pcb = hhg_tcp_new_ip_type(IPADDR_TYPE_V4);
+        if pcb.is_null() {
+            hhg_cyw43_arch_lwip_end();
+            return Err(Error::NullPtr);
+        }
+
+        let ret = hhg_tcp_bind_any(pcb, port);
+        if ret < 0 {
+            hhg_tcp_abort(pcb);
+            hhg_cyw43_arch_lwip_end();
+            return Err(Error::ReturnWithCode(ret as i32));
+        }
+
+        // On success the bound pcb is freed and replaced by a smaller listening one
+        let listen = hhg_tcp_listen(pcb);
+        if listen.is_null() {
+            hhg_tcp_abort(pcb);
+            hhg_cyw43_arch_lwip_end();
+            return Err(Error::NullPtr);
+        }
+
+        server.port = port;
+        server.listen = listen;
+        server.accepted = false;
+        hhg_tcp_arg(listen, server as *mut TcpServer as *mut c_void);
+        hhg_tcp_accept(listen, Some(tcp_accept_callback));
+
+        hhg_cyw43_arch_lwip_end();
+    }
+
+    Ok(())
+}
+
+/// Returns true once for every client accepted since the previous call
+fn tcp_accept(port: u16) -> bool {
+    let Some(server) = server(port) else {
+        return false;
+    };
+
+    unsafe {
+        hhg_cyw43_arch_lwip_begin();
+        let accepted = server.accepted;
+        server.accepted = false;
+        hhg_cyw43_arch_lwip_end();
+        accepted
+    }
+}
+
+fn tcp_server_write(port: u16, data: &[u8]) -> Result<usize> {
+    let server = server(port).ok_or(Error::Unhandled("TCP not listening"))?;
+    write(&mut server.client, data)
+}
+
+fn tcp_server_read(port: u16, buffer: &mut [u8]) -> Result<usize> {
+    let server = server(port).ok_or(Error::Unhandled("TCP not listening"))?;
+    read(&mut server.client, buffer)
+}
+
+fn tcp_server_close(port: u16) {
+    if let Some(server) = server(port) {
+        close(&mut server.client);
+    }
+}
+
+fn tcp_server_is_connected(port: u16) -> bool {
+    server(port).is_some_and(|server| server.client.connected)
+}
+
+fn tcp_unlisten(port: u16) {
+
+    let Some(server) = server(port) else {
+        return;
+    };
+
+    close(&mut server.client);
+
+    unsafe {
+        hhg_cyw43_arch_lwip_begin();
+
+        if !server.listen.is_null() {
+            hhg_tcp_accept(server.listen, None);
+            hhg_tcp_close(server.listen);
+        }
+
+        server.port = 0;
+        server.listen = null_mut();
+        server.accepted = false;
+
+        hhg_cyw43_arch_lwip_end();
+    }
+}
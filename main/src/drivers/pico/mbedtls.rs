@@ -27,7 +27,7 @@ use core::ops::{Deref, DerefMut};
 use core::ptr::null_mut;
 use osal_rs::utils::{bytes_to_hex_into_slice, Bytes, Error, Result};
 
-use crate::drivers::pico::ffi::{aes_mode, hhg_mbedtls_aes_crypt_cbc, hhg_mbedtls_aes_free, hhg_mbedtls_aes_init, hhg_mbedtls_aes_setkey_enc, hhg_mbedtls_aes_setkey_dec};
+use crate::drivers::pico::ffi::{aes_mode, hhg_mbedtls_aes_crypt_cbc, hhg_mbedtls_aes_free, hhg_mbedtls_aes_init, hhg_mbedtls_aes_setkey_enc, hhg_mbedtls_aes_setkey_dec, hhg_mbedtls_pbkdf2_sha256, hhg_mbedtls_hmac_sha256};
 use crate::drivers::encrypt::{EncryptFn, SHA256_RESULT_BYTES};
 use crate::drivers::plt::ffi::{hhg_pico_sha256_finish, hhg_pico_sha256_start_blocking, hhg_pico_sha256_update_blocking};
 
@@ -36,6 +36,8 @@ pub(in crate::drivers) const ENCRYPT_FN: EncryptFn = EncryptFn {
     aes_encrypt,
     aes_decrypt,
     get_sha256,
+    pbkdf2_sha256,
+    hmac_sha256,
     drop,
 };
 
@@ -151,6 +153,58 @@ pub(crate) fn get_sha256(data: &[u8]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>
     Ok(ret)
 }
 
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+    let mut key = Bytes::<SHA256_RESULT_BYTES>::new();
+
+    let ret = unsafe {
+        hhg_mbedtls_pbkdf2_sha256(
+            password.as_ptr(),
+            password.len(),
+            salt.as_ptr(),
+            salt.len(),
+            iterations,
+            key.as_mut_ptr(),
+            SHA256_RESULT_BYTES as u32
+        )
+    };
+    if ret != 0 {
+        return Err(Error::ReturnWithCode(ret));
+    }
+
+    let mut ret = Bytes::<{SHA256_RESULT_BYTES * 2}>::new();
+
+    if bytes_to_hex_into_slice(key.deref(), ret.deref_mut()) != SHA256_RESULT_BYTES * 2 {
+        return Err(Error::Unhandled("Failed to convert key to hex string"));
+    }
+
+    Ok(ret)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Bytes<{SHA256_RESULT_BYTES * 2}>> {
+    let mut mac = Bytes::<SHA256_RESULT_BYTES>::new();
+
+    let ret = unsafe {
+        hhg_mbedtls_hmac_sha256(
+            key.as_ptr(),
+            key.len(),
+            data.as_ptr(),
+            data.len(),
+            mac.as_mut_ptr()
+        )
+    };
+    if ret != 0 {
+        return Err(Error::ReturnWithCode(ret));
+    }
+
+    let mut ret = Bytes::<{SHA256_RESULT_BYTES * 2}>::new();
+
+    if bytes_to_hex_into_slice(mac.deref(), ret.deref_mut()) != SHA256_RESULT_BYTES * 2 {
+        return Err(Error::Unhandled("Failed to convert mac to hex string"));
+    }
+
+    Ok(ret)
+}
+
 fn drop(handler: *mut c_void) {
     if handler.is_null() {
         return;
@@ -0,0 +1,65 @@
+/***************************************************************************
+ *
+ * Hi Happy Garden
+ * Copyright (C) 2023/2026 Antonio Salsi <passy.linux@zresa.it>
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along
+ * with this program; if not, see <https://www.gnu.org/licenses/>.
+ *
+ ***************************************************************************/
+
+use osal_rs::log_info;
+use osal_rs::utils::Result;
+
+use crate::drivers::gpio::Gpio;
+use crate::drivers::platform::{GPIO_CONFIG_SIZE, GpioPeripheral};
+use crate::traits::pump_sensors::PumpSensors as PumpSensorsFn;
+use crate::traits::state::Initializable;
+
+const APP_TAG: &str = "PumpSensors";
+
+/// Pressure switch and thermal protector of the pump, both switch to ground with pull-up
+pub struct PumpSensors (Gpio<GPIO_CONFIG_SIZE>);
+
+impl Initializable for PumpSensors {
+    fn init(&mut self) -> Result<()> {
+        log_info!(APP_TAG, "Init pump sensors");
+
+        self.0.read(&GpioPeripheral::PressureSwitch)?;
+        self.0.read(&GpioPeripheral::PumpThermal)?;
+
+        Ok(())
+    }
+}
+
+impl PumpSensorsFn for PumpSensors {
+
+    /// Normally open switch, closed to ground when the line is under pressure
+    #[inline]
+    fn is_pressure_ok(&self) -> bool {
+        self.0.read(&GpioPeripheral::PressureSwitch).map_or(false, |value| value == 0)
+    }
+
+    /// Normally closed protector, opens when the pump overheats, a read error is an overheating
+    #[inline]
+    fn is_over_temperature(&self) -> bool {
+        self.0.read(&GpioPeripheral::PumpThermal).map_or(true, |value| value != 0)
+    }
+}
+
+impl PumpSensors {
+    #[inline]
+    pub fn shared() -> Self {
+        Self (Gpio::shared())
+    }
+}
@@ -49,7 +49,7 @@ impl RaleayFn for Relays {
         use GpioPeripheral::*;
 
         match relay_index {
-            Relay0 | Relay1 | Relay2 | Relay3 => {
+            Relay0 | Relay1 | Relay2 | Relay3 | DosingPump => {
                 self.0.write(&relay_index, if state { 1 } else { 0 });
                 OsalRsBool::True
             }
@@ -22,7 +22,7 @@ use osal_rs::log_info;
 use osal_rs::utils::{Error, Result};
 
 use crate::drivers::i2c::I2C;
-use crate::drivers::pico::rtc_ds3231::RTC_FN;
+use crate::drivers::plt::RTC_FN;
 use crate::drivers::platform::{I2C0_INSTANCE, I2C_BAUDRATE};
 use crate::traits::state::Initializable;
 
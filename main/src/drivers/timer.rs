@@ -25,7 +25,7 @@ use core::ffi::c_void;
 
 use osal_rs::utils::Result;
 
-use crate::drivers::pico::hw_timer::TIMER_FN;
+use crate::drivers::plt::TIMER_FN;
 
 
 
@@ -29,10 +29,9 @@ use osal_rs::os::types::StackType;
 use osal_rs::utils::{Bytes, Result};
 use osal_rs_serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::drivers::gpio::Gpio;
-use crate::drivers::pico::ffi::pico_error_codes::PICO_OK;
 use crate::traits::state::Initializable;
 use crate::drivers::platform::{GpioPeripheral, ThreadPriority};
-use crate::drivers::pico::wifi_cyw43::WIFI_FN;
+use crate::drivers::plt::WIFI_FN;
 use crate::traits::wifi::{OnWifiChangeStatus, RSSIStatus::{self, *}, SetOnWifiChangeStatus, WifiStatus};
 use crate::traits::wifi::WifiStatus::Disconnected;
 
@@ -215,7 +214,7 @@ impl Wifi {
         let ret = unsafe {
             (WIFI_FN.connect)(null_mut(), (*&raw const SSID).as_str(), (*&raw const PASSWORD).as_str(), AUTH).unwrap_or(1)
         };
-        if ret != PICO_OK as i32 {
+        if ret != 0 {
             log_error!(APP_TAG, "Error connecting to WiFi, errno: {ret}");
             unsafe { transition_wifi_status!(Error, on_wifi_change_status); }
             return WifiFsmControl::Continue;
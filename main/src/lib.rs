@@ -19,9 +19,11 @@
  ***************************************************************************/
  
 #![no_std]
-#![cfg_attr(feature = "tests", allow(dead_code))]
+#![cfg_attr(any(feature = "tests", feature = "host"), allow(dead_code))]
 
 extern crate alloc;
+#[cfg(feature = "host")]
+extern crate std;
 extern crate osal_rs;
 extern crate osal_rs_serde;
 extern crate cjson_binding;
@@ -33,7 +35,7 @@ mod traits;
 
 const APP_TAG: &str = "main";
 
-#[cfg(not(feature = "tests"))]
+#[cfg(all(feature = "pico", not(feature = "tests")))]
 mod app {
 
     mod ffi {
@@ -123,6 +125,7 @@ mod app {
 
 }
 
+#[cfg(feature = "pico")]
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn start() {
     osal_rs::log::set_enable_color(true);
@@ -20,10 +20,12 @@
  
 pub mod button;
 pub mod encoder;
+pub mod flow_meter;
 pub mod hardware;
 pub mod integer;
 pub mod lcd_display;
 pub mod network;
+pub mod pump_sensors;
 pub mod relays;
 pub mod rgb_led;
 pub mod rtc;
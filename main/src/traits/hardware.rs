@@ -23,13 +23,15 @@ use alloc::sync::Arc;
 use osal_rs::os::Mutex;
 
 use crate::traits::relays::Relays as RelaysFn;
+use crate::traits::flow_meter::FlowMeter as FlowMeterFn;
+use crate::traits::pump_sensors::PumpSensors as PumpSensorsFn;
 use crate::traits::button::OnClickable;
 use crate::traits::encoder::OnRotatableAndClickable;
 use crate::traits::rtc::RTC;
 use crate::traits::rx_tx::{SetOnReceive, SetTransmit};
 use crate::traits::wifi::SetOnWifiChangeStatus;
 
-pub trait HardwareFn<'a> : RelaysFn + SetOnWifiChangeStatus<'a> + SetOnReceive<'a> + SetTransmit {
+pub trait HardwareFn<'a> : RelaysFn + FlowMeterFn + PumpSensorsFn + SetOnWifiChangeStatus<'a> + SetOnReceive<'a> + SetTransmit {
 
     #[allow(dead_code)]
     const SAMPLES: u8 = 20;
@@ -50,6 +52,9 @@ pub trait HardwareFn<'a> : RelaysFn + SetOnWifiChangeStatus<'a> + SetOnReceive<'
 
     fn get_unique_id() -> [u8; 8];
 
+    /// Random number from the hardware entropy sources, fit for tokens and nonces
+    fn get_random() -> u64;
+
     fn get_rtc(&self) -> Arc<Mutex<dyn RTC + 'a>>;
 }
 
@@ -31,5 +31,6 @@ pub trait Relays {
       self.set_relay_state(GpioPeripheral::Relay1, false);
       self.set_relay_state(GpioPeripheral::Relay2, false);
       self.set_relay_state(GpioPeripheral::Relay3, false);
+      self.set_relay_state(GpioPeripheral::DosingPump, false);
   }
 }
\ No newline at end of file
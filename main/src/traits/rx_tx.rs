@@ -24,7 +24,9 @@ use osal_rs::utils::Result;
 #[derive(Debug, Clone, Copy)]
 pub enum Source {
     Uart,
-    Mqtt
+    Mqtt,
+    Telnet,
+    Http
 }
 
 
@@ -0,0 +1,364 @@
+// Hi Happy Garden dashboard, a client of the REST API under /api
+
+'use strict';
+
+const DAYS = ['Sun', 'Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat'];
+const MONTHS = ['Jan', 'Feb', 'Mar', 'Apr', 'May', 'Jun', 'Jul', 'Aug', 'Sep', 'Oct', 'Nov', 'Dec'];
+const STATUS = ['Inactive', 'Active', 'Running'];
+const GROUP_FLAG = 0x10;
+const REFRESH_MS = 10000;
+
+const $ = (id) => document.getElementById(id);
+
+let token = sessionStorage.getItem('token');
+let zones = [];
+let timer = null;
+
+async function api(method, path, body) {
+    const headers = {};
+    if (token) {
+        headers.Authorization = 'Bearer ' + token;
+    }
+    const response = await fetch('/api/' + path, {
+        method,
+        headers,
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    if (response.status === 401 && path !== 'session') {
+        logged(null);
+    }
+    const json = response.status === 204 ? null : await response.json();
+    if (!response.ok) {
+        throw new Error(json && json.error ? json.error : response.statusText);
+    }
+    return json;
+}
+
+// The password never leaves the browser, the login answers a nonce of the device with
+// HMAC-SHA256. WebCrypto is missing on the plain HTTP origin of the board, hence the hashes here
+const SHA256_K = new Uint32Array([
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+]);
+
+const utf8 = (text) => new TextEncoder().encode(text);
+const hex = (bytes) => Array.from(bytes, (byte) => byte.toString(16).padStart(2, '0')).join('');
+const ror = (value, bits) => (value >>> bits) | (value << (32 - bits));
+
+function sha256(bytes) {
+    const hash = new Uint32Array([0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19]);
+    const padded = new Uint8Array((bytes.length + 72) & ~63);
+    padded.set(bytes);
+    padded[bytes.length] = 0x80;
+    const view = new DataView(padded.buffer);
+    view.setUint32(padded.length - 4, bytes.length * 8);
+
+    const w = new Uint32Array(64);
+    for (let offset = 0; offset < padded.length; offset += 64) {
+        for (let i = 0; i < 16; i++) {
+            w[i] = view.getUint32(offset + i * 4);
+        }
+        for (let i = 16; i < 64; i++) {
+            const s0 = ror(w[i - 15], 7) ^ ror(w[i - 15], 18) ^ (w[i - 15] >>> 3);
+            const s1 = ror(w[i - 2], 17) ^ ror(w[i - 2], 19) ^ (w[i - 2] >>> 10);
+            w[i] = w[i - 16] + s0 + w[i - 7] + s1;
+        }
+
+        let [a, b, c, d, e, f, g, h] = hash;
+        for (let i = 0; i < 64; i++) {
+            const t1 = h + (ror(e, 6) ^ ror(e, 11) ^ ror(e, 25)) + ((e & f) ^ (~e & g)) + SHA256_K[i] + w[i];
+            const t2 = (ror(a, 2) ^ ror(a, 13) ^ ror(a, 22)) + ((a & b) ^ (a & c) ^ (b & c));
+            [h, g, f, e, d, c, b, a] = [g, f, e, (d + t1) >>> 0, c, b, a, (t1 + t2) >>> 0];
+        }
+        [a, b, c, d, e, f, g, h].forEach((value, i) => hash[i] += value);
+    }
+
+    const digest = new Uint8Array(32);
+    hash.forEach((value, i) => new DataView(digest.buffer).setUint32(i * 4, value));
+    return digest;
+}
+
+function hmac(key, data) {
+    key = key.length > 64 ? sha256(key) : key;
+    const inner = new Uint8Array(64 + data.length);
+    const outer = new Uint8Array(64 + 32);
+    for (let i = 0; i < 64; i++) {
+        inner[i] = (key[i] || 0) ^ 0x36;
+        outer[i] = (key[i] || 0) ^ 0x5c;
+    }
+    inner.set(data, 64);
+    outer.set(sha256(inner), 64);
+    return sha256(outer);
+}
+
+// A single block, the key is as long as the digest
+function pbkdf2(password, salt, iterations) {
+    const first = new Uint8Array(salt.length + 4);
+    first.set(salt);
+    first[first.length - 1] = 1;
+    let block = hmac(password, first);
+    const key = block.slice();
+    for (let i = 1; i < iterations; i++) {
+        block = hmac(password, block);
+        key.forEach((byte, j) => key[j] = byte ^ block[j]);
+    }
+    return key;
+}
+
+// Same derivation of the stored key, iterations 0 for a password still kept as a bare SHA-256
+function answer(password, { nonce, salt, iterations }) {
+    const hash = hex(sha256(utf8(password)));
+    const key = iterations ? hex(pbkdf2(utf8(hash), utf8(salt), iterations)) : hash;
+    return hex(hmac(utf8(key), utf8(nonce)));
+}
+
+function message(text) {
+    $('message').textContent = text || '';
+}
+
+// The device counts the time of the schedules and of the history on its local clock
+function time(timestamp) {
+    return timestamp ? new Date(timestamp * 1000).toISOString().slice(0, 16).replace('T', ' ') : '-';
+}
+
+function row(table, cells, header) {
+    const tr = table.insertRow();
+    for (const cell of cells) {
+        const td = document.createElement(header ? 'th' : 'td');
+        if (cell instanceof Node) {
+            td.append(cell);
+        } else {
+            td.textContent = cell;
+        }
+        tr.append(td);
+    }
+    return tr;
+}
+
+function element(tag, properties, ...children) {
+    const node = Object.assign(document.createElement(tag), properties);
+    node.append(...children);
+    return node;
+}
+
+function target(value) {
+    return value & GROUP_FLAG ? 'Group ' + (value & ~GROUP_FLAG) : zoneName(value);
+}
+
+function zoneName(relay) {
+    const zone = zones.find((zone) => zone.zone_relay === relay);
+    return zone ? zone.description : 'Zone ' + relay;
+}
+
+async function run(zone, minutes) {
+    try {
+        await api('POST', 'run', { zone, minutes });
+        message();
+        setTimeout(refresh, 1000);
+    } catch (e) {
+        message(e.message);
+    }
+}
+
+async function loadZones() {
+    zones = (await api('GET', 'zones')).zones;
+    const table = $('zones');
+    table.replaceChildren();
+    row(table, ['Zone', 'Status', 'Minutes', ''], true);
+    for (const zone of zones) {
+        const minutes = element('input', { type: 'number', min: 1, max: zone.max_minutes, value: 10 });
+        const running = zone.status === 2;
+        const button = running
+            ? element('button', { className: 'secondary', onclick: () => run(zone.zone_relay, 0) }, 'Close')
+            : element('button', { onclick: () => run(zone.zone_relay, Number(minutes.value)) }, 'Run');
+        const tr = row(table, [zone.description, running ? 'Open' : 'Closed', minutes, button]);
+        tr.className = running ? 'run' : '';
+    }
+}
+
+async function loadNext(schedules) {
+    const { starts } = await api('GET', 'next');
+    const table = $('next');
+    table.replaceChildren();
+    row(table, ['Schedule', 'Start'], true);
+    starts.forEach((start, idx) => {
+        if (start) {
+            row(table, [schedules[idx].description, time(start)]);
+        }
+    });
+}
+
+async function loadHistory() {
+    const { records } = await api('GET', 'history');
+    const table = $('history');
+    table.replaceChildren();
+    row(table, ['Start', 'Zone', 'Minutes', 'Liters'], true);
+    for (const record of records.filter((record) => record.timestamp)) {
+        const liters = record.delivered_liters === undefined || record.delivered_liters === null ? '-' : record.delivered_liters;
+        row(table, [time(record.timestamp), zoneName(record.zone_relay), Math.round(record.seconds / 60), liters]);
+    }
+}
+
+function checkboxes(names, mask) {
+    return names.map((name, bit) => element('label', {},
+        element('input', { type: 'checkbox', checked: (mask & (1 << bit)) !== 0, value: bit }), name));
+}
+
+function mask(fieldset) {
+    return [...fieldset.querySelectorAll('input:checked')].reduce((mask, input) => mask | 1 << Number(input.value), 0);
+}
+
+// Hour and minute are stored one based, 0 repeats every hour or minute
+function timeInput(value, max) {
+    return element('input', { type: 'number', min: 0, max, value: value ? value - 1 : '', placeholder: '*' });
+}
+
+function timeValue(input) {
+    return input.value === '' ? 0 : Number(input.value) + 1;
+}
+
+function scheduleForm(schedule, idx) {
+    const description = element('input', { value: schedule.description, maxLength: 32 });
+    const hour = timeInput(schedule.hour, 23);
+    const minute = timeInput(schedule.minute, 59);
+    const days = element('fieldset', {}, element('legend', {}, 'Days'), ...checkboxes(DAYS, schedule.days));
+    const months = element('fieldset', {}, element('legend', {}, 'Months'), ...checkboxes(MONTHS, schedule.month));
+    const status = element('select', {}, ...STATUS.slice(0, 2).map((name, value) =>
+        element('option', { value, selected: Math.min(schedule.status, 1) === value }, name)));
+
+    const targets = element('fieldset', {}, element('legend', {}, 'Zones, minutes or liters'));
+    const slots = schedule.zones.map((zone) => zone || { target: 0, minutes: 0, liters: 0 });
+    const inputs = slots.map((zone, slot) => {
+        const used = element('input', { type: 'checkbox', checked: !!schedule.zones[slot] });
+        const select = element('select', {}, ...zones.map((z) =>
+            element('option', { value: z.zone_relay, selected: z.zone_relay === zone.target }, z.description)));
+        if (zone.target & GROUP_FLAG) {
+            select.append(element('option', { value: zone.target, selected: true }, target(zone.target)));
+        }
+        const minutes = element('input', { type: 'number', min: 0, max: 255, value: zone.minutes });
+        const liters = element('input', { type: 'number', min: 0, max: 9999, value: zone.liters });
+        targets.append(element('div', {}, used, select, minutes, 'min', liters, 'L'));
+        return { used, select, minutes, liters };
+    });
+
+    const save = element('button', {}, 'Save');
+    const form = element('form', {},
+        element('h3', {}, 'Schedule ' + idx),
+        element('label', {}, 'Description ', description),
+        element('label', {}, 'Time ', hour, ':', minute),
+        element('label', {}, 'Status ', status),
+        days, months, targets, save);
+
+    form.onsubmit = async (event) => {
+        event.preventDefault();
+        const updated = {
+            ...schedule,
+            description: description.value,
+            hour: timeValue(hour),
+            minute: timeValue(minute),
+            days: mask(days),
+            month: mask(months),
+            status: Number(status.value),
+            zones: inputs.map((input) => input.used.checked ? {
+                target: Number(input.select.value),
+                minutes: Number(input.minutes.value),
+                liters: Number(input.liters.value),
+            } : null),
+        };
+        try {
+            await api('PUT', 'schedules/' + idx, updated);
+            message();
+            refresh();
+        } catch (e) {
+            message(e.message);
+        }
+    };
+    return form;
+}
+
+async function loadSchedules() {
+    const { schedules } = await api('GET', 'schedules');
+    // Keep the forms being edited
+    if (!$('schedules').contains(document.activeElement)) {
+        $('schedules').replaceChildren(...schedules.map(scheduleForm));
+    }
+    return schedules;
+}
+
+async function refresh() {
+    if (!token) {
+        return;
+    }
+    try {
+        await loadZones();
+        const schedules = await loadSchedules();
+        await loadNext(schedules);
+        await loadHistory();
+    } catch (e) {
+        message(e.message);
+    }
+}
+
+function logged(value) {
+    token = value;
+    if (token) {
+        sessionStorage.setItem('token', token);
+    } else {
+        sessionStorage.removeItem('token');
+    }
+    $('login').hidden = !!token;
+    $('dashboard').hidden = !token;
+    $('logout').hidden = !token;
+
+    clearInterval(timer);
+    if (token) {
+        refresh();
+        timer = setInterval(refresh, REFRESH_MS);
+    }
+}
+
+$('login').onsubmit = async (event) => {
+    event.preventDefault();
+    const form = event.target;
+    try {
+        const email = form.email.value;
+        const challenge = await api('POST', 'challenge', { email });
+        const body = { email, response: answer(form.password.value, challenge) };
+        if (form.code.value) {
+            body.code = Number(form.code.value);
+        }
+        const json = await api('POST', 'session', body);
+        form.reset();
+        message();
+        logged(json.token);
+    } catch (e) {
+        message(e.message);
+    }
+};
+
+$('logout').onclick = async () => {
+    try {
+        await api('DELETE', 'session');
+    } catch (e) {
+        // the session may have already expired
+    }
+    logged(null);
+};
+
+$('stop').onclick = async () => {
+    try {
+        await api('DELETE', 'run');
+        message();
+        setTimeout(refresh, 1000);
+    } catch (e) {
+        message(e.message);
+    }
+};
+
+logged(token);
@@ -24,6 +24,7 @@
 #include <pico/cyw43_arch.h>
 #include <lwip/dns.h>
 #include <lwip/pbuf.h>
+#include <lwip/tcp.h>
 #include <lwip/udp.h>
 
 
@@ -80,3 +81,59 @@ err_t hhg_udp_sendto(struct udp_pcb *buf, struct pbuf *p, const ip_addr_t *ipadd
 void hhg_udp_recv(struct udp_pcb *pcb, udp_recv_fn recv, void *recv_arg) {
     udp_recv(pcb, recv, recv_arg);
 }
+
+struct tcp_pcb * hhg_tcp_new_ip_type(u8_t type) {
+    return tcp_new_ip_type(type);
+}
+
+void hhg_tcp_arg(struct tcp_pcb *pcb, void *arg) {
+    tcp_arg(pcb, arg);
+}
+
+void hhg_tcp_recv(struct tcp_pcb *pcb, tcp_recv_fn recv) {
+    tcp_recv(pcb, recv);
+}
+
+void hhg_tcp_err(struct tcp_pcb *pcb, tcp_err_fn err) {
+    tcp_err(pcb, err);
+}
+
+err_t hhg_tcp_connect(struct tcp_pcb *pcb, const ip_addr_t *ipaddr, u16_t port, tcp_connected_fn connected) {
+    return tcp_connect(pcb, ipaddr, port, connected);
+}
+
+u16_t hhg_tcp_sndbuf(const struct tcp_pcb *pcb) {
+    return tcp_sndbuf(pcb);
+}
+
+err_t hhg_tcp_write(struct tcp_pcb *pcb, const void *data, u16_t len) {
+    return tcp_write(pcb, data, len, TCP_WRITE_FLAG_COPY);
+}
+
+err_t hhg_tcp_output(struct tcp_pcb *pcb) {
+    return tcp_output(pcb);
+}
+
+void hhg_tcp_recved(struct tcp_pcb *pcb, u16_t len) {
+    tcp_recved(pcb, len);
+}
+
+err_t hhg_tcp_close(struct tcp_pcb *pcb) {
+    return tcp_close(pcb);
+}
+
+void hhg_tcp_abort(struct tcp_pcb *pcb) {
+    tcp_abort(pcb);
+}
+
+err_t hhg_tcp_bind_any(struct tcp_pcb *pcb, u16_t port) {
+    return tcp_bind(pcb, IP_ANY_TYPE, port);
+}
+
+struct tcp_pcb * hhg_tcp_listen(struct tcp_pcb *pcb) {
+    return tcp_listen_with_backlog(pcb, 1);
+}
+
+void hhg_tcp_accept(struct tcp_pcb *pcb, tcp_accept_fn accept) {
+    tcp_accept(pcb, accept);
+}
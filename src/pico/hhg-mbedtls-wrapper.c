@@ -19,6 +19,8 @@
  ***************************************************************************/
 
 #include <mbedtls/aes.h>
+#include <mbedtls/md.h>
+#include <mbedtls/pkcs5.h>
 
 extern void * pvPortMalloc( size_t xWantedSize );
 extern void vPortFree( void * pv );
@@ -47,4 +49,12 @@ int hhg_mbedtls_aes_setkey_dec(void* aes, const unsigned char* key, unsigned int
 void hhg_mbedtls_aes_free(void* aes) {
     mbedtls_aes_free((mbedtls_aes_context*)aes);
     vPortFree((mbedtls_aes_context*)aes);
-}
\ No newline at end of file
+}
+
+int hhg_mbedtls_pbkdf2_sha256(const unsigned char* password, size_t password_len, const unsigned char* salt, size_t salt_len, unsigned int iterations, unsigned char* output, unsigned int output_len) {
+    return mbedtls_pkcs5_pbkdf2_hmac_ext(MBEDTLS_MD_SHA256, password, password_len, salt, salt_len, iterations, output_len, output);
+}
+
+int hhg_mbedtls_hmac_sha256(const unsigned char* key, size_t key_len, const unsigned char* input, size_t input_len, unsigned char* output) {
+    return mbedtls_md_hmac(mbedtls_md_info_from_type(MBEDTLS_MD_SHA256), key, key_len, input, input_len, output);
+}
@@ -22,6 +22,7 @@
 #include <sys/types.h>
 #include <string.h>
 #include <pico/unique_id.h>
+#include <pico/rand.h>
 #include <pico/sha256.h>
 #include <hardware/powman.h>
 
@@ -34,6 +35,10 @@ void hhg_get_unique_id(uint8_t* id_buffer) {
     memcpy(id_buffer, board_id.id, PICO_UNIQUE_BOARD_ID_SIZE_BYTES);
 }
 
+uint64_t hhg_get_rand_64(void) {
+    return get_rand_64();
+}
+
 int hhg_pico_sha256_start_blocking(void **state, bool use_dma) {
     *state = pvPortMalloc(sizeof(pico_sha256_state_t));
     return pico_sha256_start_blocking((pico_sha256_state_t *)*state, SHA256_BIG_ENDIAN, use_dma);